@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use gridder::parse::{parse_content, parse_summary};
+
+const SAMPLE_PAGE: &str = include_str!("../fixtures/sample.html");
+
+fn parse_sample(c: &mut Criterion) {
+    c.bench_function("parse_content/sample", |b| {
+        b.iter(|| parse_content(black_box(SAMPLE_PAGE)).unwrap())
+    });
+
+    c.bench_function("parse_summary/sample", |b| {
+        b.iter(|| parse_summary(black_box(SAMPLE_PAGE)).unwrap())
+    });
+
+    #[cfg(feature = "fast-parse")]
+    c.bench_function("parse_content_streaming/sample", |b| {
+        b.iter(|| gridder::parse::parse_content_streaming(black_box(SAMPLE_PAGE)).unwrap())
+    });
+}
+
+// What a replay of a month of saved pages does for each of them
+fn parse_replay(c: &mut Criterion) {
+    let pages = vec![SAMPLE_PAGE; 30];
+    c.bench_function("replay/30_pages", |b| {
+        b.iter(|| {
+            for page in black_box(&pages) {
+                parse_content(page).unwrap();
+                parse_summary(page).unwrap();
+            }
+        })
+    });
+}
+
+criterion_group!(benches, parse_sample, parse_replay);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/gridder.proto")
+        .expect("failed to compile proto/gridder.proto");
+}
@@ -71,7 +71,8 @@
           default = pkg;
         };
         devShells.default = pkgs.mkShell {
-          packages = [ toolchain pkgs.rust-analyzer-nightly ] ++ systemDeps;
+          # protobuf for building with --features grpc
+          packages = [ toolchain pkgs.rust-analyzer-nightly pkgs.protobuf ] ++ systemDeps;
         };
       }));
 }
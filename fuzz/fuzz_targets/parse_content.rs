@@ -0,0 +1,18 @@
+//! Both extractors must return either data or a `SiteParseError` for any
+//! input, never panic.
+//!
+//! The corpus in fuzz/corpus/parse_content starts from the pages in
+//! fixtures/, linked in:
+//!     cargo fuzz run parse_content
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use gridder::parse::{parse_content, parse_content_streaming};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(body) = std::str::from_utf8(data) {
+        let _ = parse_content(body);
+        let _ = parse_content_streaming(body);
+    }
+});
@@ -0,0 +1,214 @@
+use std::fmt;
+
+/// A cell, or a whole column if it has no row, in A1 notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    // 1-based, as in column_number()
+    column: usize,
+    row: Option<usize>,
+}
+
+impl Cell {
+    /// The cell at `column` (e.g. `"B"`) and 1-based `row`.
+    ///
+    /// Panics if `column` isn't a column name; it's meant for the constant
+    /// ones the sheets are laid out with.
+    pub fn new(column: &str, row: usize) -> Self {
+        Self::at(named(column), row)
+    }
+
+    /// The cell at the 1-based `column` and `row`, e.g. `(2, 3)` for B3.
+    pub fn at(column: usize, row: usize) -> Self {
+        Self {
+            column,
+            row: Some(row),
+        }
+    }
+
+    /// All of `column`, or the rest of it when used as the end of a range.
+    ///
+    /// Panics if `column` isn't a column name, as with [`Cell::new`].
+    pub fn column(column: &str) -> Self {
+        Self {
+            column: named(column),
+            row: None,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&column_name(self.column))?;
+        if let Some(row) = self.row {
+            write!(f, "{row}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The 1-based number of a column, e.g. 1 for `A` and 29 for `AC`.
+///
+/// Nothing if `column` is empty, has anything but ASCII letters in it, or
+/// is too far right to count.
+pub fn column_number(column: &str) -> Option<usize> {
+    if column.is_empty() {
+        return None;
+    }
+    column.bytes().try_fold(0usize, |number, letter| {
+        if !letter.is_ascii_alphabetic() {
+            return None;
+        }
+        let digit = usize::from(letter.to_ascii_uppercase() - b'A') + 1;
+        number.checked_mul(26)?.checked_add(digit)
+    })
+}
+
+fn named(column: &str) -> usize {
+    match column_number(column) {
+        Some(number) => number,
+        None => panic!("{column:?} isn't a column name"),
+    }
+}
+
+/// The name of the 1-based column `number`, e.g. `AC` for 29.
+pub fn column_name(mut number: usize) -> String {
+    let mut name = Vec::new();
+    while number > 0 {
+        // Each letter's 1 to 26, not 0 to 25, so there's no zero digit
+        let letter = (number - 1) % 26;
+        name.push(b'A' + letter as u8);
+        number = (number - 1) / 26;
+    }
+    name.reverse();
+
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// A range on a named sheet, e.g. `'2024-05-01'!F3:G`, or the whole of it,
+/// e.g. `'2024-05-01'`.
+///
+/// The sheet name is always quoted, with any apostrophes in it doubled, so
+/// any tab name is safe to use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    sheet: String,
+    start: Option<Cell>,
+    end: Option<Cell>,
+}
+
+impl Range {
+    /// The range covering just `start` on `sheet`.
+    pub fn new(sheet: &str, start: Cell) -> Self {
+        Self {
+            start: Some(start),
+            ..Self::sheet(sheet)
+        }
+    }
+
+    /// The range covering every cell of `sheet`.
+    pub fn sheet(sheet: &str) -> Self {
+        Self {
+            sheet: sheet.to_string(),
+            start: None,
+            end: None,
+        }
+    }
+
+    /// The range from `start` to `end` on `sheet`.
+    pub fn between(sheet: &str, start: Cell, end: Cell) -> Self {
+        Self {
+            end: Some(end),
+            ..Self::new(sheet, start)
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.sheet.replace('\'', "''"))?;
+        if let Some(start) = self.start {
+            write!(f, "!{start}")?;
+        }
+        if let Some(end) = self.end {
+            write!(f, ":{end}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_round_trips() {
+        for (name, number) in [
+            ("A", 1),
+            ("Z", 26),
+            ("AA", 27),
+            ("AZ", 52),
+            ("BA", 53),
+            ("ZZ", 702),
+            ("AAA", 703),
+            ("XFD", 16384),
+        ] {
+            assert_eq!(column_number(name), Some(number), "{name}");
+            assert_eq!(column_name(number), name, "{number}");
+        }
+        for number in 1..=20_000 {
+            assert_eq!(column_number(&column_name(number)), Some(number));
+        }
+    }
+
+    #[test]
+    fn column_number_ignores_case() {
+        assert_eq!(column_number("ac"), Some(29));
+    }
+
+    #[test]
+    fn column_number_rejects_non_letters() {
+        for name in ["", "1", "A1", "@", "[", "a-b", "É", " A"] {
+            assert_eq!(column_number(name), None, "{name:?}");
+        }
+        assert_eq!(column_number(&"Z".repeat(20)), None);
+    }
+
+    #[test]
+    fn sheet_names_are_quoted() {
+        let cell = Cell::new("B", 3);
+        assert_eq!(
+            Range::new("2024-05-01", cell).to_string(),
+            "'2024-05-01'!B3"
+        );
+        assert_eq!(
+            Range::new("Joe's grid", cell).to_string(),
+            "'Joe''s grid'!B3"
+        );
+        assert_eq!(Range::new("''", cell).to_string(), "''''''!B3");
+        assert_eq!(Range::new("Grid!", cell).to_string(), "'Grid!'!B3");
+        assert_eq!(Range::new("a b!c", cell).to_string(), "'a b!c'!B3");
+        assert_eq!(Range::sheet("It's done!").to_string(), "'It''s done!'");
+    }
+
+    #[test]
+    fn open_ended_ranges() {
+        assert_eq!(
+            Range::between("Stats", Cell::new("F", 3), Cell::column("G")).to_string(),
+            "'Stats'!F3:G"
+        );
+        assert_eq!(
+            Range::between("Stats", Cell::column("A"), Cell::column("D")).to_string(),
+            "'Stats'!A:D"
+        );
+        assert_eq!(
+            Range::between("Stats", Cell::at(27, 2), Cell::column("AB")).to_string(),
+            "'Stats'!AA2:AB"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn cell_rejects_bad_column() {
+        Cell::new("A1", 1);
+    }
+}
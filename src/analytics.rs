@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::document::GridDocument;
+
+/// Fewer days of history than this aren't enough to call a pair unusual.
+pub const MIN_HISTORY_DAYS: usize = 14;
+
+/// Pairs seen on fewer than this share of past days are unusual by default.
+pub const RARE_PAIR_SHARE: f64 = 0.05;
+
+/// How often a two-letter prefix has come up in past grids.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PairStats {
+    /// Days the pair had at least one word.
+    pub days: usize,
+    /// Words starting with the pair, over every day.
+    pub words: usize,
+}
+
+/// Two-letter prefix counts over a run of past grids.
+#[derive(Debug, Clone, Default)]
+pub struct PairHistory {
+    days: usize,
+    pairs: BTreeMap<String, PairStats>,
+}
+
+/// A pair in today's grid that's rarely been seen before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnusualPair {
+    pub pair: String,
+    /// Words starting with the pair today.
+    pub words: usize,
+    pub history: PairStats,
+    /// Days in the history it's measured against.
+    pub of_days: usize,
+}
+
+impl PairHistory {
+    pub fn from_docs<'a, I>(docs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a GridDocument>,
+    {
+        let mut history = Self::default();
+        for doc in docs {
+            history.days += 1;
+            for entry in doc.pairs.iter().filter(|entry| entry.count.0 > 0) {
+                let stats = history
+                    .pairs
+                    .entry(entry.pair.to_ascii_uppercase())
+                    .or_default();
+                stats.days += 1;
+                stats.words += entry.count.0;
+            }
+        }
+
+        history
+    }
+
+    /// Number of grids the history covers.
+    pub fn days(&self) -> usize {
+        self.days
+    }
+
+    pub fn get(&self, pair: &str) -> PairStats {
+        self.pairs
+            .get(&pair.to_ascii_uppercase())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Every pair seen, most frequent first: by the days it came up on,
+    /// then by its words.
+    pub fn ranked(&self) -> Vec<(&str, PairStats)> {
+        let mut ranked = self
+            .pairs
+            .iter()
+            .map(|(pair, stats)| (pair.as_str(), *stats))
+            .collect::<Vec<_>>();
+        // Stable, so ties stay in alphabetical order
+        ranked.sort_by_key(|(_, stats)| std::cmp::Reverse((stats.days, stats.words)));
+
+        ranked
+    }
+
+    /// The pairs in `doc` that came up on less than `max_share` of the
+    /// days in the history, rarest first. Nothing is unusual until there's
+    /// [`MIN_HISTORY_DAYS`] of history.
+    pub fn unusual(&self, doc: &GridDocument, max_share: f64) -> Vec<UnusualPair> {
+        if self.days < MIN_HISTORY_DAYS {
+            return Vec::new();
+        }
+
+        let mut unusual = doc
+            .pairs
+            .iter()
+            .filter(|entry| entry.count.0 > 0)
+            .map(|entry| UnusualPair {
+                pair: entry.pair.to_ascii_uppercase(),
+                words: entry.count.0,
+                history: self.get(&entry.pair),
+                of_days: self.days,
+            })
+            .filter(|pair| pair.share() < max_share)
+            .collect::<Vec<_>>();
+        unusual.sort_by_key(|pair| pair.history.days);
+
+        unusual
+    }
+}
+
+impl UnusualPair {
+    /// The fraction of past days the pair came up on.
+    pub fn share(&self) -> f64 {
+        self.history.days as f64 / self.of_days as f64
+    }
+}
+
+impl fmt::Display for UnusualPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.history.days {
+            0 => write!(
+                f,
+                "{} ({} today, never seen in {} days)",
+                self.pair, self.words, self.of_days
+            ),
+            days => write!(
+                f,
+                "{} ({} today, seen on {days} of {} days)",
+                self.pair, self.words, self.of_days
+            ),
+        }
+    }
+}
+
+/// How a day's grid compares to past ones, from very easy to very hard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rating {
+    VeryEasy,
+    Easy,
+    Average,
+    Hard,
+    VeryHard,
+}
+
+/// A difficulty estimate for one day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty {
+    /// How many standard deviations harder than the average past day the
+    /// grid looks; negative is easier.
+    pub score: f64,
+    pub rating: Rating,
+}
+
+// What a difficulty estimate is made from. Each goes up as a grid gets
+// harder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Features {
+    // More words means more to find
+    words: f64,
+    // Longer words are harder to spot
+    average_length: f64,
+    // Share of the day's words starting with a rarely seen pair
+    rare_pair_share: f64,
+}
+
+impl Features {
+    // With `leave_out`, `doc` is one of the days `history` was built from,
+    // and is discounted from it
+    fn of(doc: &GridDocument, history: &PairHistory, leave_out: bool) -> Self {
+        let words = doc.totals.words;
+        let letters = doc
+            .totals
+            .by_length
+            .iter()
+            .map(|(length, count)| length * count)
+            .sum::<usize>();
+
+        let discount = usize::from(leave_out);
+        let past_days = history.days.saturating_sub(discount).max(1);
+        let rare_words = doc
+            .pairs
+            .iter()
+            .filter(|entry| {
+                let seen = history.get(&entry.pair).days.saturating_sub(discount);
+                (seen as f64 / past_days as f64) < RARE_PAIR_SHARE
+            })
+            .map(|entry| entry.count.0)
+            .sum::<usize>();
+
+        let per_word = |n: usize| match words {
+            0 => 0.0,
+            words => n as f64 / words as f64,
+        };
+        Self {
+            words: words as f64,
+            average_length: per_word(letters),
+            rare_pair_share: per_word(rare_words),
+        }
+    }
+}
+
+// The mean and spread of one feature over the training days
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Distribution {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Distribution {
+    fn of(values: impl Iterator<Item = f64> + Clone) -> Self {
+        let n = values.clone().count().max(1) as f64;
+        let mean = values.clone().sum::<f64>() / n;
+        let variance = values.map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+        }
+    }
+
+    // How many standard deviations `value` is from the mean. If every
+    // training day had the same value, anything else counts as one out.
+    fn z_score(&self, value: f64) -> f64 {
+        let difference = value - self.mean;
+        if self.std_dev == 0.0 {
+            return if difference == 0.0 {
+                0.0
+            } else {
+                difference.signum()
+            };
+        }
+        difference / self.std_dev
+    }
+}
+
+/// Estimates how hard a day's grid is by how its word count, average word
+/// length and share of rare pairs compare to past grids.
+#[derive(Debug, Clone)]
+pub struct DifficultyModel {
+    pairs: PairHistory,
+    words: Distribution,
+    average_length: Distribution,
+    rare_pair_share: Distribution,
+}
+
+impl DifficultyModel {
+    /// Learns what's normal from past grids. Needs at least
+    /// [`MIN_HISTORY_DAYS`] of them.
+    pub fn train(docs: &[GridDocument]) -> Option<Self> {
+        if docs.len() < MIN_HISTORY_DAYS {
+            return None;
+        }
+
+        let pairs = PairHistory::from_docs(docs);
+        let features = docs
+            .iter()
+            .map(|doc| Features::of(doc, &pairs, true))
+            .collect::<Vec<_>>();
+
+        Some(Self {
+            words: Distribution::of(features.iter().map(|f| f.words)),
+            average_length: Distribution::of(features.iter().map(|f| f.average_length)),
+            rare_pair_share: Distribution::of(features.iter().map(|f| f.rare_pair_share)),
+            pairs,
+        })
+    }
+
+    /// Rates a grid that wasn't part of the training days.
+    pub fn estimate(&self, doc: &GridDocument) -> Difficulty {
+        let features = Features::of(doc, &self.pairs, false);
+        let score = (self.words.z_score(features.words)
+            + self.average_length.z_score(features.average_length)
+            + self.rare_pair_share.z_score(features.rare_pair_share))
+            / 3.0;
+
+        let rating = match score {
+            s if s < -1.0 => Rating::VeryEasy,
+            s if s < -0.33 => Rating::Easy,
+            s if s <= 0.33 => Rating::Average,
+            s if s <= 1.0 => Rating::Hard,
+            _ => Rating::VeryHard,
+        };
+
+        Difficulty { score, rating }
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::VeryEasy => "very easy",
+            Self::Easy => "easy",
+            Self::Average => "average",
+            Self::Hard => "hard",
+            Self::VeryHard => "very hard",
+        })
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:+.2})", self.rating, self.score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    // A grid with the given pairs, each of whose words is `length` long
+    fn doc(pairs: &[(&str, usize)], length: usize) -> GridDocument {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let mut lengths = LengthInfo::new();
+        let pairs = pairs
+            .iter()
+            .map(|(pair, count)| {
+                let mut letters = pair.chars();
+                let pair = (letters.next().unwrap(), letters.next().unwrap());
+                *lengths
+                    .entry((pair.0, WordLength(length)))
+                    .or_insert(WordCount(0)) += WordCount(*count);
+                (pair, WordCount(*count))
+            })
+            .collect::<PairInfo>();
+        GridDocument::new(date, &pairs, &lengths)
+    }
+
+    fn usual_days(days: usize) -> Vec<GridDocument> {
+        (0..days).map(|_| doc(&[("AC", 2), ("CA", 1)], 5)).collect()
+    }
+
+    #[test]
+    fn pairs_are_ranked_by_days_then_words() {
+        let docs = [
+            doc(&[("AC", 1), ("ca", 5)], 4),
+            doc(&[("AC", 1), ("TA", 2)], 4),
+            doc(&[("CA", 1), ("TA", 0)], 4),
+        ];
+        let history = PairHistory::from_docs(&docs);
+        assert_eq!(history.days(), 3);
+        assert_eq!(history.get("ca"), PairStats { days: 2, words: 6 });
+        let ranked = history.ranked();
+        let pairs = ranked.iter().map(|(pair, _)| *pair).collect::<Vec<_>>();
+        assert_eq!(pairs, ["CA", "AC", "TA"]);
+    }
+
+    #[test]
+    fn nothing_is_unusual_without_enough_history() {
+        let history = PairHistory::from_docs(&usual_days(MIN_HISTORY_DAYS - 1));
+        let today = doc(&[("ZA", 1)], 5);
+        assert!(history.unusual(&today, RARE_PAIR_SHARE).is_empty());
+    }
+
+    #[test]
+    fn rare_pairs_are_listed_rarest_first() {
+        let mut docs = usual_days(MIN_HISTORY_DAYS * 2);
+        docs[0] = doc(&[("AC", 2), ("CA", 1), ("NA", 1)], 5);
+        let history = PairHistory::from_docs(&docs);
+
+        let today = doc(&[("AC", 1), ("NA", 2), ("ZA", 3)], 5);
+        let unusual = history.unusual(&today, RARE_PAIR_SHARE);
+        let pairs = unusual.iter().map(|p| p.pair.as_str()).collect::<Vec<_>>();
+        assert_eq!(pairs, ["ZA", "NA"]);
+        assert_eq!(
+            unusual[0].to_string(),
+            "ZA (3 today, never seen in 28 days)"
+        );
+        assert_eq!(unusual[1].to_string(), "NA (2 today, seen on 1 of 28 days)");
+    }
+
+    // Grids that vary a little, so the model has a spread to measure
+    // against
+    fn training_days() -> Vec<GridDocument> {
+        (0..MIN_HISTORY_DAYS)
+            .map(|day| doc(&[("AC", 10 + day % 3), ("CA", 5)], 5 + day % 2))
+            .collect()
+    }
+
+    #[test]
+    fn the_model_needs_enough_days() {
+        assert!(DifficultyModel::train(&training_days()[1..]).is_none());
+        assert!(DifficultyModel::train(&training_days()).is_some());
+    }
+
+    #[test]
+    fn grids_are_rated_against_the_training_days() {
+        let model = DifficultyModel::train(&training_days()).unwrap();
+
+        let usual = model.estimate(&doc(&[("AC", 11), ("CA", 5)], 5));
+        assert_eq!(usual.rating, Rating::Average);
+
+        let easy = model.estimate(&doc(&[("AC", 5)], 4));
+        assert_eq!(easy.rating, Rating::VeryEasy);
+        assert!(easy.score < -1.0);
+
+        let hard = model.estimate(&doc(&[("AC", 20), ("ZA", 10)], 8));
+        assert_eq!(hard.rating, Rating::VeryHard);
+        assert!(hard.to_string().starts_with("very hard (+"));
+    }
+
+    #[test]
+    fn days_like_every_other_are_average() {
+        let docs = usual_days(MIN_HISTORY_DAYS);
+        let model = DifficultyModel::train(&docs).unwrap();
+        let difficulty = model.estimate(&docs[0]);
+        assert_eq!(difficulty.score, 0.0);
+        assert_eq!(difficulty.to_string(), "average (+0.00)");
+    }
+}
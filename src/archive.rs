@@ -0,0 +1,703 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::dictionary::Dictionary;
+use crate::document::GridDocument;
+use crate::formats::{self, FormatError};
+use crate::team::DayFinds;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("failed to list {0}: {1}")]
+    Listing(PathBuf, std::io::Error),
+    #[error("failed to read {0}: {1}")]
+    Reading(PathBuf, FormatError),
+    #[error("failed to write {0}: {1}")]
+    Writing(PathBuf, FormatError),
+    #[error("failed to read words from {0}: {1}")]
+    ReadingWords(PathBuf, std::io::Error),
+    #[error("failed to write words to {0}: {1}")]
+    WritingWords(PathBuf, std::io::Error),
+    #[error("failed to read {0} from the database: {1}")]
+    ReadingRow(String, FormatError),
+    #[error("{0}:// stores need gridder built with the {0} feature")]
+    NotBuiltIn(&'static str),
+    #[error("this needs a directory of saved grids, not a {0}:// database")]
+    NotFiles(String),
+    #[error("{0} isn't a database; give a sqlite://PATH or postgres:// URL")]
+    NotDatabase(PathBuf),
+    #[error(
+        "the database has had migration {found} applied, but this gridder only knows up to {known}"
+    )]
+    NewerSchema { found: u32, known: u32 },
+    #[error("failed to write backup {0}: {1}")]
+    WritingBackup(PathBuf, std::io::Error),
+    #[error("failed to read backup {0}: {1}")]
+    ReadingBackup(PathBuf, std::io::Error),
+    #[error("{0} isn't a backup of this kind of database")]
+    NotBackup(PathBuf),
+    #[error("{0} failed its integrity check: {1}")]
+    FailedCheck(PathBuf, String),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "postgres")]
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] ::postgres::Error),
+}
+
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "postgres")]
+pub use self::postgres::PostgresStore;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteStore;
+
+/// A directory of saved grids, one `YYYY-MM-DD.json` file per day. The words
+/// found for them are kept alongside in `YYYY-MM-DD.words.txt`, who found
+/// them in `YYYY-MM-DD.finds.json`, and the runs that wrote them in
+/// `runs.jsonl`.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+}
+
+/// One run's attempt at a day, as kept by [`Store::record_run`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub date: NaiveDate,
+    /// When the run finished with the day.
+    pub finished: DateTime<Utc>,
+    /// Why the run failed, or nothing if the grid was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Where saved grids are kept, along with who found what on each day and
+/// the runs that wrote them. [`Archive`] keeps them as files; anything that
+/// doesn't need the files themselves takes a `Store`, so it can be given
+/// another, e.g. from [`open_store`].
+pub trait Store: fmt::Debug + Send + Sync {
+    /// Loads the grid for `date`, if there is one.
+    fn load_grid(&self, date: NaiveDate) -> Result<Option<GridDocument>, ArchiveError>;
+
+    /// Saves `doc`, replacing any grid saved before for its date.
+    fn save_grid(&self, doc: &GridDocument) -> Result<(), ArchiveError>;
+
+    /// Every date with a saved grid.
+    fn list_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError>;
+
+    /// Where the grid for `date` is kept, e.g. its file, to show where a
+    /// grid was read from.
+    fn location(&self, date: NaiveDate) -> String;
+
+    /// Who found which words on `date`, empty if nothing's been recorded.
+    fn load_finds(&self, date: NaiveDate) -> Result<DayFinds, ArchiveError>;
+
+    /// Saves who found what on `date`, replacing anything saved before.
+    fn save_finds(&self, date: NaiveDate, finds: &DayFinds) -> Result<(), ArchiveError>;
+
+    /// Every date with finds recorded.
+    fn list_finds_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError>;
+
+    /// Saves the words found for `date`, replacing any saved before. Only
+    /// pass words the puzzle accepted.
+    fn save_words(&self, date: NaiveDate, words: &[String]) -> Result<(), ArchiveError>;
+
+    /// Every word saved as found on any day.
+    fn accepted_words(&self) -> Result<Dictionary, ArchiveError>;
+
+    /// Keeps a record of a run's attempt at a day.
+    fn record_run(&self, run: &RunRecord) -> Result<(), ArchiveError>;
+
+    /// Every run recorded for `date`, in the order they finished.
+    fn runs(&self, date: NaiveDate) -> Result<Vec<RunRecord>, ArchiveError>;
+
+    /// Whether grids saved here outlast the process.
+    fn persistent(&self) -> bool {
+        true
+    }
+
+    /// A SHA-256 hash of the grid saved for `date`, in hex, if there is
+    /// one. It changes whenever the grid is saved again with any difference,
+    /// so it tells whether a copy is current.
+    fn content_hash(&self, date: NaiveDate) -> Result<Option<String>, ArchiveError> {
+        // Serializing a document can't fail
+        Ok(self
+            .load_grid(date)?
+            .map(|doc| sha256_hex(&serde_json::to_vec(&doc).unwrap_or_default())))
+    }
+
+    /// Loads every saved grid from `from` to `to` inclusive, in date order.
+    /// Nothing if `to` is before `from`.
+    fn load_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<GridDocument>, ArchiveError> {
+        let mut docs = Vec::new();
+        if to < from {
+            return Ok(docs);
+        }
+        for date in self.list_dates()?.range(from..=to) {
+            docs.extend(self.load_grid(*date)?);
+        }
+        Ok(docs)
+    }
+
+    /// The saved grids from `from` to `to` inclusive, in date order, each
+    /// only loaded as it's reached, so a range of years needn't fit in
+    /// memory at once. Nothing if `to` is before `from`.
+    fn iter_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Box<dyn Iterator<Item = Result<GridDocument, ArchiveError>> + '_>, ArchiveError>
+    {
+        let dates = match from <= to {
+            true => self.list_dates()?.range(from..=to).copied().collect(),
+            false => Vec::new(),
+        };
+        Ok(Box::new(
+            dates
+                .into_iter()
+                .filter_map(|date| self.load_grid(date).transpose()),
+        ))
+    }
+
+    /// Who found what on each day from `from` to `to` inclusive that has
+    /// finds recorded, along with the day's grid if it was saved. Nothing if
+    /// `to` is before `from`.
+    fn load_finds_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(DayFinds, Option<GridDocument>)>, ArchiveError> {
+        let mut days = Vec::new();
+        if to < from {
+            return Ok(days);
+        }
+        for date in self.list_finds_dates()?.range(from..=to) {
+            days.push((self.load_finds(*date)?, self.load_grid(*date)?));
+        }
+
+        Ok(days)
+    }
+
+    /// Days from `from` to `to` inclusive that have no grid, or whose grid
+    /// scored under `min_quality` when it was read. Grids saved without a
+    /// score are taken as fine.
+    fn gaps(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        min_quality: u8,
+    ) -> Result<Vec<(NaiveDate, Gap)>, ArchiveError> {
+        let saved = self.list_dates()?;
+
+        let mut gaps = Vec::new();
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            if !saved.contains(&date) {
+                gaps.push((date, Gap::Missing));
+                continue;
+            }
+
+            let quality = self.load_grid(date)?.and_then(|doc| doc.quality);
+            match quality {
+                Some(score) if score < min_quality => gaps.push((date, Gap::LowQuality(score))),
+                _ => {}
+            }
+        }
+
+        Ok(gaps)
+    }
+}
+
+/// A change to the tables of a database store. Each store has its own,
+/// applied in order of version and recorded in its `schema_migrations`
+/// table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    #[cfg_attr(not(any(feature = "sqlite", feature = "postgres")), allow(dead_code))]
+    sql: &'static str,
+}
+
+/// One of a database's migrations, and when it was applied, if it has been.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub migration: Migration,
+    pub applied: Option<DateTime<Utc>>,
+}
+
+/// Saved grids in a database, as `gridder db` looks after them.
+pub trait Database: fmt::Debug {
+    /// Every migration this build has for the database, in order, and
+    /// which have been applied.
+    fn migrations(&self) -> Result<Vec<MigrationStatus>, ArchiveError>;
+
+    /// Applies the migrations the database is missing, in order, and
+    /// returns them.
+    fn migrate(&self) -> Result<Vec<Migration>, ArchiveError>;
+
+    /// Writes a copy of the saved grids to `to`, gzipped if its name ends
+    /// in `.gz`.
+    fn backup(&self, to: &Path) -> Result<(), ArchiveError>;
+
+    /// Replaces the saved grids with those backed up to `from`. Backups
+    /// from a newer gridder are refused, and older ones are migrated.
+    fn restore(&self, from: &Path) -> Result<(), ArchiveError>;
+
+    /// Checks the database for corruption, and with `vacuum`, reclaims the
+    /// space left by removed rows.
+    fn check(&self, vacuum: bool) -> Result<(), ArchiveError>;
+}
+
+// Backups named .gz are gzipped
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+fn is_gzipped(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+// Creates the backup file at `path` for `write` to fill in
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+fn write_backup(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), ArchiveError>,
+) -> Result<(), ArchiveError> {
+    let writing = |e| ArchiveError::WritingBackup(path.to_path_buf(), e);
+    let mut file = std::io::BufWriter::new(File::create(path).map_err(writing)?);
+    if is_gzipped(path) {
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        write(&mut encoder)?;
+        file = encoder.finish().map_err(writing)?;
+    } else {
+        write(&mut file)?;
+    }
+    file.flush().map_err(writing)
+}
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+fn read_backup(path: &Path) -> Result<Box<dyn BufRead>, ArchiveError> {
+    let file = File::open(path).map_err(|e| ArchiveError::ReadingBackup(path.to_path_buf(), e))?;
+    Ok(match is_gzipped(path) {
+        true => Box::new(BufReader::new(flate2::read::GzDecoder::new(file))),
+        false => Box::new(BufReader::new(file)),
+    })
+}
+
+// What's become of each of `all`, given when those `applied` were. A
+// database migrated by a newer gridder is refused, as its tables may no
+// longer be what this one expects.
+#[cfg_attr(not(any(feature = "sqlite", feature = "postgres")), allow(dead_code))]
+fn migration_statuses(
+    all: &[Migration],
+    mut applied: BTreeMap<u32, DateTime<Utc>>,
+) -> Result<Vec<MigrationStatus>, ArchiveError> {
+    let known = all.last().map_or(0, |migration| migration.version);
+    if let Some((&found, _)) = applied
+        .last_key_value()
+        .filter(|(found, _)| **found > known)
+    {
+        return Err(ArchiveError::NewerSchema { found, known });
+    }
+
+    Ok(all
+        .iter()
+        .map(|migration| MigrationStatus {
+            migration: *migration,
+            applied: applied.remove(&migration.version),
+        })
+        .collect())
+}
+
+/// Opens the saved grids at `location`: a `sqlite://PATH` database, a
+/// `postgres://` connection URL, or otherwise a directory of files, as an
+/// [`Archive`].
+pub fn open_store(location: &Path) -> Result<Arc<dyn Store>, ArchiveError> {
+    let url = location.to_str().unwrap_or_default();
+    match url.split_once("://") {
+        #[cfg(feature = "sqlite")]
+        Some(("sqlite", path)) => Ok(Arc::new(SqliteStore::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Some(("sqlite", _)) => Err(ArchiveError::NotBuiltIn("sqlite")),
+        #[cfg(feature = "postgres")]
+        Some(("postgres" | "postgresql", _)) => Ok(Arc::new(PostgresStore::connect(url)?)),
+        #[cfg(not(feature = "postgres"))]
+        Some(("postgres" | "postgresql", _)) => Err(ArchiveError::NotBuiltIn("postgres")),
+        _ => Ok(Arc::new(Archive::new(location))),
+    }
+}
+
+/// Opens the saved grids at `location` as files, for what works on them
+/// directly. Database URLs, as [`open_store`] takes, are refused without
+/// being opened.
+pub fn open_archive(location: &Path) -> Result<Archive, ArchiveError> {
+    let url = location.to_str().unwrap_or_default();
+    match url.split_once("://") {
+        Some((scheme @ ("sqlite" | "postgres" | "postgresql"), _)) => {
+            Err(ArchiveError::NotFiles(scheme.to_string()))
+        }
+        _ => Ok(Archive::new(location)),
+    }
+}
+
+/// Opens the database at `location`, a `sqlite://PATH` or `postgres://`
+/// URL, for `gridder db`. Unlike [`open_store`], it isn't migrated.
+pub fn open_database(location: &Path) -> Result<Box<dyn Database>, ArchiveError> {
+    let url = location.to_str().unwrap_or_default();
+    match url.split_once("://") {
+        #[cfg(feature = "sqlite")]
+        Some(("sqlite", path)) => Ok(Box::new(SqliteStore::open_unmigrated(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Some(("sqlite", _)) => Err(ArchiveError::NotBuiltIn("sqlite")),
+        #[cfg(feature = "postgres")]
+        Some(("postgres" | "postgresql", _)) => {
+            Ok(Box::new(PostgresStore::connect_unmigrated(url)?))
+        }
+        #[cfg(not(feature = "postgres"))]
+        Some(("postgres" | "postgresql", _)) => Err(ArchiveError::NotBuiltIn("postgres")),
+        _ => Err(ArchiveError::NotDatabase(location.to_path_buf())),
+    }
+}
+
+/// Why a day needs fetching again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gap {
+    Missing,
+    LowQuality(u8),
+}
+
+impl Archive {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path_for(&self, date: NaiveDate) -> PathBuf {
+        self.dir.join(format!("{}.json", date.format("%Y-%m-%d")))
+    }
+
+    pub fn words_path_for(&self, date: NaiveDate) -> PathBuf {
+        self.dir
+            .join(format!("{}.words.txt", date.format("%Y-%m-%d")))
+    }
+
+    pub fn finds_path_for(&self, date: NaiveDate) -> PathBuf {
+        self.dir
+            .join(format!("{}.finds.json", date.format("%Y-%m-%d")))
+    }
+
+    pub fn runs_path(&self) -> PathBuf {
+        self.dir.join("runs.jsonl")
+    }
+
+    /// Loads the grid for `date`, if there is one.
+    pub fn load(&self, date: NaiveDate) -> Result<Option<GridDocument>, ArchiveError> {
+        let path = self.path_for(date);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(ArchiveError::Reading(path, e.into())),
+        };
+
+        formats::read_json(file)
+            .map(Some)
+            .map_err(|e| ArchiveError::Reading(path, e))
+    }
+
+    pub fn save(&self, doc: &GridDocument) -> Result<(), ArchiveError> {
+        let path = self.path_for(doc.date);
+        File::create(&path)
+            .map_err(FormatError::Io)
+            .and_then(|file| formats::write_json(doc, file))
+            .map_err(|e| ArchiveError::Writing(path, e))
+    }
+
+    /// When the grid for `date` was last saved, if there is one.
+    pub fn modified(&self, date: NaiveDate) -> Result<Option<SystemTime>, ArchiveError> {
+        let path = self.path_for(date);
+        match std::fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => Ok(Some(modified)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ArchiveError::Reading(path, e.into())),
+        }
+    }
+
+    /// Saves the words found for `date`, one per line, replacing any saved
+    /// before. Only pass words the puzzle accepted.
+    pub fn save_words<S: AsRef<str>>(
+        &self,
+        date: NaiveDate,
+        words: &[S],
+    ) -> Result<(), ArchiveError> {
+        let path = self.words_path_for(date);
+        let mut contents = String::new();
+        for word in words {
+            contents.push_str(word.as_ref());
+            contents.push('\n');
+        }
+
+        std::fs::write(&path, contents).map_err(|e| ArchiveError::WritingWords(path, e))
+    }
+
+    /// Every word saved as found on any day.
+    pub fn accepted_words(&self) -> Result<Dictionary, ArchiveError> {
+        let entries =
+            std::fs::read_dir(&self.dir).map_err(|e| ArchiveError::Listing(self.dir.clone(), e))?;
+
+        let mut words = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| ArchiveError::Listing(self.dir.clone(), e))?
+                .path();
+            let is_words = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(".words.txt"));
+            if !is_words {
+                continue;
+            }
+
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| ArchiveError::ReadingWords(path.clone(), e))?;
+            words.extend(contents.lines().map(str::to_string));
+        }
+
+        Ok(Dictionary::from_words(words))
+    }
+
+    /// Every date with a saved grid. Other files in the directory are
+    /// ignored.
+    pub fn dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        self.dated_files(".json")
+    }
+
+    /// Dates of the files named `YYYY-MM-DD{suffix}`, e.g. `.words.txt`.
+    pub fn dated_files(&self, suffix: &str) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        let entries =
+            std::fs::read_dir(&self.dir).map_err(|e| ArchiveError::Listing(self.dir.clone(), e))?;
+
+        let mut dates = BTreeSet::new();
+        for entry in entries {
+            let name = entry
+                .map_err(|e| ArchiveError::Listing(self.dir.clone(), e))?
+                .file_name();
+            let date = name
+                .to_str()
+                .and_then(|name| name.strip_suffix(suffix))
+                .and_then(|date| date.parse().ok());
+            if let Some(date) = date {
+                dates.insert(date);
+            }
+        }
+
+        Ok(dates)
+    }
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing"),
+            Self::LowQuality(score) => write!(f, "quality {score}/100"),
+        }
+    }
+}
+
+impl Store for Archive {
+    fn load_grid(&self, date: NaiveDate) -> Result<Option<GridDocument>, ArchiveError> {
+        self.load(date)
+    }
+
+    fn save_grid(&self, doc: &GridDocument) -> Result<(), ArchiveError> {
+        self.save(doc)
+    }
+
+    fn list_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        self.dates()
+    }
+
+    fn location(&self, date: NaiveDate) -> String {
+        self.path_for(date).display().to_string()
+    }
+
+    fn load_finds(&self, date: NaiveDate) -> Result<DayFinds, ArchiveError> {
+        let path = self.finds_path_for(date);
+        match std::fs::read(&path) {
+            Ok(contents) => {
+                serde_json::from_slice(&contents).map_err(|e| ArchiveError::Reading(path, e.into()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DayFinds::default()),
+            Err(e) => Err(ArchiveError::Reading(path, e.into())),
+        }
+    }
+
+    fn save_finds(&self, date: NaiveDate, finds: &DayFinds) -> Result<(), ArchiveError> {
+        let path = self.finds_path_for(date);
+        serde_json::to_vec_pretty(finds)
+            .map_err(FormatError::from)
+            .and_then(|contents| std::fs::write(&path, contents).map_err(FormatError::from))
+            .map_err(|e| ArchiveError::Writing(path, e))
+    }
+
+    fn list_finds_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        self.dated_files(".finds.json")
+    }
+
+    fn save_words(&self, date: NaiveDate, words: &[String]) -> Result<(), ArchiveError> {
+        Archive::save_words(self, date, words)
+    }
+
+    fn accepted_words(&self) -> Result<Dictionary, ArchiveError> {
+        Archive::accepted_words(self)
+    }
+
+    // One JSON object per line, appended to, so a record is never lost to
+    // a run that stops partway through writing another
+    fn record_run(&self, run: &RunRecord) -> Result<(), ArchiveError> {
+        let path = self.runs_path();
+        let mut line = serde_json::to_vec(run)
+            .map_err(FormatError::from)
+            .map_err(|e| ArchiveError::Writing(path.clone(), e))?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&line))
+            .map_err(|e| ArchiveError::Writing(path, e.into()))
+    }
+
+    fn runs(&self, date: NaiveDate) -> Result<Vec<RunRecord>, ArchiveError> {
+        let path = self.runs_path();
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(ArchiveError::Reading(path, e.into())),
+        };
+
+        let mut runs = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| ArchiveError::Reading(path.clone(), e.into()))?;
+            let run = serde_json::from_str::<RunRecord>(&line)
+                .map_err(|e| ArchiveError::Reading(path.clone(), e.into()))?;
+            if run.date == date {
+                runs.push(run);
+            }
+        }
+
+        Ok(runs)
+    }
+
+    // Hashed as saved, so the grid needn't be parsed
+    fn content_hash(&self, date: NaiveDate) -> Result<Option<String>, ArchiveError> {
+        let path = self.path_for(date);
+        match std::fs::read(&path) {
+            Ok(contents) => Ok(Some(sha256_hex(&contents))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ArchiveError::Reading(path, e.into())),
+        }
+    }
+}
+
+/// A [`Store`] that only keeps grids for as long as the process runs, for
+/// runs with nowhere to save them that still want the days they've already
+/// read, e.g. a backfill comparing each day with the ones before.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    grids: Mutex<BTreeMap<NaiveDate, GridDocument>>,
+    finds: Mutex<BTreeMap<NaiveDate, DayFinds>>,
+    words: Mutex<BTreeMap<NaiveDate, Vec<String>>>,
+    runs: Mutex<Vec<RunRecord>>,
+}
+
+impl Store for MemoryStore {
+    fn load_grid(&self, date: NaiveDate) -> Result<Option<GridDocument>, ArchiveError> {
+        Ok(self.grids.lock().unwrap().get(&date).cloned())
+    }
+
+    fn save_grid(&self, doc: &GridDocument) -> Result<(), ArchiveError> {
+        self.grids.lock().unwrap().insert(doc.date, doc.clone());
+        Ok(())
+    }
+
+    fn list_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        Ok(self.grids.lock().unwrap().keys().copied().collect())
+    }
+
+    fn location(&self, date: NaiveDate) -> String {
+        format!("memory:{date}")
+    }
+
+    fn load_finds(&self, date: NaiveDate) -> Result<DayFinds, ArchiveError> {
+        Ok(self
+            .finds
+            .lock()
+            .unwrap()
+            .get(&date)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn save_finds(&self, date: NaiveDate, finds: &DayFinds) -> Result<(), ArchiveError> {
+        self.finds.lock().unwrap().insert(date, finds.clone());
+        Ok(())
+    }
+
+    fn list_finds_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        Ok(self.finds.lock().unwrap().keys().copied().collect())
+    }
+
+    fn save_words(&self, date: NaiveDate, words: &[String]) -> Result<(), ArchiveError> {
+        self.words.lock().unwrap().insert(date, words.to_vec());
+        Ok(())
+    }
+
+    fn accepted_words(&self) -> Result<Dictionary, ArchiveError> {
+        Ok(Dictionary::from_words(
+            self.words.lock().unwrap().values().flatten(),
+        ))
+    }
+
+    fn record_run(&self, run: &RunRecord) -> Result<(), ArchiveError> {
+        self.runs.lock().unwrap().push(run.clone());
+        Ok(())
+    }
+
+    fn runs(&self, date: NaiveDate) -> Result<Vec<RunRecord>, ArchiveError> {
+        let runs = self.runs.lock().unwrap();
+        Ok(runs
+            .iter()
+            .filter(|run| run.date == date)
+            .cloned()
+            .collect())
+    }
+
+    fn persistent(&self) -> bool {
+        false
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
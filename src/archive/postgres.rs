@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use postgres::{Client, GenericClient, IsolationLevel, NoTls};
+
+use super::{ArchiveError, Database, Migration, MigrationStatus, RunRecord, Store};
+use crate::dictionary::Dictionary;
+use crate::document::GridDocument;
+use crate::formats::FormatError;
+use crate::team::DayFinds;
+
+// Databases from before migrations were recorded already have the first's
+// tables, hence IF NOT EXISTS
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "create the grids, finds, words and runs tables",
+    sql: "
+        CREATE TABLE IF NOT EXISTS grids (date DATE PRIMARY KEY, document JSONB NOT NULL);
+        CREATE TABLE IF NOT EXISTS finds (date DATE PRIMARY KEY, finds JSONB NOT NULL);
+        CREATE TABLE IF NOT EXISTS words (date DATE NOT NULL, word TEXT NOT NULL);
+        CREATE INDEX IF NOT EXISTS words_by_date ON words (date);
+        CREATE TABLE IF NOT EXISTS runs (
+            date DATE NOT NULL,
+            finished TIMESTAMPTZ NOT NULL,
+            error TEXT
+        );
+        CREATE INDEX IF NOT EXISTS runs_by_date ON runs (date);
+    ",
+}];
+
+// The tables a backup holds, and their columns. Backups are written the
+// way pg_dump writes data, so psql can load them into empty tables too.
+const BACKED_UP: &[(&str, &str)] = &[
+    ("grids", "date, document"),
+    ("finds", "date, finds"),
+    ("words", "date, word"),
+    ("runs", "date, finished, error"),
+];
+
+const BACKUP_HEADER: &str = "-- gridder backup, at schema migration ";
+
+const MIGRATIONS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        applied TIMESTAMPTZ NOT NULL
+    );
+";
+
+/// Saved grids in a Postgres database, a row per day, with the grid as its
+/// JSON document. The tables are migrated when it's connected to.
+pub struct PostgresStore {
+    // Only taken when dropped, which blocks too
+    client: Mutex<Option<Client>>,
+}
+
+// The client blocks on a runtime of its own, which can't be started from
+// a thread already running async code, so it's handed a thread to block
+fn blocking<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(_) => tokio::task::block_in_place(f),
+        Err(_) => f(),
+    }
+}
+
+impl PostgresStore {
+    /// Connects to the database at `url`, e.g.
+    /// `postgres://gridder@localhost/gridder`, and applies any migrations
+    /// it's missing.
+    pub fn connect(url: &str) -> Result<Self, ArchiveError> {
+        let store = Self::connect_unmigrated(url)?;
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Connects to the database at `url` as it is, for `gridder db`.
+    pub fn connect_unmigrated(url: &str) -> Result<Self, ArchiveError> {
+        let client = blocking(|| Client::connect(url, NoTls))?;
+        Ok(Self {
+            client: Mutex::new(Some(client)),
+        })
+    }
+
+    fn with_client<T>(
+        &self,
+        f: impl FnOnce(&mut Client) -> Result<T, postgres::Error>,
+    ) -> Result<T, ArchiveError> {
+        blocking(|| {
+            let mut client = self.client.lock().unwrap();
+            Ok(f(client.as_mut().expect("client already closed"))?)
+        })
+    }
+}
+
+impl Drop for PostgresStore {
+    fn drop(&mut self) {
+        let client = self.client.get_mut().unwrap().take();
+        blocking(|| drop(client));
+    }
+}
+
+// The URL may have a password in it
+impl fmt::Debug for PostgresStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostgresStore").finish_non_exhaustive()
+    }
+}
+
+impl Database for PostgresStore {
+    fn migrations(&self) -> Result<Vec<MigrationStatus>, ArchiveError> {
+        let applied = self.with_client(|client| {
+            let recorded = client
+                .query_one("SELECT to_regclass('schema_migrations') IS NOT NULL", &[])?
+                .get(0);
+            match recorded {
+                true => applied(client),
+                false => Ok(BTreeMap::new()),
+            }
+        })?;
+        super::migration_statuses(MIGRATIONS, applied)
+    }
+
+    fn migrate(&self) -> Result<Vec<Migration>, ArchiveError> {
+        blocking(|| {
+            let mut client = self.client.lock().unwrap();
+            let client = client.as_mut().expect("client already closed");
+            let mut transaction = client.transaction()?;
+            transaction.batch_execute(MIGRATIONS_TABLE)?;
+            // So two gridders can't both apply one
+            transaction.batch_execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE")?;
+            let pending = super::migration_statuses(MIGRATIONS, applied(&mut transaction)?)?
+                .into_iter()
+                .filter(|status| status.applied.is_none())
+                .map(|status| status.migration)
+                .collect::<Vec<_>>();
+            for migration in &pending {
+                transaction.batch_execute(migration.sql)?;
+                transaction.execute(
+                    "INSERT INTO schema_migrations (version, description, applied)
+                        VALUES ($1, $2, now())",
+                    &[&(migration.version as i32), &migration.description],
+                )?;
+            }
+            transaction.commit()?;
+            Ok(pending)
+        })
+    }
+
+    fn backup(&self, to: &Path) -> Result<(), ArchiveError> {
+        let writing = |e| ArchiveError::WritingBackup(to.to_path_buf(), e);
+        blocking(|| {
+            let mut client = self.client.lock().unwrap();
+            let client = client.as_mut().expect("client already closed");
+            // Every table as of the same moment
+            let mut transaction = client
+                .build_transaction()
+                .isolation_level(IsolationLevel::RepeatableRead)
+                .read_only(true)
+                .start()?;
+            let version = applied(&mut transaction)?
+                .last_key_value()
+                .map_or(0, |(version, _)| *version);
+            super::write_backup(to, |out| {
+                writeln!(out, "{BACKUP_HEADER}{version}").map_err(writing)?;
+                for (table, columns) in BACKED_UP {
+                    writeln!(out, "COPY {table} ({columns}) FROM stdin;").map_err(writing)?;
+                    let mut rows =
+                        transaction.copy_out(&format!("COPY {table} ({columns}) TO STDOUT"))?;
+                    std::io::copy(&mut rows, out).map_err(writing)?;
+                    writeln!(out, "\\.").map_err(writing)?;
+                }
+                Ok(())
+            })?;
+            transaction.commit()?;
+            Ok(())
+        })
+    }
+
+    fn restore(&self, from: &Path) -> Result<(), ArchiveError> {
+        self.migrate()?;
+        let reading = |e| ArchiveError::ReadingBackup(from.to_path_buf(), e);
+        let not_backup = || ArchiveError::NotBackup(from.to_path_buf());
+        let mut backup = super::read_backup(from)?;
+        let mut line = String::new();
+        backup.read_line(&mut line).map_err(reading)?;
+        let found = line
+            .trim_end()
+            .strip_prefix(BACKUP_HEADER)
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(not_backup)?;
+        let known = MIGRATIONS.last().map_or(0, |migration| migration.version);
+        if found > known {
+            return Err(ArchiveError::NewerSchema { found, known });
+        }
+
+        blocking(|| {
+            let mut client = self.client.lock().unwrap();
+            let client = client.as_mut().expect("client already closed");
+            let mut transaction = client.transaction()?;
+            transaction.batch_execute("TRUNCATE grids, finds, words, runs")?;
+            loop {
+                line.clear();
+                if backup.read_line(&mut line).map_err(reading)? == 0 {
+                    break;
+                }
+                // Only the statements a backup is written with are run
+                let copy = BACKED_UP
+                    .iter()
+                    .map(|(table, columns)| format!("COPY {table} ({columns})"))
+                    .find(|copy| line.trim_end() == format!("{copy} FROM stdin;"))
+                    .ok_or_else(not_backup)?;
+                let mut rows = transaction.copy_in(&format!("{copy} FROM STDIN"))?;
+                loop {
+                    line.clear();
+                    if backup.read_line(&mut line).map_err(reading)? == 0 {
+                        return Err(not_backup());
+                    }
+                    if line.trim_end() == "\\." {
+                        break;
+                    }
+                    rows.write_all(line.as_bytes()).map_err(reading)?;
+                }
+                rows.finish()?;
+            }
+            transaction.commit()?;
+            Ok(())
+        })
+    }
+
+    // Postgres looks after its own integrity, so there's only vacuuming
+    fn check(&self, vacuum: bool) -> Result<(), ArchiveError> {
+        if vacuum {
+            self.with_client(|client| client.batch_execute("VACUUM ANALYZE"))?;
+        }
+        Ok(())
+    }
+}
+
+fn applied(
+    client: &mut impl GenericClient,
+) -> Result<BTreeMap<u32, DateTime<Utc>>, postgres::Error> {
+    Ok(client
+        .query("SELECT version, applied FROM schema_migrations", &[])?
+        .iter()
+        .map(|row| (row.get::<_, i32>(0) as u32, row.get(1)))
+        .collect())
+}
+
+impl Store for PostgresStore {
+    fn load_grid(&self, date: NaiveDate) -> Result<Option<GridDocument>, ArchiveError> {
+        let row = self.with_client(|client| {
+            client.query_opt("SELECT document FROM grids WHERE date = $1", &[&date])
+        })?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let reading = |e| ArchiveError::ReadingRow(format!("the grid for {date}"), e);
+        let doc =
+            serde_json::from_value::<GridDocument>(row.get(0)).map_err(|e| reading(e.into()))?;
+        doc.check_version().map_err(|e| reading(e.into()))?;
+        Ok(Some(doc))
+    }
+
+    fn save_grid(&self, doc: &GridDocument) -> Result<(), ArchiveError> {
+        // Serializing a document can't fail
+        let document = serde_json::to_value(doc).unwrap_or_default();
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO grids (date, document) VALUES ($1, $2)
+                    ON CONFLICT (date) DO UPDATE SET document = excluded.document",
+                &[&doc.date, &document],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn list_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        self.with_client(|client| dates(client, "SELECT date FROM grids"))
+    }
+
+    fn location(&self, date: NaiveDate) -> String {
+        format!("postgres:{date}")
+    }
+
+    fn load_finds(&self, date: NaiveDate) -> Result<DayFinds, ArchiveError> {
+        let row = self.with_client(|client| {
+            client.query_opt("SELECT finds FROM finds WHERE date = $1", &[&date])
+        })?;
+
+        match row {
+            Some(row) => serde_json::from_value(row.get(0)).map_err(|e| {
+                ArchiveError::ReadingRow(format!("the finds for {date}"), FormatError::from(e))
+            }),
+            None => Ok(DayFinds::default()),
+        }
+    }
+
+    fn save_finds(&self, date: NaiveDate, finds: &DayFinds) -> Result<(), ArchiveError> {
+        let finds = serde_json::to_value(finds).unwrap_or_default();
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO finds (date, finds) VALUES ($1, $2)
+                    ON CONFLICT (date) DO UPDATE SET finds = excluded.finds",
+                &[&date, &finds],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn list_finds_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        self.with_client(|client| dates(client, "SELECT date FROM finds"))
+    }
+
+    fn save_words(&self, date: NaiveDate, words: &[String]) -> Result<(), ArchiveError> {
+        self.with_client(|client| {
+            let mut transaction = client.transaction()?;
+            transaction.execute("DELETE FROM words WHERE date = $1", &[&date])?;
+            for word in words {
+                transaction.execute(
+                    "INSERT INTO words (date, word) VALUES ($1, $2)",
+                    &[&date, word],
+                )?;
+            }
+            transaction.commit()
+        })
+    }
+
+    fn accepted_words(&self) -> Result<Dictionary, ArchiveError> {
+        let rows = self.with_client(|client| client.query("SELECT word FROM words", &[]))?;
+        Ok(Dictionary::from_words(
+            rows.iter().map(|row| row.get::<_, String>(0)),
+        ))
+    }
+
+    fn record_run(&self, run: &RunRecord) -> Result<(), ArchiveError> {
+        self.with_client(|client| {
+            client.execute(
+                "INSERT INTO runs (date, finished, error) VALUES ($1, $2, $3)",
+                &[&run.date, &run.finished, &run.error],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn runs(&self, date: NaiveDate) -> Result<Vec<RunRecord>, ArchiveError> {
+        let rows = self.with_client(|client| {
+            client.query(
+                "SELECT finished, error FROM runs WHERE date = $1 ORDER BY finished",
+                &[&date],
+            )
+        })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RunRecord {
+                date,
+                finished: row.get::<_, DateTime<Utc>>(0),
+                error: row.get(1),
+            })
+            .collect())
+    }
+}
+
+fn dates(client: &mut Client, query: &str) -> Result<BTreeSet<NaiveDate>, postgres::Error> {
+    Ok(client
+        .query(query, &[])?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
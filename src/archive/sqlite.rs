@@ -0,0 +1,460 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+
+use super::{ArchiveError, Database, Migration, MigrationStatus, RunRecord, Store};
+use crate::dictionary::Dictionary;
+use crate::document::GridDocument;
+use crate::formats::{self, FormatError};
+use crate::team::DayFinds;
+
+// Databases from before migrations were recorded already have the first's
+// tables, hence IF NOT EXISTS
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "create the grids, finds, words and runs tables",
+    sql: "
+        CREATE TABLE IF NOT EXISTS grids (date TEXT PRIMARY KEY, document TEXT NOT NULL);
+        CREATE TABLE IF NOT EXISTS finds (date TEXT PRIMARY KEY, finds TEXT NOT NULL);
+        CREATE TABLE IF NOT EXISTS words (date TEXT NOT NULL, word TEXT NOT NULL);
+        CREATE INDEX IF NOT EXISTS words_by_date ON words (date);
+        CREATE TABLE IF NOT EXISTS runs (date TEXT NOT NULL, finished TEXT NOT NULL, error TEXT);
+        CREATE INDEX IF NOT EXISTS runs_by_date ON runs (date);
+    ",
+}];
+
+// The tables a backup restores, leaving schema_migrations as migrating
+// made it
+const BACKED_UP: &[&str] = &["grids", "finds", "words", "runs"];
+
+const MIGRATIONS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        applied TEXT NOT NULL
+    );
+";
+
+/// Saved grids in a SQLite database file, a row per day, with the grid as
+/// its JSON document. The tables are migrated when it's opened.
+#[derive(Debug)]
+pub struct SqliteStore {
+    path: PathBuf,
+    // Connections can be sent between threads, but not shared
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it if need be, and applies
+    /// any migrations it's missing.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        let store = Self::open_unmigrated(path)?;
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Opens the database at `path` as it is, for `gridder db`.
+    pub fn open_unmigrated<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            conn: Mutex::new(Connection::open(&path)?),
+        })
+    }
+}
+
+impl Database for SqliteStore {
+    fn migrations(&self) -> Result<Vec<MigrationStatus>, ArchiveError> {
+        let conn = self.conn.lock().unwrap();
+        let recorded = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?;
+        let applied = match recorded {
+            Some(()) => applied(&conn)?,
+            None => BTreeMap::new(),
+        };
+        super::migration_statuses(MIGRATIONS, applied)
+    }
+
+    fn migrate(&self) -> Result<Vec<Migration>, ArchiveError> {
+        let mut conn = self.conn.lock().unwrap();
+        // Taking the write lock first, so two gridders can't both apply one
+        let transaction = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        transaction.execute_batch(MIGRATIONS_TABLE)?;
+        let pending = super::migration_statuses(MIGRATIONS, applied(&transaction)?)?
+            .into_iter()
+            .filter(|status| status.applied.is_none())
+            .map(|status| status.migration)
+            .collect::<Vec<_>>();
+        for migration in &pending {
+            transaction.execute_batch(migration.sql)?;
+            transaction.execute(
+                "INSERT INTO schema_migrations (version, description, applied)
+                    VALUES (?1, ?2, ?3)",
+                params![migration.version, migration.description, Utc::now()],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(pending)
+    }
+
+    fn backup(&self, to: &Path) -> Result<(), ArchiveError> {
+        // VACUUM INTO writes a compacted copy, consistent even while others
+        // are writing, which is checked before it replaces any older backup
+        let mut copy = to.as_os_str().to_owned();
+        copy.push(".partial");
+        let copy = PathBuf::from(copy);
+        let _ = std::fs::remove_file(&copy);
+        let written = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("VACUUM INTO ?1", [copy.to_string_lossy()])
+            .map_err(ArchiveError::from)
+            .and_then(|_| check_integrity(&Connection::open(&copy)?, to))
+            .and_then(|()| {
+                super::write_backup(to, |out| {
+                    let writing = |e| ArchiveError::WritingBackup(to.to_path_buf(), e);
+                    let mut copied = File::open(&copy).map_err(writing)?;
+                    std::io::copy(&mut copied, out).map_err(writing)?;
+                    Ok(())
+                })
+            });
+        let _ = std::fs::remove_file(&copy);
+        written
+    }
+
+    fn restore(&self, from: &Path) -> Result<(), ArchiveError> {
+        self.migrate()?;
+        // Unpacked to a scratch copy, which is checked and migrated before
+        // anything is replaced
+        let copy = std::env::temp_dir().join(format!(
+            "gridder-restore-{}-{}",
+            std::process::id(),
+            from.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        let restored = unpack(from, &copy).and_then(|()| self.restore_from(&copy));
+        let _ = std::fs::remove_file(&copy);
+        restored
+    }
+
+    fn check(&self, vacuum: bool) -> Result<(), ArchiveError> {
+        let conn = self.conn.lock().unwrap();
+        check_integrity(&conn, &self.path)?;
+        if vacuum {
+            conn.execute_batch("VACUUM")?;
+        }
+        Ok(())
+    }
+}
+
+impl SqliteStore {
+    fn restore_from(&self, copy: &Path) -> Result<(), ArchiveError> {
+        let backup = SqliteStore::open_unmigrated(copy)?;
+        check_integrity(&backup.conn.lock().unwrap(), copy)?;
+        backup.migrate()?;
+        drop(backup);
+
+        let mut conn = self.conn.lock().unwrap();
+        conn.execute("ATTACH DATABASE ?1 AS backup", [copy.to_string_lossy()])?;
+        let copied = conn.transaction().and_then(|transaction| {
+            for table in BACKED_UP {
+                transaction.execute_batch(&format!(
+                    "DELETE FROM main.{table};
+                        INSERT INTO main.{table} SELECT * FROM backup.{table};"
+                ))?;
+            }
+            transaction.commit()
+        });
+        conn.execute("DETACH DATABASE backup", [])?;
+        Ok(copied?)
+    }
+}
+
+// Copies the backup at `from`, gunzipping it if need be, to `to`
+fn unpack(from: &Path, to: &Path) -> Result<(), ArchiveError> {
+    let reading = |e| ArchiveError::ReadingBackup(from.to_path_buf(), e);
+    let mut backup = super::read_backup(from)?;
+    let mut copy = File::create(to).map_err(reading)?;
+    std::io::copy(&mut backup, &mut copy).map_err(reading)?;
+    Ok(())
+}
+
+// PRAGMA integrity_check gives back a row per problem, or just "ok"
+fn check_integrity(conn: &Connection, path: &Path) -> Result<(), ArchiveError> {
+    let mut statement = conn.prepare("PRAGMA integrity_check")?;
+    let problems = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .filter(|row| !matches!(row.as_deref(), Ok("ok")))
+        .collect::<Result<Vec<_>, _>>()?;
+    match problems.is_empty() {
+        true => Ok(()),
+        false => Err(ArchiveError::FailedCheck(
+            path.to_path_buf(),
+            problems.join("; "),
+        )),
+    }
+}
+
+fn applied(conn: &Connection) -> Result<BTreeMap<u32, DateTime<Utc>>, ArchiveError> {
+    let mut statement = conn.prepare("SELECT version, applied FROM schema_migrations")?;
+    let applied = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(applied)
+}
+
+impl Store for SqliteStore {
+    fn load_grid(&self, date: NaiveDate) -> Result<Option<GridDocument>, ArchiveError> {
+        let document = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT document FROM grids WHERE date = ?1",
+                [date],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+
+        document
+            .map(|document| formats::read_json(document.as_bytes()))
+            .transpose()
+            .map_err(|e| ArchiveError::ReadingRow(format!("the grid for {date}"), e))
+    }
+
+    fn save_grid(&self, doc: &GridDocument) -> Result<(), ArchiveError> {
+        // Serializing a document can't fail
+        let document = serde_json::to_string(doc).unwrap_or_default();
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO grids (date, document) VALUES (?1, ?2)
+                ON CONFLICT (date) DO UPDATE SET document = excluded.document",
+            params![doc.date, document],
+        )?;
+        Ok(())
+    }
+
+    fn list_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        dates(&self.conn.lock().unwrap(), "SELECT date FROM grids")
+    }
+
+    fn location(&self, date: NaiveDate) -> String {
+        format!("sqlite://{}#{date}", self.path.display())
+    }
+
+    fn load_finds(&self, date: NaiveDate) -> Result<DayFinds, ArchiveError> {
+        let finds = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT finds FROM finds WHERE date = ?1", [date], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()?;
+
+        match finds {
+            Some(finds) => serde_json::from_str(&finds).map_err(|e| {
+                ArchiveError::ReadingRow(format!("the finds for {date}"), FormatError::from(e))
+            }),
+            None => Ok(DayFinds::default()),
+        }
+    }
+
+    fn save_finds(&self, date: NaiveDate, finds: &DayFinds) -> Result<(), ArchiveError> {
+        let finds = serde_json::to_string(finds).unwrap_or_default();
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO finds (date, finds) VALUES (?1, ?2)
+                ON CONFLICT (date) DO UPDATE SET finds = excluded.finds",
+            params![date, finds],
+        )?;
+        Ok(())
+    }
+
+    fn list_finds_dates(&self) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+        dates(&self.conn.lock().unwrap(), "SELECT date FROM finds")
+    }
+
+    fn save_words(&self, date: NaiveDate, words: &[String]) -> Result<(), ArchiveError> {
+        let mut conn = self.conn.lock().unwrap();
+        let transaction = conn.transaction()?;
+        transaction.execute("DELETE FROM words WHERE date = ?1", [date])?;
+        {
+            let mut insert =
+                transaction.prepare("INSERT INTO words (date, word) VALUES (?1, ?2)")?;
+            for word in words {
+                insert.execute(params![date, word])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn accepted_words(&self) -> Result<Dictionary, ArchiveError> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare("SELECT word FROM words")?;
+        let words = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Dictionary::from_words(words))
+    }
+
+    fn record_run(&self, run: &RunRecord) -> Result<(), ArchiveError> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO runs (date, finished, error) VALUES (?1, ?2, ?3)",
+            params![run.date, run.finished, run.error],
+        )?;
+        Ok(())
+    }
+
+    fn runs(&self, date: NaiveDate) -> Result<Vec<RunRecord>, ArchiveError> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement =
+            conn.prepare("SELECT finished, error FROM runs WHERE date = ?1 ORDER BY rowid")?;
+        let runs = statement
+            .query_map([date], |row| {
+                Ok(RunRecord {
+                    date,
+                    finished: row.get::<_, DateTime<Utc>>(0)?,
+                    error: row.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(runs)
+    }
+}
+
+fn dates(conn: &Connection, query: &str) -> Result<BTreeSet<NaiveDate>, ArchiveError> {
+    let mut statement = conn.prepare(query)?;
+    let dates = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(dates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn grid(date: NaiveDate) -> GridDocument {
+        let lengths = LengthInfo::from([(('A', WordLength(4)), WordCount(2))]);
+        let pairs = PairInfo::from([(('A', 'B'), WordCount(2))]);
+        GridDocument::new(date, &pairs, &lengths)
+    }
+
+    #[test]
+    fn grids_and_runs_round_trip() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert_eq!(store.load_grid(date).unwrap(), None);
+
+        store.save_grid(&grid(date)).unwrap();
+        let mut changed = grid(date);
+        changed.quality = Some(40);
+        store.save_grid(&changed).unwrap();
+        assert_eq!(store.load_grid(date).unwrap(), Some(changed));
+        assert_eq!(store.list_dates().unwrap(), BTreeSet::from([date]));
+
+        let run = RunRecord {
+            date,
+            finished: DateTime::from_timestamp(1_714_550_400, 0).unwrap(),
+            error: Some("timed out".to_string()),
+        };
+        store.record_run(&run).unwrap();
+        assert_eq!(store.runs(date).unwrap(), [run]);
+        assert_eq!(store.runs(date.succ_opt().unwrap()).unwrap(), []);
+    }
+
+    #[test]
+    fn saving_words_again_replaces_them() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let words = |words: &[&str]| {
+            words
+                .iter()
+                .map(|word| word.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        store.save_words(date, &words(&["ACNE", "ANCON"])).unwrap();
+        store.save_words(date, &words(&["CANON"])).unwrap();
+        store
+            .save_words(date.succ_opt().unwrap(), &words(&["NONCE"]))
+            .unwrap();
+        let accepted = store.accepted_words().unwrap();
+        assert_eq!(
+            accepted.fitting(&['A', 'C', 'E', 'N', 'O'], None),
+            ["CANON", "NONCE"]
+        );
+    }
+
+    #[test]
+    fn migrations_are_applied_once() {
+        let store = SqliteStore::open_unmigrated(":memory:").unwrap();
+        let statuses = store.migrations().unwrap();
+        assert_eq!(statuses.len(), MIGRATIONS.len());
+        assert!(statuses.iter().all(|status| status.applied.is_none()));
+
+        assert_eq!(store.migrate().unwrap(), MIGRATIONS);
+        assert!(store
+            .migrations()
+            .unwrap()
+            .iter()
+            .all(|status| status.applied.is_some()));
+        assert_eq!(store.migrate().unwrap(), []);
+    }
+
+    #[test]
+    fn backups_restore_the_saved_grids() {
+        let dir = std::env::temp_dir().join(format!("gridder-sqlite-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::open(":memory:").unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        store.save_grid(&grid(date)).unwrap();
+        store.save_words(date, &["CANON".to_string()]).unwrap();
+
+        for name in ["grids.sqlite", "grids.sqlite.gz"] {
+            let path = dir.join(name);
+            store.backup(&path).unwrap();
+            let restored = SqliteStore::open(":memory:").unwrap();
+            restored.save_grid(&grid(date.succ_opt().unwrap())).unwrap();
+            restored.restore(&path).unwrap();
+            assert_eq!(restored.list_dates().unwrap(), BTreeSet::from([date]));
+            assert_eq!(restored.load_grid(date).unwrap(), Some(grid(date)));
+            assert_eq!(restored.accepted_words().unwrap().len(), 1);
+        }
+        store.check(true).unwrap();
+
+        let not_backup = dir.join("notes.txt");
+        std::fs::write(&not_backup, "not a database").unwrap();
+        assert!(store.restore(&not_backup).is_err());
+        assert_eq!(store.list_dates().unwrap(), BTreeSet::from([date]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn newer_databases_are_refused() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO schema_migrations (version, description, applied)
+                    VALUES (99, 'from the future', '2030-01-01T00:00:00Z')",
+                [],
+            )
+            .unwrap();
+        assert!(matches!(
+            store.migrate(),
+            Err(ArchiveError::NewerSchema { found: 99, .. })
+        ));
+    }
+}
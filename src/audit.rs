@@ -0,0 +1,187 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    #[error("failed to open audit log {0}: {1}")]
+    Opening(PathBuf, std::io::Error),
+    #[error("failed to add to audit log {0}: {1}")]
+    Writing(PathBuf, std::io::Error),
+}
+
+/// A change made somewhere outside this machine, e.g. a tab created or a
+/// message posted, as it goes in an [`AuditLog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Write {
+    /// What was done, e.g. `update values`.
+    pub action: String,
+    /// What it was done to, e.g. a range or a date.
+    pub subject: String,
+    /// The SHA-256 of what was sent, in hex.
+    pub digest: String,
+}
+
+impl Write {
+    pub fn new(action: &str, subject: &str, payload: &[u8]) -> Self {
+        Self {
+            action: action.to_string(),
+            subject: subject.to_string(),
+            digest: Sha256::digest(payload)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+        }
+    }
+
+    /// A write whose payload is `body`, sent as JSON.
+    pub fn json(action: &str, subject: &str, body: &impl Serialize) -> Self {
+        // Serializing API requests can't fail
+        Self::new(
+            action,
+            subject,
+            &serde_json::to_vec(body).unwrap_or_default(),
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    time: DateTime<Utc>,
+    target: &'a str,
+    action: &'a str,
+    subject: &'a str,
+    digest: &'a str,
+    // "ok", or the error; a failed request may still have been acted on
+    outcome: String,
+}
+
+/// An append-only record of every change made outside this machine, to
+/// piece together what gridder did when something looks wrong. Each line
+/// is a JSON object with the `time`, the `target` (e.g. `sheets:ID` or a
+/// notifier), the `action`, its `subject`, the `digest` of what was sent,
+/// and its `outcome`.
+///
+/// Changes that can't be recorded have still been made, so they don't
+/// stop anything; the first such failure is kept for [`AuditLog::check`].
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    path: PathBuf,
+    file: Mutex<File>,
+    failure: Mutex<Option<std::io::Error>>,
+}
+
+impl AuditLog {
+    /// Opens the log at `path` to add to, creating it if need be.
+    pub fn open(path: &Path) -> Result<Self, AuditError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| AuditError::Opening(path.to_path_buf(), e))?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                path: path.to_path_buf(),
+                file: Mutex::new(file),
+                failure: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Records `write`, made to `target`, and how it went.
+    pub fn record<E: fmt::Display>(&self, target: &str, write: &Write, outcome: Result<(), E>) {
+        let entry = Entry {
+            time: Utc::now(),
+            target,
+            action: &write.action,
+            subject: &write.subject,
+            digest: &write.digest,
+            outcome: match outcome {
+                Ok(()) => "ok".to_string(),
+                Err(e) => e.to_string(),
+            },
+        };
+        // Serializing an entry can't fail
+        let mut line = serde_json::to_vec(&entry).unwrap_or_default();
+        line.push(b'\n');
+        // One write per line, so lines from writes made at the same time
+        // don't interleave
+        let written = self.inner.file.lock().unwrap().write_all(&line);
+        if let Err(e) = written {
+            self.inner.failure.lock().unwrap().get_or_insert(e);
+        }
+    }
+
+    /// Fails if anything couldn't be recorded.
+    pub fn check(&self) -> Result<(), AuditError> {
+        match self.inner.failure.lock().unwrap().take() {
+            Some(e) => Err(AuditError::Writing(self.inner.path.clone(), e)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_are_known_by_their_digest() {
+        let write = Write::new("post grid", "2024-05-01", b"abc");
+        assert_eq!(
+            write.digest,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let json = Write::json("post grid", "2024-05-01", &"abc");
+        assert_eq!(json, Write::new("post grid", "2024-05-01", b"\"abc\""));
+    }
+
+    #[test]
+    fn entries_are_added_a_line_each() {
+        let path = std::env::temp_dir().join(format!("gridder-audit-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let write = Write::new("update values", "2024-05-01!B3:D9", b"[]");
+        AuditLog::open(&path)
+            .unwrap()
+            .record("sheets:abc", &write, Ok::<_, String>(()));
+        let log = AuditLog::open(&path).unwrap();
+        log.record("telegram", &write, Err("timed out"));
+        log.check().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let entries = contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["target"], "sheets:abc");
+        assert_eq!(entries[0]["subject"], "2024-05-01!B3:D9");
+        assert_eq!(entries[0]["digest"], write.digest);
+        assert_eq!(entries[0]["outcome"], "ok");
+        assert_eq!(entries[1]["outcome"], "timed out");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn failures_to_record_are_kept_for_later() {
+        let log = AuditLog::open(Path::new("/dev/full")).unwrap();
+        let write = Write::new("post grid", "2024-05-01", b"");
+        log.record("telegram", &write, Ok::<_, String>(()));
+        assert!(matches!(log.check(), Err(AuditError::Writing(..))));
+        // Only reported the once
+        log.check().unwrap();
+    }
+}
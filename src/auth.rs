@@ -0,0 +1,92 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+
+/// The credentials a server accepts in an `Authorization` header, or gRPC's
+/// `authorization` metadata: `Bearer TOKEN`, or HTTP basic auth.
+///
+/// With none, there's nothing to check, and anyone is let in.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    // Whole header values, e.g. "Bearer TOKEN"
+    accepted: Vec<String>,
+    basic: bool,
+}
+
+impl Credentials {
+    /// Accepts callers sending `Bearer TOKEN`.
+    pub fn bearer_token(mut self, token: &str) -> Self {
+        self.accepted.push(format!("Bearer {token}"));
+        self
+    }
+
+    /// Accepts callers logging in with HTTP basic auth.
+    pub fn basic(mut self, username: &str, password: &str) -> Self {
+        let encoded = BASE64_STANDARD.encode(format!("{username}:{password}"));
+        self.accepted.push(format!("Basic {encoded}"));
+        self.basic = true;
+        self
+    }
+
+    /// Whether any were given.
+    pub fn is_empty(&self) -> bool {
+        self.accepted.is_empty()
+    }
+
+    /// Whether callers can log in with HTTP basic auth, e.g. to ask a
+    /// browser for a username and password.
+    pub fn has_basic(&self) -> bool {
+        self.basic
+    }
+
+    /// Whether `given`, a whole header value, is one of them.
+    pub fn accepts(&self, given: &str) -> bool {
+        // Every one is compared, so how long it takes doesn't say which
+        // came closest
+        self.accepted
+            .iter()
+            .fold(false, |matched, accepted| matched | same(accepted, given))
+    }
+}
+
+// Compares secrets in a time that doesn't depend on where they differ
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |differences, (a, b)| differences | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_credentials_given_are_accepted() {
+        let credentials = Credentials::default()
+            .bearer_token("s3cret")
+            .basic("ana", "hunter2");
+        assert!(credentials.accepts("Bearer s3cret"));
+        // ana:hunter2
+        assert!(credentials.accepts("Basic YW5hOmh1bnRlcjI="));
+
+        for given in [
+            "",
+            "Bearer",
+            "Bearer s3cre",
+            "Bearer s3cret2",
+            "bearer s3cret",
+            "s3cret",
+        ] {
+            assert!(!credentials.accepts(given), "{given:?}");
+        }
+        assert!(credentials.has_basic());
+    }
+
+    #[test]
+    fn no_credentials_accept_nothing_themselves() {
+        let credentials = Credentials::default();
+        assert!(credentials.is_empty());
+        assert!(!credentials.accepts(""));
+        assert!(!Credentials::default().bearer_token("t").has_basic());
+    }
+}
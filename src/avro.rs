@@ -0,0 +1,143 @@
+use crate::document::GridDocument;
+
+/// The Avro schema [`encode`] writes to, for consumers of the event sinks.
+/// Counts are longs, the date is days since 1970-01-01, `by_length` is
+/// keyed by the length in decimal (Avro map keys are strings), and `extra`
+/// is JSON text.
+pub const SCHEMA: &str = r#"{
+  "type": "record",
+  "name": "GridDocument",
+  "namespace": "gridder",
+  "fields": [
+    {"name": "schema_version", "type": "int"},
+    {"name": "date", "type": {"type": "int", "logicalType": "date"}},
+    {"name": "letters", "type": {"type": "array", "items": "string"}},
+    {"name": "lengths", "type": {"type": "array", "items": {
+      "type": "record",
+      "name": "LengthEntry",
+      "fields": [
+        {"name": "letter", "type": "string"},
+        {"name": "length", "type": "long"},
+        {"name": "count", "type": "long"}
+      ]
+    }}},
+    {"name": "pairs", "type": {"type": "array", "items": {
+      "type": "record",
+      "name": "PairEntry",
+      "fields": [
+        {"name": "pair", "type": "string"},
+        {"name": "count", "type": "long"}
+      ]
+    }}},
+    {"name": "hints", "type": {"type": "array", "items": "string"}},
+    {"name": "totals", "type": {
+      "type": "record",
+      "name": "Totals",
+      "fields": [
+        {"name": "words", "type": "long"},
+        {"name": "by_letter", "type": {"type": "map", "values": "long"}},
+        {"name": "by_length", "type": {"type": "map", "values": "long"}}
+      ]
+    }},
+    {"name": "quality", "type": ["null", "int"], "default": null},
+    {"name": "summary", "type": ["null", {
+      "type": "record",
+      "name": "Summary",
+      "fields": [
+        {"name": "points", "type": "long"},
+        {"name": "pangrams", "type": "long"}
+      ]
+    }], "default": null},
+    {"name": "extra", "type": "string"}
+  ]
+}"#;
+
+/// `doc` in Avro's binary encoding, following [`SCHEMA`].
+pub fn encode(doc: &GridDocument) -> Vec<u8> {
+    let mut out = Vec::new();
+    long(&mut out, doc.schema_version.into());
+    // 1970-01-01
+    let epoch = chrono::NaiveDate::default();
+    long(&mut out, (doc.date - epoch).num_days());
+    array(&mut out, &doc.letters, |out, letter| {
+        string(out, &letter.to_string())
+    });
+    array(&mut out, &doc.lengths, |out, entry| {
+        string(out, &entry.letter.to_string());
+        count(out, entry.length.0);
+        count(out, entry.count.0);
+    });
+    array(&mut out, &doc.pairs, |out, entry| {
+        string(out, &entry.pair);
+        count(out, entry.count.0);
+    });
+    array(&mut out, &doc.hints, |out, hint| string(out, hint));
+
+    count(&mut out, doc.totals.words);
+    let by_letter = doc.totals.by_letter.iter().collect::<Vec<_>>();
+    array(&mut out, &by_letter, |out, (letter, words)| {
+        string(out, &letter.to_string());
+        count(out, **words);
+    });
+    let by_length = doc.totals.by_length.iter().collect::<Vec<_>>();
+    array(&mut out, &by_length, |out, (length, words)| {
+        string(out, &length.to_string());
+        count(out, **words);
+    });
+
+    // Unions are written as the index of the branch, then its value
+    match doc.quality {
+        None => long(&mut out, 0),
+        Some(quality) => {
+            long(&mut out, 1);
+            long(&mut out, quality.into());
+        }
+    }
+    match doc.summary {
+        None => long(&mut out, 0),
+        Some(summary) => {
+            long(&mut out, 1);
+            count(&mut out, summary.points);
+            count(&mut out, summary.pangrams);
+        }
+    }
+    // Serializing JSON values can't fail
+    string(
+        &mut out,
+        &serde_json::to_string(&doc.extra).unwrap_or_default(),
+    );
+
+    out
+}
+
+// Ints and longs are zig-zag encoded, then written 7 bits at a time, least
+// significant first
+fn long(out: &mut Vec<u8>, n: i64) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn count(out: &mut Vec<u8>, n: usize) {
+    long(out, i64::try_from(n).unwrap_or(i64::MAX));
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+    count(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+// Arrays (and maps, as arrays of key and value) are written as one block
+// of all the items, then an empty block to end them
+fn array<T>(out: &mut Vec<u8>, items: &[T], mut item: impl FnMut(&mut Vec<u8>, &T)) {
+    if !items.is_empty() {
+        count(out, items.len());
+        for each in items {
+            item(out, each);
+        }
+    }
+    long(out, 0);
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "xlsx")]
+use rust_xlsxwriter::{Formula, Workbook, XlsxError};
+use serde_json::Value;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("gridder was built without {0} support (the {0} feature)")]
+    NotBuiltIn(&'static str),
+    #[cfg(feature = "xlsx")]
+    #[error("failed to write workbook {0}: {1}")]
+    WritingWorkbook(PathBuf, XlsxError),
+    #[error("failed to create {0}: {1}")]
+    CreatingDir(PathBuf, std::io::Error),
+    #[error("failed to write {0}: {1}")]
+    WritingCsv(PathBuf, csv::Error),
+}
+
+/// Everything on one tab of a spreadsheet, row by row from A1. Cells are
+/// as the Sheets API gives them: formulas as their text (starting `=`),
+/// and other values as strings, numbers or booleans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tab {
+    pub name: String,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Writes `tabs` to `path`: as an Excel workbook with a sheet per tab if
+/// it ends in `.xlsx`, otherwise as a directory of `TAB.csv` files.
+pub fn write(tabs: &[Tab], path: &Path) -> Result<(), BackupError> {
+    match is_workbook(path) {
+        true => write_workbook(tabs, path),
+        false => write_csv_dir(tabs, path),
+    }
+}
+
+/// Fails if this build can't write to `path`, so that's known before the
+/// tabs are read. Workbooks need the `xlsx` feature.
+pub fn check(path: &Path) -> Result<(), BackupError> {
+    match is_workbook(path) && !cfg!(feature = "xlsx") {
+        true => Err(BackupError::NotBuiltIn("xlsx")),
+        false => Ok(()),
+    }
+}
+
+fn is_workbook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("xlsx"))
+}
+
+/// Writes `tabs` to an Excel workbook. Formulas are kept as formulas.
+#[cfg(feature = "xlsx")]
+pub fn write_workbook(tabs: &[Tab], path: &Path) -> Result<(), BackupError> {
+    let failed = |e| BackupError::WritingWorkbook(path.to_path_buf(), e);
+    let mut workbook = Workbook::new();
+    for tab in tabs {
+        let worksheet = workbook.add_worksheet();
+        worksheet
+            .set_name(worksheet_name(&tab.name))
+            .map_err(failed)?;
+        for (row, cells) in tab.rows.iter().enumerate() {
+            // Sheets' limits are well within Excel's
+            let row = row as u32;
+            for (column, cell) in cells.iter().enumerate() {
+                let column = column as u16;
+                match cell {
+                    Value::String(s) if s.starts_with('=') => {
+                        worksheet.write_formula(row, column, Formula::new(s))
+                    }
+                    Value::String(s) if s.is_empty() => continue,
+                    Value::String(s) => worksheet.write_string(row, column, s),
+                    Value::Number(n) => {
+                        worksheet.write_number(row, column, n.as_f64().unwrap_or_default())
+                    }
+                    Value::Bool(b) => worksheet.write_boolean(row, column, *b),
+                    _ => continue,
+                }
+                .map_err(failed)?;
+            }
+        }
+    }
+
+    workbook.save(path).map_err(failed)
+}
+
+#[cfg(not(feature = "xlsx"))]
+pub fn write_workbook(_tabs: &[Tab], _path: &Path) -> Result<(), BackupError> {
+    Err(BackupError::NotBuiltIn("xlsx"))
+}
+
+/// Writes each of `tabs` to `TAB.csv` in `dir`, creating it if need be.
+/// Formulas are written as their text.
+pub fn write_csv_dir(tabs: &[Tab], dir: &Path) -> Result<(), BackupError> {
+    std::fs::create_dir_all(dir).map_err(|e| BackupError::CreatingDir(dir.to_path_buf(), e))?;
+    for tab in tabs {
+        let path = dir.join(format!("{}.csv", file_name(&tab.name)));
+        let failed = |e| BackupError::WritingCsv(path.clone(), e);
+        // Rows end where their last value is, so they vary in length
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_path(&path)
+            .map_err(failed)?;
+        for cells in &tab.rows {
+            writer
+                .write_record(cells.iter().map(cell_text))
+                .map_err(failed)?;
+        }
+        writer.flush().map_err(|e| failed(e.into()))?;
+    }
+
+    Ok(())
+}
+
+fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+// Excel's sheet names are at most 31 characters, without []:*?/\
+#[cfg(feature = "xlsx")]
+fn worksheet_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .take(31)
+        .collect()
+}
+
+// Tab names can have anything in them, including path separators
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
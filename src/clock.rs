@@ -0,0 +1,107 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// New releases happen at midnight US-West time
+pub const RELEASE_TZ: Tz = chrono_tz::America::Los_Angeles;
+
+/// A source of the current time, so that date handling can be run against
+/// a simulated one.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock stopped at one instant.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// The date of the puzzle that's current at `now`.
+pub fn puzzle_date(now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&RELEASE_TZ).date_naive()
+}
+
+/// When the puzzle for `date` is released: the start of that day in
+/// US-West time.
+///
+/// Midnight is never skipped or repeated in America/Los_Angeles, whose DST
+/// changes happen at 2am, but this doesn't rely on that. If the day starts
+/// in a DST gap, the release is the first moment that exists; if its start
+/// is repeated, it's the earlier of the two.
+pub fn release_time(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    match RELEASE_TZ.from_local_datetime(&midnight) {
+        LocalResult::Single(time) => time.to_utc(),
+        LocalResult::Ambiguous(earliest, _) => earliest.to_utc(),
+        LocalResult::None => {
+            // Gaps are whole minutes, and never longer than a few hours
+            (1..=24 * 60)
+                .map(|minutes| midnight + Duration::minutes(minutes))
+                .find_map(|time| RELEASE_TZ.from_local_datetime(&time).earliest())
+                .expect("DST gap longer than a day")
+                .to_utc()
+        }
+    }
+}
+
+/// When the next puzzle after the one current at `now` is released.
+pub fn next_release(now: DateTime<Utc>) -> DateTime<Utc> {
+    release_time(puzzle_date(now) + Duration::days(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn release_time_around_spring_forward() {
+        // Clocks go forward at 2am on the 8th, so its midnight is still PST
+        assert_eq!(release_time(date(2026, 3, 8)), utc(2026, 3, 8, 8, 0));
+        assert_eq!(release_time(date(2026, 3, 9)), utc(2026, 3, 9, 7, 0));
+    }
+
+    #[test]
+    fn release_time_around_fall_back() {
+        // Clocks go back at 2am on the 1st, so its midnight is still PDT
+        assert_eq!(release_time(date(2026, 11, 1)), utc(2026, 11, 1, 7, 0));
+        assert_eq!(release_time(date(2026, 11, 2)), utc(2026, 11, 2, 8, 0));
+    }
+
+    #[test]
+    fn next_release_across_changes() {
+        let clock = FixedClock(utc(2026, 3, 8, 12, 0));
+        assert_eq!(next_release(clock.now()), utc(2026, 3, 9, 7, 0));
+        let clock = FixedClock(utc(2026, 11, 1, 12, 0));
+        assert_eq!(next_release(clock.now()), utc(2026, 11, 2, 8, 0));
+    }
+
+    #[test]
+    fn puzzle_date_at_release() {
+        assert_eq!(puzzle_date(utc(2026, 3, 9, 6, 59)), date(2026, 3, 8));
+        assert_eq!(puzzle_date(utc(2026, 3, 9, 7, 0)), date(2026, 3, 9));
+        assert_eq!(puzzle_date(utc(2026, 11, 2, 7, 59)), date(2026, 11, 1));
+        assert_eq!(puzzle_date(utc(2026, 11, 2, 8, 0)), date(2026, 11, 2));
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt::Write;
+
+use crate::progress::Progress;
+use crate::{WordCount, WordLength};
+
+/// Formats remaining slots can be exported in, for other solving tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConstraintFormat {
+    /// One anchored regular expression per slot, e.g. `^CA[ACDELNT]{2}$`.
+    Regex,
+    /// One crossword-filler pattern per slot, with `?` for any letter,
+    /// e.g. `CA??`.
+    Pattern,
+}
+
+/// Words still to find that start with `pair` and are `length` long.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub pair: String,
+    pub length: WordLength,
+    /// The most words there can be in the slot: the grid only gives
+    /// counts per pair and per (letter, length), not for both together.
+    pub max_words: WordCount,
+}
+
+/// Every (pair, length) that could still have words in it, by pair then
+/// length.
+pub fn remaining(progress: &Progress) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    for (pair, pair_count) in &progress.remaining_pairs {
+        let Some(first) = pair.chars().next() else {
+            continue;
+        };
+        for ((letter, length), length_count) in &progress.remaining_lengths {
+            if *letter == first && length.0 >= pair.chars().count() {
+                constraints.push(Constraint {
+                    pair: pair.clone(),
+                    length: *length,
+                    max_words: (*pair_count).min(*length_count),
+                });
+            }
+        }
+    }
+
+    constraints
+}
+
+/// Writes `constraints` one per line, with `letters` as the ones words can
+/// be made from.
+pub fn render(constraints: &[Constraint], letters: &[char], format: ConstraintFormat) -> String {
+    let letters = letters
+        .iter()
+        .map(|letter| letter.to_ascii_uppercase())
+        .collect::<String>();
+
+    // Writing to a String can't fail
+    let mut out = String::new();
+    for constraint in constraints {
+        let rest = constraint.length.0 - constraint.pair.chars().count();
+        let _ = match format {
+            ConstraintFormat::Regex => {
+                writeln!(out, "^{}[{letters}]{{{rest}}}$", constraint.pair)
+            }
+            ConstraintFormat::Pattern => {
+                writeln!(out, "{}{}", constraint.pair, "?".repeat(rest))
+            }
+        };
+    }
+
+    out
+}
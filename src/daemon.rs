@@ -0,0 +1,299 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("expected 5 fields (minute hour day-of-month month day-of-week), got {0}")]
+    WrongFieldCount(usize),
+    #[error("invalid {field} field {value:?}")]
+    BadField { field: &'static str, value: String },
+}
+
+/// A cron expression: `minute hour day-of-month month day-of-week`.
+///
+/// Each field is `*`, a number, a range (`1-5`), or a comma-separated list
+/// of those, optionally with a step (`*/15`, `0-30/10`). Days of the week
+/// run from 0 (Sunday) to 6, with 7 also meaning Sunday. As in cron, if both
+/// day fields are restricted, a day matching either one will do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+// Parses one field into a bitmask of the values it allows
+fn parse_field(field: &'static str, value: &str, min: u32, max: u32) -> Result<u64, ScheduleError> {
+    let bad = || ScheduleError::BadField {
+        field,
+        value: value.to_string(),
+    };
+    let number = |s: &str| -> Result<u32, ScheduleError> {
+        s.parse()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(bad)
+    };
+
+    let mut mask = 0;
+    for item in value.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(bad)?,
+            ),
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                None => {
+                    let n = number(range)?;
+                    // "5/10" means "from 5 onwards, every 10"
+                    (n, if item.contains('/') { max } else { n })
+                }
+            },
+        };
+        if start > end {
+            return Err(bad());
+        }
+
+        for n in (start..=end).step_by(step as usize) {
+            mask |= 1 << n;
+        }
+    }
+
+    Ok(mask)
+}
+
+fn has(mask: u64, n: u32) -> bool {
+    mask & (1 << n) != 0
+}
+
+// A field as a systemd calendar component: `*` if it allows everything,
+// else a list of what it does
+fn calendar_field(mask: u64, min: u32, max: u32, name: impl Fn(u32) -> String) -> String {
+    let values = (min..=max).filter(|n| has(mask, *n)).collect::<Vec<_>>();
+    if values.len() == (min..=max).count() {
+        return "*".to_string();
+    }
+    values.into_iter().map(name).collect::<Vec<_>>().join(",")
+}
+
+impl Schedule {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+
+        let dom = has(self.days_of_month, date.day());
+        let dow = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+
+    /// The first time strictly after `after` that this schedule fires,
+    /// evaluated in `after`'s timezone.
+    ///
+    /// Local times skipped by a DST change never fire; local times repeated
+    /// by one fire once, at the earlier instant.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let start = after.naive_local();
+
+        // Long enough to reach the next 29th of February
+        for day_offset in 0..(366 * 8) {
+            let date = start.date() + Duration::days(day_offset);
+            if !self.matches_day(date) {
+                continue;
+            }
+
+            for hour in (0..24).filter(|h| has(self.hours, *h)) {
+                for minute in (0..60).filter(|m| has(self.minutes, *m)) {
+                    let naive = date.and_hms_opt(hour, minute, 0)?;
+                    if let Some(candidate) = tz.from_local_datetime(&naive).earliest() {
+                        if candidate > *after {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The same times as systemd `OnCalendar=` values, for a timer, in
+    /// `timezone` (e.g. `America/Los_Angeles`).
+    ///
+    /// systemd wants both day fields to match where cron wants either, so
+    /// a schedule restricting both needs one value for each.
+    pub fn on_calendar(&self, timezone: &str) -> Vec<String> {
+        let two_digits = |n: u32| format!("{n:02}");
+        let time = format!(
+            "{}:{}:00 {timezone}",
+            calendar_field(self.hours, 0, 23, two_digits),
+            calendar_field(self.minutes, 0, 59, two_digits),
+        );
+        let months = calendar_field(self.months, 1, 12, two_digits);
+        let by_day_of_month = format!(
+            "*-{months}-{} {time}",
+            calendar_field(self.days_of_month, 1, 31, two_digits)
+        );
+        let by_day_of_week = || {
+            const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+            let days = calendar_field(self.days_of_week, 0, 6, |n| DAYS[n as usize].to_string());
+            format!("{days} *-{months}-* {time}")
+        };
+
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => vec![by_day_of_month, by_day_of_week()],
+            (false, true) => vec![by_day_of_week()],
+            _ => vec![by_day_of_month],
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(ScheduleError::WrongFieldCount(fields.len()));
+        };
+
+        let mut days_of_week = parse_field("day-of-week", dow, 0, 7)?;
+        // 7 is another name for Sunday
+        if has(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            expr: expr.to_string(),
+            minutes: parse_field("minute", minute, 0, 59)?,
+            hours: parse_field("hour", hour, 0, 23)?,
+            days_of_month: parse_field("day-of-month", dom, 1, 31)?,
+            months: parse_field("month", month, 1, 12)?,
+            days_of_week,
+            dom_restricted: dom != "*",
+            dow_restricted: dow != "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::clock::{Clock, FixedClock, RELEASE_TZ};
+
+    fn next(expr: &str, clock: &FixedClock) -> DateTime<Utc> {
+        let schedule = expr.parse::<Schedule>().unwrap();
+        schedule
+            .next_after(&clock.now().with_timezone(&RELEASE_TZ))
+            .unwrap()
+            .to_utc()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn daily_run_across_spring_forward() {
+        // 00:30 PST on the day clocks go forward
+        let clock = FixedClock(utc(2026, 3, 8, 8, 30));
+        assert_eq!(next("2 0 * * *", &clock), utc(2026, 3, 9, 7, 2));
+    }
+
+    #[test]
+    fn skipped_time_waits_for_the_next_day() {
+        // 2:30 doesn't happen on the 8th
+        let clock = FixedClock(utc(2026, 3, 8, 9, 0));
+        assert_eq!(next("30 2 * * *", &clock), utc(2026, 3, 9, 9, 30));
+    }
+
+    #[test]
+    fn repeated_time_fires_once() {
+        // 00:00 PDT on the day clocks go back; 1:30 happens twice
+        let clock = FixedClock(utc(2026, 11, 1, 7, 0));
+        assert_eq!(next("30 1 * * *", &clock), utc(2026, 11, 1, 8, 30));
+        // Just after the first 1:30, the second one is skipped
+        let clock = FixedClock(utc(2026, 11, 1, 8, 31));
+        assert_eq!(next("30 1 * * *", &clock), utc(2026, 11, 2, 9, 30));
+    }
+
+    #[test]
+    fn daily_run_across_fall_back() {
+        let clock = FixedClock(utc(2026, 11, 1, 8, 0));
+        assert_eq!(next("2 0 * * *", &clock), utc(2026, 11, 2, 8, 2));
+    }
+
+    fn on_calendar(expr: &str) -> Vec<String> {
+        expr.parse::<Schedule>()
+            .unwrap()
+            .on_calendar("America/Los_Angeles")
+    }
+
+    #[test]
+    fn timer_for_daily_run() {
+        assert_eq!(
+            on_calendar("2 0 * * *"),
+            ["*-*-* 00:02:00 America/Los_Angeles"]
+        );
+    }
+
+    #[test]
+    fn timer_for_lists_and_steps() {
+        assert_eq!(
+            on_calendar("*/20 0,12 1-2 */6 *"),
+            ["*-01,07-01,02 00,12:00,20,40:00 America/Los_Angeles"]
+        );
+    }
+
+    #[test]
+    fn timer_for_days_of_week() {
+        assert_eq!(
+            on_calendar("0 6 * * 1-5"),
+            ["Mon,Tue,Wed,Thu,Fri *-*-* 06:00:00 America/Los_Angeles"]
+        );
+        assert_eq!(
+            on_calendar("0 6 * * 0,7"),
+            ["Sun *-*-* 06:00:00 America/Los_Angeles"]
+        );
+    }
+
+    #[test]
+    fn timer_for_either_day_field() {
+        assert_eq!(
+            on_calendar("0 6 1 * 1"),
+            [
+                "*-*-01 06:00:00 America/Los_Angeles",
+                "Mon *-*-* 06:00:00 America/Los_Angeles",
+            ]
+        );
+    }
+}
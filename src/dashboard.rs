@@ -0,0 +1,504 @@
+use axum::extract::{Path, Query, Request, State};
+use axum::http::header::{self, HeaderMap, HeaderValue};
+use axum::http::{Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use std::sync::Arc;
+
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::archive::{ArchiveError, Store};
+use crate::auth::Credentials;
+#[cfg(feature = "openapi")]
+use crate::document::GridDocument;
+
+const INDEX: &str = include_str!("dashboard.html");
+// Days of history shown unless asked for more
+const DEFAULT_HISTORY_DAYS: usize = 14;
+// A year and a bit, so one request can't read the whole archive
+const MAX_HISTORY_DAYS: usize = 400;
+// The latest grid and the history change whenever a day is saved, so
+// clients check theirs every time; it's a 304 if nothing has
+const REVALIDATE: &str = "no-cache";
+// A past day only changes if it's fetched again, e.g. to fill a gap
+const PAST_GRID: &str = "max-age=300";
+
+/// A self-hosted dashboard of the grids in a [`Store`]: a page at `/`
+/// showing the latest grid and how recent days compare, and the JSON API it
+/// reads them from:
+///
+/// - `/api/grids/latest` and `/api/grids/YYYY-MM-DD`, each a `GridDocument`
+/// - `/api/history?days=N`, a [`HistoryEntry`] for each of the last `N`
+///   saved days, newest first
+///
+/// API responses carry an `ETag` made from the saved grids' content hashes
+/// and are answered with a 304 when `If-None-Match` shows the client's copy
+/// is current, without loading the grids, so polling it is cheap.
+///
+/// The page is a single file built into the binary, so there's nothing to
+/// install alongside it. With the openapi feature, the API is described by
+/// [`api_docs`].
+pub fn router(store: Arc<dyn Store>) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/api/grids/latest", get(latest))
+        .route("/api/grids/:date", get(grid))
+        .route("/api/history", get(history))
+        .with_state(store)
+}
+
+/// The API's OpenAPI document at `/openapi.json`, for generating clients,
+/// and a Swagger UI for trying it at `/docs`.
+#[cfg(feature = "openapi")]
+pub fn api_docs() -> Router {
+    use utoipa::OpenApi;
+
+    Router::from(utoipa_swagger_ui::SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
+}
+
+#[cfg(feature = "openapi")]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    info(title = "gridder", description = "The grids saved by gridder."),
+    paths(latest, grid, history)
+)]
+struct ApiDoc;
+
+/// Who may use which of the server's routes, for serving beyond localhost.
+///
+/// With no credentials, every route is open to anyone. Otherwise requests
+/// have to send one of them in the `Authorization` header, as
+/// `Bearer TOKEN` or HTTP basic auth, except to public paths.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    credentials: Credentials,
+    public: Vec<String>,
+}
+
+impl Access {
+    /// Accepts requests sending `Bearer TOKEN`.
+    pub fn bearer_token(mut self, token: &str) -> Self {
+        self.credentials = self.credentials.bearer_token(token);
+        self
+    }
+
+    /// Accepts requests logging in with HTTP basic auth.
+    pub fn basic(mut self, username: &str, password: &str) -> Self {
+        self.credentials = self.credentials.basic(username, password);
+        self
+    }
+
+    /// Lets anyone request `path`, credentials or not. A path ending in
+    /// `*` covers every path starting with the rest, e.g. `/api/grids/*`.
+    pub fn public(mut self, path: &str) -> Self {
+        self.public.push(path.to_string());
+        self
+    }
+
+    fn allows(&self, path: &str, headers: &HeaderMap) -> bool {
+        let public = self
+            .public
+            .iter()
+            .any(|public| match public.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == public,
+            });
+        if self.credentials.is_empty() || public {
+            return true;
+        }
+
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|given| self.credentials.accepts(given))
+    }
+}
+
+/// Puts every route in `router` behind `access`, and lets pages from
+/// `cors_origins` call them from the browser. Give `*` to let any page.
+/// Routes merged in later aren't covered, so this goes last.
+pub fn protect(router: Router, access: Access, cors_origins: &[HeaderValue]) -> Router {
+    let router = router.layer(middleware::from_fn_with_state(
+        Arc::new(access),
+        require_access,
+    ));
+    if cors_origins.is_empty() {
+        return router;
+    }
+
+    let allow_origin = match cors_origins.iter().any(|origin| origin == "*") {
+        true => AllowOrigin::any(),
+        false => AllowOrigin::list(cors_origins.iter().cloned()),
+    };
+    // Outside the credentials check, as browsers send preflight requests
+    // without them
+    router.layer(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                header::IF_NONE_MATCH,
+            ])
+            .expose_headers([header::ETAG]),
+    )
+}
+
+async fn require_access(
+    State(access): State<Arc<Access>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if access.allows(request.uri().path(), request.headers()) {
+        return next.run(request).await;
+    }
+
+    // Browsers only ask for a username and password when told to
+    let challenge = match access.credentials.has_basic() {
+        true => "Basic realm=\"gridder\"",
+        false => "Bearer",
+    };
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, challenge)],
+        "invalid or missing credentials",
+    )
+        .into_response()
+}
+
+/// A day's headline numbers, as listed by `/api/history`.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HistoryEntry {
+    pub date: NaiveDate,
+    pub letters: String,
+    pub words: usize,
+    pub quality: Option<u8>,
+    pub points: Option<usize>,
+    pub pangrams: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+struct HistoryQuery {
+    /// How many saved days to list, up to 400.
+    #[cfg_attr(feature = "openapi", param(default = 14))]
+    days: Option<usize>,
+}
+
+// Errors go back as a status and a line of text
+struct ApiError(StatusCode, String);
+
+impl From<ArchiveError> for ApiError {
+    fn from(e: ArchiveError) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, self.1).into_response()
+    }
+}
+
+async fn index() -> Html<&'static str> {
+    Html(INDEX)
+}
+
+/// The most recently saved grid.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/grids/latest",
+    responses(
+        (status = 200, body = GridDocument, headers(("ETag" = String))),
+        (status = 304, description = "The copy in If-None-Match is current"),
+        (status = 404, body = String, description = "No grids are saved"),
+    )
+))]
+async fn latest(
+    State(store): State<Arc<dyn Store>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let date = store
+        .list_dates()?
+        .last()
+        .copied()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "no grids are saved".to_string()))?;
+    load(&*store, date, &headers, REVALIDATE)
+}
+
+/// The grid saved for a day.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/grids/{date}",
+    params(("date" = NaiveDate, Path, description = "The day, as YYYY-MM-DD")),
+    responses(
+        (status = 200, body = GridDocument, headers(("ETag" = String))),
+        (status = 304, description = "The copy in If-None-Match is current"),
+        (status = 400, body = String, description = "The date isn't valid"),
+        (status = 404, body = String, description = "No grid is saved for the day"),
+    )
+))]
+async fn grid(
+    State(store): State<Arc<dyn Store>>,
+    Path(date): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let date = date
+        .parse()
+        .map_err(|_| ApiError(StatusCode::BAD_REQUEST, format!("invalid date {date:?}")))?;
+    load(&*store, date, &headers, PAST_GRID)
+}
+
+fn load(
+    store: &dyn Store,
+    date: NaiveDate,
+    headers: &HeaderMap,
+    cache_control: &'static str,
+) -> Result<Response, ApiError> {
+    let not_found = || {
+        ApiError(
+            StatusCode::NOT_FOUND,
+            format!("no grid is saved for {date}"),
+        )
+    };
+    let etag = format!("\"{}\"", store.content_hash(date)?.ok_or_else(not_found)?);
+    conditional(headers, &etag, cache_control, || {
+        Ok(Json(store.load_grid(date)?.ok_or_else(not_found)?).into_response())
+    })
+}
+
+/// The headline numbers of the most recently saved days, newest first.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/history",
+    params(HistoryQuery),
+    responses(
+        (status = 200, body = Vec<HistoryEntry>, headers(("ETag" = String))),
+        (status = 304, description = "The copy in If-None-Match is current"),
+    )
+))]
+async fn history(
+    State(store): State<Arc<dyn Store>>,
+    Query(query): Query<HistoryQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_HISTORY_DAYS)
+        .min(MAX_HISTORY_DAYS);
+    let dates = store
+        .list_dates()?
+        .into_iter()
+        .rev()
+        .take(days)
+        .collect::<Vec<_>>();
+    // Changes when any of the days shown does, or a new one is saved
+    let mut hasher = Sha256::new();
+    for &date in &dates {
+        if let Some(hash) = store.content_hash(date)? {
+            hasher.update(format!("{date} {hash}\n"));
+        }
+    }
+    let etag = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    conditional(&headers, &format!("\"{etag}\""), REVALIDATE, || {
+        history_entries(&*store, dates).map(|entries| Json(entries).into_response())
+    })
+}
+
+fn history_entries(
+    store: &dyn Store,
+    dates: Vec<NaiveDate>,
+) -> Result<Vec<HistoryEntry>, ApiError> {
+    let mut entries = Vec::new();
+    for date in dates {
+        // One being written right now turns up next time
+        let Some(doc) = store.load_grid(date)? else {
+            continue;
+        };
+        entries.push(HistoryEntry {
+            date,
+            letters: doc.letters.iter().collect(),
+            words: doc.totals.words,
+            quality: doc.quality,
+            points: doc.summary.map(|summary| summary.points),
+            pangrams: doc.summary.map(|summary| summary.pangrams),
+        });
+    }
+
+    Ok(entries)
+}
+
+// A 304 if `If-None-Match` has `etag`, or else the response `body` makes,
+// either way with the `ETag` and `Cache-Control` headers set
+fn conditional(
+    headers: &HeaderMap,
+    etag: &str,
+    cache_control: &'static str,
+    body: impl FnOnce() -> Result<Response, ApiError>,
+) -> Result<Response, ApiError> {
+    let current = headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    let response = match current {
+        true => StatusCode::NOT_MODIFIED.into_response(),
+        false => body()?,
+    };
+    let cache_headers = [
+        (header::ETAG, etag.to_string()),
+        (header::CACHE_CONTROL, cache_control.to_string()),
+    ];
+
+    Ok((cache_headers, response).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::MemoryStore;
+    use crate::document::GridDocument;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn store(days: &[u32]) -> Arc<dyn Store> {
+        let store = MemoryStore::default();
+        let lengths = LengthInfo::from([(('A', WordLength(4)), WordCount(2))]);
+        for &day in days {
+            let doc = GridDocument::new(date(day), &PairInfo::new(), &lengths);
+            store.save_grid(&doc).unwrap();
+        }
+        Arc::new(store)
+    }
+
+    // Serves `router` on a port of its own, and gives its base URL
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        base_url
+    }
+
+    #[tokio::test]
+    async fn requests_need_credentials_once_there_are_some() {
+        let access = Access::default().bearer_token("s3cret").public("/");
+        let base_url = serve(protect(router(store(&[1])), access, &[])).await;
+        let client = reqwest::Client::new();
+        let get = |path: &str| client.get(format!("{base_url}{path}"));
+
+        let refused = get("/api/grids/latest").send().await.unwrap();
+        assert_eq!(refused.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(refused.headers()[header::WWW_AUTHENTICATE], "Bearer");
+        let wrong = get("/api/grids/latest").bearer_auth("guess");
+        assert_eq!(
+            wrong.send().await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let allowed = get("/api/grids/latest").bearer_auth("s3cret");
+        assert_eq!(allowed.send().await.unwrap().status(), StatusCode::OK);
+        assert_eq!(get("/").send().await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn public_paths_may_end_in_a_wildcard() {
+        let access = Access::default()
+            .basic("ana", "hunter2")
+            .public("/api/grids/*");
+        let headers = HeaderMap::new();
+        assert!(access.allows("/api/grids/latest", &headers));
+        assert!(!access.allows("/api/history", &headers));
+        assert!(!access.allows("/", &headers));
+
+        let mut headers = HeaderMap::new();
+        let login = HeaderValue::from_static("Basic YW5hOmh1bnRlcjI=");
+        headers.insert(header::AUTHORIZATION, login);
+        assert!(access.allows("/api/history", &headers));
+        assert!(Access::default().allows("/api/history", &HeaderMap::new()));
+    }
+
+    fn if_none_match(tags: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for tag in tags {
+            headers.append(header::IF_NONE_MATCH, HeaderValue::from_static(tag));
+        }
+        headers
+    }
+
+    fn respond(headers: &HeaderMap) -> Response {
+        let body = || Ok("grid".into_response());
+        match conditional(headers, "\"abc\"", REVALIDATE, body) {
+            Ok(response) => response,
+            Err(ApiError(status, e)) => panic!("{status}: {e}"),
+        }
+    }
+
+    #[test]
+    fn current_copies_get_a_304() {
+        for tags in [
+            &["\"abc\""][..],
+            &["W/\"abc\""],
+            &["\"old\", \"abc\""],
+            &["\"old\"", "\"abc\""],
+            &["*"],
+        ] {
+            let response = respond(&if_none_match(tags));
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{tags:?}");
+            assert_eq!(response.headers()[header::ETAG], "\"abc\"");
+        }
+    }
+
+    #[test]
+    fn stale_copies_get_the_body() {
+        for tags in [&[][..], &["\"old\""], &["abc"]] {
+            let response = respond(&if_none_match(tags));
+            assert_eq!(response.status(), StatusCode::OK, "{tags:?}");
+            assert_eq!(response.headers()[header::ETAG], "\"abc\"");
+            assert_eq!(response.headers()[header::CACHE_CONTROL], REVALIDATE);
+        }
+    }
+
+    #[tokio::test]
+    async fn etags_change_with_the_saved_grids() {
+        let store = store(&[1, 2]);
+        let base_url = serve(router(store.clone())).await;
+        let client = reqwest::Client::new();
+        let history = || client.get(format!("{base_url}/api/history"));
+
+        let first = history().send().await.unwrap();
+        let etag = first.headers()[header::ETAG].clone();
+        let again = history().header(header::IF_NONE_MATCH, etag.clone());
+        assert_eq!(
+            again.send().await.unwrap().status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        let lengths = LengthInfo::from([(('C', WordLength(5)), WordCount(1))]);
+        let doc = GridDocument::new(date(3), &PairInfo::new(), &lengths);
+        store.save_grid(&doc).unwrap();
+        let changed = history().header(header::IF_NONE_MATCH, etag);
+        assert_eq!(changed.send().await.unwrap().status(), StatusCode::OK);
+
+        let past = client.get(format!("{base_url}/api/grids/2024-05-01"));
+        let past = past.send().await.unwrap();
+        assert_eq!(past.headers()[header::CACHE_CONTROL], PAST_GRID);
+    }
+}
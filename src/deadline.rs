@@ -0,0 +1,100 @@
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid duration {0:?}, expected e.g. 90s, 5m or 1h30m")]
+pub struct BudgetError(String);
+
+/// What a run was doing when its [`Deadline`] passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("deadline exceeded at {0}")]
+pub struct DeadlineExceeded(pub &'static str);
+
+/// How long a whole run may take, written as hours, minutes and seconds,
+/// e.g. `90s`, `5m` or `1h30m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget(Duration);
+
+impl FromStr for Budget {
+    type Err = BudgetError;
+
+    fn from_str(budget: &str) -> Result<Self, Self::Err> {
+        let bad_budget = || BudgetError(budget.to_string());
+        let mut seconds = 0u64;
+        let mut rest = budget.trim();
+        if rest.is_empty() {
+            return Err(bad_budget());
+        }
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(bad_budget)?;
+            let n: u64 = rest[..digits].parse().map_err(|_| bad_budget())?;
+            let unit = match rest[digits..].chars().next() {
+                Some('h') => 3600,
+                Some('m') => 60,
+                Some('s') => 1,
+                _ => return Err(bad_budget()),
+            };
+            seconds = n
+                .checked_mul(unit)
+                .and_then(|n| seconds.checked_add(n))
+                .ok_or_else(bad_budget)?;
+            rest = &rest[digits + 1..];
+        }
+        if seconds == 0 {
+            return Err(bad_budget());
+        }
+
+        Ok(Self(Duration::from_secs(seconds)))
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        for (n, unit) in [(hours, "h"), (minutes, "m"), (seconds, "s")] {
+            if n > 0 {
+                write!(f, "{n}{unit}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// When a run has to be finished by, so one stuck on a slow site or sink
+/// can't hang a cron job. Every stage of the run, and any retries in it,
+/// share the one [`Budget`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// A deadline `budget` from now, or none at all.
+    pub fn after(budget: Option<Budget>) -> Self {
+        Self(budget.map(|budget| Instant::now() + budget.0))
+    }
+
+    /// Runs `stage`, giving up on it with [`DeadlineExceeded`] if the
+    /// deadline passes first, or not starting it at all if it already has.
+    /// `name` says what it is, e.g. "fetching the page".
+    pub async fn within<T, E, F>(self, name: &'static str, stage: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<DeadlineExceeded>,
+    {
+        match self.0 {
+            None => stage.await,
+            // A timeout polls its future once even when it's already late,
+            // which would start e.g. a post that's then abandoned
+            Some(at) if Instant::now() >= at => Err(DeadlineExceeded(name).into()),
+            Some(at) => tokio::time::timeout_at(at, stage)
+                .await
+                .unwrap_or_else(|_| Err(DeadlineExceeded(name).into())),
+        }
+    }
+}
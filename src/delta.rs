@@ -0,0 +1,203 @@
+use serde_json::{json, Value};
+
+use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+/// A run of neighbouring cells along a row to write: its row and first
+/// column (0-based, from the block's top left) and its values.
+pub type Run = (usize, usize, Vec<Value>);
+
+// Whether a cell already shows the value to be written. The API leaves
+// out empty cells at the ends of rows, and may give back whole numbers as
+// floats.
+fn same_cell(current: Option<&Value>, wanted: Option<&Value>) -> bool {
+    let is_empty = |cell: Option<&Value>| match cell {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.is_empty(),
+        _ => false,
+    };
+    match (current, wanted) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64() == b.as_f64(),
+        (a, b) if is_empty(a) && is_empty(b) => true,
+        (a, b) => a == b,
+    }
+}
+
+// Cells are only made through these, so a length can't end up in a count's
+// column or the other way around
+pub fn count_cell(count: WordCount) -> Value {
+    json!(count.0)
+}
+
+fn length_cell(length: WordLength) -> Value {
+    json!(length.0)
+}
+
+/// The rows of a day's pairs block (pair, count), in order of pair, so the
+/// same grid always gives the same rows.
+pub fn pairs_to_values(pairs: &PairInfo) -> Vec<Vec<Value>> {
+    let mut pairs = pairs
+        .iter()
+        .filter(|(_, count)| count.0 > 0)
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|((a, b), count)| vec![json!(format!("{a}{b}")), count_cell(*count)])
+        .collect()
+}
+
+/// The rows of a day's lengths block (letter, length, count), in order of
+/// letter and then length.
+pub fn lengths_to_values(lengths: &LengthInfo) -> Vec<Vec<Value>> {
+    let mut lengths = lengths
+        .iter()
+        .filter(|(_, count)| count.0 > 0)
+        .collect::<Vec<_>>();
+    lengths.sort();
+    lengths
+        .into_iter()
+        .map(|((letter, len), count)| vec![json!(letter), length_cell(*len), count_cell(*count)])
+        .collect()
+}
+
+/// The cells of `wanted` that differ from `current`, a block of values as
+/// read from a sheet. Cells of `current` outside `wanted` are cleared.
+pub fn changed_runs(current: &[Vec<Value>], wanted: &[Vec<Value>]) -> Vec<Run> {
+    let mut runs = Vec::new();
+    for row in 0..current.len().max(wanted.len()) {
+        let have = current.get(row).map(Vec::as_slice).unwrap_or_default();
+        let want = wanted.get(row).map(Vec::as_slice).unwrap_or_default();
+        let mut run: Option<(usize, Vec<Value>)> = None;
+        for column in 0..have.len().max(want.len()) {
+            if same_cell(have.get(column), want.get(column)) {
+                runs.extend(run.take().map(|(start, cells)| (row, start, cells)));
+                continue;
+            }
+            let cell = want.get(column).cloned().unwrap_or_else(|| json!(""));
+            run.get_or_insert_with(|| (column, Vec::new())).1.push(cell);
+        }
+        runs.extend(run.map(|(start, cells)| (row, start, cells)));
+    }
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_blocks_need_nothing() {
+        let block = vec![vec![json!("AC"), json!(2)], vec![json!("AN"), json!(2)]];
+        assert!(changed_runs(&block, &block).is_empty());
+    }
+
+    #[test]
+    fn numbers_read_back_as_floats_are_unchanged() {
+        let current = vec![vec![json!("A"), json!(4.0), json!(2.0)]];
+        let wanted = vec![vec![json!("A"), json!(4), json!(2)]];
+        assert!(changed_runs(&current, &wanted).is_empty());
+    }
+
+    #[test]
+    fn changes_are_grouped_into_runs_along_rows() {
+        let current = vec![vec![json!(1), json!(2), json!(3), json!(4), json!(5)]];
+        let wanted = vec![vec![json!(1), json!(9), json!(9), json!(4), json!(9)]];
+        assert_eq!(
+            changed_runs(&current, &wanted),
+            vec![(0, 1, vec![json!(9), json!(9)]), (0, 4, vec![json!(9)])]
+        );
+    }
+
+    #[test]
+    fn missing_cells_are_written() {
+        let current = vec![vec![json!("Rank")]];
+        let wanted = vec![
+            vec![json!("Rank"), json!("Member")],
+            vec![json!(1), json!("ana")],
+        ];
+        assert_eq!(
+            changed_runs(&current, &wanted),
+            vec![
+                (0, 1, vec![json!("Member")]),
+                (1, 0, vec![json!(1), json!("ana")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn leftover_cells_are_cleared() {
+        let current = vec![
+            vec![json!("AC"), json!(2), json!("note")],
+            vec![json!("AN"), json!(2)],
+        ];
+        let wanted = vec![vec![json!("AC"), json!(2)]];
+        assert_eq!(
+            changed_runs(&current, &wanted),
+            vec![(0, 2, vec![json!("")]), (1, 0, vec![json!(""), json!("")])]
+        );
+    }
+
+    #[test]
+    fn refilling_the_same_grid_changes_nothing() {
+        // Each map iterates in its own order
+        let pairs = || {
+            ["AC", "AN", "CA", "NA", "NO", "ON"]
+                .iter()
+                .enumerate()
+                .map(|(i, pair)| {
+                    let mut letters = pair.chars();
+                    let pair = (letters.next().unwrap(), letters.next().unwrap());
+                    (pair, WordCount(i + 1))
+                })
+                .collect::<PairInfo>()
+        };
+        let lengths = || {
+            (4..10)
+                .flat_map(|len| {
+                    ['A', 'C', 'N'].map(|letter| ((letter, WordLength(len)), WordCount(len)))
+                })
+                .collect::<LengthInfo>()
+        };
+
+        for _ in 0..10 {
+            let written = pairs_to_values(&pairs());
+            assert!(changed_runs(&written, &pairs_to_values(&pairs())).is_empty());
+            let written = lengths_to_values(&lengths());
+            assert!(changed_runs(&written, &lengths_to_values(&lengths())).is_empty());
+        }
+    }
+
+    #[test]
+    fn blocks_are_in_order_without_empty_counts() {
+        let pairs = PairInfo::from([
+            (('N', 'O'), WordCount(1)),
+            (('A', 'N'), WordCount(0)),
+            (('A', 'C'), WordCount(2)),
+        ]);
+        assert_eq!(
+            pairs_to_values(&pairs),
+            vec![vec![json!("AC"), json!(2)], vec![json!("NO"), json!(1)]]
+        );
+        let lengths = LengthInfo::from([
+            (('C', WordLength(4)), WordCount(1)),
+            (('A', WordLength(5)), WordCount(3)),
+            (('A', WordLength(4)), WordCount(2)),
+        ]);
+        assert_eq!(
+            lengths_to_values(&lengths),
+            vec![
+                vec![json!('A'), json!(4), json!(2)],
+                vec![json!('A'), json!(5), json!(3)],
+                vec![json!('C'), json!(4), json!(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_strings_match_missing_cells() {
+        let current = vec![vec![json!("A")], vec![]];
+        let wanted = vec![vec![json!("A"), json!("")], vec![json!(null)]];
+        assert!(changed_runs(&current, &wanted).is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use crate::document::GridDocument;
+
+// The puzzle never accepts anything shorter
+const MIN_WORD_LENGTH: usize = 4;
+
+/// A local word list, for suggesting words that might fit a grid.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: BTreeSet<String>,
+}
+
+/// How many of a grid's words the dictionary's suggestions can account for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub covered: usize,
+    pub total: usize,
+    /// (covered, published) for each (letter, length) in the grid.
+    pub by_slot: BTreeMap<(char, usize), (usize, usize)>,
+}
+
+impl Dictionary {
+    /// Loads a word list with one word per line. Blank lines, and words with
+    /// anything but ASCII letters in them, are skipped.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::from_words(contents.lines()))
+    }
+
+    pub fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let words = words
+            .into_iter()
+            .map(|word| word.as_ref().trim().to_ascii_uppercase())
+            .filter(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_alphabetic()))
+            .collect();
+
+        Self { words }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Words that could be made from `letters`: long enough, using nothing
+    /// else, and using `center` if it's given.
+    pub fn fitting(&self, letters: &[char], center: Option<char>) -> Vec<String> {
+        let letters = letters
+            .iter()
+            .map(|letter| letter.to_ascii_uppercase())
+            .collect::<BTreeSet<_>>();
+        let center = center.map(|c| c.to_ascii_uppercase());
+
+        self.words
+            .iter()
+            .filter(|word| {
+                word.chars().count() >= MIN_WORD_LENGTH
+                    && word.chars().all(|c| letters.contains(&c))
+                    && center.is_none_or(|center| word.contains(center))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Words that could be in `doc`'s grid: made only from its letters, and
+    /// starting with a pair and (letter, length) it has words for.
+    pub fn candidates(&self, doc: &GridDocument) -> Vec<String> {
+        let slots = doc
+            .lengths
+            .iter()
+            .map(|e| (e.letter.to_ascii_uppercase(), e.length.0))
+            .collect::<BTreeSet<_>>();
+        let pairs = doc
+            .pairs
+            .iter()
+            .map(|e| e.pair.to_ascii_uppercase())
+            .collect::<BTreeSet<_>>();
+
+        self.fitting(&doc.letters, None)
+            .into_iter()
+            .filter(|word| {
+                let first = word.chars().next().unwrap_or_default();
+                let pair = word.chars().take(2).collect::<String>();
+                slots.contains(&(first, word.chars().count())) && pairs.contains(&pair)
+            })
+            .collect()
+    }
+
+    /// Compares the suggestions for `doc` against its published counts.
+    /// A slot can't be covered by more words than it has.
+    pub fn coverage(&self, doc: &GridDocument) -> Coverage {
+        let mut suggested = BTreeMap::new();
+        for word in self.candidates(doc) {
+            let first = word.chars().next().unwrap_or_default();
+            *suggested.entry((first, word.chars().count())).or_insert(0) += 1;
+        }
+
+        let mut coverage = Coverage {
+            covered: 0,
+            total: 0,
+            by_slot: BTreeMap::new(),
+        };
+        for entry in &doc.lengths {
+            let slot = (entry.letter.to_ascii_uppercase(), entry.length.0);
+            let covered = suggested
+                .get(&slot)
+                .copied()
+                .unwrap_or(0)
+                .min(entry.count.0);
+            coverage.covered += covered;
+            coverage.total += entry.count.0;
+            coverage.by_slot.insert(slot, (covered, entry.count.0));
+        }
+
+        coverage
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "dictionary covers {}/{} words", self.covered, self.total)?;
+
+        let short = self
+            .by_slot
+            .iter()
+            .filter(|(_, (covered, published))| covered < published)
+            .collect::<Vec<_>>();
+        if !short.is_empty() {
+            writeln!(f, "short of the grid:")?;
+            for ((letter, length), (covered, published)) in short {
+                writeln!(f, "  {letter}{length}: {covered}/{published}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+/// Version of the [`GridDocument`] shape.
+///
+/// Compatibility rules for consumers:
+/// - Fields may be added without bumping this. Only `schema_version`,
+///   `date`, `letters`, `lengths`, `pairs` and `totals` are required;
+///   every other field, including any added later, is defaulted when
+///   missing, so older documents keep loading. Ignore fields you don't
+///   recognise.
+/// - Removing, renaming or changing the meaning of a field bumps this.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The serializable form of one day's grid, shared by every structured
+/// output gridder produces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GridDocument {
+    pub schema_version: u32,
+    pub date: NaiveDate,
+    /// Letters with a row in the grid, in alphabetical order.
+    pub letters: Vec<char>,
+    /// The letter every word uses, if the page said which it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<char>,
+    /// Non-zero (letter, length) counts, ordered by letter then length.
+    pub lengths: Vec<LengthEntry>,
+    /// Non-zero two-letter prefix counts, ordered by prefix.
+    pub pairs: Vec<PairEntry>,
+    /// Free-text hints published alongside the grid. Nothing captures
+    /// these yet, so gridder always writes an empty list.
+    #[serde(default)]
+    pub hints: Vec<String>,
+    pub totals: Totals,
+    /// How far the grid can be trusted, from 0 to 100, if it was checked
+    /// when read from the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    /// The page's headline numbers, if they could be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<SummaryEntry>,
+    /// Anything a `--transform` command added, e.g. derived figures, passed
+    /// on as-is to the outputs that write the document.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LengthEntry {
+    pub letter: char,
+    pub length: WordLength,
+    pub count: WordCount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PairEntry {
+    /// The two-letter prefix, e.g. "ab".
+    pub pair: String,
+    pub count: WordCount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SummaryEntry {
+    pub points: usize,
+    pub pangrams: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Totals {
+    pub words: usize,
+    pub by_letter: BTreeMap<char, usize>,
+    pub by_length: BTreeMap<usize, usize>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DocumentError {
+    #[error("unsupported schema version {0} (expected {SCHEMA_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("pair {0:?} is not exactly two letters")]
+    MalformedPair(String),
+}
+
+impl GridDocument {
+    pub fn new(date: NaiveDate, pairs: &PairInfo, lengths: &LengthInfo) -> Self {
+        let letters = lengths
+            .keys()
+            .map(|(letter, _)| *letter)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut length_entries = lengths
+            .iter()
+            .filter(|(_, count)| count.0 > 0)
+            .map(|((letter, length), count)| LengthEntry {
+                letter: *letter,
+                length: *length,
+                count: *count,
+            })
+            .collect::<Vec<_>>();
+        length_entries.sort_by_key(|e| (e.letter, e.length));
+
+        let mut pair_entries = pairs
+            .iter()
+            .filter(|(_, count)| count.0 > 0)
+            .map(|((a, b), count)| PairEntry {
+                pair: format!("{a}{b}"),
+                count: *count,
+            })
+            .collect::<Vec<_>>();
+        pair_entries.sort_by(|a, b| a.pair.cmp(&b.pair));
+
+        let mut totals = Totals::default();
+        for entry in &length_entries {
+            totals.words += entry.count.0;
+            *totals.by_letter.entry(entry.letter).or_default() += entry.count.0;
+            *totals.by_length.entry(entry.length.0).or_default() += entry.count.0;
+        }
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            date,
+            letters,
+            center: None,
+            lengths: length_entries,
+            pairs: pair_entries,
+            hints: Vec::new(),
+            totals,
+            quality: None,
+            summary: None,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Rejects documents written against a schema this build doesn't know.
+    pub fn check_version(&self) -> Result<(), DocumentError> {
+        if self.schema_version != SCHEMA_VERSION {
+            return Err(DocumentError::UnsupportedVersion(self.schema_version));
+        }
+
+        Ok(())
+    }
+
+    pub fn pair_info(&self) -> Result<PairInfo, DocumentError> {
+        let mut pairs = PairInfo::default();
+        for entry in &self.pairs {
+            let mut chars = entry.pair.chars();
+            let (a, b) = match (chars.next(), chars.next(), chars.next()) {
+                (Some(a), Some(b), None) => (a, b),
+                _ => return Err(DocumentError::MalformedPair(entry.pair.clone())),
+            };
+            pairs.insert((a, b), entry.count);
+        }
+
+        Ok(pairs)
+    }
+
+    pub fn length_info(&self) -> LengthInfo {
+        self.lengths
+            .iter()
+            .map(|e| ((e.letter, e.length), e.count))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimal_v1_document_loads() {
+        let doc: GridDocument = serde_json::from_str(
+            r#"{
+                "schema_version": 1,
+                "date": "2024-05-01",
+                "letters": ["A", "C"],
+                "lengths": [{"letter": "A", "length": 4, "count": 2}],
+                "pairs": [{"pair": "AC", "count": 2}],
+                "totals": {"words": 2, "by_letter": {"A": 2}, "by_length": {"4": 2}}
+            }"#,
+        )
+        .unwrap();
+
+        doc.check_version().unwrap();
+        assert_eq!(doc.center, None);
+        assert!(doc.hints.is_empty());
+        assert_eq!(doc.quality, None);
+        assert_eq!(doc.summary, None);
+        assert!(doc.extra.is_empty());
+        assert_eq!(doc.pair_info().unwrap()[&('A', 'C')], WordCount(2));
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+
+use reqwest::header;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::document::GridDocument;
+use crate::formats::{self, DayFile, FormatError};
+use crate::network::Network;
+use crate::sheets::{self, NewSheetError};
+
+const FILES_API: &str = "https://www.googleapis.com/drive/v3/files";
+const UPLOAD_API: &str = "https://www.googleapis.com/upload/drive/v3/files";
+// Files shared with the service account aren't visible with a narrower one
+const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive";
+// Between the parts of a new file's upload; CSV never has it
+const BOUNDARY: &str = "gridder-upload-boundary";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DriveError {
+    #[error("failed to log in to Google Drive: {0}")]
+    LoggingIn(#[from] NewSheetError),
+    #[error("failed to write the grid ({0})")]
+    Formatting(#[from] FormatError),
+    #[error("failed to upload {0} to Google Drive ({1})")]
+    Uploading(String, reqwest::Error),
+    #[error("unexpected response from Google Drive: {0}")]
+    UnexpectedResponse(serde_json::Error),
+}
+
+/// Uploads each day's CSV files to a Google Drive folder, for groups that
+/// import them by hand but want them in a shared folder as they're made.
+///
+/// It logs in as a service account, which needs to be an editor of the
+/// folder (or a member of its shared drive). A day's files replace any
+/// uploaded for it before, rather than sitting beside them.
+#[derive(Debug, Clone)]
+pub struct DriveSink {
+    network: Network,
+    client: reqwest::Client,
+    service_account_file: PathBuf,
+    folder_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileList {
+    files: Vec<FileId>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileId {
+    id: String,
+}
+
+impl DriveSink {
+    /// `folder_id` is the last part of the folder's URL.
+    pub fn new(network: Network, service_account_file: &Path, folder_id: &str) -> Self {
+        Self {
+            network,
+            client: network.client(),
+            service_account_file: service_account_file.to_path_buf(),
+            folder_id: folder_id.to_string(),
+        }
+    }
+
+    pub fn folder_id(&self) -> &str {
+        &self.folder_id
+    }
+
+    pub async fn upload(&self, doc: &GridDocument) -> Result<(), DriveError> {
+        let token =
+            sheets::access_token(self.network, &self.service_account_file, &[DRIVE_SCOPE]).await?;
+        let csv_files = formats::day_files(doc)?
+            .into_iter()
+            .filter(|file| file.name.ends_with(".csv"));
+        for file in csv_files {
+            match self.find(&token, &file.name).await? {
+                Some(id) => self.replace(&token, &id, &file).await,
+                None => self.create(&token, &file).await,
+            }
+            .map_err(|e| DriveError::Uploading(file.name.clone(), e))?;
+        }
+
+        Ok(())
+    }
+
+    // The ID of the file in the folder called `name`, if there is one
+    async fn find(&self, token: &str, name: &str) -> Result<Option<String>, DriveError> {
+        let finding = |e| DriveError::Uploading(name.to_string(), e);
+        // Names are dates, so there's nothing to escape
+        let query = format!(
+            "name = '{name}' and '{}' in parents and trashed = false",
+            self.folder_id
+        );
+        let response = self
+            .client
+            .get(FILES_API)
+            .bearer_auth(token)
+            .query(&[
+                ("q", query.as_str()),
+                ("fields", "files(id)"),
+                ("supportsAllDrives", "true"),
+                ("includeItemsFromAllDrives", "true"),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(finding)?;
+        let body = response.bytes().await.map_err(finding)?;
+        let list: FileList =
+            serde_json::from_slice(&body).map_err(DriveError::UnexpectedResponse)?;
+
+        Ok(list.files.into_iter().next().map(|file| file.id))
+    }
+
+    async fn replace(&self, token: &str, id: &str, file: &DayFile) -> Result<(), reqwest::Error> {
+        // IDs are letters, digits, - and _
+        self.client
+            .patch(format!("{UPLOAD_API}/{id}"))
+            .bearer_auth(token)
+            .query(&[("uploadType", "media"), ("supportsAllDrives", "true")])
+            .header(header::CONTENT_TYPE, file.content_type)
+            .body(file.contents.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    // Drive takes the file's name and folder along with its contents, as
+    // the parts of a multipart/related body
+    async fn create(&self, token: &str, file: &DayFile) -> Result<(), reqwest::Error> {
+        let metadata = json!({
+            "name": file.name,
+            "parents": [self.folder_id],
+            "mimeType": file.content_type,
+        });
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{metadata}\r\n\
+             --{BOUNDARY}\r\nContent-Type: {}\r\n\r\n",
+            file.content_type
+        )
+        .into_bytes();
+        body.extend_from_slice(&file.contents);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        self.client
+            .post(UPLOAD_API)
+            .bearer_auth(token)
+            .query(&[("uploadType", "multipart"), ("supportsAllDrives", "true")])
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/related; boundary={BOUNDARY}"),
+            )
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
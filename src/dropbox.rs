@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use reqwest::header;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::document::GridDocument;
+use crate::formats::{self, FormatError};
+use crate::network::Network;
+
+const TOKEN_API: &str = "https://api.dropboxapi.com/oauth2/token";
+const UPLOAD_API: &str = "https://content.dropboxapi.com/2/files/upload";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DropboxError {
+    #[error("invalid Dropbox folder {0:?}, expected dropbox:/PATH")]
+    BadFolder(String),
+    #[error("failed to write the grid ({0})")]
+    Formatting(#[from] FormatError),
+    #[error("failed to log in to Dropbox ({0})")]
+    LoggingIn(reqwest::Error),
+    #[error("failed to upload {0} to Dropbox ({1})")]
+    Uploading(String, reqwest::Error),
+    #[error("unexpected response from Dropbox: {0}")]
+    UnexpectedResponse(serde_json::Error),
+}
+
+/// Uploads each day's CSV files to a Dropbox folder, for groups that
+/// import them by hand but want them in a shared folder as they're made.
+///
+/// Dropbox's access tokens only last a few hours, so it logs in with the
+/// refresh token of a Dropbox app (one using PKCE, so there's no app secret
+/// to keep) each time. A day's files replace any uploaded for it before.
+#[derive(Debug)]
+pub struct DropboxSink {
+    client: reqwest::Client,
+    // Starts with a slash, and doesn't end with one
+    folder: String,
+    app_key: String,
+    refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+impl DropboxSink {
+    /// `folder` is the path of the folder in the Dropbox, e.g. `/Grids`.
+    pub fn new(
+        network: Network,
+        folder: &str,
+        app_key: &str,
+        refresh_token: &str,
+    ) -> Result<Self, DropboxError> {
+        if !folder.starts_with('/') {
+            return Err(DropboxError::BadFolder(folder.to_string()));
+        }
+
+        Ok(Self {
+            client: network.client(),
+            folder: folder.trim_end_matches('/').to_string(),
+            app_key: app_key.to_string(),
+            refresh_token: refresh_token.to_string(),
+        })
+    }
+
+    /// The folder's path, e.g. `/Grids`.
+    pub fn folder(&self) -> &str {
+        &self.folder
+    }
+
+    pub async fn upload(&self, doc: &GridDocument) -> Result<(), DropboxError> {
+        let token = self.log_in().await?;
+        let csv_files = formats::day_files(doc)?
+            .into_iter()
+            .filter(|file| file.name.ends_with(".csv"));
+        for file in csv_files {
+            // The file's details go in a header, as the body is its contents
+            let arg = json!({
+                "path": format!("{}/{}", self.folder, file.name),
+                "mode": "overwrite",
+                "mute": true,
+            });
+            self.client
+                .post(UPLOAD_API)
+                .bearer_auth(&token)
+                .header("Dropbox-API-Arg", ascii_json(&arg))
+                .header(header::CONTENT_TYPE, "application/octet-stream")
+                .body(file.contents)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| DropboxError::Uploading(file.name.clone(), e))?;
+        }
+
+        Ok(())
+    }
+
+    // A new access token, from the refresh token
+    async fn log_in(&self) -> Result<String, DropboxError> {
+        let response = self
+            .client
+            .post(TOKEN_API)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &self.refresh_token),
+                ("client_id", &self.app_key),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(DropboxError::LoggingIn)?;
+        let body = response.bytes().await.map_err(DropboxError::LoggingIn)?;
+        let token: Token =
+            serde_json::from_slice(&body).map_err(DropboxError::UnexpectedResponse)?;
+
+        Ok(token.access_token)
+    }
+}
+
+// Headers can only hold ASCII, so anything else in a folder's name is
+// escaped, as JSON allows
+fn ascii_json(value: &serde_json::Value) -> String {
+    let mut out = String::new();
+    for c in value.to_string().chars() {
+        match c.is_ascii() {
+            true => out.push(c),
+            false => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    let _ = write!(out, "\\u{unit:04x}");
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// What encrypted files start with, to tell them apart from plain ones.
+const MAGIC: &[u8] = b"gridder-encrypted-1\n";
+
+const SALT_LEN: usize = 16;
+
+const KEY_LEN: usize = 32;
+
+// OWASP's recommendation for PBKDF2-HMAC-SHA256
+const ITERATIONS: NonZeroU32 = match NonZeroU32::new(600_000) {
+    Some(iterations) => iterations,
+    None => unreachable!(),
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("failed to read key file {0}: {1}")]
+    ReadingKeyFile(PathBuf, std::io::Error),
+    #[error("the passphrase or key file is empty")]
+    EmptyKey,
+    #[error("encrypted file is cut short")]
+    Truncated,
+    #[error("file can't be decrypted with this passphrase or key file")]
+    WrongKey,
+    #[error("no passphrase or key file was given")]
+    NoKey,
+    #[error("file isn't encrypted, though a passphrase or key file was given (encrypt it with `gridder encrypt-file`)")]
+    NotEncrypted,
+    #[error("no random numbers to encrypt with")]
+    NoRandomness,
+}
+
+/// Encrypts and decrypts files at rest with a passphrase or key file, for
+/// machines shared with people who shouldn't read them.
+///
+/// Files are encrypted with ChaCha20-Poly1305, under a key derived from the
+/// passphrase with PBKDF2-HMAC-SHA256 and a random salt kept in the file's
+/// header. Deriving a key is slow on purpose, so each salt's key is kept
+/// once worked out, and files written are given the salt of one already
+/// read where possible.
+pub struct Cipher {
+    passphrase: Vec<u8>,
+    keys: Mutex<Vec<([u8; SALT_LEN], [u8; KEY_LEN])>>,
+}
+
+impl fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cipher").finish_non_exhaustive()
+    }
+}
+
+impl Cipher {
+    pub fn from_passphrase(passphrase: &str) -> Result<Self, EncryptionError> {
+        Self::new(passphrase.as_bytes().to_vec())
+    }
+
+    /// Uses the whole of a file as the passphrase, e.g. 32 bytes from
+    /// `/dev/urandom`.
+    pub fn from_key_file(path: &Path) -> Result<Self, EncryptionError> {
+        let key = std::fs::read(path)
+            .map_err(|e| EncryptionError::ReadingKeyFile(path.to_path_buf(), e))?;
+        Self::new(key)
+    }
+
+    fn new(passphrase: Vec<u8>) -> Result<Self, EncryptionError> {
+        if passphrase.is_empty() {
+            return Err(EncryptionError::EmptyKey);
+        }
+
+        Ok(Self {
+            passphrase,
+            keys: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Whether `data` was written by [`Cipher::encrypt`].
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let rng = SystemRandom::new();
+        let first_salt = self.keys.lock().unwrap().first().map(|(salt, _)| *salt);
+        let salt = match first_salt {
+            Some(salt) => salt,
+            None => {
+                let mut salt = [0; SALT_LEN];
+                rng.fill(&mut salt)
+                    .map_err(|_| EncryptionError::NoRandomness)?;
+                salt
+            }
+        };
+        let mut nonce = [0; NONCE_LEN];
+        rng.fill(&mut nonce)
+            .map_err(|_| EncryptionError::NoRandomness)?;
+
+        let mut data = [MAGIC, &salt, &nonce].concat();
+        let mut sealed = plaintext.to_vec();
+        self.key(&salt)
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                // The header can't be changed without it showing
+                Aad::from(&data[..MAGIC.len() + SALT_LEN]),
+                &mut sealed,
+            )
+            .map_err(|_| EncryptionError::WrongKey)?;
+        data.extend(sealed);
+
+        Ok(data)
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+        if !Self::is_encrypted(data) || data.len() < header_len {
+            return Err(EncryptionError::Truncated);
+        }
+        let (header, sealed) = data.split_at(header_len);
+        let (aad, nonce) = header.split_at(MAGIC.len() + SALT_LEN);
+        // Lengths checked above
+        let salt: [u8; SALT_LEN] = aad[MAGIC.len()..].try_into().unwrap();
+        let nonce = Nonce::try_assume_unique_for_key(nonce).unwrap();
+
+        let mut opened = sealed.to_vec();
+        let plaintext = self
+            .key(&salt)
+            .open_in_place(nonce, Aad::from(aad), &mut opened)
+            .map_err(|_| EncryptionError::WrongKey)?;
+
+        Ok(plaintext.to_vec())
+    }
+
+    // The key for files with `salt`, worked out the first time it's needed
+    fn key(&self, salt: &[u8; SALT_LEN]) -> LessSafeKey {
+        let mut keys = self.keys.lock().unwrap();
+        let key = match keys.iter().find(|(s, _)| s == salt) {
+            Some((_, key)) => *key,
+            None => {
+                let mut key = [0; KEY_LEN];
+                pbkdf2::derive(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    ITERATIONS,
+                    salt,
+                    &self.passphrase,
+                    &mut key,
+                );
+                keys.push((*salt, key));
+                key
+            }
+        };
+
+        // Always the right length for the algorithm
+        LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &key).unwrap())
+    }
+}
+
+/// The contents of a file, decrypted with `cipher` if there is one. A
+/// plain file is refused then, so one put in place of an encrypted file
+/// isn't taken as it; existing files are moved over with `gridder
+/// encrypt-file`.
+pub fn decrypt_if_needed(
+    data: Vec<u8>,
+    cipher: Option<&Cipher>,
+) -> Result<Vec<u8>, EncryptionError> {
+    match (Cipher::is_encrypted(&data), cipher) {
+        (false, None) => Ok(data),
+        (false, Some(_)) => Err(EncryptionError::NotEncrypted),
+        (true, Some(cipher)) => cipher.decrypt(&data),
+        (true, None) => Err(EncryptionError::NoKey),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &[u8] = b"{\"spreadsheet_id\": \"abc\"}";
+
+    #[test]
+    fn encrypted_files_round_trip() {
+        let cipher = Cipher::from_passphrase("correct horse").unwrap();
+        let encrypted = cipher.encrypt(PLAINTEXT).unwrap();
+        assert!(Cipher::is_encrypted(&encrypted));
+        assert_ne!(&encrypted[MAGIC.len()..], PLAINTEXT);
+
+        let decrypted = decrypt_if_needed(encrypted, Some(&cipher)).unwrap();
+        assert_eq!(decrypted, PLAINTEXT);
+    }
+
+    #[test]
+    fn the_wrong_key_is_refused() {
+        let encrypted = Cipher::from_passphrase("correct horse")
+            .unwrap()
+            .encrypt(PLAINTEXT)
+            .unwrap();
+        let other = Cipher::from_passphrase("battery staple").unwrap();
+        assert!(matches!(
+            decrypt_if_needed(encrypted.clone(), Some(&other)),
+            Err(EncryptionError::WrongKey)
+        ));
+        assert!(matches!(
+            decrypt_if_needed(encrypted, None),
+            Err(EncryptionError::NoKey)
+        ));
+    }
+
+    #[test]
+    fn cut_short_and_tampered_files_are_refused() {
+        let cipher = Cipher::from_passphrase("correct horse").unwrap();
+        let encrypted = cipher.encrypt(PLAINTEXT).unwrap();
+
+        let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+        assert!(matches!(
+            cipher.decrypt(&encrypted[..header_len - 1]),
+            Err(EncryptionError::Truncated)
+        ));
+        // Losing part of the tag is caught too
+        assert!(matches!(
+            cipher.decrypt(&encrypted[..encrypted.len() - 1]),
+            Err(EncryptionError::WrongKey)
+        ));
+
+        // The nonce, outside what's authenticated, and the end of the salt
+        // within it
+        for at in [header_len - 1, MAGIC.len() + SALT_LEN - 1] {
+            let mut tampered = encrypted.clone();
+            tampered[at] ^= 1;
+            assert!(matches!(
+                cipher.decrypt(&tampered),
+                Err(EncryptionError::WrongKey)
+            ));
+        }
+    }
+
+    #[test]
+    fn plain_files_are_refused_with_a_key() {
+        let cipher = Cipher::from_passphrase("correct horse").unwrap();
+        assert!(matches!(
+            decrypt_if_needed(PLAINTEXT.to_vec(), Some(&cipher)),
+            Err(EncryptionError::NotEncrypted)
+        ));
+        assert_eq!(
+            decrypt_if_needed(PLAINTEXT.to_vec(), None).unwrap(),
+            PLAINTEXT
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+use crate::avro;
+use crate::document::GridDocument;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventError {
+    #[error("invalid event sink {0:?}, expected nats://HOST[:PORT] or kafka://BROKER[,BROKER...]")]
+    BadSink(String),
+    #[error("gridder was built without {0} support (the {0} feature)")]
+    NotBuiltIn(&'static str),
+    #[cfg(feature = "nats")]
+    #[error("failed to connect to NATS: {0}")]
+    NatsConnecting(async_nats::ConnectError),
+    #[cfg(feature = "nats")]
+    #[error("failed to publish to NATS: {0}")]
+    NatsPublishing(async_nats::PublishError),
+    #[cfg(feature = "nats")]
+    #[error("failed to flush to NATS: {0}")]
+    NatsFlushing(async_nats::client::FlushError),
+    #[cfg(feature = "kafka")]
+    #[error("failed to publish to Kafka: {0}")]
+    Kafka(rdkafka::error::KafkaError),
+}
+
+/// How documents are serialized when published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EventFormat {
+    /// The `GridDocument` JSON (see `gridder schema`).
+    Json,
+    /// Avro's binary encoding (see `gridder schema --avro`).
+    Avro,
+}
+
+impl EventFormat {
+    /// What the `content-type` header of messages in this format says.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Avro => "avro/binary",
+        }
+    }
+
+    fn encode(self, doc: &GridDocument) -> Vec<u8> {
+        match self {
+            // Serializing a document can't fail
+            Self::Json => serde_json::to_vec(doc).unwrap_or_default(),
+            Self::Avro => avro::encode(doc),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Broker {
+    Nats(String),
+    // Its bootstrap servers, comma-separated
+    Kafka(String),
+}
+
+/// Publishes each day's [`GridDocument`] to NATS or Kafka, for stream
+/// processing downstream. Each message has a `content-type` header, and on
+/// Kafka is keyed by the grid's date.
+///
+/// Only brokers gridder was built with support for can be used, through the
+/// `nats` and `kafka` features.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventSink {
+    broker: Broker,
+    topic: String,
+    format: EventFormat,
+}
+
+impl EventSink {
+    /// `url` is `nats://HOST[:PORT]` or `kafka://BROKER[,BROKER...]`, and
+    /// `topic` the NATS subject or Kafka topic to publish to.
+    pub fn new(url: &str, topic: &str, format: EventFormat) -> Result<Self, EventError> {
+        let bad_sink = || EventError::BadSink(url.to_string());
+        let broker = match url.split_once("://") {
+            Some(("nats", _)) => Broker::Nats(url.to_string()),
+            Some(("kafka", servers)) if !servers.is_empty() => Broker::Kafka(servers.to_string()),
+            _ => return Err(bad_sink()),
+        };
+        match broker {
+            Broker::Nats(_) if !cfg!(feature = "nats") => Err(EventError::NotBuiltIn("nats")),
+            Broker::Kafka(_) if !cfg!(feature = "kafka") => Err(EventError::NotBuiltIn("kafka")),
+            broker => Ok(Self {
+                broker,
+                topic: topic.to_string(),
+                format,
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.broker {
+            Broker::Nats(_) => "nats",
+            Broker::Kafka(_) => "kafka",
+        }
+    }
+
+    pub async fn publish(&self, doc: &GridDocument) -> Result<(), EventError> {
+        let payload = self.format.encode(doc);
+        match &self.broker {
+            Broker::Nats(url) => self.publish_nats(url, payload).await,
+            Broker::Kafka(servers) => self.publish_kafka(servers, &doc.date, payload).await,
+        }
+    }
+
+    #[cfg(feature = "nats")]
+    async fn publish_nats(&self, url: &str, payload: Vec<u8>) -> Result<(), EventError> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(EventError::NatsConnecting)?;
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert("content-type", self.format.content_type());
+        client
+            .publish_with_headers(self.topic.clone(), headers, payload.into())
+            .await
+            .map_err(EventError::NatsPublishing)?;
+        // Published messages are only buffered until flushed
+        client.flush().await.map_err(EventError::NatsFlushing)
+    }
+
+    #[cfg(not(feature = "nats"))]
+    async fn publish_nats(&self, _url: &str, _payload: Vec<u8>) -> Result<(), EventError> {
+        Err(EventError::NotBuiltIn("nats"))
+    }
+
+    #[cfg(feature = "kafka")]
+    async fn publish_kafka(
+        &self,
+        servers: &str,
+        date: &impl fmt::Display,
+        payload: Vec<u8>,
+    ) -> Result<(), EventError> {
+        use rdkafka::message::{Header, OwnedHeaders};
+        use rdkafka::producer::{FutureProducer, FutureRecord};
+        use rdkafka::ClientConfig;
+
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", servers)
+            .set("message.timeout.ms", "30000")
+            .create()
+            .map_err(EventError::Kafka)?;
+        let key = date.to_string();
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "content-type",
+            value: Some(self.format.content_type()),
+        });
+        let record = FutureRecord::to(&self.topic)
+            .key(&key)
+            .payload(&payload)
+            .headers(headers);
+        // The message's timeout above already bounds this
+        producer
+            .send(record, rdkafka::util::Timeout::Never)
+            .await
+            .map_err(|(e, _)| EventError::Kafka(e))?;
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    async fn publish_kafka(
+        &self,
+        _servers: &str,
+        _date: &impl fmt::Display,
+        _payload: Vec<u8>,
+    ) -> Result<(), EventError> {
+        Err(EventError::NotBuiltIn("kafka"))
+    }
+}
@@ -1,12 +1,28 @@
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
 use base64::{prelude::BASE64_STANDARD, Engine};
 use chrono::NaiveDate;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{header, StatusCode};
+use tokio::time::Instant;
+
+use crate::http_log::{Exchange, HttpLog};
+use crate::network::Network;
+use crate::parse::page_date;
 
 const URL_PREFIX: &str = "aHR0cHM6Ly93d3cubnl0aW1lcy5jb20=";
 const URL_SUFFIX: &str = "Y3Jvc3N3b3Jkcy9zcGVsbGluZy1iZWUtZm9ydW0uaHRtbA==";
+// Where an article ends up if it's republished after an amendment
+const AMENDED_URL_SUFFIX: &str = "Y3Jvc3N3b3Jkcy9zcGVsbGluZy1iZWUtZm9ydW0tMi5odG1s";
 
 lazy_static::lazy_static! {
     static ref STR_URL_PREFIX: Vec<u8> = BASE64_STANDARD.decode(URL_PREFIX).unwrap();
     static ref STR_URL_SUFFIX: Vec<u8> = BASE64_STANDARD.decode(URL_SUFFIX).unwrap();
+    static ref STR_AMENDED_URL_SUFFIX: Vec<u8> =
+        BASE64_STANDARD.decode(AMENDED_URL_SUFFIX).unwrap();
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -17,20 +33,582 @@ pub enum FetchDataError {
     BadResponse(reqwest::Error),
     #[error("failed to read response body ({0})")]
     ReadingBody(reqwest::Error),
+    #[error("every session was rejected by the server")]
+    AllSessionsExpired,
+    #[error("no page found for {0}")]
+    NotFound(NaiveDate),
+    #[error("failed to set up the connection to the site ({0})")]
+    Configuring(reqwest::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid host override {0:?}, expected HOST=IP")]
+pub struct HostOverrideError(String);
+
+/// Where pages are fetched from, unless a [`Fetcher`] is told otherwise.
+pub fn default_base_url() -> String {
+    String::from_utf8_lossy(&STR_URL_PREFIX).into_owned()
 }
 
-pub async fn fetch_for_date(date: NaiveDate) -> Result<String, FetchDataError> {
-    let prefix = String::from_utf8_lossy(&STR_URL_PREFIX);
-    let suffix = String::from_utf8_lossy(&STR_URL_SUFFIX);
+fn url_for_date(base_url: &str, date: NaiveDate) -> String {
+    url_with_suffix(base_url, date, &STR_URL_SUFFIX)
+}
+
+fn url_with_suffix(base_url: &str, date: NaiveDate, suffix: &[u8]) -> String {
+    let suffix = String::from_utf8_lossy(suffix);
     let date_str = date.format("%Y/%m/%d");
-    let url_str = format!("{prefix}/{date_str}/{suffix}");
+    format!("{base_url}/{date_str}/{suffix}")
+}
+
+// Other places the article for a date has turned up, most likely first
+fn alternative_urls(base_url: &str, date: NaiveDate) -> Vec<String> {
+    let mut urls = vec![url_with_suffix(base_url, date, &STR_AMENDED_URL_SUFFIX)];
+    for adjacent in [date.pred_opt(), date.succ_opt()].into_iter().flatten() {
+        urls.push(url_for_date(base_url, adjacent));
+        urls.push(url_with_suffix(base_url, adjacent, &STR_AMENDED_URL_SUFFIX));
+    }
+
+    urls
+}
 
-    // TODO: subtle user agent?
-    let resp = reqwest::get(url_str)
+pub async fn fetch_for_date(network: Network, date: NaiveDate) -> Result<String, FetchDataError> {
+    Fetcher::new(network, vec![Profile::anonymous()])
+        .fetch_for_date(date)
         .await
-        .map_err(FetchDataError::FetchingUrl)?
-        .error_for_status()
-        .map_err(FetchDataError::BadResponse)?;
+}
+
+/// A session to fetch pages with.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    /// Value of the `Cookie` header to send, if any.
+    pub cookie: Option<String>,
+    /// Minimum time between two requests made with this profile.
+    pub min_interval: Duration,
+}
+
+impl Profile {
+    pub fn anonymous() -> Self {
+        Self {
+            name: "anonymous".to_string(),
+            cookie: None,
+            min_interval: Duration::ZERO,
+        }
+    }
+}
+
+/// Which version of IP to reach the site over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum IpVersion {
+    #[value(name = "4")]
+    V4,
+    #[value(name = "6")]
+    V6,
+}
+
+impl IpVersion {
+    fn matches(self, ip: IpAddr) -> bool {
+        match self {
+            Self::V4 => ip.is_ipv4(),
+            Self::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V4 => write!(f, "IPv4"),
+            Self::V6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+/// A host to connect to at a fixed address rather than the one DNS gives,
+/// written `HOST=IP`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostOverride {
+    pub host: String,
+    pub ip: IpAddr,
+}
+
+impl FromStr for HostOverride {
+    type Err = HostOverrideError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad_override = || HostOverrideError(s.to_string());
+        let (host, ip) = s.split_once('=').ok_or_else(bad_override)?;
+        let host = host.trim();
+        if host.is_empty() {
+            return Err(bad_override());
+        }
+        // IPv6 addresses may be bracketed, as in URLs
+        let ip = ip.trim().trim_start_matches('[').trim_end_matches(']');
+
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            ip: ip.parse().map_err(|_| bad_override())?,
+        })
+    }
+}
+
+/// How a [`Fetcher`] connects to the site, for networks where looking it
+/// up the usual way goes wrong.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// Only connect over this version of IP, e.g. where IPv6 is broken.
+    pub ip_version: Option<IpVersion>,
+    /// Hosts to connect to at a fixed address. These are used whatever
+    /// `ip_version` is.
+    pub overrides: Vec<HostOverride>,
+}
+
+impl ConnectOptions {
+    fn client(&self, network: Network) -> Result<reqwest::Client, reqwest::Error> {
+        let mut builder = network.client_builder();
+        if let Some(version) = self.ip_version {
+            builder = builder.dns_resolver(std::sync::Arc::new(VersionResolver(version)));
+        }
+        for host_override in &self.overrides {
+            // The port is ignored, the URL's is used
+            builder = builder.resolve(&host_override.host, SocketAddr::new(host_override.ip, 0));
+        }
+        builder.build()
+    }
+}
+
+// Looks hosts up as usual, keeping only the addresses of one IP version
+struct VersionResolver(IpVersion);
+
+impl Resolve for VersionResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let version = self.0;
+        Box::pin(async move {
+            let found = tokio::net::lookup_host((name.as_str(), 0)).await?;
+            let addrs = found
+                .filter(|addr| version.matches(addr.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                let message = format!("{} has no {version} address", name.as_str());
+                return Err(std::io::Error::new(std::io::ErrorKind::NotFound, message).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+struct ProfileState {
+    profile: Profile,
+    last_request: Option<Instant>,
+    expired: bool,
+}
+
+/// Fetches pages, spreading requests across several profiles in turn.
+///
+/// Each profile is rate-limited on its own. A profile whose session is
+/// rejected (401/403) is dropped for the rest of the run, and the request is
+/// retried with the next one.
+pub struct Fetcher {
+    network: Network,
+    client: reqwest::Client,
+    profiles: Vec<ProfileState>,
+    next: usize,
+    probe_alternatives: bool,
+    base_url: String,
+    last_url: Option<String>,
+    last_addr: Option<SocketAddr>,
+    http_log: Option<HttpLog>,
+}
+
+impl Fetcher {
+    pub fn new(network: Network, profiles: Vec<Profile>) -> Self {
+        Self {
+            network,
+            client: network.client(),
+            profiles: profiles
+                .into_iter()
+                .map(|profile| ProfileState {
+                    profile,
+                    last_request: None,
+                    expired: false,
+                })
+                .collect(),
+            next: 0,
+            probe_alternatives: false,
+            base_url: default_base_url(),
+            last_url: None,
+            last_addr: None,
+            http_log: None,
+        }
+    }
+
+    /// Records each request made, and what came back, in `log`.
+    pub fn http_log(mut self, log: HttpLog) -> Self {
+        self.http_log = Some(log);
+        self
+    }
+
+    /// Connects to the site as `options` say rather than as usual.
+    pub fn connect_options(mut self, options: &ConnectOptions) -> Result<Self, FetchDataError> {
+        self.client = options
+            .client(self.network)
+            .map_err(FetchDataError::Configuring)?;
+        Ok(self)
+    }
+
+    /// Fetches pages from under `base_url` (e.g. a mirror, a proxy or a
+    /// local test server) instead of the usual site. Each fetcher keeps its
+    /// own, so several can point at different places at once.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// When a date's page isn't at its usual URL, look for it under the
+    /// neighbouring dates and the amended slug. A page found that way is
+    /// only used if it says it's for the right date.
+    pub fn probe_alternatives(mut self, probe: bool) -> Self {
+        self.probe_alternatives = probe;
+        self
+    }
+
+    /// The URL the last page fetched was found at.
+    pub fn last_url(&self) -> Option<&str> {
+        self.last_url.as_deref()
+    }
+
+    /// The address the last response came from, e.g. to check which of a
+    /// site's addresses is being used.
+    pub fn last_addr(&self) -> Option<SocketAddr> {
+        self.last_addr
+    }
+
+    pub async fn fetch_for_date(&mut self, date: NaiveDate) -> Result<String, FetchDataError> {
+        let url = url_for_date(&self.base_url, date);
+        if let Some(body) = self.fetch_url(&url).await? {
+            self.last_url = Some(url);
+            return Ok(body);
+        }
+        if !self.probe_alternatives {
+            return Err(FetchDataError::NotFound(date));
+        }
+
+        for url in alternative_urls(&self.base_url, date) {
+            let Some(body) = self.fetch_url(&url).await? else {
+                continue;
+            };
+            if page_date(&body) == Some(date) {
+                self.last_url = Some(url);
+                return Ok(body);
+            }
+        }
+
+        Err(FetchDataError::NotFound(date))
+    }
+
+    // Gives None if there's no page at the URL
+    async fn fetch_url(&mut self, url_str: &str) -> Result<Option<String>, FetchDataError> {
+        // Each attempt either succeeds, fails outright, or expires a profile,
+        // so this is bounded by the number of profiles
+        while let Some(index) = self.next_live_profile() {
+            let state = &mut self.profiles[index];
+            if let Some(last) = state.last_request {
+                tokio::time::sleep_until(last + state.profile.min_interval).await;
+            }
+            state.last_request = Some(Instant::now());
+
+            // TODO: subtle user agent?
+            let mut request = self.client.get(url_str);
+            if let Some(cookie) = &state.profile.cookie {
+                request = request.header(header::COOKIE, cookie);
+            }
+            let request = request.build().map_err(FetchDataError::FetchingUrl)?;
+            let mut exchange = Exchange::start("fetch", &format!("GET {}", request.url()));
+            exchange.request_headers(header_pairs(request.headers()));
+            let resp = match self.client.execute(request).await {
+                Ok(resp) => resp,
+                Err(e) => {
+                    exchange.failed(&e);
+                    record(&self.http_log, exchange, None);
+                    return Err(FetchDataError::FetchingUrl(e));
+                }
+            };
+            self.last_addr = resp.remote_addr();
+            exchange.response(resp.status().as_u16(), header_pairs(resp.headers()));
+
+            // Without a session there's nothing to rotate away from
+            let rejected = matches!(
+                resp.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            );
+            if rejected && state.profile.cookie.is_some() {
+                record_response(&self.http_log, exchange, resp).await;
+                state.expired = true;
+                continue;
+            }
+
+            if resp.status() == StatusCode::NOT_FOUND {
+                record_response(&self.http_log, exchange, resp).await;
+                return Ok(None);
+            }
+
+            if let Err(e) = resp.error_for_status_ref() {
+                record_response(&self.http_log, exchange, resp).await;
+                return Err(FetchDataError::BadResponse(e));
+            }
+            let body = resp.text().await.map_err(FetchDataError::ReadingBody)?;
+            record(&self.http_log, exchange, Some(body.as_bytes()));
+            return Ok(Some(body));
+        }
+
+        Err(FetchDataError::AllSessionsExpired)
+    }
+
+    fn next_live_profile(&mut self) -> Option<usize> {
+        let count = self.profiles.len();
+        let index = (0..count)
+            .map(|offset| (self.next + offset) % count)
+            .find(|i| !self.profiles[*i].expired)?;
+        self.next = (index + 1) % count;
+        Some(index)
+    }
+}
+
+fn header_pairs(headers: &header::HeaderMap) -> impl Iterator<Item = (&str, &[u8])> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_bytes()))
+}
+
+fn record(log: &Option<HttpLog>, exchange: Exchange, body: Option<&[u8]>) {
+    if let Some(log) = log {
+        log.record(exchange, body);
+    }
+}
+
+// Records a response that isn't the page, with its body if bodies are
+// kept, as that usually says what went wrong
+async fn record_response(log: &Option<HttpLog>, exchange: Exchange, resp: reqwest::Response) {
+    if let Some(log) = log {
+        let body = match log.records_bodies() {
+            true => resp.bytes().await.ok(),
+            false => None,
+        };
+        log.record(exchange, body.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Connectivity;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn page(published: &str) -> String {
+        format!(
+            r#"<html><head><meta property="article:published_time" content="{published}"></head></html>"#
+        )
+    }
+
+    // Serves each request with what `route` gives for its path and head,
+    // closing the connection after each
+    async fn serve(route: impl Fn(&str, &str) -> (u16, String) + Send + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = route(path, &request);
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        base_url
+    }
+
+    fn fetcher(base_url: &str) -> Fetcher {
+        let network = Connectivity::Online.network("testing").unwrap();
+        Fetcher::new(network, vec![Profile::anonymous()]).base_url(base_url)
+    }
+
+    fn profile(name: &str, cookie: Option<&str>) -> Profile {
+        Profile {
+            name: name.to_string(),
+            cookie: cookie.map(str::to_string),
+            min_interval: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn profiles_are_taken_in_turn_skipping_expired_ones() {
+        let network = Connectivity::Online.network("testing").unwrap();
+        let profiles = ["a", "b", "c"].map(|name| profile(name, Some(name)));
+        let mut fetcher = Fetcher::new(network, profiles.to_vec());
+        let turns = |fetcher: &mut Fetcher| {
+            (0..4)
+                .map(|_| fetcher.next_live_profile())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(turns(&mut fetcher), [Some(0), Some(1), Some(2), Some(0)]);
+
+        fetcher.profiles[2].expired = true;
+        assert_eq!(turns(&mut fetcher), [Some(1), Some(0), Some(1), Some(0)]);
+
+        fetcher.profiles[0].expired = true;
+        fetcher.profiles[1].expired = true;
+        assert_eq!(fetcher.next_live_profile(), None);
+    }
+
+    #[tokio::test]
+    async fn rejected_sessions_are_dropped_for_the_next() {
+        let base_url = serve(|_, request| match request.contains("cookie: stale") {
+            true => (403, String::new()),
+            false => (200, page("2024-05-02T07:00:00.000Z")),
+        })
+        .await;
+        let network = Connectivity::Online.network("testing").unwrap();
+        let profiles = vec![
+            profile("stale", Some("stale")),
+            profile("fresh", Some("fresh")),
+        ];
+        let mut fetcher = Fetcher::new(network, profiles).base_url(&base_url);
+
+        for _ in 0..2 {
+            fetcher.fetch_for_date(date(2)).await.unwrap();
+        }
+        assert!(fetcher.profiles[0].expired);
+        assert!(!fetcher.profiles[1].expired);
+    }
+
+    #[tokio::test]
+    async fn every_session_rejected_fails() {
+        let base_url = serve(|_, _| (401, String::new())).await;
+        let network = Connectivity::Online.network("testing").unwrap();
+        let mut fetcher =
+            Fetcher::new(network, vec![profile("stale", Some("stale"))]).base_url(&base_url);
+
+        let fetched = fetcher.fetch_for_date(date(2)).await;
+        assert!(matches!(fetched, Err(FetchDataError::AllSessionsExpired)));
+    }
+
+    #[test]
+    fn alternatives_are_the_amended_slug_then_the_neighbouring_days() {
+        let base = "https://example.com";
+        let urls = alternative_urls(base, date(2));
+        let amended = |date| url_with_suffix(base, date, &STR_AMENDED_URL_SUFFIX);
+
+        assert_eq!(
+            urls,
+            [
+                amended(date(2)),
+                url_for_date(base, date(1)),
+                amended(date(1)),
+                url_for_date(base, date(3)),
+                amended(date(3)),
+            ]
+        );
+        assert!(urls[0].starts_with("https://example.com/2024/05/02/"));
+    }
+
+    #[tokio::test]
+    async fn missing_pages_are_only_probed_for_when_asked() {
+        let base_url = serve(|_, _| (404, String::new())).await;
+
+        let fetched = fetcher(&base_url).fetch_for_date(date(2)).await;
+        assert!(matches!(fetched, Err(FetchDataError::NotFound(day)) if day == date(2)));
+    }
+
+    #[tokio::test]
+    async fn probed_pages_must_be_for_the_date() {
+        // The next day's page is the next day's; the amended one for the day
+        // before is really this day's
+        let next_day = url_for_date("", date(3));
+        let amended_day_before = url_with_suffix("", date(1), &STR_AMENDED_URL_SUFFIX);
+        let base_url = serve(move |path, _| match path {
+            path if path == next_day => (200, page("2024-05-03T07:00:00.000Z")),
+            path if path == amended_day_before => (200, page("2024-05-02T07:00:00.000Z")),
+            _ => (404, String::new()),
+        })
+        .await;
+
+        let mut fetcher = fetcher(&base_url).probe_alternatives(true);
+        let body = fetcher.fetch_for_date(date(2)).await.unwrap();
+        assert_eq!(page_date(&body), Some(date(2)));
+        assert_eq!(
+            fetcher.last_url(),
+            Some(
+                format!(
+                    "{base_url}{}",
+                    url_with_suffix("", date(1), &STR_AMENDED_URL_SUFFIX)
+                )
+                .as_str()
+            )
+        );
+
+        let fetched = fetcher.fetch_for_date(date(10)).await;
+        assert!(matches!(fetched, Err(FetchDataError::NotFound(_))));
+    }
+
+    #[test]
+    fn host_overrides_are_host_and_ip() {
+        let parsed = "Example.com=127.0.0.1".parse::<HostOverride>().unwrap();
+        assert_eq!(parsed.host, "example.com");
+        assert_eq!(parsed.ip, IpAddr::from([127, 0, 0, 1]));
+        let parsed = "example.com = [::1]".parse::<HostOverride>().unwrap();
+        assert_eq!(parsed.ip, "::1".parse::<IpAddr>().unwrap());
+
+        for bad in [
+            "example.com",
+            "=127.0.0.1",
+            "example.com=",
+            "example.com=localhost",
+        ] {
+            assert!(bad.parse::<HostOverride>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn ip_versions_match_their_addresses() {
+        let (v4, v6) = (IpAddr::from([127, 0, 0, 1]), "::1".parse().unwrap());
+        assert!(IpVersion::V4.matches(v4) && !IpVersion::V4.matches(v6));
+        assert!(IpVersion::V6.matches(v6) && !IpVersion::V6.matches(v4));
+    }
+
+    #[tokio::test]
+    async fn overridden_hosts_are_reached_at_their_address() {
+        let base_url = serve(|_, request| (200, request.to_string())).await;
+        let port = base_url.rsplit(':').next().unwrap();
+        let options = ConnectOptions {
+            ip_version: Some(IpVersion::V6),
+            overrides: vec!["grids.invalid=127.0.0.1".parse().unwrap()],
+        };
+        let mut fetcher = fetcher(&format!("http://grids.invalid:{port}"))
+            .connect_options(&options)
+            .unwrap();
 
-    resp.text().await.map_err(FetchDataError::ReadingBody)
+        let body = fetcher.fetch_for_date(date(2)).await.unwrap();
+        assert!(body.contains("host: grids.invalid"));
+        assert_eq!(
+            fetcher.last_addr().map(|addr| addr.ip()),
+            Some(IpAddr::from([127, 0, 0, 1]))
+        );
+    }
 }
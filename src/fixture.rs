@@ -0,0 +1,25 @@
+use chrono::NaiveDate;
+
+/// A day's page bundled with gridder, so its outputs can be produced the
+/// same anywhere, e.g. for other projects to snapshot-test what they make
+/// of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub date: NaiveDate,
+    pub page: &'static str,
+}
+
+pub const FIXTURES: [Fixture; 1] = [Fixture {
+    name: "sample",
+    date: match NaiveDate::from_ymd_opt(2024, 5, 1) {
+        Some(date) => date,
+        None => unreachable!(),
+    },
+    page: include_str!("../fixtures/sample.html"),
+}];
+
+/// The bundled fixture called `name`.
+pub fn find(name: &str) -> Option<&'static Fixture> {
+    FIXTURES.iter().find(|fixture| fixture.name == name)
+}
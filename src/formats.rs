@@ -0,0 +1,289 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::document::{DocumentError, GridDocument};
+use crate::parse::CsvImportError;
+
+/// File formats a [`GridDocument`] can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// A lengths file (`letter,length,count`) and a pairs file (`pair,count`).
+    Csv,
+    /// The versioned `GridDocument` JSON (see `gridder schema`).
+    Json,
+    /// Any text, rendered from a Tera template given with `--template`.
+    /// Output only, in builds with the templates feature.
+    Template,
+    /// The grid described in sentences rather than tables, for screen
+    /// readers. Output only.
+    Prose,
+}
+
+impl Format {
+    /// Guesses the format of a file from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON document: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to write CSV: {0}")]
+    WritingCsv(#[from] csv::Error),
+    #[error("failed to read CSV: {0}")]
+    ReadingCsv(#[from] CsvImportError),
+    #[error("unusable document: {0}")]
+    Document(#[from] DocumentError),
+    #[error("gridder was built without {0} support (the {0} feature)")]
+    NotBuiltIn(&'static str),
+    #[cfg(feature = "templates")]
+    #[error("failed to render template: {}", error_chain(.0))]
+    Template(#[from] tera::Error),
+}
+
+// Tera keeps the useful part of the message (what went wrong, and where) in
+// the error's sources
+#[cfg(feature = "templates")]
+fn error_chain(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    message
+}
+
+pub fn read_json<R: Read>(reader: R) -> Result<GridDocument, FormatError> {
+    let doc: GridDocument = serde_json::from_reader(reader)?;
+    doc.check_version()?;
+    Ok(doc)
+}
+
+pub fn write_json<W: Write>(doc: &GridDocument, mut writer: W) -> Result<(), FormatError> {
+    serde_json::to_writer_pretty(&mut writer, doc)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Renders `doc` through a Tera `template`, whose variables are the fields
+/// of the JSON document (e.g. `{{ date }}`, `{% for p in pairs %}`).
+#[cfg(feature = "templates")]
+pub fn write_template<W: Write>(
+    doc: &GridDocument,
+    template: &str,
+    mut writer: W,
+) -> Result<(), FormatError> {
+    let context = tera::Context::from_serialize(doc)?;
+    // Output is text of any kind, not necessarily HTML
+    let rendered = tera::Tera::one_off(template, &context, false)?;
+    writer.write_all(rendered.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(not(feature = "templates"))]
+pub fn write_template<W: Write>(
+    _doc: &GridDocument,
+    _template: &str,
+    _writer: W,
+) -> Result<(), FormatError> {
+    Err(FormatError::NotBuiltIn("templates"))
+}
+
+/// Describes `doc` in plain sentences, one letter per line (e.g. "M: three
+/// 4-letter words, two 5-letter words."), since tables read badly aloud.
+pub fn write_prose<W: Write>(doc: &GridDocument, mut writer: W) -> Result<(), FormatError> {
+    write!(
+        writer,
+        "Grid for {}: {}",
+        doc.date,
+        counted(doc.totals.words, "word")
+    )?;
+    if let Some(summary) = doc.summary {
+        write!(
+            writer,
+            ", {} and {}",
+            counted(summary.points, "point"),
+            counted(summary.pangrams, "pangram")
+        )?;
+    }
+    writeln!(writer, ".")?;
+    let letters = doc.letters.iter().map(char::to_string).collect::<Vec<_>>();
+    writeln!(writer, "Letters: {}.", letters.join(", "))?;
+
+    for letter in &doc.letters {
+        let lengths = doc
+            .lengths
+            .iter()
+            .filter(|e| e.letter == *letter)
+            .map(|e| counted(e.count.0, &format!("{}-letter word", e.length)))
+            .collect::<Vec<_>>();
+        let total = doc.totals.by_letter.get(letter).copied().unwrap_or(0);
+        writeln!(
+            writer,
+            "{letter}: {}. {} in all.",
+            lengths.join(", "),
+            capitalized(&counted(total, "word"))
+        )?;
+    }
+
+    if !doc.pairs.is_empty() {
+        let pairs = doc
+            .pairs
+            .iter()
+            .map(|e| format!("{} {}", e.pair.to_ascii_uppercase(), number(e.count.0)))
+            .collect::<Vec<_>>();
+        writeln!(
+            writer,
+            "Words starting with each pair: {}.",
+            pairs.join(", ")
+        )?;
+    }
+
+    Ok(())
+}
+
+// Small numbers are spelled out, as they'd be read
+fn number(n: usize) -> String {
+    const WORDS: [&str; 13] = [
+        "no", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve",
+    ];
+    WORDS
+        .get(n)
+        .map(|word| word.to_string())
+        .unwrap_or_else(|| n.to_string())
+}
+
+fn counted(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("one {noun}"),
+        _ => format!("{} {noun}s", number(n)),
+    }
+}
+
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// One of the files a day's grid is kept as, e.g. in a bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayFile {
+    pub name: String,
+    pub contents: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+/// The day's JSON document and two CSV files, named after its date as
+/// `gridder replay` names them (`YYYY-MM-DD.json`, `YYYY-MM-DD.lengths.csv`
+/// and `YYYY-MM-DD.pairs.csv`).
+pub fn day_files(doc: &GridDocument) -> Result<Vec<DayFile>, FormatError> {
+    let mut json = Vec::new();
+    write_json(doc, &mut json)?;
+    let (mut lengths, mut pairs) = (Vec::new(), Vec::new());
+    write_csv(doc, &mut lengths, &mut pairs)?;
+
+    let date = doc.date;
+    Ok(vec![
+        DayFile {
+            name: format!("{date}.json"),
+            contents: json,
+            content_type: "application/json",
+        },
+        DayFile {
+            name: format!("{date}.lengths.csv"),
+            contents: lengths,
+            content_type: "text/csv",
+        },
+        DayFile {
+            name: format!("{date}.pairs.csv"),
+            contents: pairs,
+            content_type: "text/csv",
+        },
+    ])
+}
+
+/// Writes grids as one long table, a row per count
+/// (`date,kind,key,length,count`), for loading into other tools. Lengths are
+/// `length` rows keyed by letter, and pairs `pair` rows with no length.
+///
+/// Rows are written a day at a time through a buffer of a set size, so an
+/// export of years of grids takes no more memory than one of a day.
+pub struct LongCsvWriter<W: Write> {
+    writer: csv::Writer<W>,
+}
+
+impl<W: Write> LongCsvWriter<W> {
+    /// Writes the header, holding up to `buffer_size` bytes of rows before
+    /// writing them out.
+    pub fn new(writer: W, buffer_size: usize) -> Result<Self, FormatError> {
+        let mut writer = csv::WriterBuilder::new()
+            .buffer_capacity(buffer_size)
+            .from_writer(writer);
+        writer.write_record(["date", "kind", "key", "length", "count"])?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, doc: &GridDocument) -> Result<(), FormatError> {
+        let date = doc.date.to_string();
+        for entry in &doc.lengths {
+            self.writer.write_record([
+                date.as_str(),
+                "length",
+                &entry.letter.to_string(),
+                &entry.length.to_string(),
+                &entry.count.to_string(),
+            ])?;
+        }
+        for entry in &doc.pairs {
+            let count = entry.count.to_string();
+            let row = [date.as_str(), "pair", &entry.pair, "", &count];
+            self.writer.write_record(row)?;
+        }
+        Ok(())
+    }
+
+    /// Writes out the rows held so far.
+    pub fn flush(&mut self) -> Result<(), FormatError> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Writes the two CSV files read back by [`crate::parse::from_csv`].
+pub fn write_csv<L, P>(doc: &GridDocument, lengths: L, pairs: P) -> Result<(), FormatError>
+where
+    L: Write,
+    P: Write,
+{
+    let mut lengths_writer = csv::Writer::from_writer(lengths);
+    lengths_writer.write_record(["letter", "length", "count"])?;
+    for entry in &doc.lengths {
+        lengths_writer.write_record([
+            entry.letter.to_string(),
+            entry.length.to_string(),
+            entry.count.to_string(),
+        ])?;
+    }
+    lengths_writer.flush()?;
+
+    let mut pairs_writer = csv::Writer::from_writer(pairs);
+    pairs_writer.write_record(["pair", "count"])?;
+    for entry in &doc.pairs {
+        pairs_writer.write_record([entry.pair.clone(), entry.count.to_string()])?;
+    }
+    pairs_writer.flush()?;
+
+    Ok(())
+}
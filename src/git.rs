@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use tokio::process::Command;
+
+use crate::document::GridDocument;
+use crate::formats::{self, FormatError};
+use crate::network::Network;
+use crate::notify::PostTemplate;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GitError {
+    #[error("failed to write the grid ({0})")]
+    Formatting(#[from] FormatError),
+    #[error("failed to write {0} ({1})")]
+    Writing(PathBuf, std::io::Error),
+    #[error("failed to run git ({0})")]
+    Running(std::io::Error),
+    #[error("git {command} failed in {dir}: {message}")]
+    Failed {
+        command: &'static str,
+        dir: PathBuf,
+        message: String,
+    },
+}
+
+/// Keeps each day's grid in a git repository, one commit per day, for
+/// history and diffs of the daily data for free.
+///
+/// Each day's [files](formats::day_files) are written to the directory,
+/// which must be in a repository already, and committed on their own (so
+/// anything else staged there is left alone), then optionally pushed. A
+/// day whose files haven't changed isn't committed again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSink {
+    dir: PathBuf,
+    message: PostTemplate,
+    push: bool,
+}
+
+impl GitSink {
+    /// Commits to `dir` with `message` rendered for each day, and pushes
+    /// after if given the network to.
+    pub fn new(dir: PathBuf, message: PostTemplate, push: Option<Network>) -> Self {
+        Self {
+            dir,
+            message,
+            push: push.is_some(),
+        }
+    }
+
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    pub async fn commit(&self, doc: &GridDocument) -> Result<(), GitError> {
+        let mut names = Vec::new();
+        for file in formats::day_files(doc)? {
+            let path = self.dir.join(&file.name);
+            tokio::fs::write(&path, file.contents)
+                .await
+                .map_err(|e| GitError::Writing(path, e))?;
+            names.push(file.name);
+        }
+
+        self.git("add", &["add", "--"], &names).await?;
+        // Exits with 1 if any of the files changed
+        let unchanged = self
+            .command(&["diff", "--cached", "--quiet", "--"], &names)
+            .status()
+            .await
+            .map_err(GitError::Running)?
+            .success();
+        if unchanged {
+            return Ok(());
+        }
+        let message = self.message.render(doc);
+        self.git(
+            "commit",
+            &["commit", "--quiet", "-m", &message, "--"],
+            &names,
+        )
+        .await?;
+        if self.push {
+            self.git("push", &["push", "--quiet"], &[]).await?;
+        }
+
+        Ok(())
+    }
+
+    fn command(&self, args: &[&str], names: &[String]) -> Command {
+        let mut command = Command::new("git");
+        command
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .args(names)
+            // Asking for a password would hang a scheduled run
+            .env("GIT_TERMINAL_PROMPT", "0")
+            // A push given up on mustn't land after the run's failed
+            .kill_on_drop(true);
+        command
+    }
+
+    async fn git(
+        &self,
+        name: &'static str,
+        args: &[&str],
+        names: &[String],
+    ) -> Result<(), GitError> {
+        let output = self
+            .command(args, names)
+            .output()
+            .await
+            .map_err(GitError::Running)?;
+        if !output.status.success() {
+            // git's last line is usually the one that says what's wrong
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .map_or_else(|| output.status.to_string(), |line| line.trim().to_string());
+            return Err(GitError::Failed {
+                command: name,
+                dir: self.dir.clone(),
+                message,
+            });
+        }
+
+        Ok(())
+    }
+}
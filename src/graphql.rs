@@ -0,0 +1,326 @@
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::NaiveDate;
+
+use std::sync::Arc;
+
+use crate::archive::Store;
+use crate::document::GridDocument;
+use crate::report::{Period, Report};
+
+// Longest range one query can read, as with the dashboard's history
+const MAX_RANGE_DAYS: i64 = 400;
+
+pub type GridSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// A GraphQL endpoint at `/graphql` over the grids in a [`Store`], for
+/// frontends that would rather ask for what they need than use the
+/// dashboard's fixed JSON API. Queries are POSTed as JSON in the usual
+/// `{"query": ..., "variables": ...}` form.
+pub fn router(store: Arc<dyn Store>) -> Router {
+    Router::new()
+        .route("/graphql", post(execute))
+        .with_state(schema(store))
+}
+
+pub fn schema(store: Arc<dyn Store>) -> GridSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(store)
+        .finish()
+}
+
+async fn execute(
+    State(schema): State<GridSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The grid saved for `date`, if there is one.
+    async fn grid(
+        &self,
+        ctx: &Context<'_>,
+        date: NaiveDate,
+    ) -> async_graphql::Result<Option<Grid>> {
+        Ok(ctx.data::<Arc<dyn Store>>()?.load_grid(date)?.map(Grid))
+    }
+
+    /// The most recently saved grid.
+    async fn latest(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Grid>> {
+        let store = ctx.data::<Arc<dyn Store>>()?;
+        match store.list_dates()?.last() {
+            Some(date) => Ok(store.load_grid(*date)?.map(Grid)),
+            None => Ok(None),
+        }
+    }
+
+    /// The grids saved from `from` to `to` inclusive, in date order.
+    async fn grids(
+        &self,
+        ctx: &Context<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> async_graphql::Result<Vec<Grid>> {
+        Ok(load_range(ctx, from, to)?.into_iter().map(Grid).collect())
+    }
+
+    /// Totals over the grids saved from `from` to `to` inclusive.
+    async fn aggregate(
+        &self,
+        ctx: &Context<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> async_graphql::Result<Aggregate> {
+        let docs = load_range(ctx, from, to)?;
+        let period = Period {
+            name: format!("{from} to {to}"),
+            first: from,
+            last: to,
+        };
+        Ok(Aggregate(Report::build(period, &docs, None)))
+    }
+
+    /// Each day from `from` to `to` inclusive with `letter` in its grid,
+    /// with the words starting with it.
+    async fn letter(
+        &self,
+        ctx: &Context<'_>,
+        letter: String,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> async_graphql::Result<Vec<LetterDay>> {
+        let letter = parse_letter(&letter)?;
+        let days = load_range(ctx, from, to)?
+            .iter()
+            .filter(|doc| doc.letters.contains(&letter))
+            .map(|doc| LetterDay {
+                date: doc.date,
+                words: doc.totals.by_letter.get(&letter).copied().unwrap_or(0),
+                lengths: lengths(doc, Some(letter)),
+            })
+            .collect();
+
+        Ok(days)
+    }
+}
+
+fn load_range(
+    ctx: &Context<'_>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> async_graphql::Result<Vec<GridDocument>> {
+    if to < from {
+        return Err(format!("{to} is before {from}").into());
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!("ranges can be at most {MAX_RANGE_DAYS} days").into());
+    }
+
+    Ok(ctx.data::<Arc<dyn Store>>()?.load_range(from, to)?)
+}
+
+// Letters are kept in upper case, but either is accepted
+fn parse_letter(letter: &str) -> async_graphql::Result<char> {
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(c.to_ascii_uppercase()),
+        _ => Err(format!("{letter:?} isn't a single letter").into()),
+    }
+}
+
+fn lengths(doc: &GridDocument, letter: Option<char>) -> Vec<LengthCount> {
+    doc.lengths
+        .iter()
+        .filter(|entry| letter.is_none_or(|letter| entry.letter == letter))
+        .map(|entry| LengthCount {
+            letter: entry.letter.to_string(),
+            length: entry.length.0,
+            count: entry.count.0,
+        })
+        .collect()
+}
+
+/// A saved grid.
+pub struct Grid(GridDocument);
+
+#[Object]
+impl Grid {
+    async fn date(&self) -> NaiveDate {
+        self.0.date
+    }
+
+    /// The grid's letters, e.g. "ACDELNT".
+    async fn letters(&self) -> String {
+        self.0.letters.iter().collect()
+    }
+
+    async fn words(&self) -> usize {
+        self.0.totals.words
+    }
+
+    /// How far the grid can be trusted, from 0 to 100, if it was checked.
+    async fn quality(&self) -> Option<u8> {
+        self.0.quality
+    }
+
+    async fn points(&self) -> Option<usize> {
+        self.0.summary.map(|summary| summary.points)
+    }
+
+    async fn pangrams(&self) -> Option<usize> {
+        self.0.summary.map(|summary| summary.pangrams)
+    }
+
+    async fn hints(&self) -> &[String] {
+        &self.0.hints
+    }
+
+    /// The grid's non-zero counts, or only `letter`'s row.
+    async fn lengths(&self, letter: Option<String>) -> async_graphql::Result<Vec<LengthCount>> {
+        let letter = letter.as_deref().map(parse_letter).transpose()?;
+        Ok(lengths(&self.0, letter))
+    }
+
+    /// The two-letter list, or only the pairs starting with `letter`.
+    async fn pairs(&self, letter: Option<String>) -> async_graphql::Result<Vec<PairCount>> {
+        let letter = letter.as_deref().map(parse_letter).transpose()?;
+        let pairs = self
+            .0
+            .pairs
+            .iter()
+            .filter(|entry| {
+                letter.is_none_or(|letter| entry.pair.to_ascii_uppercase().starts_with(letter))
+            })
+            .map(|entry| PairCount {
+                pair: entry.pair.clone(),
+                count: entry.count.0,
+            })
+            .collect();
+
+        Ok(pairs)
+    }
+
+    /// Words by their first letter.
+    async fn by_letter(&self) -> Vec<LetterTotal> {
+        self.0
+            .totals
+            .by_letter
+            .iter()
+            .map(|(letter, words)| LetterTotal {
+                letter: letter.to_string(),
+                words: *words,
+            })
+            .collect()
+    }
+
+    /// Words by their length.
+    async fn by_length(&self) -> Vec<LengthTotal> {
+        length_totals(&self.0.totals.by_length)
+    }
+}
+
+/// Totals over a range of days.
+pub struct Aggregate(Report);
+
+#[Object]
+impl Aggregate {
+    /// How many days in the range have a saved grid.
+    async fn days(&self) -> usize {
+        self.0.days.len()
+    }
+
+    /// Days in the range with no saved grid.
+    async fn missing(&self) -> &[NaiveDate] {
+        &self.0.missing
+    }
+
+    async fn words(&self) -> usize {
+        self.0.words
+    }
+
+    /// Mean length of every word in the range.
+    async fn average_length(&self) -> Option<f64> {
+        self.0.average_length
+    }
+
+    /// Points over the days whose summary was saved.
+    async fn points(&self) -> usize {
+        self.0.days.iter().filter_map(|day| day.points).sum()
+    }
+
+    /// Pangrams over the days whose summary was saved.
+    async fn pangrams(&self) -> usize {
+        self.0.pangrams
+    }
+
+    /// How many days `points` and `pangrams` were counted from.
+    async fn days_with_summary(&self) -> usize {
+        self.0.days_with_summary
+    }
+
+    async fn by_length(&self) -> Vec<LengthTotal> {
+        length_totals(&self.0.by_length)
+    }
+
+    /// The most common two-letter prefixes, most common first.
+    async fn top_pairs(&self) -> Vec<PairCount> {
+        self.0
+            .top_pairs
+            .iter()
+            .map(|(pair, count)| PairCount {
+                pair: pair.clone(),
+                count: *count,
+            })
+            .collect()
+    }
+}
+
+fn length_totals(by_length: &std::collections::BTreeMap<usize, usize>) -> Vec<LengthTotal> {
+    by_length
+        .iter()
+        .map(|(length, words)| LengthTotal {
+            length: *length,
+            words: *words,
+        })
+        .collect()
+}
+
+/// A day's words starting with one letter.
+#[derive(SimpleObject)]
+pub struct LetterDay {
+    pub date: NaiveDate,
+    pub words: usize,
+    pub lengths: Vec<LengthCount>,
+}
+
+/// How many words start with `letter` and are `length` letters long.
+#[derive(SimpleObject)]
+pub struct LengthCount {
+    pub letter: String,
+    pub length: usize,
+    pub count: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct PairCount {
+    pub pair: String,
+    pub count: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct LetterTotal {
+    pub letter: String,
+    pub words: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct LengthTotal {
+    pub length: usize,
+    pub words: usize,
+}
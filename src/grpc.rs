@@ -0,0 +1,290 @@
+// Errors are tonic's Status, which is what the service has to give back
+#![allow(clippy::result_large_err)]
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::archive::{ArchiveError, Store};
+use crate::auth::Credentials;
+use crate::document::GridDocument;
+
+pub mod proto {
+    tonic::include_proto!("gridder.v1");
+}
+
+use proto::grids_server::{Grids, GridsServer};
+use proto::{GetGridRequest, GetRangeRequest, GetRangeResponse, Grid, SubscribeRequest};
+
+// Grids saved while a subscriber's still catching up before it misses some
+const SUBSCRIBER_BACKLOG: usize = 16;
+// Longest range one GetRange can read, as with the GraphQL endpoint
+const MAX_RANGE_DAYS: i64 = 400;
+
+/// One of the service's methods, as named in `proto/gridder.proto`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Method {
+    GetGrid,
+    GetRange,
+    Subscribe,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GetGrid => write!(f, "GetGrid"),
+            Self::GetRange => write!(f, "GetRange"),
+            Self::Subscribe => write!(f, "Subscribe"),
+        }
+    }
+}
+
+/// Who may call which of the service's methods, for serving beyond
+/// localhost.
+///
+/// With no credentials, every method is open to anyone. Otherwise callers
+/// have to send one of them in the `authorization` metadata, as
+/// `Bearer TOKEN` or HTTP basic auth, except to methods made public.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    credentials: Credentials,
+    public: BTreeSet<Method>,
+}
+
+impl Access {
+    /// Accepts callers sending `Bearer TOKEN`.
+    pub fn bearer_token(mut self, token: &str) -> Self {
+        self.credentials = self.credentials.bearer_token(token);
+        self
+    }
+
+    /// Accepts callers logging in with HTTP basic auth.
+    pub fn basic(mut self, username: &str, password: &str) -> Self {
+        self.credentials = self.credentials.basic(username, password);
+        self
+    }
+
+    /// Lets anyone call `method`, credentials or not.
+    pub fn public(mut self, method: Method) -> Self {
+        self.public.insert(method);
+        self
+    }
+
+    fn check<T>(&self, method: Method, request: &Request<T>) -> Result<(), Status> {
+        if self.credentials.is_empty() || self.public.contains(&method) {
+            return Ok(());
+        }
+        let given = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| Status::unauthenticated(format!("{method} needs credentials")))?;
+        match self.credentials.accepts(given) {
+            true => Ok(()),
+            false => Err(Status::unauthenticated("invalid credentials")),
+        }
+    }
+}
+
+/// Serves the grids in a [`Store`] over gRPC, as described in
+/// `proto/gridder.proto`, for things that already speak it.
+///
+/// Subscribers are sent the grids saved after they subscribe, as
+/// [`GridService::watch`] finds them.
+#[derive(Debug, Clone)]
+pub struct GridService {
+    store: Arc<dyn Store>,
+    saved: broadcast::Sender<GridDocument>,
+    access: Access,
+}
+
+impl GridService {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        let (saved, _) = broadcast::channel(SUBSCRIBER_BACKLOG);
+        Self {
+            store,
+            saved,
+            access: Access::default(),
+        }
+    }
+
+    /// Only lets callers `access` allows use the service. Open to anyone
+    /// by default.
+    pub fn access(mut self, access: Access) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// The service, to add to a [`tonic::transport::Server`].
+    pub fn server(self) -> GridsServer<Self> {
+        GridsServer::new(self)
+    }
+
+    /// Looks for newly saved grids every `interval`, and sends each to the
+    /// subscribers. Only stops if the saved grids can't be listed.
+    pub async fn watch(&self, interval: Duration) -> Result<(), ArchiveError> {
+        let mut known = self.store.list_dates()?;
+        loop {
+            tokio::time::sleep(interval).await;
+            let new = self
+                .store
+                .list_dates()?
+                .difference(&known)
+                .copied()
+                .collect::<BTreeSet<_>>();
+            for date in new {
+                // One still being written is picked up next time
+                let Ok(Some(doc)) = self.store.load_grid(date) else {
+                    continue;
+                };
+                known.insert(date);
+                // Having no subscribers is fine
+                let _ = self.saved.send(doc);
+            }
+        }
+    }
+
+    // The date asked for, or the latest saved if none was
+    fn date_or_latest(&self, date: &str) -> Result<NaiveDate, Status> {
+        if !date.is_empty() {
+            return date
+                .parse()
+                .map_err(|_| Status::invalid_argument(format!("invalid date {date:?}")));
+        }
+
+        self.store
+            .list_dates()
+            .map_err(internal)?
+            .last()
+            .copied()
+            .ok_or_else(|| Status::not_found("no grids are saved"))
+    }
+}
+
+#[tonic::async_trait]
+impl Grids for GridService {
+    async fn get_grid(&self, request: Request<GetGridRequest>) -> Result<Response<Grid>, Status> {
+        self.access.check(Method::GetGrid, &request)?;
+        let date = self.date_or_latest(&request.get_ref().date)?;
+        let doc = self
+            .store
+            .load_grid(date)
+            .map_err(internal)?
+            .ok_or_else(|| Status::not_found(format!("no grid is saved for {date}")))?;
+
+        Ok(Response::new(Grid::from(&doc)))
+    }
+
+    async fn get_range(
+        &self,
+        request: Request<GetRangeRequest>,
+    ) -> Result<Response<GetRangeResponse>, Status> {
+        self.access.check(Method::GetRange, &request)?;
+        let request = request.get_ref();
+        let since = request
+            .since
+            .parse()
+            .map_err(|_| Status::invalid_argument(format!("invalid date {:?}", request.since)))?;
+        let until = self.date_or_latest(&request.until)?;
+        if until < since {
+            return Err(Status::invalid_argument(format!(
+                "{until} is before {since}"
+            )));
+        }
+        if (until - since).num_days() >= MAX_RANGE_DAYS {
+            return Err(Status::invalid_argument(format!(
+                "ranges can be at most {MAX_RANGE_DAYS} days"
+            )));
+        }
+        let grids = self
+            .store
+            .load_range(since, until)
+            .map_err(internal)?
+            .iter()
+            .map(Grid::from)
+            .collect();
+
+        Ok(Response::new(GetRangeResponse { grids }))
+    }
+
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<Grid, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        self.access.check(Method::Subscribe, &request)?;
+        // A subscriber that falls too far behind skips what it missed
+        let grids = BroadcastStream::new(self.saved.subscribe())
+            .filter_map(|doc| doc.ok().map(|doc| Ok(Grid::from(&doc))));
+
+        Ok(Response::new(Box::pin(grids)))
+    }
+}
+
+fn internal(e: ArchiveError) -> Status {
+    Status::internal(e.to_string())
+}
+
+// Counts are far below u32::MAX
+fn number(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+impl From<&GridDocument> for Grid {
+    fn from(doc: &GridDocument) -> Self {
+        Self {
+            schema_version: doc.schema_version,
+            date: doc.date.to_string(),
+            letters: doc.letters.iter().map(char::to_string).collect(),
+            lengths: doc
+                .lengths
+                .iter()
+                .map(|entry| proto::LengthEntry {
+                    letter: entry.letter.to_string(),
+                    length: number(entry.length.0),
+                    count: number(entry.count.0),
+                })
+                .collect(),
+            pairs: doc
+                .pairs
+                .iter()
+                .map(|entry| proto::PairEntry {
+                    pair: entry.pair.clone(),
+                    count: number(entry.count.0),
+                })
+                .collect(),
+            hints: doc.hints.clone(),
+            totals: Some(proto::Totals {
+                words: number(doc.totals.words),
+                by_letter: doc
+                    .totals
+                    .by_letter
+                    .iter()
+                    .map(|(letter, words)| (letter.to_string(), number(*words)))
+                    .collect(),
+                by_length: doc
+                    .totals
+                    .by_length
+                    .iter()
+                    .map(|(length, words)| (number(*length), number(*words)))
+                    .collect(),
+            }),
+            quality: doc.quality.map(u32::from),
+            summary: doc.summary.map(|summary| proto::Summary {
+                points: number(summary.points),
+                pangrams: number(summary.pangrams),
+            }),
+            // Serializing JSON values can't fail
+            extra_json: serde_json::to_string(&doc.extra).unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::process::Stdio;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use tokio::process::Command;
+
+use crate::document::GridDocument;
+use crate::transform::feed_and_wait;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HookError {
+    #[error("failed to run {0:?}: {1}")]
+    Running(String, std::io::Error),
+    #[error("{stage} hook {command:?} stopped the run ({status})")]
+    Vetoed {
+        stage: Stage,
+        command: String,
+        status: std::process::ExitStatus,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid hook {0:?}, expected STAGE=COMMAND, with STAGE one of pre-fetch, post-parse or pre-sink")]
+pub struct HookSpecError(String);
+
+/// Where in a run a [`Hook`] is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Before the day's page is fetched.
+    PreFetch,
+    /// Once the grid's been read from the page, before any transform.
+    PostParse,
+    /// Just before the grid is written anywhere, after any transform.
+    PreSink,
+}
+
+impl FromStr for Stage {
+    type Err = ();
+
+    fn from_str(stage: &str) -> Result<Self, Self::Err> {
+        match stage {
+            "pre-fetch" => Ok(Self::PreFetch),
+            "post-parse" => Ok(Self::PostParse),
+            "pre-sink" => Ok(Self::PreSink),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PreFetch => "pre-fetch",
+            Self::PostParse => "post-parse",
+            Self::PreSink => "pre-sink",
+        })
+    }
+}
+
+/// A command run at a [`Stage`] of each day's run, e.g. to check the grid
+/// before it's written anywhere.
+///
+/// The command is run with `sh -c`, with the grid's date in `GRIDDER_DATE`
+/// and the stage in `GRIDDER_HOOK_STAGE`. Once the grid's been read, its
+/// [`GridDocument`] JSON is on the command's stdin. Anything it prints is
+/// reported back, and anything but exiting with 0 stops the run before
+/// anything more is done. Its stderr is passed through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    pub stage: Stage,
+    command: String,
+}
+
+impl Hook {
+    pub fn new(stage: Stage, command: &str) -> Self {
+        Self {
+            stage,
+            command: command.to_string(),
+        }
+    }
+
+    /// Runs the hook for `date`, giving it `doc` if the grid's been read,
+    /// and gives back what it printed.
+    pub async fn run(
+        &self,
+        date: NaiveDate,
+        doc: Option<&GridDocument>,
+    ) -> Result<String, HookError> {
+        let running = |e| HookError::Running(self.command.clone(), e);
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("GRIDDER_DATE", date.to_string())
+            .env("GRIDDER_HOOK_STAGE", self.stage.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Given up on (e.g. past the deadline), it mustn't carry on
+            // writing after the run's been reported failed
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(running)?;
+
+        // Serializing a document can't fail
+        let json = doc
+            .map(|doc| serde_json::to_vec_pretty(doc).unwrap_or_default())
+            .unwrap_or_default();
+        let output = feed_and_wait(child, json).await.map_err(running)?;
+        if !output.status.success() {
+            return Err(HookError::Vetoed {
+                stage: self.stage,
+                command: self.command.clone(),
+                status: output.status,
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+impl FromStr for Hook {
+    type Err = HookSpecError;
+
+    /// Parses `STAGE=COMMAND`, e.g. `post-parse=./check-grid`.
+    fn from_str(hook: &str) -> Result<Self, Self::Err> {
+        let invalid = || HookSpecError(hook.to_string());
+        let (stage, command) = hook.split_once('=').ok_or_else(invalid)?;
+        let stage = stage.trim().parse().map_err(|()| invalid())?;
+        if command.trim().is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(stage, command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+    }
+
+    #[test]
+    fn hooks_are_given_as_stage_and_command() {
+        let hook = "post-parse=./check-grid --strict".parse::<Hook>().unwrap();
+        assert_eq!(hook, Hook::new(Stage::PostParse, "./check-grid --strict"));
+        // Only the first = splits
+        let hook = "pre-sink=test \"$X\" = y".parse::<Hook>().unwrap();
+        assert_eq!(hook, Hook::new(Stage::PreSink, "test \"$X\" = y"));
+
+        for hook in [
+            "./check-grid",
+            "post-fetch=true",
+            "pre-fetch=",
+            "pre-fetch= ",
+        ] {
+            assert!(hook.parse::<Hook>().is_err(), "{hook:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_see_the_day_and_the_grid() {
+        let lengths = LengthInfo::from([(('A', WordLength(4)), WordCount(2))]);
+        let doc = GridDocument::new(date(), &PairInfo::new(), &lengths);
+        let hook = Hook::new(
+            Stage::PreSink,
+            "echo $GRIDDER_HOOK_STAGE $GRIDDER_DATE; grep -c '\"letter\": \"A\"'",
+        );
+        let printed = hook.run(date(), Some(&doc)).await.unwrap();
+        assert_eq!(printed, "pre-sink 2024-05-01\n1");
+
+        // Nothing's been read before the fetch
+        let hook = Hook::new(Stage::PreFetch, "wc -c");
+        assert_eq!(hook.run(date(), None).await.unwrap(), "0");
+    }
+
+    #[tokio::test]
+    async fn failing_hooks_stop_the_run() {
+        let hook = Hook::new(Stage::PreFetch, "exit 4");
+        match hook.run(date(), None).await {
+            Err(HookError::Vetoed { stage, status, .. }) => {
+                assert_eq!(stage, Stage::PreFetch);
+                assert_eq!(status.code(), Some(4));
+            }
+            other => panic!("expected the hook to stop the run, got {other:?}"),
+        }
+    }
+}
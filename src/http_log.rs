@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::Instant;
+
+// Headers that carry credentials, whose values are never recorded
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+    "x-goog-api-key",
+];
+// Likewise for URL query parameters
+const SECRET_PARAMS: &[&str] = &["access_token", "key", "token"];
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpLogError {
+    #[error("failed to create HTTP debug directory {0}: {1}")]
+    Creating(PathBuf, std::io::Error),
+    #[error("failed to record an HTTP request in {0}: {1}")]
+    Writing(PathBuf, std::io::Error),
+}
+
+/// One request and what came back, as recorded in an [`HttpLog`].
+#[derive(Debug, Serialize)]
+pub struct Exchange {
+    client: &'static str,
+    started: DateTime<Utc>,
+    /// e.g. `GET https://HOST/PATH`, or the API call for clients that
+    /// don't show their requests
+    request: String,
+    request_headers: BTreeMap<String, String>,
+    status: Option<u16>,
+    response_headers: BTreeMap<String, String>,
+    duration_ms: u128,
+    error: Option<String>,
+    response_body: Option<String>,
+    #[serde(skip)]
+    timer: Instant,
+}
+
+impl Exchange {
+    /// Starts timing a request made by `client`, e.g. `fetch`.
+    pub fn start(client: &'static str, request: &str) -> Self {
+        Self {
+            client,
+            started: Utc::now(),
+            request: sanitize_url(request),
+            request_headers: BTreeMap::new(),
+            status: None,
+            response_headers: BTreeMap::new(),
+            duration_ms: 0,
+            error: None,
+            response_body: None,
+            timer: Instant::now(),
+        }
+    }
+
+    pub fn request_headers<'a>(&mut self, headers: impl IntoIterator<Item = (&'a str, &'a [u8])>) {
+        self.request_headers = sanitize_headers(headers);
+    }
+
+    /// Records the response's status and headers, and stops the timer.
+    pub fn response<'a>(
+        &mut self,
+        status: u16,
+        headers: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) {
+        self.status = Some(status);
+        self.response_headers = sanitize_headers(headers);
+        self.stop();
+    }
+
+    /// Records why the request got no response, and stops the timer.
+    pub fn failed(&mut self, error: &impl std::fmt::Display) {
+        self.error = Some(error.to_string());
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.duration_ms = self.timer.elapsed().as_millis();
+    }
+}
+
+/// Keeps a record of the HTTP requests made to the site and to Sheets,
+/// with their headers, status and timing, to debug problems on the other
+/// side without a packet capture. Each goes in its own JSON file in the
+/// directory, named by when it was made.
+///
+/// Credentials (cookies, tokens and keys) are left out of what's recorded.
+/// Response bodies are only kept if asked for, as they can be large and may
+/// hold the spreadsheet's contents.
+///
+/// Requests that can't be recorded have still been made, so they don't
+/// stop anything; the first such failure is kept for [`HttpLog::check`].
+#[derive(Debug, Clone)]
+pub struct HttpLog {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    bodies: bool,
+    count: AtomicUsize,
+    failure: Mutex<Option<std::io::Error>>,
+}
+
+impl HttpLog {
+    /// Records requests in `dir`, creating it if need be, with their
+    /// response bodies if `bodies` is set.
+    pub fn open(dir: &Path, bodies: bool) -> Result<Self, HttpLogError> {
+        std::fs::create_dir_all(dir).map_err(|e| HttpLogError::Creating(dir.to_path_buf(), e))?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                dir: dir.to_path_buf(),
+                bodies,
+                count: AtomicUsize::new(0),
+                failure: Mutex::new(None),
+            }),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.inner.dir
+    }
+
+    /// Whether response bodies should be given to [`HttpLog::record`].
+    pub fn records_bodies(&self) -> bool {
+        self.inner.bodies
+    }
+
+    /// Records `exchange`, with `body` if bodies are being kept.
+    pub fn record(&self, mut exchange: Exchange, body: Option<&[u8]>) {
+        if self.inner.bodies {
+            exchange.response_body = body.map(|body| String::from_utf8_lossy(body).into_owned());
+        }
+        // Counted too, so requests made in the same millisecond keep their
+        // order
+        let count = self.inner.count.fetch_add(1, Ordering::SeqCst);
+        let name = format!(
+            "{}-{count:04}-{}.json",
+            exchange.started.format("%Y%m%dT%H%M%S%.3f"),
+            exchange.client
+        );
+        // Serializing an exchange can't fail
+        let json = serde_json::to_vec_pretty(&exchange).unwrap_or_default();
+        if let Err(e) = std::fs::write(self.inner.dir.join(name), json) {
+            self.inner.failure.lock().unwrap().get_or_insert(e);
+        }
+    }
+
+    /// Fails if any request couldn't be recorded.
+    pub fn check(&self) -> Result<(), HttpLogError> {
+        match self.inner.failure.lock().unwrap().take() {
+            Some(e) => Err(HttpLogError::Writing(self.inner.dir.clone(), e)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn sanitize_headers<'a>(
+    headers: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+) -> BTreeMap<String, String> {
+    let mut sanitized = BTreeMap::<String, String>::new();
+    for (name, value) in headers {
+        let name = name.to_ascii_lowercase();
+        let value = match SECRET_HEADERS.contains(&name.as_str()) {
+            true => REDACTED.to_string(),
+            false => String::from_utf8_lossy(value).into_owned(),
+        };
+        // Repeated headers are joined, as they'd be read
+        sanitized
+            .entry(name)
+            .and_modify(|values| {
+                values.push_str(", ");
+                values.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    sanitized
+}
+
+// Anything after a `?` with a secret's name has its value replaced
+fn sanitize_url(request: &str) -> String {
+    let Some((start, query)) = request.split_once('?') else {
+        return request.to_string();
+    };
+    let params = query
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if SECRET_PARAMS.contains(&name) => format!("{name}={REDACTED}"),
+            _ => param.to_string(),
+        })
+        .collect::<Vec<_>>();
+    format!("{start}?{}", params.join("&"))
+}
@@ -0,0 +1,470 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Languages gridder's messages can be shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+}
+
+impl Lang {
+    /// The language of the environment's locale, going by LC_ALL,
+    /// LC_MESSAGES then LANG, as C programs do. English if none is set, or
+    /// the first one set isn't a language gridder has.
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|locale| Self::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    /// The language of a POSIX locale name, e.g. `es_ES.UTF-8`.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let language = locale.split(['_', '.', '@', '-']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "en" | "c" | "posix" => Some(Self::En),
+            "es" => Some(Self::Es),
+            _ => None,
+        }
+    }
+}
+
+/// A message shown to whoever's running gridder, with what goes in it.
+#[derive(Clone, Copy)]
+pub enum Message<'a> {
+    Warning,
+    Error,
+    Ok,
+    Failed,
+    Quality(&'a dyn fmt::Display),
+    Difficulty(&'a dyn fmt::Display),
+    PastGridsUnavailable(&'a dyn fmt::Display),
+    TelemetryFailed(&'a str),
+    StrayPairs(&'a [char]),
+    LayoutChanged,
+    PairsDontAddUp,
+    RepairedPairs,
+    CheckByHand,
+    ApplyFixesHint,
+    FoundWords {
+        found: usize,
+        total: usize,
+    },
+    FoundCount(usize),
+    Points(usize),
+    RemainingByLength,
+    RemainingByPair,
+    DidntFit,
+    RecordedFinds(usize),
+    WroteFile(&'a dyn fmt::Display),
+    EnableTimer,
+    NextRun(&'a dyn fmt::Display),
+    ServingGrpc(&'a dyn fmt::Display),
+    ServingDashboard(&'a dyn fmt::Display),
+    WroteGrid(NaiveDate),
+    Removed {
+        files: usize,
+        bytes: u64,
+    },
+    CleanupFailed(&'a dyn fmt::Display),
+    RecordingRunFailed(&'a dyn fmt::Display),
+    NoSavedGrids,
+    NoCenterLetter,
+    SavedGrid(NaiveDate),
+    AlreadySaved(NaiveDate),
+    ToReview(usize),
+    InterruptedAfter(&'a str),
+    RunFailed(NaiveDate, &'a dyn fmt::Display),
+    DayFailed(NaiveDate, &'a dyn fmt::Display),
+    UpdateFailed(&'a dyn fmt::Display),
+    NextPuzzleOut,
+    UnusualPairs,
+    MostFrequentPairs(usize),
+    PairStats {
+        days: usize,
+        words: usize,
+    },
+    NotEnoughHistory {
+        date: NaiveDate,
+        need: usize,
+    },
+    NoUnusualPairs(NaiveDate),
+    PastWordsFit {
+        count: usize,
+        letters: &'a str,
+    },
+    StoppingAfterStep,
+    UpToDate(&'a dyn fmt::Display),
+    UpdateAvailable {
+        current: &'a dyn fmt::Display,
+        latest: &'a dyn fmt::Display,
+    },
+    Updated {
+        from: &'a dyn fmt::Display,
+        to: &'a dyn fmt::Display,
+    },
+    Skipped,
+    Hint,
+    CheckSite,
+    CheckPage,
+    CheckSpreadsheet,
+    CheckGrids,
+    CheckFingerprints,
+    FetchedPage {
+        date: NaiveDate,
+        from: Option<std::net::SocketAddr>,
+    },
+    ReadPage {
+        words: usize,
+        quality: u8,
+    },
+    NoPageToRead,
+    NetworkOff,
+    AlreadyProcessed(NaiveDate),
+    AlreadySent,
+    CreatedTab(NaiveDate),
+    FilledTab(NaiveDate),
+    WouldCreateTab(NaiveDate),
+    WouldFillTab(NaiveDate),
+    TabsUpToDate(usize),
+    BackedUp {
+        tabs: usize,
+        path: &'a dyn fmt::Display,
+    },
+    Rotated(&'a dyn fmt::Display),
+    EnterSecret(&'a dyn fmt::Display),
+    StoredSecret(&'a dyn fmt::Display),
+    DeletedSecret(&'a dyn fmt::Display),
+    Encrypted(&'a dyn fmt::Display),
+    Decrypted(&'a dyn fmt::Display),
+    AlreadyEncrypted(&'a dyn fmt::Display),
+    NotEncrypted(&'a dyn fmt::Display),
+    Migrated {
+        version: u32,
+        description: &'a str,
+    },
+    SchemaUpToDate(u32),
+    Restored(&'a dyn fmt::Display),
+    DatabaseChecked,
+    MigrationApplied {
+        version: u32,
+        description: &'a str,
+        applied: DateTime<Utc>,
+    },
+    MigrationPending {
+        version: u32,
+        description: &'a str,
+    },
+    SpreadsheetReady,
+    GridsReadable(usize),
+    NoGridDir,
+    NoFingerprintFile,
+    FingerprintsReadable,
+    ChecksPassed(usize),
+}
+
+impl Message<'_> {
+    pub fn text(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => self.english(),
+            Lang::Es => self.spanish(),
+        }
+    }
+
+    fn english(&self) -> String {
+        match *self {
+            Self::Warning => "warning".to_string(),
+            Self::Error => "error".to_string(),
+            Self::Ok => "ok".to_string(),
+            Self::Failed => "failed:".to_string(),
+            Self::Quality(quality) => format!("quality: {quality}"),
+            Self::Difficulty(difficulty) => format!("difficulty: {difficulty}"),
+            Self::PastGridsUnavailable(e) => format!("couldn't compare against past grids: {e}"),
+            Self::TelemetryFailed(e) => format!("couldn't report the parse failure: {e}"),
+            Self::StrayPairs(stray) => format!(
+                "two-letter list has pairs starting with {stray:?}, which aren't in the table"
+            ),
+            Self::LayoutChanged => {
+                "the page is laid out differently to recent ones, check the grid was read correctly"
+                    .to_string()
+            }
+            Self::PairsDontAddUp => {
+                "the two-letter list doesn't add up to the table, it may have been misread:"
+                    .to_string()
+            }
+            Self::RepairedPairs => {
+                "the two-letter list didn't add up to the table, and was repaired:".to_string()
+            }
+            Self::CheckByHand => "(check by hand)".to_string(),
+            Self::ApplyFixesHint => {
+                "run with --apply-fixes to make the fixes not marked to check by hand".to_string()
+            }
+            Self::FoundWords { found, total } => format!("found {found}/{total} words"),
+            Self::FoundCount(found) => format!("found {found} words"),
+            Self::Points(points) => format!("({points} points)"),
+            Self::RemainingByLength => "remaining by length:".to_string(),
+            Self::RemainingByPair => "remaining by pair:".to_string(),
+            Self::DidntFit => "didn't fit the grid:".to_string(),
+            Self::RecordedFinds(new) => format!("recorded {new} new finds"),
+            Self::WroteFile(path) => format!("wrote {path}"),
+            Self::EnableTimer => {
+                "enable with: systemctl daemon-reload && systemctl enable --now gridder.timer"
+                    .to_string()
+            }
+            Self::NextRun(next) => format!("next run at {next}"),
+            Self::ServingGrpc(address) => format!("serving gRPC on {address}"),
+            Self::ServingDashboard(address) => {
+                format!("serving the dashboard on http://{address}/")
+            }
+            Self::WroteGrid(date) => format!("wrote grid for {date}"),
+            Self::Removed { files, bytes } => format!("removed {files} old files ({bytes} bytes)"),
+            Self::CleanupFailed(e) => format!("couldn't remove old files: {e}"),
+            Self::RecordingRunFailed(e) => format!("couldn't record the run: {e}"),
+            Self::NoSavedGrids => "no saved grids for those days".to_string(),
+            Self::NoCenterLetter => "the grid doesn't say which is the center letter".to_string(),
+            Self::SavedGrid(date) => format!("saved grid for {date}"),
+            Self::AlreadySaved(date) => {
+                format!("a grid is already saved for {date}, skipping (--replace to overwrite)")
+            }
+            Self::ToReview(days) => format!("days left for review in the report: {days}"),
+            Self::InterruptedAfter(step) => format!("interrupted after {step}"),
+            Self::RunFailed(date, e) => format!("run for {date} failed: {e}"),
+            Self::DayFailed(date, e) => format!("{date} failed: {e}"),
+            Self::UpdateFailed(e) => format!("update failed: {e}"),
+            Self::NextPuzzleOut => "the next puzzle is out, stopping".to_string(),
+            Self::UnusualPairs => "unusual pairs:".to_string(),
+            Self::MostFrequentPairs(days) => format!("most frequent pairs over {days} days:"),
+            Self::PairStats { days, words } => format!("{days} days, {words} words"),
+            Self::NotEnoughHistory { date, need } => format!(
+                "not enough history to call any of {date}'s pairs unusual (need {need} days)"
+            ),
+            Self::NoUnusualPairs(date) => format!("no unusual pairs on {date}"),
+            Self::PastWordsFit { count, letters } => format!("{count} past words fit {letters}"),
+            Self::StoppingAfterStep => {
+                "stopping after the current step (signal again to stop now)".to_string()
+            }
+            Self::UpToDate(version) => format!("gridder {version} is the latest release"),
+            Self::UpdateAvailable { current, latest } => {
+                format!("gridder {latest} is out (this is {current})")
+            }
+            Self::Updated { from, to } => format!("updated gridder from {from} to {to}"),
+            Self::Skipped => "skipped".to_string(),
+            Self::Hint => "hint".to_string(),
+            Self::CheckSite => "site".to_string(),
+            Self::CheckPage => "parser".to_string(),
+            Self::CheckSpreadsheet => "spreadsheet".to_string(),
+            Self::CheckGrids => "saved grids".to_string(),
+            Self::CheckFingerprints => "fingerprints".to_string(),
+            Self::FetchedPage { date, from: None } => format!("fetched the page for {date}"),
+            Self::FetchedPage {
+                date,
+                from: Some(addr),
+            } => format!("fetched the page for {date} from {}", addr.ip()),
+            Self::ReadPage { words, quality } => {
+                format!("read a grid of {words} words (quality {quality}/100)")
+            }
+            Self::NoPageToRead => "no page to read".to_string(),
+            Self::NetworkOff => "the network is off (--offline)".to_string(),
+            Self::AlreadyProcessed(date) => {
+                format!("{date} has already been written everywhere, skipping it")
+            }
+            Self::AlreadySent => "already done".to_string(),
+            Self::CreatedTab(date) => format!("created the tab for {date}"),
+            Self::FilledTab(date) => format!("filled in the empty tab for {date}"),
+            Self::WouldCreateTab(date) => format!("would create the tab for {date}"),
+            Self::WouldFillTab(date) => format!("would fill in the empty tab for {date}"),
+            Self::TabsUpToDate(count) => format!("{count} tabs were already up to date"),
+            Self::BackedUp { tabs, path } => format!("backed up {tabs} tabs to {path}"),
+            Self::Rotated(url) => format!("gridder has moved to a new spreadsheet: {url}"),
+            Self::EnterSecret(secret) => format!("{secret} (shown as typed)"),
+            Self::StoredSecret(secret) => format!("stored {secret} in the keyring"),
+            Self::DeletedSecret(secret) => format!("removed {secret} from the keyring"),
+            Self::Encrypted(path) => format!("encrypted {path}"),
+            Self::Decrypted(path) => format!("decrypted {path}"),
+            Self::AlreadyEncrypted(path) => format!("{path} is already encrypted, skipping it"),
+            Self::NotEncrypted(path) => format!("{path} isn't encrypted, skipping it"),
+            Self::Migrated {
+                version,
+                description,
+            } => format!("applied migration {version}: {description}"),
+            Self::SchemaUpToDate(version) => {
+                format!("the database is up to date (migration {version})")
+            }
+            Self::MigrationApplied {
+                version,
+                description,
+                applied,
+            } => format!(
+                "{version}: {description} (applied {})",
+                applied.format("%Y-%m-%d %H:%M UTC")
+            ),
+            Self::MigrationPending {
+                version,
+                description,
+            } => format!("{version}: {description} (pending)"),
+            Self::Restored(path) => format!("restored the saved grids from {path}"),
+            Self::DatabaseChecked => "the database is fine".to_string(),
+            Self::SpreadsheetReady => {
+                "logged in, and the TEMPLATE tab has room for every output".to_string()
+            }
+            Self::GridsReadable(count) => format!("all {count} read back"),
+            Self::NoGridDir => "no --grid-dir given".to_string(),
+            Self::NoFingerprintFile => "no --fingerprint-file given".to_string(),
+            Self::FingerprintsReadable => "history reads back".to_string(),
+            Self::ChecksPassed(count) => format!("all {count} checks passed"),
+        }
+    }
+
+    fn spanish(&self) -> String {
+        match *self {
+            Self::Warning => "aviso".to_string(),
+            Self::Error => "error".to_string(),
+            Self::Ok => "correcto".to_string(),
+            Self::Failed => "falló:".to_string(),
+            Self::Quality(quality) => format!("calidad: {quality}"),
+            Self::Difficulty(difficulty) => format!("dificultad: {difficulty}"),
+            Self::PastGridsUnavailable(e) => {
+                format!("no se pudo comparar con las cuadrículas anteriores: {e}")
+            }
+            Self::TelemetryFailed(e) => format!("no se pudo informar del fallo de lectura: {e}"),
+            Self::StrayPairs(stray) => format!(
+                "la lista de pares tiene pares que empiezan por {stray:?}, que no están en la tabla"
+            ),
+            Self::LayoutChanged => "la página tiene una estructura distinta a la de las recientes, comprueba que la cuadrícula se leyó bien".to_string(),
+            Self::PairsDontAddUp => {
+                "la lista de pares no cuadra con la tabla, puede que se leyera mal:".to_string()
+            }
+            Self::RepairedPairs => {
+                "la lista de pares no cuadraba con la tabla y se ha corregido:".to_string()
+            }
+            Self::CheckByHand => "(compruébalo a mano)".to_string(),
+            Self::ApplyFixesHint => {
+                "ejecuta con --apply-fixes para aplicar las correcciones no marcadas para comprobar a mano".to_string()
+            }
+            Self::FoundWords { found, total } => format!("encontradas {found}/{total} palabras"),
+            Self::FoundCount(found) => format!("encontradas {found} palabras"),
+            Self::Points(points) => format!("({points} puntos)"),
+            Self::RemainingByLength => "pendientes por longitud:".to_string(),
+            Self::RemainingByPair => "pendientes por par:".to_string(),
+            Self::DidntFit => "no encajan en la cuadrícula:".to_string(),
+            Self::RecordedFinds(new) => format!("registrados {new} hallazgos nuevos"),
+            Self::WroteFile(path) => format!("escrito {path}"),
+            Self::EnableTimer => {
+                "actívalo con: systemctl daemon-reload && systemctl enable --now gridder.timer"
+                    .to_string()
+            }
+            Self::NextRun(next) => format!("próxima ejecución: {next}"),
+            Self::ServingGrpc(address) => format!("sirviendo gRPC en {address}"),
+            Self::ServingDashboard(address) => {
+                format!("sirviendo el panel en http://{address}/")
+            }
+            Self::WroteGrid(date) => format!("cuadrícula del {date} escrita"),
+            Self::Removed { files, bytes } => {
+                format!("se eliminaron {files} archivos antiguos ({bytes} bytes)")
+            }
+            Self::CleanupFailed(e) => format!("no se pudieron eliminar los archivos antiguos: {e}"),
+            Self::RecordingRunFailed(e) => format!("no se pudo registrar la ejecución: {e}"),
+            Self::NoSavedGrids => "no hay cuadrículas guardadas para esos días".to_string(),
+            Self::NoCenterLetter => "la cuadrícula no indica cuál es la letra central".to_string(),
+            Self::SavedGrid(date) => format!("cuadrícula del {date} guardada"),
+            Self::AlreadySaved(date) => {
+                format!("ya hay una cuadrícula guardada del {date}, se omite (--replace para sobrescribirla)")
+            }
+            Self::ToReview(days) => format!("días pendientes de revisión en el informe: {days}"),
+            Self::InterruptedAfter(step) => format!("interrumpido tras {step}"),
+            Self::RunFailed(date, e) => format!("la ejecución del {date} falló: {e}"),
+            Self::DayFailed(date, e) => format!("el {date} falló: {e}"),
+            Self::UpdateFailed(e) => format!("la actualización falló: {e}"),
+            Self::NextPuzzleOut => "ya salió el siguiente puzle, se detiene".to_string(),
+            Self::UnusualPairs => "pares inusuales:".to_string(),
+            Self::MostFrequentPairs(days) => format!("pares más frecuentes en {days} días:"),
+            Self::PairStats { days, words } => format!("{days} días, {words} palabras"),
+            Self::NotEnoughHistory { date, need } => format!(
+                "no hay historial suficiente para considerar inusual ningún par del {date} (hacen falta {need} días)"
+            ),
+            Self::NoUnusualPairs(date) => format!("no hay pares inusuales el {date}"),
+            Self::PastWordsFit { count, letters } => {
+                format!("{count} palabras anteriores encajan con {letters}")
+            }
+            Self::StoppingAfterStep => {
+                "se detendrá tras el paso actual (repite la señal para parar ya)".to_string()
+            }
+            Self::UpToDate(version) => format!("gridder {version} es la última versión"),
+            Self::UpdateAvailable { current, latest } => {
+                format!("ya salió gridder {latest} (esta es la {current})")
+            }
+            Self::Updated { from, to } => format!("gridder actualizado de {from} a {to}"),
+            Self::Skipped => "omitido".to_string(),
+            Self::Hint => "pista".to_string(),
+            Self::CheckSite => "sitio".to_string(),
+            Self::CheckPage => "lector".to_string(),
+            Self::CheckSpreadsheet => "hoja de cálculo".to_string(),
+            Self::CheckGrids => "cuadrículas guardadas".to_string(),
+            Self::CheckFingerprints => "huellas".to_string(),
+            Self::FetchedPage { date, from: None } => format!("descargada la página del {date}"),
+            Self::FetchedPage {
+                date,
+                from: Some(addr),
+            } => format!("descargada la página del {date} desde {}", addr.ip()),
+            Self::ReadPage { words, quality } => {
+                format!("leída una cuadrícula de {words} palabras (calidad {quality}/100)")
+            }
+            Self::NoPageToRead => "no hay página que leer".to_string(),
+            Self::NetworkOff => "la red está desactivada (--offline)".to_string(),
+            Self::AlreadyProcessed(date) => {
+                format!("el {date} ya se escribió en todas partes, se omite")
+            }
+            Self::AlreadySent => "ya hecho".to_string(),
+            Self::CreatedTab(date) => format!("creada la pestaña del {date}"),
+            Self::FilledTab(date) => format!("rellenada la pestaña vacía del {date}"),
+            Self::WouldCreateTab(date) => format!("se crearía la pestaña del {date}"),
+            Self::WouldFillTab(date) => format!("se rellenaría la pestaña vacía del {date}"),
+            Self::TabsUpToDate(count) => format!("{count} pestañas ya estaban al día"),
+            Self::BackedUp { tabs, path } => {
+                format!("copiadas {tabs} pestañas en {path}")
+            }
+            Self::Rotated(url) => format!("gridder se ha pasado a una hoja de cálculo nueva: {url}"),
+            Self::EnterSecret(secret) => format!("{secret} (se muestra al escribirlo)"),
+            Self::StoredSecret(secret) => format!("{secret} guardado en el llavero"),
+            Self::DeletedSecret(secret) => format!("{secret} eliminado del llavero"),
+            Self::Encrypted(path) => format!("{path} cifrado"),
+            Self::Decrypted(path) => format!("{path} descifrado"),
+            Self::AlreadyEncrypted(path) => format!("{path} ya está cifrado, se omite"),
+            Self::NotEncrypted(path) => format!("{path} no está cifrado, se omite"),
+            Self::Migrated {
+                version,
+                description,
+            } => format!("aplicada la migración {version}: {description}"),
+            Self::SchemaUpToDate(version) => {
+                format!("la base de datos está al día (migración {version})")
+            }
+            Self::MigrationApplied {
+                version,
+                description,
+                applied,
+            } => format!(
+                "{version}: {description} (aplicada el {})",
+                applied.format("%Y-%m-%d %H:%M UTC")
+            ),
+            Self::MigrationPending {
+                version,
+                description,
+            } => format!("{version}: {description} (pendiente)"),
+            Self::Restored(path) => format!("restauradas las cuadrículas guardadas desde {path}"),
+            Self::DatabaseChecked => "la base de datos está bien".to_string(),
+            Self::SpreadsheetReady => {
+                "sesión iniciada, y la pestaña TEMPLATE tiene sitio para todo".to_string()
+            }
+            Self::GridsReadable(count) => format!("las {count} se leen bien"),
+            Self::NoGridDir => "no se indicó --grid-dir".to_string(),
+            Self::NoFingerprintFile => "no se indicó --fingerprint-file".to_string(),
+            Self::FingerprintsReadable => "el historial se lee bien".to_string(),
+            Self::ChecksPassed(count) => format!("las {count} comprobaciones pasaron"),
+        }
+    }
+}
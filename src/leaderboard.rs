@@ -0,0 +1,58 @@
+use std::fmt;
+
+use crate::document::GridDocument;
+use crate::progress;
+
+/// One member's place on a day's leaderboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    /// 1-based; members with equal points share a rank.
+    pub rank: usize,
+    pub member: String,
+    pub words: usize,
+    pub points: usize,
+}
+
+/// Scores each member's found words against the grid and ranks them by
+/// points, then by word count. Words that don't fit the grid score nothing.
+pub fn rank<S: AsRef<str>>(doc: &GridDocument, members: &[(String, Vec<S>)]) -> Vec<Standing> {
+    let mut standings = members
+        .iter()
+        .map(|(member, words)| {
+            let progress = progress::compute(doc, words);
+            Standing {
+                rank: 0,
+                member: member.clone(),
+                words: progress.found.len(),
+                points: progress.points,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    standings.sort_by(|a, b| {
+        (b.points, b.words)
+            .cmp(&(a.points, a.words))
+            .then_with(|| a.member.cmp(&b.member))
+    });
+
+    // Standard competition ranking ("1224")
+    for i in 0..standings.len() {
+        standings[i].rank = match i {
+            0 => 1,
+            _ if standings[i].points == standings[i - 1].points => standings[i - 1].rank,
+            _ => i + 1,
+        };
+    }
+
+    standings
+}
+
+impl fmt::Display for Standing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}. {} - {} points ({} words)",
+            self.rank, self.member, self.points, self.words
+        )
+    }
+}
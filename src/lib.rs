@@ -1,9 +1,147 @@
+pub mod a1;
+pub mod analytics;
+pub mod archive;
+pub mod audit;
+pub mod auth;
+pub mod avro;
+pub mod backup;
+pub mod clock;
+pub mod constraints;
+pub mod daemon;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod deadline;
+pub mod delta;
+pub mod dictionary;
+pub mod document;
+pub mod drive;
+pub mod dropbox;
+pub mod encryption;
+pub mod events;
 pub mod fetch;
+pub mod fixture;
+pub mod formats;
+pub mod git;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hooks;
+pub mod http_log;
+pub mod i18n;
+pub mod leaderboard;
+pub mod merge;
+pub mod naming;
+pub mod network;
+pub mod notify;
+pub mod pacer;
 pub mod parse;
+pub mod profile;
+pub mod progress;
+pub mod quality;
+pub mod repair;
+pub mod report;
+pub mod retention;
+pub mod score;
+pub mod secrets;
 pub mod sheets;
+pub mod spoiler;
+pub mod state;
+pub mod storage;
+pub mod systemd;
+pub mod team;
+pub mod telemetry;
+pub mod theme;
+pub mod transform;
+pub mod update;
+pub mod webdav;
+pub mod wheel;
 
 use std::collections::HashMap;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
 
-pub type PairInfo = HashMap<(char, char), usize>;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-pub type LengthInfo = HashMap<(char, usize), usize>;
+/// A number of words, e.g. how many start with a given pair. Kept apart from
+/// [`WordLength`] so the two can't be mixed up.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(transparent)]
+pub struct WordCount(pub usize);
+
+/// How many letters a word has.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(transparent)]
+pub struct WordLength(pub usize);
+
+pub type PairInfo = HashMap<(char, char), WordCount>;
+
+pub type LengthInfo = HashMap<(char, WordLength), WordCount>;
+
+impl fmt::Display for WordCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for WordLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Add for WordCount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for WordCount {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl Sum for WordCount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self(iter.map(|count| count.0).sum())
+    }
+}
+
+impl<'a> Sum<&'a WordCount> for WordCount {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
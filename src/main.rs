@@ -1,67 +1,4697 @@
 use chrono_tz::Tz;
-use clap::Parser;
-use gridder::sheets::{NewSheetError, SheetCreationError, SheetManager};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, FromArgMatches};
+use gridder::analytics::{
+    DifficultyModel, PairHistory, UnusualPair, MIN_HISTORY_DAYS, RARE_PAIR_SHARE,
+};
+use gridder::archive::{
+    open_archive, open_database, open_store, Archive, ArchiveError, MemoryStore, Migration,
+    RunRecord, Store,
+};
+use gridder::avro;
+use gridder::backup::{self, BackupError};
+use gridder::clock::{self, Clock, SystemClock, RELEASE_TZ};
+use gridder::constraints::{self, ConstraintFormat};
+use gridder::daemon::Schedule;
+#[cfg(feature = "dashboard")]
+use gridder::dashboard;
+use gridder::deadline::{Budget, Deadline, DeadlineExceeded};
+use gridder::dictionary::Dictionary;
+use gridder::document::{DocumentError, GridDocument, SummaryEntry};
+use gridder::drive::DriveSink;
+use gridder::dropbox::DropboxSink;
+use gridder::events::{EventFormat, EventSink};
+use gridder::fixture::{self, FIXTURES};
+use gridder::formats::{self, Format, FormatError, LongCsvWriter};
+use gridder::git::GitSink;
+#[cfg(feature = "graphql")]
+use gridder::graphql;
+#[cfg(feature = "grpc")]
+use gridder::grpc::{Access, GridService, Method as GrpcMethod};
+use gridder::hooks::{Hook, HookError, Stage};
+use gridder::i18n::{Lang, Message};
+use gridder::leaderboard;
+use gridder::merge::{self, Policy};
+use gridder::naming::SheetNameTemplate;
+use gridder::network::{Connectivity, OfflineError};
+use gridder::progress;
+use gridder::quality::{self, Quality};
+use gridder::repair::{self, Fix};
+use gridder::report::{Period, Report, ReportFormat};
+use gridder::retention::{Collected, Retention, RetentionError};
+use gridder::sheets::{
+    CheckTemplateError, DayTab, NewSheetError, Provenance, ReadProgressError, ReadTabsError,
+    RotateError, SheetCreationError, SheetManager, UpdateStatsError, WriteLeaderboardError,
+    WriteRemainingError, CELL_LIMIT,
+};
+use gridder::spoiler::SpoilerLevel;
+use gridder::state::{StateError, StateStore};
+use gridder::storage::BucketSink;
+use gridder::systemd::{self, ServiceConfig};
+use gridder::team;
+use gridder::telemetry::{ParseFailureReport, Telemetry, TelemetryChoice, TelemetryError};
+use gridder::theme::{ColorChoice, Palette, Role, Theme};
+use gridder::transform::{Transform, TransformError};
+use gridder::update::{self, UpdateError, Updater, Version};
+use gridder::webdav::WebdavSink;
+use miette::{GraphicalReportHandler, GraphicalTheme};
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 
-use gridder::fetch::{fetch_for_date, FetchDataError};
-use gridder::parse::parse_content;
+use tokio::sync::Notify;
 
-// New releases happen at midnight US-West time
-const US_WEST_TZ: Tz = chrono_tz::America::Los_Angeles;
+use gridder::audit::{AuditError, AuditLog, Write};
+use gridder::encryption::{Cipher, EncryptionError};
+use gridder::fetch::{ConnectOptions, FetchDataError, Fetcher, HostOverride, IpVersion, Profile};
+use gridder::http_log::{HttpLog, HttpLogError};
+use gridder::notify::{
+    BlueskyNotifier, MastodonNotifier, MatrixNotifier, MqttNotifier, Notifier, NotifyError,
+    PostTemplate, Sink, TelegramNotifier,
+};
+use gridder::parse::{
+    check_grid, from_csv, from_mapped_csv, parse_center_with, parse_content_with,
+    parse_summary_with, ColumnMap, CsvImportError, Fingerprint, FingerprintError,
+    FingerprintHistory, ParseOptions, SiteParseError, Summary,
+};
+use gridder::profile::{self, ProfileError, Profiles};
+use gridder::secrets::{Keyring, Secret, SecretError};
+use gridder::wheel;
+use gridder::{LengthInfo, PairInfo};
+
+// Set when SIGINT/SIGTERM arrives. Checked between steps, so a write to the
+// spreadsheet that's already under way gets to finish.
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+// Wakes `gridder watch` up if it's waiting for its next run
+static SHUTDOWN_NOTIFY: Notify = Notify::const_new();
+// Set once the arguments are parsed. Output before then is plain, and in
+// English
+static THEMES: OnceLock<Themes> = OnceLock::new();
+static LANG: OnceLock<Lang> = OnceLock::new();
+// Set once the arguments are parsed, if files are to be encrypted
+static CIPHER: OnceLock<Option<Arc<Cipher>>> = OnceLock::new();
+static AUDIT_LOG: OnceLock<Option<AuditLog>> = OnceLock::new();
+static HTTP_LOG: OnceLock<Option<HttpLog>> = OnceLock::new();
+// Telemetry is only a courtesy, so it mustn't hold up a failing run long
+const TELEMETRY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 #[derive(clap::Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // Running without a subcommand is the same as `gridder run`
+    #[command(flatten)]
+    run: Option<RunArgs>,
+
+    #[command(flatten)]
+    color: ColorArgs,
+
+    /// Language to show messages in. Taken from the locale (LC_ALL,
+    /// LC_MESSAGES or LANG) if unspecified, falling back to English.
+    #[arg(long, global = true, env = "GRIDDER_LANG")]
+    lang: Option<Lang>,
+
+    /// Make no network requests at all. Grids can only be read from saved
+    /// files and written to files; anything that needs the site, the
+    /// spreadsheet or a notifier fails before starting, as do --to sinks
+    /// that upload (webdav:, drive:, dropbox:, s3:// and gs://) or push
+    /// (git: with --git-push). Commands run with --to exec: or --transform
+    /// aren't restricted.
+    #[arg(long, global = true, env = "GRIDDER_OFFLINE")]
+    offline: bool,
+
+    /// Take settings from this profile in the config file, e.g. to run for
+    /// several groups from one machine. Options given on the command line
+    /// or in the environment still win. Give each profile its own
+    /// --state-dir.
+    #[arg(long, global = true, env = "GRIDDER_PROFILE")]
+    profile: Option<String>,
+
+    /// Config file to read profiles from. Defaults to
+    /// $XDG_CONFIG_HOME/gridder/config.toml.
+    #[arg(long, global = true, env = "GRIDDER_CONFIG")]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    encryption: EncryptionArgs,
+
+    /// Add a line to this file for every change made to the spreadsheet
+    /// and every post sent, with when it was made and a SHA-256 digest of
+    /// what was sent, to piece together what happened if something looks
+    /// wrong. The file is only ever added to.
+    #[arg(long, global = true, env = "GRIDDER_AUDIT_LOG")]
+    audit_log: Option<PathBuf>,
+
+    /// Record every request made to the site and to Sheets in this
+    /// directory, with its headers, status and timing, to debug problems on
+    /// their side. Cookies, tokens and keys are left out.
+    #[arg(long, global = true, value_name = "DIR", env = "GRIDDER_DEBUG_HTTP")]
+    debug_http: Option<PathBuf>,
+
+    /// Also record the bodies of responses with --debug-http. These can be
+    /// large, and hold the spreadsheet's contents.
+    #[arg(long, global = true, requires = "debug_http")]
+    debug_http_bodies: bool,
+}
+
+impl Args {
+    // Parses the command line, with the settings of the profile it picks
+    // (if any) and secrets from the keyring filling in for options not
+    // given. The profile has to be found first, as it decides how the rest
+    // is parsed.
+    fn parse_with_profile() -> Result<Self, Error> {
+        let argv = std::env::args_os().collect::<Vec<_>>();
+        let name = profile::find_option(&argv, "profile", "GRIDDER_PROFILE")
+            .map(|name| name.to_string_lossy().into_owned());
+        let keyring = Keyring::new(name.as_deref());
+        let profile = match name {
+            Some(name) => {
+                let path = match profile::find_option(&argv, "config", "GRIDDER_CONFIG") {
+                    Some(path) => PathBuf::from(path),
+                    None => profile::default_config_file()?,
+                };
+                // The config file may be encrypted, so its key has to be
+                // found before the rest of the command line is parsed
+                let key_file = profile::find_option(
+                    &argv,
+                    "encryption-key-file",
+                    "GRIDDER_ENCRYPTION_KEY_FILE",
+                );
+                let passphrase = profile::find_option(&argv, "passphrase", "GRIDDER_PASSPHRASE")
+                    .map(|passphrase| passphrase.to_string_lossy().into_owned())
+                    .or_else(|| keyring.get(Secret::Passphrase).ok());
+                let cipher = make_cipher(key_file.map(PathBuf::from).as_deref(), passphrase)?;
+                let settings = Profiles::load(&path, cipher.as_deref())?
+                    .get(&name)?
+                    .clone();
+                Some((name, settings))
+            }
+            None => None,
+        };
+        // A secret a profile gives in the open wins over the keyring's
+        keyring.export(&argv, |secret| {
+            profile
+                .as_ref()
+                .is_some_and(|(_, settings)| settings.contains_key(secret.option()))
+        });
+        let mut command = Self::command();
+        if let Some((name, settings)) = &profile {
+            command = profile::apply(command, name, settings)?;
+        }
+        let matches = command.get_matches_from(argv);
+
+        let mut args = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        // clap never fills in a flattened Option whose struct flattens others
+        // itself, so the run arguments are read here when there's no
+        // subcommand
+        if args.command.is_none() {
+            args.run = Some(RunArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()));
+        }
+        Ok(args)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct ColorArgs {
+    /// When to color terminal output. With auto, NO_COLOR turns it off.
+    #[arg(long, global = true, env = "GRIDDER_COLOR", default_value = "auto")]
+    color: ColorChoice,
+
+    #[arg(long, global = true, env = "GRIDDER_THEME", default_value = "standard")]
+    theme: Palette,
+}
+
+// Colors for stdout and stderr, which may not both be terminals
+#[derive(Debug, Default)]
+struct Themes {
+    stdout: Theme,
+    stderr: Theme,
+}
+
+impl ColorArgs {
+    fn themes(&self) -> Themes {
+        Themes {
+            stdout: Theme::new(self.color, self.theme, std::io::stdout().is_terminal()),
+            stderr: Theme::new(self.color, self.theme, std::io::stderr().is_terminal()),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct EncryptionArgs {
+    /// Encrypt the state directory and config file with this passphrase,
+    /// e.g. on machines shared with others. Plain files are refused, so
+    /// encrypt existing ones first with `gridder encrypt-file`. Best given
+    /// in the environment or the keyring (`gridder secret set passphrase`)
+    /// rather than here, where others can see it.
+    #[arg(
+        long,
+        global = true,
+        env = "GRIDDER_PASSPHRASE",
+        hide_env_values = true
+    )]
+    passphrase: Option<String>,
+
+    /// Encrypt with the contents of this file instead of a passphrase, e.g.
+    /// 32 bytes from /dev/urandom.
+    #[arg(
+        long,
+        global = true,
+        env = "GRIDDER_ENCRYPTION_KEY_FILE",
+        conflicts_with = "passphrase"
+    )]
+    encryption_key_file: Option<PathBuf>,
+}
+
+impl EncryptionArgs {
+    fn cipher(&self) -> Result<Option<Arc<Cipher>>, Error> {
+        make_cipher(self.encryption_key_file.as_deref(), self.passphrase.clone())
+    }
+}
+
+// A key file takes precedence over a passphrase, as it can only have been
+// given on purpose
+fn make_cipher(
+    key_file: Option<&Path>,
+    passphrase: Option<String>,
+) -> Result<Option<Arc<Cipher>>, Error> {
+    let cipher = match (key_file, passphrase) {
+        (Some(path), _) => Cipher::from_key_file(path)?,
+        (None, Some(passphrase)) => Cipher::from_passphrase(&passphrase)?,
+        (None, None) => return Ok(None),
+    };
+
+    Ok(Some(Arc::new(cipher)))
+}
+
+fn cipher() -> Option<Arc<Cipher>> {
+    CIPHER.get().cloned().flatten()
+}
+
+fn audit_log() -> Option<AuditLog> {
+    AUDIT_LOG.get().cloned().flatten()
+}
+
+fn http_log() -> Option<HttpLog> {
+    HTTP_LOG.get().cloned().flatten()
+}
+
+// Adds a notifier's post to the audit log, if there is one
+fn audit_post<T, E: std::fmt::Display>(notifier: &Notifier, write: Write, result: &Result<T, E>) {
+    if let Some(log) = audit_log() {
+        log.record(&notifier.key(), &write, result.as_ref().map(|_| ()));
+    }
+}
+
+fn themes() -> &'static Themes {
+    THEMES.get_or_init(Themes::default)
+}
+
+fn lang() -> Lang {
+    LANG.get().copied().unwrap_or_default()
+}
+
+// A message in the chosen language
+fn tr(message: Message) -> String {
+    message.text(lang())
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Fetch the grid for a day and write it to a new sheet (the default).
+    Run(RunArgs),
+    /// Print the JSON Schema of the structured output document.
+    Schema(SchemaArgs),
+    /// Convert a saved grid from one format to another.
+    Convert(ConvertArgs),
+    /// Write the grid of a page bundled with gridder, the same every time,
+    /// e.g. to snapshot-test something that reads gridder's output.
+    Emit(EmitArgs),
+    /// Show a day's letters as the puzzle's wheel, with the center letter
+    /// highlighted.
+    Show(ShowArgs),
+    /// Show which words are still to be found for a day.
+    Progress(ProgressArgs),
+    /// Score each member's found words and write a leaderboard to the day's
+    /// tab.
+    Leaderboard(LeaderboardArgs),
+    /// Write a systemd service and timer that run gridder daily.
+    InstallService(InstallServiceArgs),
+    /// Stay running, and fetch and write each day's grid on a schedule.
+    /// Under systemd, it can be a Type=notify service, and keeps a
+    /// WatchdogSec= watchdog fed.
+    Watch(WatchArgs),
+    /// Fetch and write the grids for a range of past days.
+    Backfill(BackfillArgs),
+    /// Write the saved grids for a range of days again, e.g. after changing
+    /// a template or transform. Nothing is fetched, and the spreadsheet and
+    /// chats are left alone.
+    Replay(ReplayArgs),
+    /// Serve the saved grids over gRPC (see proto/gridder.proto), including
+    /// a stream of each new day's grid as it's saved.
+    #[cfg(feature = "grpc")]
+    Grpc(GrpcArgs),
+    /// Serve a web dashboard of the saved grids, with the latest grid and
+    /// recent days, and the JSON API it's built on. Builds with the graphql
+    /// feature also answer GraphQL queries at /graphql, and those with the
+    /// openapi feature describe the API at /openapi.json and /docs. Give
+    /// --auth-token or --auth-username before serving beyond localhost.
+    #[cfg(feature = "dashboard")]
+    Serve(ServeArgs),
+    /// List days missing from the saved grids, or saved with low quality.
+    Gaps(GapsArgs),
+    /// Remove saved grids, words and recorded requests older than the
+    /// --keep-* limits. `gridder watch` does this after each run.
+    Gc(GcArgs),
+    /// Summarise the saved grids for a month or week.
+    Report(ReportArgs),
+    /// Write the saved grids for a range of days as one long CSV table, a
+    /// row per count, e.g. for a spreadsheet or database to load.
+    Export(ExportArgs),
+    /// Load letter and length counts from hand-made CSV files into the
+    /// saved grids, e.g. to include days from before gridder was set up.
+    ImportCsv(ImportCsvArgs),
+    /// Merge saved grids from other directories, e.g. imports and
+    /// re-fetches, into --grid-dir, and report how days they disagree on
+    /// were settled.
+    Merge(MergeArgs),
+    /// Rank two-letter pairs by how often they've come up in the saved
+    /// grids, and list the ones that are unusual for a day.
+    Pairs(PairsArgs),
+    /// Show how many of a day's words a local dictionary can account for.
+    Coverage(CoverageArgs),
+    /// List words found on past days that could be made from a set of
+    /// letters.
+    PastWords(PastWordsArgs),
+    /// Write the day's remaining (pair, length) slots in formats other
+    /// solving tools can read.
+    ExportConstraints(ExportConstraintsArgs),
+    /// Keep the counts still to find on the day's tab up to date as the
+    /// group finds words, until the next puzzle is released.
+    Track(TrackArgs),
+    /// Show each member's totals from the finds recorded by `gridder
+    /// leaderboard --grid-dir`.
+    MemberStats(MemberStatsArgs),
+    /// Replace this binary with the latest release, if it's newer. The
+    /// release must be signed with the key given in GRIDDER_RELEASE_KEY
+    /// when this was built, so builds without one can only check.
+    SelfUpdate(SelfUpdateArgs),
+    /// Check that the site, spreadsheet and saved files are all usable, and
+    /// say what to do about any that aren't.
+    Doctor(DoctorArgs),
+    /// Work on the spreadsheet as a whole.
+    #[command(subcommand)]
+    Sheets(SheetsCommand),
+    /// Keep tokens and passwords in the OS keyring, instead of on the
+    /// command line or in a file. They're used whenever the option isn't
+    /// given otherwise, so environment variables can stand in for them on
+    /// servers without a keyring. Each --profile has its own. Needs a build
+    /// with the keyring feature.
+    #[command(subcommand)]
+    Secret(SecretCommand),
+    /// Look after a database of saved grids, a sqlite://PATH or
+    /// postgres:// URL given as --grid-dir. Needs a build with the sqlite
+    /// or postgres feature.
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Encrypt files in place with the --passphrase or
+    /// --encryption-key-file, e.g. the config file or what's in the state
+    /// directory. Files already encrypted are left alone.
+    EncryptFile(EncryptFileArgs),
+    /// Decrypt files in place, e.g. to edit the config file or to stop
+    /// encrypting.
+    DecryptFile(EncryptFileArgs),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum DbCommand {
+    /// Apply the migrations the database is missing. Every other command
+    /// does this when it opens the database too.
+    Migrate(DbArgs),
+    /// List the database's migrations, and which have been applied.
+    Status(DbArgs),
+    /// Write a copy of the saved grids to a file, gzipped if its name ends
+    /// in .gz, e.g. to move them to another server. SQLite backups are
+    /// checked for corruption as they're written.
+    Backup(DbFileArgs),
+    /// Replace the saved grids with those in a file written by `gridder db
+    /// backup`, from the same kind of database.
+    Restore(DbFileArgs),
+    /// Check a SQLite database for corruption, and optionally vacuum it.
+    Check(DbCheckArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct DbFileArgs {
+    #[command(flatten)]
+    db: DbArgs,
+
+    /// The backup file, e.g. grids.sql.gz.
+    file: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct DbCheckArgs {
+    #[command(flatten)]
+    db: DbArgs,
+
+    /// Afterwards, reclaim the space left by removed rows. Postgres
+    /// databases are only vacuumed, not checked.
+    #[arg(long)]
+    vacuum: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct DbArgs {
+    /// The database of saved grids, a sqlite://PATH or postgres:// URL.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct EncryptFileArgs {
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum SecretCommand {
+    /// Store a secret, read from stdin (e.g. piped from a password
+    /// manager).
+    Set(SecretArgs),
+    /// Print a stored secret.
+    Get(SecretArgs),
+    /// Remove a stored secret.
+    Delete(SecretArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct SecretArgs {
+    /// Which secret, by the option it's for.
+    secret: Secret,
+}
+
+// Only made once, when parsing the command line
+#[allow(clippy::large_enum_variant)]
+#[derive(clap::Subcommand, Debug)]
+enum SheetsCommand {
+    /// Create the tabs of days saved with --grid-dir that are missing from
+    /// the spreadsheet, and fill in any that are empty, e.g. to recover from
+    /// tabs being deleted or cleared.
+    Sync(SyncArgs),
+    /// Save a copy of every tab, as an Excel workbook or a directory of CSV
+    /// files.
+    Backup(BackupArgs),
+    /// Move to a new spreadsheet with a copy of the TEMPLATE tab, e.g. at
+    /// the start of a year, or when this one's full. Later runs with the
+    /// same --state-dir write to the new one.
+    Rotate(RotateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
     /// The date to retrieve data for. If unspecified, the data for today will
     /// be requested.
     /// Format: YYYY-MM-DD
     date: Option<String>,
 
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    /// Also record the day's totals on the "Stats" tab, and chart them over
+    /// time.
+    #[arg(long, env = "GRIDDER_UPDATE_STATS")]
+    stats: bool,
+
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+
+    #[command(flatten)]
+    steps: StepArgs,
+
+    /// Write to every output, even where the state directory says a day is
+    /// done.
+    #[arg(long)]
+    force: bool,
+
+    /// Longest the whole run may take, e.g. 5m or 1h30m, so one stuck on a
+    /// slow site or output can't hang a cron job. Fetching, the hooks and
+    /// every output (with any retries) share it, and the run fails with the
+    /// stage it got to.
+    #[arg(long, env = "GRIDDER_DEADLINE")]
+    deadline: Option<Budget>,
+
+    #[command(flatten)]
+    rotation: RotationArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+// What `run`, `watch` and `backfill` keep each day's grid in, and what
+// they pass it through on the way
+#[derive(clap::Args, Debug)]
+struct PipelineArgs {
+    /// Also save each grid as JSON to DATE.json in this directory. Builds
+    /// with the sqlite or postgres feature can be given a sqlite://PATH or
+    /// postgres:// URL to save to a database instead. Without it, `watch`
+    /// and `backfill` only keep grids in memory, for later days to be
+    /// compared with.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    transform: TransformArgs,
+
+    /// Run a command at a stage of each day's run, as STAGE=COMMAND, where
+    /// STAGE is pre-fetch, post-parse or pre-sink. It gets the date in
+    /// GRIDDER_DATE and, once the grid's been read, its JSON document on
+    /// stdin. What it prints is shown, and exiting with anything but 0
+    /// stops the run, e.g. to check the grid before it's written. Repeat
+    /// for several.
+    #[arg(long = "hook", value_name = "STAGE=COMMAND")]
+    hooks: Vec<Hook>,
+
+    /// Directory to keep track of runs in. Only one gridder at a time can
+    /// use it, and days already written to every output are skipped.
+    #[arg(long, env = "GRIDDER_STATE_DIR")]
+    state_dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct TransformArgs {
+    /// Command to pass each grid's JSON document through before anything
+    /// is written, e.g. to add figures under "extra". It gets the document
+    /// on stdin (and the date in GRIDDER_DATE), and must print the document
+    /// to use on stdout.
+    #[arg(long, env = "GRIDDER_TRANSFORM")]
+    transform: Option<String>,
+
+    /// rhai script to pass each grid's document through instead of a
+    /// --transform command. The script has the document as `doc`, and
+    /// whatever it leaves there is used, e.g.
+    /// `doc.extra.pairs = doc.pairs.len();`.
+    #[cfg(feature = "scripting")]
+    #[arg(long, env = "GRIDDER_TRANSFORM_SCRIPT", conflicts_with = "transform")]
+    transform_script: Option<PathBuf>,
+}
+
+impl TransformArgs {
+    fn transform(&self) -> Result<Option<Transform>, Error> {
+        #[cfg(feature = "scripting")]
+        if let Some(path) = &self.transform_script {
+            return Ok(Some(Transform::script(path)?));
+        }
+        Ok(self.transform.as_deref().map(Transform::new))
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct FetchArgs {
+    /// File holding a Cookie header value to fetch pages with. Repeat to
+    /// rotate between several sessions.
+    #[arg(long = "session-cookie-file")]
+    session_cookie_files: Vec<PathBuf>,
+
+    /// Minimum number of seconds between two fetches with the same session.
+    #[arg(long, default_value_t = 0)]
+    min_fetch_interval: u64,
+
+    /// If the day's page isn't at its usual URL, look for it under the
+    /// neighbouring dates and alternative names.
+    #[arg(long, env = "GRIDDER_PROBE_URLS")]
+    probe_urls: bool,
+
+    /// Fetch pages from under this URL instead of the puzzle's site, e.g. a
+    /// mirror, a caching proxy or a local test server. Pages are looked for
+    /// at BASE_URL/YYYY/MM/DD/<page>.
+    #[arg(long, env = "GRIDDER_BASE_URL")]
+    base_url: Option<String>,
+
+    /// Only reach the site over this version of IP, for networks where the
+    /// other is broken. `gridder doctor` shows the address used.
+    #[arg(long, env = "GRIDDER_IP_VERSION")]
+    ip_version: Option<IpVersion>,
+
+    /// Connect to HOST at IP rather than where DNS says, e.g.
+    /// `--resolve www.example.com=192.0.2.1`. Repeat for several hosts.
+    #[arg(long = "resolve", value_name = "HOST=IP")]
+    host_overrides: Vec<HostOverride>,
+
+    #[command(flatten)]
+    parse: ParseArgs,
+}
+
+impl FetchArgs {
+    fn fetcher(self, connectivity: Connectivity) -> Result<Fetcher, Error> {
+        let network = connectivity.network("fetching pages")?;
+        let min_interval = std::time::Duration::from_secs(self.min_fetch_interval);
+        let profiles = if self.session_cookie_files.is_empty() {
+            vec![Profile {
+                min_interval,
+                ..Profile::anonymous()
+            }]
+        } else {
+            self.session_cookie_files
+                .into_iter()
+                .map(|path| {
+                    let cookie = std::fs::read_to_string(&path)
+                        .map_err(|e| Error::ReadingCookieFile(path.clone(), e))?;
+                    Ok(Profile {
+                        name: path.display().to_string(),
+                        cookie: Some(cookie.trim().to_string()),
+                        min_interval,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+        };
+
+        let connect = ConnectOptions {
+            ip_version: self.ip_version,
+            overrides: self.host_overrides,
+        };
+        let mut fetcher = Fetcher::new(network, profiles)
+            .probe_alternatives(self.probe_urls)
+            .connect_options(&connect)?;
+        if let Some(base_url) = &self.base_url {
+            fetcher = fetcher.base_url(base_url);
+        }
+        if let Some(log) = http_log() {
+            fetcher = fetcher.http_log(log);
+        }
+        Ok(fetcher)
+    }
+}
+
+// Overrides for the CSS selectors used to find things on the page, to cope
+// with its markup changing
+#[derive(clap::Args, Debug)]
+struct ParseArgs {
+    /// CSS selector for the grid's table, if the page has several and the
+    /// wrong one is picked.
+    #[arg(long, env = "GRIDDER_TABLE_SELECTOR")]
+    table_selector: Option<String>,
+
+    /// CSS selector for the table's rows [default: tr.row]
+    #[arg(long, env = "GRIDDER_ROW_SELECTOR")]
+    row_selector: Option<String>,
+
+    /// CSS selector for the cells in each row [default: td.cell]
+    #[arg(long, env = "GRIDDER_CELL_SELECTOR")]
+    cell_selector: Option<String>,
+
+    /// CSS selector for the paragraphs holding the summary and two-letter
+    /// list [default: p.content]
+    #[arg(long, env = "GRIDDER_PARAGRAPH_SELECTOR")]
+    paragraph_selector: Option<String>,
+
+    /// File to keep an outline of recent pages' structure in. A warning is
+    /// printed when a page's structure differs from what's been normal, even
+    /// if it parsed.
+    #[arg(long, env = "GRIDDER_FINGERPRINT_FILE")]
+    fingerprint_file: Option<PathBuf>,
+
+    /// Whether to tell the maintainers when a day's page fails to parse,
+    /// so changes to the site's layout are noticed quickly. Only the kind
+    /// of failure and how many things the selectors matched are sent,
+    /// nothing from the page or your settings. Off unless turned on here or
+    /// in a profile.
+    #[arg(long, env = "GRIDDER_TELEMETRY", default_value = "off")]
+    telemetry: TelemetryChoice,
+
+    /// Where to send parse failure reports with --telemetry on.
+    #[arg(long, env = "GRIDDER_TELEMETRY_URL", required_if_eq("telemetry", "on"))]
+    telemetry_url: Option<String>,
+
+    /// When the two-letter list doesn't add up to the table, make the
+    /// fixes suggested for it that there's only one way to make, rather
+    /// than only printing them.
+    #[arg(long, env = "GRIDDER_APPLY_FIXES")]
+    apply_fixes: bool,
+}
+
+impl ParseArgs {
+    fn reader(&self, connectivity: Connectivity) -> Result<PageReader, Error> {
+        let telemetry = match (self.telemetry, &self.telemetry_url) {
+            (TelemetryChoice::On, Some(url)) => match connectivity.network("sending telemetry") {
+                Ok(network) => Some(Telemetry::new(network, url)?),
+                // Offline means no requests at all, so there's nothing to
+                // complain about
+                Err(_) => None,
+            },
+            _ => None,
+        };
+        let custom_selectors = [
+            &self.table_selector,
+            &self.row_selector,
+            &self.cell_selector,
+            &self.paragraph_selector,
+        ]
+        .iter()
+        .any(|selector| selector.is_some());
+
+        Ok(PageReader {
+            options: self.options()?,
+            fingerprint_file: self.fingerprint_file.clone(),
+            custom_selectors,
+            telemetry,
+            apply_fixes: self.apply_fixes,
+        })
+    }
+
+    fn options(&self) -> Result<ParseOptions, Error> {
+        let mut options = ParseOptions::default();
+        if let Some(selector) = &self.table_selector {
+            options = options.table_selector(selector)?;
+        }
+        if let Some(selector) = &self.row_selector {
+            options = options.row_selector(selector)?;
+        }
+        if let Some(selector) = &self.cell_selector {
+            options = options.cell_selector(selector)?;
+        }
+        if let Some(selector) = &self.paragraph_selector {
+            options = options.paragraph_selector(selector)?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// A part of a day's run, for --only and --skip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Step {
+    /// Fetching and reading the day's page. Without it, the grid saved in
+    /// --grid-dir is used, as it was written (so it isn't transformed
+    /// again).
+    Fetch,
+    /// Passing the grid through --transform.
+    Transform,
+    /// Creating the day's tab in the spreadsheet.
+    Sheets,
+    /// Recording the day's totals on the Stats tab, with --stats.
+    Stats,
+    /// Saving the grid to --grid-dir.
+    GridFile,
+    /// Posting to chats and social accounts, and handing the grid to --to
+    /// commands.
+    Notify,
+}
+
+// Which parts of each day's run to do
+#[derive(clap::Args, Debug, Clone)]
+struct StepArgs {
+    /// Only do these parts of each day's run, e.g. --only fetch,grid-file
+    /// to save the day's grid now and write it everywhere else later with
+    /// --skip fetch. Days only count as done in --state-dir once every
+    /// output's been written.
+    #[arg(long, value_delimiter = ',', conflicts_with = "skip")]
+    only: Vec<Step>,
+
+    /// Leave out these parts of each day's run, e.g. --skip sheets.
+    #[arg(long, value_delimiter = ',')]
+    skip: Vec<Step>,
+}
+
+impl StepArgs {
+    fn runs(&self, step: Step) -> bool {
+        let picked = self.only.is_empty() || self.only.contains(&step);
+        let runs = picked && !self.skip.contains(&step);
+        match step {
+            // The saved grid's already been through them
+            Step::Transform | Step::GridFile => runs && self.runs(Step::Fetch),
+            _ => runs,
+        }
+    }
+
+    // Whether an output the run would write is left out. A grid read from
+    // --grid-dir doesn't need saving there again.
+    fn leaves_out_outputs(&self) -> bool {
+        let outputs = [Step::Sheets, Step::Stats, Step::Notify];
+        outputs.into_iter().any(|step| !self.runs(step))
+            || (self.runs(Step::Fetch) && !self.runs(Step::GridFile))
+    }
+}
+
+// Where to post each day's grid, besides the spreadsheet
+#[derive(clap::Args, Debug)]
+struct NotifyArgs {
+    /// Base URL of a Matrix homeserver to post each day's grid through,
+    /// e.g. https://matrix.org
+    #[arg(
+        long,
+        env = "GRIDDER_MATRIX_HOMESERVER",
+        requires_all = ["matrix_access_token", "matrix_room"]
+    )]
+    matrix_homeserver: Option<String>,
+
+    /// Access token of the Matrix account to post as.
+    #[arg(long, env = "GRIDDER_MATRIX_ACCESS_TOKEN", hide_env_values = true)]
+    matrix_access_token: Option<String>,
+
+    /// ID of the Matrix room to post to, e.g. !abc123:matrix.org. The
+    /// account must already have joined it.
+    #[arg(long, env = "GRIDDER_MATRIX_ROOM")]
+    matrix_room: Option<String>,
+
+    /// Token of a Telegram bot to send each day's grid with.
+    #[arg(
+        long,
+        env = "GRIDDER_TELEGRAM_BOT_TOKEN",
+        hide_env_values = true,
+        requires = "telegram_chat_id"
+    )]
+    telegram_bot_token: Option<String>,
+
+    /// ID of the Telegram chat to send to, or @name for a public channel.
+    /// The bot must already be in it.
+    #[arg(long, env = "GRIDDER_TELEGRAM_CHAT_ID")]
+    telegram_chat_id: Option<String>,
+
+    /// Base URL of a Mastodon server to post each day's summary to, e.g.
+    /// https://mastodon.social. Summaries never include the letters.
+    #[arg(
+        long,
+        env = "GRIDDER_MASTODON_INSTANCE",
+        requires = "mastodon_access_token"
+    )]
+    mastodon_instance: Option<String>,
+
+    /// Access token with the write:statuses scope.
+    #[arg(long, env = "GRIDDER_MASTODON_ACCESS_TOKEN", hide_env_values = true)]
+    mastodon_access_token: Option<String>,
+
+    /// Bluesky handle to post each day's summary as. Summaries never
+    /// include the letters.
+    #[arg(
+        long,
+        env = "GRIDDER_BLUESKY_HANDLE",
+        requires = "bluesky_app_password"
+    )]
+    bluesky_handle: Option<String>,
+
+    /// An app password for the Bluesky account.
+    #[arg(long, env = "GRIDDER_BLUESKY_APP_PASSWORD", hide_env_values = true)]
+    bluesky_app_password: Option<String>,
+
+    /// The Bluesky account's server.
+    #[arg(
+        long,
+        env = "GRIDDER_BLUESKY_SERVICE",
+        default_value = "https://bsky.social"
+    )]
+    bluesky_service: String,
+
+    /// URL of an MQTT broker to publish each day's counts to as JSON, e.g.
+    /// mqtt://homeassistant.local, or mqtts:// for TLS. Never includes the
+    /// letters. Needs a build with the mqtt feature.
+    #[arg(long, env = "GRIDDER_MQTT_BROKER")]
+    mqtt_broker: Option<String>,
+
+    /// Topic to publish to. The message is retained.
+    #[arg(long, env = "GRIDDER_MQTT_TOPIC", default_value = "gridder/daily")]
+    mqtt_topic: String,
+
+    /// User to log in to the MQTT broker as, if it needs one.
+    #[arg(long, env = "GRIDDER_MQTT_USERNAME", requires = "mqtt_password")]
+    mqtt_username: Option<String>,
+
+    /// Password of the MQTT user.
+    #[arg(long, env = "GRIDDER_MQTT_PASSWORD", hide_env_values = true)]
+    mqtt_password: Option<String>,
+
+    /// Also publish each day's grid document to NATS (nats://HOST[:PORT])
+    /// or Kafka (kafka://BROKER[,BROKER...]), e.g. for stream processing.
+    /// gridder must be built with the nats or kafka feature.
+    #[arg(long, env = "GRIDDER_EVENT_SINK")]
+    event_sink: Option<String>,
+
+    /// NATS subject or Kafka topic to publish to.
+    #[arg(long, env = "GRIDDER_EVENT_TOPIC", default_value = "gridder.grids")]
+    event_topic: String,
+
+    /// How to serialize the published documents.
+    #[arg(long, env = "GRIDDER_EVENT_FORMAT", default_value = "json")]
+    event_format: EventFormat,
+
+    /// Text of the Mastodon and Bluesky posts. May use {date}, {words},
+    /// {points} and {pangrams}.
+    #[arg(long, env = "GRIDDER_POST_TEMPLATE", default_value_t = PostTemplate::default())]
+    post_template: PostTemplate,
+
+    /// Also hand each day's grid to a command, as exec:COMMAND. The command
+    /// is run with `sh -c`, gets the grid's JSON document on stdin and its
+    /// date in GRIDDER_DATE, and must exit with 0 for the output to count
+    /// as written. Or commit it to a git repository, as git:DIR, or upload
+    /// it to a WebDAV folder (e.g. Nextcloud's), as webdav:URL, or to a
+    /// bucket, as s3://BUCKET[/PREFIX] or gs://BUCKET[/PREFIX], as
+    /// YYYY-MM-DD.json and the two CSV files. Buckets need a build with the
+    /// buckets feature, and take credentials from the usual AWS_* or
+    /// GOOGLE_* variables. Or upload just the CSV files
+    /// to a Google Drive folder, as drive:FOLDER_ID, or a Dropbox one, as
+    /// dropbox:/PATH. Repeat for several.
+    #[arg(long = "to", value_name = "SINK")]
+    sinks: Vec<Sink>,
+
+    #[command(flatten)]
+    sink_options: SinkArgs,
+
+    /// How much of each day's grid the terminal output and posts may give
+    /// away. Mastodon and Bluesky posts never go past the counts.
+    #[arg(long, env = "GRIDDER_SPOILERS", default_value = "full-hints")]
+    spoilers: SpoilerLevel,
+}
+
+impl NotifyArgs {
+    fn notifiers(&self, connectivity: Connectivity) -> Result<Vec<Notifier>, Error> {
+        let network = || connectivity.network("posting the grid");
+        let mut notifiers = Vec::new();
+        if let (Some(homeserver), Some(token), Some(room)) = (
+            &self.matrix_homeserver,
+            &self.matrix_access_token,
+            &self.matrix_room,
+        ) {
+            let matrix = MatrixNotifier::new(network()?, homeserver, token, room)?;
+            notifiers.push(Notifier::Matrix(matrix));
+        }
+        if let (Some(token), Some(chat_id)) = (&self.telegram_bot_token, &self.telegram_chat_id) {
+            notifiers.push(Notifier::Telegram(TelegramNotifier::new(
+                network()?,
+                token,
+                chat_id,
+            )));
+        }
+        if let (Some(instance), Some(token)) =
+            (&self.mastodon_instance, &self.mastodon_access_token)
+        {
+            let mastodon = MastodonNotifier::new(network()?, instance, token);
+            notifiers.push(Notifier::Mastodon(mastodon, self.post_template.clone()));
+        }
+        if let (Some(handle), Some(password)) = (&self.bluesky_handle, &self.bluesky_app_password) {
+            let bluesky = BlueskyNotifier::new(network()?, &self.bluesky_service, handle, password);
+            notifiers.push(Notifier::Bluesky(bluesky, self.post_template.clone()));
+        }
+        if let Some(broker) = &self.mqtt_broker {
+            let credentials = self
+                .mqtt_username
+                .as_deref()
+                .zip(self.mqtt_password.as_deref());
+            let mqtt = MqttNotifier::new(network()?, broker, &self.mqtt_topic, credentials)?;
+            notifiers.push(Notifier::Mqtt(mqtt));
+        }
+        if let Some(url) = &self.event_sink {
+            let sink = EventSink::new(url, &self.event_topic, self.event_format)
+                .map_err(NotifyError::from)?;
+            notifiers.push(Notifier::Event(sink));
+        }
+        for sink in &self.sinks {
+            notifiers.push(sink_notifier(sink, &self.sink_options, connectivity)?);
+        }
+
+        Ok(notifiers)
+    }
+}
+
+// How git: sinks commit, and the others log in
+#[derive(clap::Args, Debug)]
+struct SinkArgs {
+    /// Message of the commits to git: sinks. May use {date}, {words},
+    /// {points} and {pangrams}.
+    #[arg(long, env = "GRIDDER_GIT_MESSAGE", default_value = "Grid for {date}")]
+    git_message: PostTemplate,
+
+    /// Push git: sinks after each commit, to their upstream.
+    #[arg(long, env = "GRIDDER_GIT_PUSH")]
+    git_push: bool,
+
+    /// User to log in to webdav: sinks as, if they need it.
+    #[arg(long, env = "GRIDDER_WEBDAV_USERNAME", requires = "webdav_password")]
+    webdav_username: Option<String>,
+
+    /// Password of the WebDAV user. For Nextcloud, use an app password.
+    #[arg(long, env = "GRIDDER_WEBDAV_PASSWORD", hide_env_values = true)]
+    webdav_password: Option<String>,
+
+    /// JSON key of the service account to upload to drive: sinks as, e.g.
+    /// the same one as --service-account-file. The folder must be shared
+    /// with it as an editor.
+    #[arg(long, env = "GRIDDER_DRIVE_SERVICE_ACCOUNT_FILE")]
+    drive_service_account_file: Option<PathBuf>,
+
+    /// App key of the Dropbox app to upload to dropbox: sinks through.
+    #[arg(
+        long,
+        env = "GRIDDER_DROPBOX_APP_KEY",
+        requires = "dropbox_refresh_token"
+    )]
+    dropbox_app_key: Option<String>,
+
+    /// Refresh token the Dropbox app was given for the account.
+    #[arg(long, env = "GRIDDER_DROPBOX_REFRESH_TOKEN", hide_env_values = true)]
+    dropbox_refresh_token: Option<String>,
+}
+
+// Buckets are only set up here, as they need the network
+fn sink_notifier(
+    sink: &Sink,
+    options: &SinkArgs,
+    connectivity: Connectivity,
+) -> Result<Notifier, Error> {
+    match sink {
+        Sink::Exec(exec) => Ok(Notifier::Exec(exec.clone())),
+        Sink::Git(dir) => {
+            let push = match options.git_push {
+                true => Some(connectivity.network("pushing the grid")?),
+                false => None,
+            };
+            let repo = GitSink::new(dir.clone(), options.git_message.clone(), push);
+            Ok(Notifier::Git(repo))
+        }
+        Sink::Webdav(url) => {
+            let network = connectivity.network("uploading the grid")?;
+            let credentials = options
+                .webdav_username
+                .as_deref()
+                .zip(options.webdav_password.as_deref());
+            let folder = WebdavSink::new(network, url, credentials).map_err(NotifyError::from)?;
+            Ok(Notifier::Webdav(folder))
+        }
+        Sink::Drive(folder_id) => {
+            let network = connectivity.network("uploading the grid")?;
+            let service_account_file = options
+                .drive_service_account_file
+                .as_deref()
+                .ok_or(Error::MissingArgument("--drive-service-account-file"))?;
+            let folder = DriveSink::new(network, service_account_file, folder_id);
+            Ok(Notifier::Drive(folder))
+        }
+        Sink::Dropbox(path) => {
+            let network = connectivity.network("uploading the grid")?;
+            let (Some(app_key), Some(refresh_token)) =
+                (&options.dropbox_app_key, &options.dropbox_refresh_token)
+            else {
+                return Err(Error::MissingArgument("--dropbox-app-key"));
+            };
+            let folder = DropboxSink::new(network, path, app_key, refresh_token)
+                .map_err(NotifyError::from)?;
+            Ok(Notifier::Dropbox(folder))
+        }
+        Sink::Bucket(url) => {
+            let network = connectivity.network("uploading the grid")?;
+            let bucket = BucketSink::new(network, url).map_err(NotifyError::from)?;
+            Ok(Notifier::Bucket(bucket))
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct SheetsArgs {
     #[arg(short = 'i', long, env = "GRIDDER_SPREADSHEET_ID")]
     spreadsheet_id: String,
 
     #[arg(short = 'p', long, env = "GRIDDER_SERVICE_ACCOUNT_FILE")]
     service_account_file: PathBuf,
+
+    /// How to name each day's tab: a strftime format, which may also use
+    /// {weekday}, {weekday_short}, {month}, {month_short} and {day}.
+    #[arg(long, env = "GRIDDER_SHEET_NAME_TEMPLATE", default_value = "%Y-%m-%d")]
+    sheet_name_template: SheetNameTemplate,
+
+    /// Add warning-only protection to the TEMPLATE tab and to the data
+    /// written to each new tab.
+    #[arg(long, env = "GRIDDER_PROTECT_RANGES")]
+    protect_ranges: bool,
+
+    /// Freeze the header rows and fit the data columns to their contents on
+    /// each new tab.
+    #[arg(long, env = "GRIDDER_TIDY_LAYOUT")]
+    tidy_layout: bool,
+
+    /// Leave a new tab in place if writing the data to it fails, instead of
+    /// removing it.
+    #[arg(long, env = "GRIDDER_KEEP_FAILED_SHEETS")]
+    keep_failed_sheets: bool,
+
+    /// Note the gridder version, when the grid was read and where from on
+    /// the first cell of each block of the grid written to a tab.
+    #[arg(long, env = "GRIDDER_PROVENANCE_NOTES")]
+    provenance_notes: bool,
+
+    /// Most write requests to make to the Sheets API in a minute. Writes
+    /// slow down further if the API reports the quota's been hit.
+    #[arg(long, env = "GRIDDER_MAX_WRITES_PER_MINUTE", default_value_t = 60)]
+    max_writes_per_minute: u32,
 }
 
-#[derive(thiserror::Error, Debug)]
-enum Error {
-    #[error("failed to parse {0} into a date ({1})")]
-    ParsingDate(String, chrono::ParseError),
-    #[error("failed to fetch site data: {0}")]
-    FetchingSiteData(#[from] FetchDataError),
-    #[error("failed to create Sheets API client: {0}")]
-    CreatingSheetManager(#[from] NewSheetError),
-    #[error("failed to create new daily sheet: {0}")]
-    UpdatingSpreadsheet(#[from] SheetCreationError),
+impl SheetsArgs {
+    async fn connect(self, connectivity: Connectivity) -> Result<SheetManager, Error> {
+        let network = connectivity.network("writing to the spreadsheet")?;
+        let mut builder = SheetManager::builder(&self.spreadsheet_id, self.service_account_file)
+            .sheet_names(self.sheet_name_template)
+            .protect_ranges(self.protect_ranges)
+            .tidy_layout(self.tidy_layout)
+            .remove_failed_sheets(!self.keep_failed_sheets)
+            .provenance_notes(self.provenance_notes)
+            .max_writes_per_minute(self.max_writes_per_minute);
+        if let Some(log) = audit_log() {
+            builder = builder.audit_log(log);
+        }
+        if let Some(log) = http_log() {
+            builder = builder.http_log(log);
+        }
+
+        Ok(builder.build(network).await?)
+    }
+
+    // The same settings, as the environment variables they can be read from
+    fn environment(&self) -> Vec<(String, String)> {
+        let mut env = vec![
+            ("GRIDDER_SPREADSHEET_ID", self.spreadsheet_id.clone()),
+            (
+                "GRIDDER_SERVICE_ACCOUNT_FILE",
+                self.service_account_file.display().to_string(),
+            ),
+            (
+                "GRIDDER_SHEET_NAME_TEMPLATE",
+                self.sheet_name_template.to_string(),
+            ),
+        ];
+        if self.protect_ranges {
+            env.push(("GRIDDER_PROTECT_RANGES", "true".to_string()));
+        }
+        if self.tidy_layout {
+            env.push(("GRIDDER_TIDY_LAYOUT", "true".to_string()));
+        }
+        if self.keep_failed_sheets {
+            env.push(("GRIDDER_KEEP_FAILED_SHEETS", "true".to_string()));
+        }
+        if self.provenance_notes {
+            env.push(("GRIDDER_PROVENANCE_NOTES", "true".to_string()));
+        }
+        env.push((
+            "GRIDDER_MAX_WRITES_PER_MINUTE",
+            self.max_writes_per_minute.to_string(),
+        ));
+
+        env.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+    }
 }
 
-async fn real_main() -> Result<(), Error> {
-    let args = Args::parse();
-    let date = args
-        .date
-        // If a datestring was given, try to parse it into a NaiveDate
-        .map(|i| i.parse().map_err(|e| Error::ParsingDate(i, e)))
-        // Put the Result<..> on the outside, and exit if it failed
-        .transpose()?
-        // If no date was given, fall back to using today (in US-Western)
-        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&US_WEST_TZ).date_naive());
+#[derive(clap::Args, Debug)]
+struct SchemaArgs {
+    /// Print the Avro schema of documents published with --event-format
+    /// avro instead.
+    #[arg(long)]
+    avro: bool,
+}
 
-    let body = fetch_for_date(date).await?;
-    let (pairs, table_info) = parse_content(&body).expect("failed to extract info from document");
+#[derive(clap::Args, Debug)]
+struct ConvertArgs {
+    /// File to convert. For CSV input, this is the lengths file.
+    input: PathBuf,
 
-    let sheets_client = SheetManager::new(&args.spreadsheet_id, args.service_account_file).await?;
-    sheets_client
-        .create_for_date(&date, &pairs, &table_info)
-        .await?;
+    /// The pairs file, when converting from CSV.
+    #[arg(long)]
+    pairs_input: Option<PathBuf>,
 
-    Ok(())
+    /// Format of the input. Guessed from its extension if unspecified.
+    #[arg(long)]
+    from: Option<Format>,
+
+    #[arg(long, alias = "format")]
+    to: Format,
+
+    /// Date of the grid. Required when converting from CSV, which doesn't
+    /// record it.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    date: Option<chrono::NaiveDate>,
+
+    /// Where to write the output. For CSV output, this is the lengths file.
+    /// Other formats are written to stdout if unspecified.
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// Where to write the pairs file, when converting to CSV.
+    #[arg(long)]
+    pairs_output: Option<PathBuf>,
+
+    /// Tera template to render, when converting to a template. Its
+    /// variables are the JSON document's fields (see `gridder schema`).
+    #[arg(long)]
+    template: Option<PathBuf>,
 }
 
-#[tokio::main]
-async fn main() {
-    if let Err(e) = real_main().await {
-        eprintln!("error: {e}");
-        std::process::exit(1);
+#[derive(clap::Args, Debug)]
+struct EmitArgs {
+    /// Which bundled page to read.
+    #[arg(
+        long,
+        default_value = "sample",
+        value_parser = clap::builder::PossibleValuesParser::new(FIXTURES.map(|f| f.name))
+    )]
+    fixture: String,
+
+    #[arg(long, alias = "format", default_value = "json")]
+    to: Format,
+
+    /// Where to write the output. For CSV output, this is the lengths file.
+    /// Other formats are written to stdout if unspecified.
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// Where to write the pairs file, when writing CSV.
+    #[arg(long)]
+    pairs_output: Option<PathBuf>,
+
+    /// Tera template to render, when writing a template.
+    #[arg(long)]
+    template: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ProgressArgs {
+    /// The date to check progress for. If unspecified, today's grid is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// A saved grid (JSON) to compare against. Fetched from the site if
+    /// unspecified.
+    #[arg(long)]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    /// File of found words, one per line.
+    #[arg(long, conflicts_with = "from_sheets")]
+    words: Option<PathBuf>,
+
+    /// Read found words from the day's tab in the spreadsheet instead.
+    #[arg(long)]
+    from_sheets: bool,
+
+    #[arg(short = 'i', long, env = "GRIDDER_SPREADSHEET_ID")]
+    spreadsheet_id: Option<String>,
+
+    #[arg(short = 'p', long, env = "GRIDDER_SERVICE_ACCOUNT_FILE")]
+    service_account_file: Option<PathBuf>,
+
+    /// Also save the found words that fit the grid to DATE.words.txt in
+    /// this directory, or to a database as with `gridder run`, for
+    /// `gridder past-words`.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: Option<PathBuf>,
+
+    /// How much of the grid to give away. Below full-hints, what's left to
+    /// find isn't shown.
+    #[arg(long, env = "GRIDDER_SPOILERS", default_value = "full-hints")]
+    spoilers: SpoilerLevel,
+}
+
+#[derive(clap::Args, Debug)]
+struct LeaderboardArgs {
+    /// The date to rank. If unspecified, today's grid is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// A saved grid (JSON) to score against. Fetched from the site if
+    /// unspecified.
+    #[arg(long)]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    /// Also record who found which word, and when, to DATE.finds.json in
+    /// this directory, or to a database as with `gridder run`. A word keeps
+    /// the time it was first seen.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct InstallServiceArgs {
+    /// Directory to write gridder.service and gridder.timer to.
+    #[arg(long, default_value = "/etc/systemd/system")]
+    unit_dir: PathBuf,
+
+    /// When the timer runs gridder, as with `gridder watch --schedule`.
+    /// The default is two minutes after the new puzzle is released.
+    #[arg(long = "schedule", default_value = "2 0 * * *")]
+    schedules: Vec<Schedule>,
+
+    /// Timezone the schedules are evaluated in.
+    #[arg(long, env = "GRIDDER_TIMEZONE", default_value_t = RELEASE_TZ)]
+    timezone: Tz,
+
+    /// User to run the service as.
+    #[arg(long)]
+    user: Option<String>,
+
+    /// Also record the day's totals on the "Stats" tab.
+    #[arg(long)]
+    stats: bool,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct SelfUpdateArgs {
+    /// Only say whether there's a newer release.
+    #[arg(long)]
+    check: bool,
+
+    /// GitHub repository (OWNER/NAME) to take releases from.
+    #[arg(long, env = "GRIDDER_UPDATE_REPO", default_value = update::DEFAULT_REPO)]
+    repo: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct SyncArgs {
+    /// First day to sync.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    since: chrono::NaiveDate,
+
+    /// Last day to sync. Defaults to today.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    until: Option<chrono::NaiveDate>,
+
+    /// Directory of saved grids, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Only list the tabs that would be created or filled in.
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct RotationArgs {
+    /// Move to a new spreadsheet once this one takes up this percentage of
+    /// Google's limit of 10 million cells. The new one is remembered in
+    /// --state-dir, and used from then on instead of --spreadsheet-id.
+    #[arg(
+        long,
+        env = "GRIDDER_ROTATE_AT",
+        requires = "state_dir",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    rotate_at: Option<u8>,
+
+    /// Email address to share a new spreadsheet with, as an editor. Repeat
+    /// for several people.
+    #[arg(long = "share-with", env = "GRIDDER_SHARE_WITH", value_delimiter = ',')]
+    share_with: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct RotateArgs {
+    /// Title of the new spreadsheet. Defaults to "Gridder from DATE", with
+    /// today's date.
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Directory to keep track of runs in, where the new spreadsheet is
+    /// remembered.
+    #[arg(long, env = "GRIDDER_STATE_DIR")]
+    state_dir: PathBuf,
+
+    /// Email address to share the new spreadsheet with, as an editor.
+    /// Repeat for several people.
+    #[arg(long = "share-with", env = "GRIDDER_SHARE_WITH", value_delimiter = ',')]
+    share_with: Vec<String>,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct BackupArgs {
+    /// Where to save the copy. A path ending in .xlsx is written as a
+    /// workbook with a sheet per tab, formulas included, in builds with the
+    /// xlsx feature; anything else is a directory with a CSV file per tab.
+    output: PathBuf,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct DoctorArgs {
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    /// Directory of saved grids, as written with --grid-dir, to check can
+    /// all be read.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct WatchArgs {
+    /// When to run, as a cron expression (minute hour day-of-month month
+    /// day-of-week). Repeat to run at several times, e.g. to retry later in
+    /// the day.
+    #[arg(long = "schedule", default_value = "2 0 * * *")]
+    schedules: Vec<Schedule>,
+
+    /// Timezone the schedules are evaluated in.
+    #[arg(long, env = "GRIDDER_TIMEZONE", default_value_t = RELEASE_TZ)]
+    timezone: Tz,
+
+    /// Longest each run may take, e.g. 5m, as with `gridder run`.
+    #[arg(long, env = "GRIDDER_DEADLINE")]
+    deadline: Option<Budget>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    /// Also record each day's totals on the "Stats" tab.
+    #[arg(long, env = "GRIDDER_UPDATE_STATS")]
+    stats: bool,
+
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+
+    #[command(flatten)]
+    steps: StepArgs,
+
+    #[command(flatten)]
+    rotation: RotationArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+
+    #[command(flatten)]
+    retention: RetentionArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct BackfillArgs {
+    /// First day to write.
+    /// Format: YYYY-MM-DD
+    from: chrono::NaiveDate,
+
+    /// Last day to write. Defaults to today.
+    /// Format: YYYY-MM-DD
+    to: Option<chrono::NaiveDate>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    /// Also record each day's totals on the "Stats" tab.
+    #[arg(long, env = "GRIDDER_UPDATE_STATS")]
+    stats: bool,
+
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+
+    #[command(flatten)]
+    steps: StepArgs,
+
+    /// Write to every output, even where the state directory says a day is
+    /// done.
+    #[arg(long)]
+    force: bool,
+
+    #[command(flatten)]
+    rotation: RotationArgs,
+
+    #[command(flatten)]
+    notify: NotifyArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct GcArgs {
+    /// Directory of saved grids, as written with --grid-dir. Only files are
+    /// removed, so databases can't be given.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    retention: RetentionArgs,
+}
+
+// How long to keep what builds up over time; the requests recorded are in
+// the global --debug-http directory
+#[derive(clap::Args, Debug)]
+struct RetentionArgs {
+    /// Remove saved grids more than this many days old. Kept forever if
+    /// not given.
+    #[arg(long, value_name = "DAYS", env = "GRIDDER_KEEP_GRIDS")]
+    keep_grids: Option<u64>,
+
+    /// Remove the words and finds saved alongside grids when they're more
+    /// than this many days old.
+    #[arg(long, value_name = "DAYS", env = "GRIDDER_KEEP_WORDS")]
+    keep_words: Option<u64>,
+
+    /// Remove requests recorded with --debug-http more than this many days
+    /// ago, e.g. to only keep the pages they hold for a while.
+    #[arg(long, value_name = "DAYS", env = "GRIDDER_KEEP_HTTP_LOG")]
+    keep_http_log: Option<u64>,
+}
+
+impl RetentionArgs {
+    fn retention(&self) -> Retention {
+        Retention {
+            grids: self.keep_grids,
+            words: self.keep_words,
+            http_log: self.keep_http_log,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct GapsArgs {
+    /// First day to check.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    since: chrono::NaiveDate,
+
+    /// Last day to check. Defaults to today.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    until: Option<chrono::NaiveDate>,
+
+    /// Directory of saved grids, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Grids that scored lower than this when read count as gaps.
+    #[arg(long, default_value_t = 80)]
+    min_quality: u8,
+
+    /// Fetch the grids for the days found, and save them.
+    #[arg(long)]
+    fetch: bool,
+
+    #[command(flatten)]
+    fetch_args: FetchArgs,
+}
+
+#[cfg(feature = "dashboard")]
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    /// Address to listen on.
+    #[arg(long, env = "GRIDDER_SERVE_LISTEN", default_value = "127.0.0.1:8080")]
+    listen: std::net::SocketAddr,
+
+    /// Directory of saved grids to show, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Only answer requests sending this as a bearer token. Anyone can use
+    /// the dashboard and API without this or --auth-username, so set one
+    /// before listening beyond localhost.
+    #[arg(long, env = "GRIDDER_SERVE_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    /// Only answer requests logging in with this username and
+    /// --auth-password, using HTTP basic auth, which browsers ask for. Can
+    /// be given along with --auth-token, and either is accepted.
+    #[arg(long, env = "GRIDDER_SERVE_USERNAME", requires = "auth_password")]
+    auth_username: Option<String>,
+
+    #[arg(long, env = "GRIDDER_SERVE_PASSWORD", hide_env_values = true)]
+    auth_password: Option<String>,
+
+    /// A path anyone can request, even with --auth-token or
+    /// --auth-username, e.g. /api/history. End it with * for every path
+    /// starting with it, e.g. /docs*. Repeat for several.
+    #[arg(long = "public-path", value_name = "PATH")]
+    public_paths: Vec<String>,
+
+    /// An origin whose pages may call the API from the browser, e.g.
+    /// https://grids.example.com, or * for any. Repeat for several.
+    /// Cross-origin requests are refused unless this is given.
+    #[arg(long = "cors-origin", value_name = "ORIGIN", value_parser = parse_origin)]
+    cors_origins: Vec<http::HeaderValue>,
+}
+
+#[cfg(feature = "dashboard")]
+fn parse_origin(origin: &str) -> Result<http::HeaderValue, String> {
+    http::HeaderValue::from_str(origin.trim_end_matches('/'))
+        .map_err(|_| format!("{origin:?} isn't a valid origin"))
+}
+
+#[cfg(feature = "grpc")]
+#[derive(clap::Args, Debug)]
+struct GrpcArgs {
+    /// Address to listen on.
+    #[arg(long, env = "GRIDDER_GRPC_LISTEN", default_value = "127.0.0.1:50051")]
+    listen: std::net::SocketAddr,
+
+    /// Directory of saved grids to serve, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Number of seconds between looks for newly saved grids to send to
+    /// subscribers.
+    #[arg(long, default_value_t = 60)]
+    poll_interval: u64,
+
+    /// Only serve callers sending this as a bearer token. Anyone can call
+    /// the service without this or --auth-username, so set one before
+    /// listening beyond localhost.
+    #[arg(long, env = "GRIDDER_GRPC_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    /// Only serve callers logging in with this username and
+    /// --auth-password, using HTTP basic auth. Can be given along with
+    /// --auth-token, and either is accepted.
+    #[arg(long, env = "GRIDDER_GRPC_USERNAME", requires = "auth_password")]
+    auth_username: Option<String>,
+
+    #[arg(long, env = "GRIDDER_GRPC_PASSWORD", hide_env_values = true)]
+    auth_password: Option<String>,
+
+    /// A method anyone can call, even with --auth-token or
+    /// --auth-username. Repeat for several.
+    #[arg(long = "public-method", value_name = "METHOD")]
+    public_methods: Vec<GrpcMethod>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReplayArgs {
+    /// First day to replay. Defaults to the first saved day.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    since: Option<chrono::NaiveDate>,
+
+    /// Last day to replay. Defaults to the last saved day.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    until: Option<chrono::NaiveDate>,
+
+    /// Directory of saved grids, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Format to write each day's grid in (csv, json, template or prose),
+    /// into --output-dir. Or hand it to a command, as exec:COMMAND, commit
+    /// it to a git repository, as git:DIR, or upload it to a shared folder
+    /// or bucket, as webdav:URL, drive:FOLDER_ID, dropbox:/PATH, s3:// or
+    /// gs://, as `gridder run --to` does. Repeat for several.
+    #[arg(long = "to", value_name = "FORMAT|SINK", required = true)]
+    targets: Vec<ReplayTarget>,
+
+    /// Directory to write the grids to, one file per day named after it
+    /// (e.g. 2024-05-01.json). CSV is written to YYYY-MM-DD.lengths.csv
+    /// and YYYY-MM-DD.pairs.csv. Required with a format for --to.
+    #[arg(short = 'o', long)]
+    output_dir: Option<PathBuf>,
+
+    /// Tera template to render, with --to template. Files take its
+    /// extension.
+    #[arg(long)]
+    template: Option<PathBuf>,
+
+    #[command(flatten)]
+    transform: TransformArgs,
+
+    #[command(flatten)]
+    sink_options: SinkArgs,
+}
+
+/// Somewhere `gridder replay --to` puts each day's grid: files in a format,
+/// or a sink as `gridder run --to` takes.
+#[derive(Debug, Clone)]
+enum ReplayTarget {
+    Format(Format),
+    Sink(Sink),
+}
+
+impl std::str::FromStr for ReplayTarget {
+    type Err = String;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        match <Format as clap::ValueEnum>::from_str(target, true) {
+            Ok(format) => Ok(Self::Format(format)),
+            Err(_) => target
+                .parse()
+                .map(Self::Sink)
+                .map_err(|e| format!("{e}, or a format: csv, json, template or prose")),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct ReportArgs {
+    /// Month to report on.
+    /// Format: YYYY-MM
+    #[arg(long, value_parser = Period::month, required_unless_present = "week")]
+    month: Option<Period>,
+
+    /// Report on the week (Monday to Sunday) that this day falls in instead.
+    /// Format: YYYY-MM-DD
+    #[arg(long, conflicts_with = "month")]
+    week: Option<chrono::NaiveDate>,
+
+    /// Directory of saved grids, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    #[arg(long, default_value = "markdown")]
+    format: ReportFormat,
+
+    /// How much of each day's grid the report may give away.
+    #[arg(long, env = "GRIDDER_SPOILERS", default_value = "full-hints")]
+    spoilers: SpoilerLevel,
+
+    /// Where to write the report. Written to stdout if unspecified.
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportArgs {
+    /// First day to export. Defaults to the first saved day.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    since: Option<chrono::NaiveDate>,
+
+    /// Last day to export. Defaults to the last saved day.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    until: Option<chrono::NaiveDate>,
+
+    /// Directory of saved grids, as written with --grid-dir.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Where to write the table. Written to stdout if unspecified.
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// Bytes of rows to hold before writing them out. Larger means fewer,
+    /// bigger writes, e.g. for network filesystems.
+    #[arg(long, default_value_t = 64 * 1024)]
+    buffer_size: usize,
+
+    /// Also write out the rows held after every this many days, e.g. so
+    /// something following the output sees the days as they're done.
+    #[arg(long)]
+    flush_every: Option<std::num::NonZeroUsize>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportCsvArgs {
+    /// Which column holds what, counting from 1. Add date=N for files with
+    /// more than one day in them; otherwise each file is for the day in its
+    /// name, e.g. 2023-06-01.csv.
+    #[arg(long, default_value = "letter=1,length=2,count=3")]
+    map: ColumnMap,
+
+    /// The character between columns, e.g. ';'. "tab" for tabs.
+    #[arg(long, default_value = ",", value_parser = parse_delimiter)]
+    delimiter: u8,
+
+    /// Directory of saved grids to load the days into.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// Overwrite days that are already saved, rather than skipping them.
+    #[arg(long)]
+    replace: bool,
+
+    /// CSV files, or directories of them.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        _ if s == "tab" => Ok(b'\t'),
+        [byte] if byte.is_ascii() => Ok(*byte),
+        _ => Err(format!("{s:?} isn't a single character")),
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct MergeArgs {
+    /// Directory of saved grids to merge into. Databases can't be merged.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// How to settle days the sources have different grids for.
+    #[arg(long, value_enum, default_value_t = Policy::HighestQuality)]
+    policy: Policy,
+
+    /// Where to write the reconciliation report. Written to stdout if
+    /// unspecified.
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Only write the report; leave --grid-dir as it is.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Directories of saved grids to merge from. Earlier ones win ties.
+    #[arg(required = true)]
+    sources: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct PairsArgs {
+    /// The day to look for unusual pairs in. If unspecified, today's grid
+    /// is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// Directory of saved grids, as written with --grid-dir. The day's grid
+    /// is fetched if it hasn't been saved.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+
+    /// How many of the most frequent pairs to list.
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+
+    /// Pairs seen on fewer than this share of past days count as unusual.
+    #[arg(long, default_value_t = RARE_PAIR_SHARE)]
+    rare_below: f64,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct ShowArgs {
+    /// The date to show. If unspecified, today's grid is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// A saved grid (JSON) to show. Fetched from the site if unspecified.
+    #[arg(long)]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct CoverageArgs {
+    /// The date to check. If unspecified, today's grid is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// A saved grid (JSON) to check against. Fetched from the site if
+    /// unspecified.
+    #[arg(long)]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    /// Word list to check, one word per line.
+    #[arg(long, env = "GRIDDER_DICTIONARY")]
+    dictionary: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct PastWordsArgs {
+    /// The puzzle's letters, e.g. ACDELNT.
+    letters: String,
+
+    /// Only list words using this letter.
+    #[arg(long)]
+    center: Option<char>,
+
+    /// Directory or database the found words were saved to by `gridder
+    /// progress`.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportConstraintsArgs {
+    /// The date to export. If unspecified, today's grid is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// A saved grid (JSON) to export from. Fetched from the site if
+    /// unspecified.
+    #[arg(long)]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    /// File of words already found, one per line, to leave out.
+    #[arg(long)]
+    words: Option<PathBuf>,
+
+    #[arg(long, default_value = "regex")]
+    format: ConstraintFormat,
+
+    /// Where to write the constraints. Written to stdout if unspecified.
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct TrackArgs {
+    /// The date to track. If unspecified, today's grid is used.
+    /// Format: YYYY-MM-DD
+    date: Option<String>,
+
+    /// A saved grid (JSON) to track against. Fetched from the site if
+    /// unspecified.
+    #[arg(long)]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    sheets: SheetsArgs,
+
+    /// Number of seconds between updates.
+    #[arg(long, env = "GRIDDER_TRACK_INTERVAL", default_value_t = 300)]
+    interval: u64,
+}
+
+#[derive(clap::Args, Debug)]
+struct MemberStatsArgs {
+    /// First day to count.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    since: chrono::NaiveDate,
+
+    /// Last day to count. Defaults to today.
+    /// Format: YYYY-MM-DD
+    #[arg(long)]
+    until: Option<chrono::NaiveDate>,
+
+    /// Directory the finds were recorded to.
+    #[arg(long, env = "GRIDDER_GRID_DIR")]
+    grid_dir: PathBuf,
+}
+
+// Messages already include their causes, so only the variants where there's
+// something useful to suggest get a diagnostic
+#[derive(thiserror::Error, miette::Diagnostic, Debug)]
+enum Error {
+    #[error("failed to parse {0} into a date ({1})")]
+    #[diagnostic(
+        code(gridder::bad_date),
+        help("dates are written YYYY-MM-DD, e.g. 2024-04-30")
+    )]
+    ParsingDate(String, chrono::ParseError),
+    #[error("the puzzle for {0} isn't released until {1}")]
+    #[diagnostic(
+        code(gridder::not_released),
+        help("try again then, or leave `gridder watch` running to pick it up")
+    )]
+    NotReleased(chrono::NaiveDate, chrono::DateTime<Tz>),
+    #[error("interrupted after {0}")]
+    Interrupted(&'static str),
+    #[error(transparent)]
+    #[diagnostic(
+        code(gridder::deadline),
+        help("give the run a longer --deadline, or find out why that stage is slow")
+    )]
+    DeadlineExceeded(#[from] DeadlineExceeded),
+    #[error("failed to fetch site data: {0}")]
+    #[diagnostic(
+        code(gridder::fetch),
+        help("if the page has moved, --probe-urls looks for it under other names, and --base-url fetches from a mirror")
+    )]
+    FetchingSiteData(#[from] FetchDataError),
+    #[error("failed to read session cookie from {0}: {1}")]
+    ReadingCookieFile(PathBuf, std::io::Error),
+    #[error("failed to extract info from document: {0}")]
+    #[diagnostic(
+        code(gridder::parse),
+        help("the page structure may have changed; run with --fingerprint-file to see what moved, and point --table-selector, --row-selector or --paragraph-selector at the new markup")
+    )]
+    ParsingSiteData(#[from] SiteParseError),
+    #[error("failed to create Sheets API client: {0}")]
+    #[diagnostic(
+        code(gridder::sheets_login),
+        help("--service-account-file should be the JSON key of a service account")
+    )]
+    CreatingSheetManager(#[from] NewSheetError),
+    #[error("failed to create new daily sheet: {0}")]
+    #[diagnostic(
+        code(gridder::sheets_write),
+        help("the spreadsheet must be shared with the service account as an editor, and have a TEMPLATE tab to copy")
+    )]
+    UpdatingSpreadsheet(#[from] SheetCreationError),
+    #[error("failed to serialize schema: {0}")]
+    SerializingSchema(serde_json::Error),
+    #[error("can't tell the format of {0}, pass --from")]
+    UnknownFormat(PathBuf),
+    #[error("{0} can only be written, not read")]
+    OutputOnly(&'static str),
+    #[error("{0} is required here")]
+    MissingArgument(&'static str),
+    #[error("--since {0} is after --until {1}")]
+    #[diagnostic(
+        code(gridder::reversed_range),
+        help("--since is the first day to include, and --until the last")
+    )]
+    ReversedRange(chrono::NaiveDate, chrono::NaiveDate),
+    #[error("failed to convert: {0}")]
+    Converting(#[from] FormatError),
+    #[error("failed to read found words: {0}")]
+    ReadingWords(std::io::Error),
+    #[error("failed to read found words from spreadsheet: {0}")]
+    ReadingProgress(#[from] ReadProgressError),
+    #[error("failed to write leaderboard: {0}")]
+    WritingLeaderboard(#[from] WriteLeaderboardError),
+    #[error(transparent)]
+    #[diagnostic(
+        code(gridder::offline),
+        help("run without --offline, or give saved files (e.g. --grid) instead")
+    )]
+    Offline(#[from] OfflineError),
+    #[error("failed to keep track of runs: {0}")]
+    #[diagnostic(
+        code(gridder::state),
+        help("each gridder needs its --state-dir to itself; wait for the other one to finish, or stop it")
+    )]
+    TrackingRuns(StateError),
+    #[error("failed to transform the grid: {0}")]
+    Transforming(#[from] TransformError),
+    #[error("failed to post the grid: {0}")]
+    Notifying(#[from] NotifyError),
+    #[error("failed to write remaining counts: {0}")]
+    WritingRemaining(#[from] WriteRemainingError),
+    #[error("failed to update stats: {0}")]
+    UpdatingStats(#[from] UpdateStatsError),
+    #[error("failed to find the gridder executable: {0}")]
+    LocatingExecutable(std::io::Error),
+    #[error("failed to write systemd unit {0}: {1}")]
+    WritingUnit(PathBuf, std::io::Error),
+    #[error("no schedule has an upcoming run")]
+    NoUpcomingRun,
+    #[error("failed to track page structure in {0}: {1}")]
+    #[diagnostic(
+        code(gridder::fingerprint),
+        help("delete the file to start tracking the page's structure afresh")
+    )]
+    TrackingFingerprint(PathBuf, FingerprintError),
+    #[error("failed to save grid: {0}")]
+    SavingGrid(ArchiveError),
+    #[error("failed to read saved grids: {0}")]
+    ReadingArchive(ArchiveError),
+    #[error("failed to remove old files: {0}")]
+    CollectingGarbage(#[from] RetentionError),
+    #[error("failed to import {0}: {1}")]
+    #[diagnostic(
+        code(gridder::import_csv),
+        help("--map says which column holds what, e.g. letter=2,length=3,count=4, and --delimiter what's between them")
+    )]
+    ImportingCsv(PathBuf, CsvImportError),
+    #[error("{0} is in both {1} and {2}")]
+    ImportedTwice(chrono::NaiveDate, PathBuf, PathBuf),
+    #[error("failed to list {0}: {1}")]
+    ListingCsvFiles(PathBuf, std::io::Error),
+    #[error("failed to merge saved grids: {0}")]
+    Merging(ArchiveError),
+    #[error("failed to migrate the database: {0}")]
+    Migrating(ArchiveError),
+    #[error("failed to back up the database: {0}")]
+    BackingUpDatabase(ArchiveError),
+    #[error("failed to restore the database: {0}")]
+    #[diagnostic(
+        code(gridder::db_restore),
+        help("backups can only be restored to the kind of database they came from")
+    )]
+    RestoringDatabase(ArchiveError),
+    #[error("failed to check the database: {0}")]
+    #[diagnostic(
+        code(gridder::db_check),
+        help("restore it from a backup with `gridder db restore`")
+    )]
+    CheckingDatabase(ArchiveError),
+    #[cfg(feature = "grpc")]
+    #[error("failed to serve gRPC: {0}")]
+    #[diagnostic(
+        code(gridder::grpc),
+        help("another program may already be listening on --listen")
+    )]
+    ServingGrpc(tonic::transport::Error),
+    #[cfg(feature = "dashboard")]
+    #[error("failed to serve the dashboard on {0}: {1}")]
+    #[diagnostic(
+        code(gridder::serve),
+        help("another program may already be listening on --listen")
+    )]
+    ServingDashboard(std::net::SocketAddr, std::io::Error),
+    #[error("failed to read dictionary {0}: {1}")]
+    ReadingDictionary(PathBuf, std::io::Error),
+    #[error("failed to write {0}: {1}")]
+    WritingOutput(PathBuf, std::io::Error),
+    #[error("failed to check the spreadsheet: {0}")]
+    #[diagnostic(
+        code(gridder::template),
+        help("the spreadsheet must be shared with the service account, and its TEMPLATE tab must reach at least column AC")
+    )]
+    CheckingTemplate(#[from] CheckTemplateError),
+    #[error("failed to read the spreadsheet's tabs: {0}")]
+    ReadingTabs(#[from] ReadTabsError),
+    #[error(transparent)]
+    #[diagnostic(
+        code(gridder::secret),
+        help("on machines without a keyring, set the option's environment variable (e.g. GRIDDER_MATRIX_ACCESS_TOKEN) instead")
+    )]
+    Secret(#[from] SecretError),
+    #[error("failed to read the secret from stdin: {0}")]
+    ReadingSecret(std::io::Error),
+    #[error("no secret was given on stdin")]
+    EmptySecret,
+    #[error("failed to set up encryption: {0}")]
+    #[diagnostic(
+        code(gridder::encryption),
+        help("give --passphrase (or GRIDDER_PASSPHRASE) or --encryption-key-file")
+    )]
+    Encryption(#[from] EncryptionError),
+    #[error("failed to read {0}: {1}")]
+    ReadingFile(PathBuf, std::io::Error),
+    #[error("failed to decrypt {0}: {1}")]
+    #[diagnostic(
+        code(gridder::decrypt),
+        help("give the same --passphrase or --encryption-key-file it was encrypted with")
+    )]
+    DecryptingFile(PathBuf, EncryptionError),
+    #[error(transparent)]
+    Audit(#[from] AuditError),
+    #[error(transparent)]
+    HttpLog(#[from] HttpLogError),
+    #[error(transparent)]
+    Telemetry(#[from] TelemetryError),
+    #[error("no grid is saved for {0}")]
+    #[diagnostic(
+        code(gridder::not_saved),
+        help("run with --only fetch,grid-file first, or without --skip fetch")
+    )]
+    NotSaved(chrono::NaiveDate),
+    #[error("hook failed: {0}")]
+    #[diagnostic(
+        code(gridder::hook),
+        help("hooks stop the run by exiting with anything but 0; what it printed to stderr should say why")
+    )]
+    Hook(#[from] HookError),
+    #[error("failed to read profiles: {0}")]
+    #[diagnostic(
+        code(gridder::profile),
+        help("profiles are [profile.NAME] sections in the config file, setting options by their long names, e.g. grid-dir = \"/srv/grids\"")
+    )]
+    ReadingProfile(ProfileError),
+    #[error("failed to move to a new spreadsheet: {0}")]
+    #[diagnostic(
+        code(gridder::rotate),
+        help("the new spreadsheet belongs to the service account, so give --share-with to be able to open it")
+    )]
+    RotatingSpreadsheet(#[from] RotateError),
+    #[error("failed to save the backup: {0}")]
+    BackingUp(#[from] BackupError),
+    #[error("saved grid can't be written: {0}")]
+    UnusableGrid(#[from] DocumentError),
+    #[error("failed to update gridder: {0}")]
+    #[diagnostic(
+        code(gridder::update),
+        help("releases are at https://github.com/denbeigh2000/gridder/releases, for installing by hand")
+    )]
+    Updating(#[from] UpdateError),
+    #[error("{failed} of {total} outputs failed")]
+    #[diagnostic(help("each failure is listed above"))]
+    OutputsFailed { failed: usize, total: usize },
+    #[error("{failed} of {total} days failed")]
+    #[diagnostic(help("each failure is listed above"))]
+    DaysFailed { failed: usize, total: usize },
+    #[error("{failed} of {total} checks failed")]
+    #[diagnostic(help("each failure is listed above, with what to do about it"))]
+    ChecksFailed { failed: usize, total: usize },
+}
+
+// Files that won't decrypt get the same advice wherever they're from
+impl From<StateError> for Error {
+    fn from(e: StateError) -> Self {
+        match e {
+            StateError::Decrypting(path, e) => Self::DecryptingFile(path, e),
+            e => Self::TrackingRuns(e),
+        }
+    }
+}
+
+impl From<ProfileError> for Error {
+    fn from(e: ProfileError) -> Self {
+        match e {
+            ProfileError::Decrypting(path, e) => Self::DecryptingFile(path, e),
+            e => Self::ReadingProfile(e),
+        }
+    }
+}
+
+// Whether an output got the day's grid from this run or an earlier one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Delivery {
+    Sent,
+    AlreadySent,
+}
+
+// Writes to one output unless the state directory's journal says it's
+// already had the day, and journals it once it has
+async fn deliver<F>(
+    outputs: &Outputs,
+    date: chrono::NaiveDate,
+    delivered: &BTreeSet<String>,
+    key: &str,
+    send: F,
+) -> Result<Delivery, Error>
+where
+    F: Future<Output = Result<(), Error>>,
+{
+    if delivered.contains(key) {
+        return Ok(Delivery::AlreadySent);
+    }
+    send.await?;
+    if let Some(state) = &outputs.state {
+        state.mark_delivered(date, key)?;
+    }
+
+    Ok(Delivery::Sent)
+}
+
+// What a run for a day did
+#[derive(PartialEq, Eq)]
+enum RunOutcome {
+    Written,
+    // Skipped, as the state directory says it's been done
+    AlreadyDone,
+}
+
+// Everywhere a run's grid gets written to
+struct Outputs {
+    sheets: SheetManager,
+    stats: bool,
+    // Kept in memory for the run without --grid-dir
+    store: Arc<dyn Store>,
+    notifiers: Vec<Notifier>,
+    spoilers: SpoilerLevel,
+    transform: Option<Transform>,
+    hooks: Vec<Hook>,
+    steps: StepArgs,
+    state: Option<StateStore>,
+    force: bool,
+}
+
+fn resolve_date(clock: &dyn Clock, date: Option<String>) -> Result<chrono::NaiveDate, Error> {
+    let date = date
+        // If a datestring was given, try to parse it into a NaiveDate
+        .map(|i| i.parse().map_err(|e| Error::ParsingDate(i, e)))
+        // Put the Result<..> on the outside, and exit if it failed
+        .transpose()?
+        // If no date was given, fall back to using today (in US-Western)
+        .unwrap_or_else(|| clock::puzzle_date(clock.now()));
+
+    Ok(date)
+}
+
+async fn run(
+    clock: &dyn Clock,
+    mut args: RunArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let deadline = Deadline::after(args.deadline);
+    let date = resolve_date(clock, args.date)?;
+    let release = clock::release_time(date);
+    if release > clock.now() {
+        return Err(Error::NotReleased(date, release.with_timezone(&RELEASE_TZ)));
+    }
+    let reader = args.fetch.parse.reader(connectivity)?;
+    let mut fetcher = args.fetch.fetcher(connectivity)?;
+    let state = open_state(args.pipeline.state_dir, &mut args.sheets)?;
+    let sheets = deadline
+        .within(
+            "connecting to the spreadsheet",
+            args.sheets.connect(connectivity),
+        )
+        .await?;
+    let mut outputs = Outputs {
+        sheets,
+        stats: args.stats,
+        store: grid_store(args.pipeline.grid_dir)?,
+        notifiers: args.notify.notifiers(connectivity)?,
+        spoilers: args.notify.spoilers,
+        transform: args.pipeline.transform.transform()?,
+        hooks: args.pipeline.hooks,
+        steps: args.steps,
+        state,
+        force: args.force,
+    };
+
+    let outcome = run_for_date(date, &mut fetcher, &reader, &outputs, deadline).await?;
+    if outcome == RunOutcome::Written {
+        rotate_if_full(&mut outputs, &args.rotation, date).await?;
+    }
+
+    Ok(())
+}
+
+// Runs the hooks for `stage` in order, showing what each reports. One that
+// fails stops the run.
+async fn run_hooks(
+    hooks: &[Hook],
+    stage: Stage,
+    date: chrono::NaiveDate,
+    doc: Option<&GridDocument>,
+) -> Result<(), Error> {
+    for hook in hooks.iter().filter(|hook| hook.stage == stage) {
+        let report = hook.run(date, doc).await?;
+        let ok = themes().stdout.paint(Role::Good, tr(Message::Ok));
+        match report.is_empty() {
+            true => println!("{stage} hook: {ok}"),
+            false => println!("{stage} hook: {ok} ({report})"),
+        }
+    }
+
+    Ok(())
+}
+
+// Where a run's grids are saved: the --grid-dir if there is one, or else
+// just memory, so the rest of the run can still look back at them
+fn grid_store(grid_dir: Option<PathBuf>) -> Result<Arc<dyn Store>, Error> {
+    match grid_dir {
+        Some(location) => open_store(&location).map_err(Error::ReadingArchive),
+        None => Ok(Arc::new(MemoryStore::default())),
+    }
+}
+
+// Where a grid read back from the saved grids came from; when it was first
+// read isn't kept
+fn saved_provenance(store: &dyn Store, date: chrono::NaiveDate) -> Provenance {
+    Provenance {
+        parsed_at: None,
+        source: store.location(date),
+    }
+}
+
+// Runs a day, and records how it went alongside the saved grids
+async fn run_for_date(
+    date: chrono::NaiveDate,
+    fetcher: &mut Fetcher,
+    reader: &PageReader,
+    outputs: &Outputs,
+    deadline: Deadline,
+) -> Result<RunOutcome, Error> {
+    let result = write_date(date, fetcher, reader, outputs, deadline).await;
+    let error = match &result {
+        Ok(RunOutcome::AlreadyDone) => return result,
+        Ok(RunOutcome::Written) => None,
+        Err(e) => Some(e.to_string()),
+    };
+    let run = RunRecord {
+        date,
+        finished: chrono::Utc::now(),
+        error,
+    };
+    // Only a record, so it mustn't fail the run
+    if let Err(e) = outputs.store.record_run(&run) {
+        eprintln!(
+            "{}: {}",
+            tr(Message::Warning),
+            tr(Message::RecordingRunFailed(&e))
+        );
+    }
+
+    result
+}
+
+async fn write_date(
+    date: chrono::NaiveDate,
+    fetcher: &mut Fetcher,
+    reader: &PageReader,
+    outputs: &Outputs,
+    deadline: Deadline,
+) -> Result<RunOutcome, Error> {
+    if let Some(state) = &outputs.state {
+        if !outputs.force && state.is_processed(date)? {
+            println!("{}", tr(Message::AlreadyProcessed(date)));
+            return Ok(RunOutcome::AlreadyDone);
+        }
+    }
+
+    let steps = &outputs.steps;
+    let (mut doc, page_summary, provenance) = match steps.runs(Step::Fetch) {
+        true => {
+            let hooks = run_hooks(&outputs.hooks, Stage::PreFetch, date, None);
+            deadline.within("the pre-fetch hooks", hooks).await?;
+            let fetch = async { Ok::<_, Error>(fetcher.fetch_for_date(date).await?) };
+            let body = deadline.within("fetching the page", fetch).await?;
+            check_shutdown("fetching the page")?;
+            let grid = match reader.read_grid(&body) {
+                Err(Error::ParsingSiteData(e)) => {
+                    reader.report_failure(date, &body, &e).await;
+                    return Err(e.into());
+                }
+                grid => grid?,
+            };
+            println!("{}", tr(Message::Quality(&grid.quality)));
+            let provenance = Provenance {
+                parsed_at: Some(chrono::Utc::now()),
+                source: fetcher.last_url().unwrap_or_default().to_string(),
+            };
+            (grid.document(date), grid.summary, provenance)
+        }
+        false => {
+            let store = &*outputs.store;
+            if !store.persistent() {
+                return Err(Error::MissingArgument("--grid-dir"));
+            }
+            let doc = store
+                .load_grid(date)
+                .map_err(Error::ReadingArchive)?
+                .ok_or(Error::NotSaved(date))?;
+            let summary = doc.summary.as_ref().map(|summary| Summary {
+                words: doc.totals.words,
+                points: summary.points,
+                pangrams: summary.pangrams,
+            });
+            (doc, summary, saved_provenance(store, date))
+        }
+    };
+    let hooks = run_hooks(&outputs.hooks, Stage::PostParse, date, Some(&doc));
+    deadline.within("the post-parse hooks", hooks).await?;
+    if let (Some(transform), true) = (&outputs.transform, steps.runs(Step::Transform)) {
+        let apply = async { Ok::<_, Error>(transform.apply(&doc).await?) };
+        doc = deadline.within("transforming the grid", apply).await?;
+    }
+    // Checked to be usable when read or transformed
+    let (pairs, lengths) = (doc.pair_info().unwrap_or_default(), doc.length_info());
+    // Only a nice-to-have, so it mustn't stop the grid being written
+    match past_grids(&*outputs.store, date) {
+        Ok(past) => {
+            if let Some(model) = DifficultyModel::train(&past) {
+                println!("{}", tr(Message::Difficulty(&model.estimate(&doc))));
+            }
+            if outputs.spoilers.shows_hints() {
+                let history = PairHistory::from_docs(&past);
+                print_unusual_pairs(&history.unusual(&doc, RARE_PAIR_SHARE));
+            }
+        }
+        Err(e) => eprintln!(
+            "{}: {}",
+            tr(Message::Warning),
+            tr(Message::PastGridsUnavailable(&e))
+        ),
+    }
+    // Check this up front so a missing summary doesn't leave a half-done run
+    let summary = match (outputs.stats && steps.runs(Step::Stats), page_summary) {
+        (true, None) => return Err(SiteParseError::MissingSummary.into()),
+        (true, summary) => summary,
+        (false, _) => None,
+    };
+    let hooks = run_hooks(&outputs.hooks, Stage::PreSink, date, Some(&doc));
+    deadline.within("the pre-sink hooks", hooks).await?;
+    let (pairs, table_info) = (&pairs, &lengths);
+    // A retry after some outputs failed only writes to the others
+    let delivered = match &outputs.state {
+        Some(state) if !outputs.force => state.delivered(date)?,
+        _ => BTreeSet::new(),
+    };
+
+    // Each output is written at the same time as the others, and one
+    // failing doesn't stop the rest
+    let sheets = async {
+        let mut delivery = None;
+        if steps.runs(Step::Sheets) {
+            let create = async {
+                outputs
+                    .sheets
+                    .create_for_date(&date, pairs, table_info, &provenance)
+                    .await
+                    .map_err(Error::from)
+            };
+            delivery = Some(deliver(outputs, date, &delivered, "sheets", create).await?);
+            check_shutdown("creating the day's sheet")?;
+        }
+
+        if let Some(summary) = summary {
+            let update = async {
+                outputs
+                    .sheets
+                    .update_stats(&date, &summary)
+                    .await
+                    .map_err(Error::from)
+            };
+            let updated = deliver(outputs, date, &delivered, "stats", update).await?;
+            if delivery != Some(Delivery::Sent) {
+                delivery = Some(updated);
+            }
+        }
+        Ok(delivery)
+    };
+    let sheets = deadline.within("writing to the spreadsheet", sheets);
+    let grid_file = async {
+        let store = Some(&outputs.store)
+            .filter(|store| store.persistent() && steps.runs(Step::GridFile))?;
+        let save = async { store.save_grid(&doc).map_err(Error::SavingGrid) };
+        Some(deliver(outputs, date, &delivered, "grid file", save).await)
+    };
+    let notifications = async {
+        let mut results = Vec::new();
+        let notifiers = match steps.runs(Step::Notify) {
+            true => outputs.notifiers.as_slice(),
+            false => &[],
+        };
+        for notifier in notifiers {
+            let notify = async {
+                // Stopping, so the rest are left for the next run
+                check_shutdown("the previous notification")?;
+                let result = notifier.notify(&doc, outputs.spoilers).await;
+                // What's posted is made from the document
+                let write = Write::json("post grid", &date.to_string(), &doc);
+                audit_post(notifier, write, &result);
+                result.map_err(Error::from)
+            };
+            let key = notifier.key();
+            let deliver = deliver(outputs, date, &delivered, &key, notify);
+            let result = deadline.within(notifier.name(), deliver).await;
+            results.push((notifier.name(), Some(result)));
+        }
+        results
+    };
+    let (sheets, grid_file, notifications) = tokio::join!(sheets, grid_file, notifications);
+
+    let mut results = vec![("sheets", sheets.transpose()), ("grid file", grid_file)];
+    results.extend(notifications);
+    report_outputs(results)?;
+    // Not an output, just for later days in the run to compare with
+    if !outputs.store.persistent() {
+        outputs.store.save_grid(&doc).map_err(Error::SavingGrid)?;
+    }
+    // A day with outputs left out has to be run again for them
+    if let (Some(state), false) = (&outputs.state, steps.leaves_out_outputs()) {
+        state.mark_processed(date)?;
+    }
+
+    Ok(RunOutcome::Written)
+}
+
+// Prints how each output went, failing if any of them did. Outputs that
+// weren't written to are None.
+fn report_outputs(results: Vec<(&str, Option<Result<Delivery, Error>>)>) -> Result<(), Error> {
+    let mut total = 0;
+    let mut failed = 0;
+    let mut exceeded = None;
+    for (name, result) in results {
+        match result {
+            None => continue,
+            Some(Ok(Delivery::Sent)) => println!(
+                "{name}: {}",
+                themes().stdout.paint(Role::Good, tr(Message::Ok))
+            ),
+            Some(Ok(Delivery::AlreadySent)) => println!(
+                "{name}: {}",
+                themes().stdout.paint(Role::Muted, tr(Message::AlreadySent))
+            ),
+            Some(Err(Error::Interrupted(step))) => return Err(Error::Interrupted(step)),
+            Some(Err(e)) => {
+                eprintln!(
+                    "{name}: {} {e}",
+                    themes().stderr.paint(Role::Bad, tr(Message::Failed))
+                );
+                if let Error::DeadlineExceeded(stage) = e {
+                    exceeded.get_or_insert(stage);
+                }
+                failed += 1;
+            }
+        }
+        total += 1;
+    }
+
+    // Running out of time is what needs fixing, more than the outputs
+    if let Some(stage) = exceeded {
+        return Err(stage.into());
+    }
+    if failed > 0 {
+        return Err(Error::OutputsFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+// How the day's page gets read
+struct PageReader {
+    options: ParseOptions,
+    fingerprint_file: Option<PathBuf>,
+    custom_selectors: bool,
+    telemetry: Option<Telemetry>,
+    apply_fixes: bool,
+}
+
+// A grid read from the day's page
+struct PageGrid {
+    pairs: PairInfo,
+    lengths: LengthInfo,
+    summary: Option<Summary>,
+    center: Option<char>,
+    quality: Quality,
+}
+
+impl PageGrid {
+    fn document(&self, date: chrono::NaiveDate) -> GridDocument {
+        let mut doc = GridDocument::new(date, &self.pairs, &self.lengths);
+        doc.center = self.center;
+        doc.quality = Some(self.quality.score);
+        doc.summary = self.summary.as_ref().map(|summary| SummaryEntry {
+            points: summary.points,
+            pangrams: summary.pangrams,
+        });
+        doc
+    }
+}
+
+impl PageReader {
+    // Parses the day's page, refusing grids that came out incomplete
+    fn read_grid(&self, body: &str) -> Result<PageGrid, Error> {
+        let (mut pairs, lengths) = parse_content_with(body, &self.options)?;
+        let stray = check_grid(&pairs, &lengths)?;
+        if !stray.is_empty() {
+            eprintln!(
+                "{}: {}",
+                tr(Message::Warning),
+                tr(Message::StrayPairs(&stray))
+            );
+        }
+        let drift = self.check_fingerprint(body)?;
+        let summary = parse_summary_with(body, &self.options).ok();
+        let center = parse_center_with(body, &self.options);
+        let fixes = repair::suggest(&pairs, &lengths);
+        let applied = match self.apply_fixes {
+            true => fixes
+                .iter()
+                .filter(|fix| fix.applicable())
+                .cloned()
+                .collect(),
+            false => Vec::new(),
+        };
+        for fix in &applied {
+            fix.apply(&mut pairs);
+        }
+        print_fixes(&fixes, self.apply_fixes);
+        let mut quality = quality::assess(&pairs, &lengths, summary.as_ref(), &drift);
+        quality.note_repairs(&applied);
+
+        Ok(PageGrid {
+            pairs,
+            lengths,
+            summary,
+            center,
+            quality,
+        })
+    }
+
+    // Misreading a page that's changed shape is worse than failing on it,
+    // so make some noise if this one doesn't look like the last few. Gives
+    // what changed.
+    fn check_fingerprint(&self, body: &str) -> Result<Vec<String>, Error> {
+        let Some(path) = &self.fingerprint_file else {
+            return Ok(Vec::new());
+        };
+        let tracking_error = |e| Error::TrackingFingerprint(path.clone(), e);
+
+        let fingerprint = Fingerprint::of_page(body, &self.options);
+        let mut history = FingerprintHistory::load(path).map_err(tracking_error)?;
+        let differences = history
+            .norm()
+            .map(|norm| fingerprint.differences(norm))
+            .unwrap_or_default();
+        if !differences.is_empty() {
+            let theme = themes().stderr;
+            eprintln!(
+                "{}: {}",
+                theme.paint(Role::Warning, tr(Message::Warning).to_uppercase()),
+                tr(Message::LayoutChanged)
+            );
+            for difference in &differences {
+                eprintln!("  {}", theme.paint(Role::Muted, difference));
+            }
+        }
+        history.record(fingerprint);
+        history.save(path).map_err(tracking_error)?;
+
+        Ok(differences)
+    }
+
+    // Tells the maintainers the page couldn't be read, if that's been
+    // opted into. It's only a courtesy, so it can't fail the run
+    async fn report_failure(&self, date: chrono::NaiveDate, body: &str, error: &SiteParseError) {
+        let Some(telemetry) = &self.telemetry else {
+            return;
+        };
+        let fingerprint = Fingerprint::of_page(body, &self.options);
+        let report = ParseFailureReport::new(date, error, self.custom_selectors, fingerprint);
+        let sent = tokio::time::timeout(TELEMETRY_TIMEOUT, telemetry.report(&report)).await;
+        let failure = match sent {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("{} timed out", telemetry.url()),
+        };
+        eprintln!(
+            "{}: {}",
+            tr(Message::Warning),
+            tr(Message::TelemetryFailed(&failure))
+        );
+    }
+}
+
+fn schema(args: SchemaArgs) -> Result<(), Error> {
+    if args.avro {
+        println!("{}", avro::SCHEMA);
+        return Ok(());
+    }
+
+    let schema = schemars::schema_for!(GridDocument);
+    let out = serde_json::to_string_pretty(&schema).map_err(Error::SerializingSchema)?;
+    println!("{out}");
+
+    Ok(())
+}
+
+fn convert(args: ConvertArgs) -> Result<(), Error> {
+    let from = args
+        .from
+        .or_else(|| Format::from_path(&args.input))
+        .ok_or_else(|| Error::UnknownFormat(args.input.clone()))?;
+
+    let doc = match from {
+        Format::Json => {
+            let file = File::open(&args.input).map_err(FormatError::Io)?;
+            formats::read_json(file)?
+        }
+        Format::Csv => {
+            let pairs_input = args
+                .pairs_input
+                .ok_or(Error::MissingArgument("--pairs-input"))?;
+            let date = args.date.ok_or(Error::MissingArgument("--date"))?;
+            let (pairs, lengths) = from_csv(&args.input, pairs_input).map_err(FormatError::from)?;
+            GridDocument::new(date, &pairs, &lengths)
+        }
+        Format::Template => return Err(Error::OutputOnly("template")),
+        Format::Prose => return Err(Error::OutputOnly("prose")),
+    };
+
+    let template = read_template(args.to, args.template.as_deref())?;
+    write_format(
+        &doc,
+        args.to,
+        args.output.as_deref(),
+        args.pairs_output.as_deref(),
+        template.as_deref(),
+    )
+}
+
+// Reads a bundled page with the default settings, so nothing about the
+// machine or the command line changes what comes out
+fn emit(args: EmitArgs) -> Result<(), Error> {
+    // Only names of bundled fixtures get through parsing
+    let fixture = fixture::find(&args.fixture).expect("unknown fixture");
+    let reader = PageReader {
+        options: ParseOptions::default(),
+        fingerprint_file: None,
+        custom_selectors: false,
+        telemetry: None,
+        apply_fixes: false,
+    };
+    let doc = reader.read_grid(fixture.page)?.document(fixture.date);
+    let template = read_template(args.to, args.template.as_deref())?;
+
+    write_format(
+        &doc,
+        args.to,
+        args.output.as_deref(),
+        args.pairs_output.as_deref(),
+        template.as_deref(),
+    )
+}
+
+// The template to render, if `format` needs one
+fn read_template(format: Format, path: Option<&Path>) -> Result<Option<String>, Error> {
+    if format != Format::Template {
+        return Ok(None);
+    }
+    if !cfg!(feature = "templates") {
+        return Err(FormatError::NotBuiltIn("templates").into());
+    }
+    let path = path.ok_or(Error::MissingArgument("--template"))?;
+    let template = std::fs::read_to_string(path).map_err(FormatError::Io)?;
+
+    Ok(Some(template))
+}
+
+// Writes `doc` in `format` to `output`, or to stdout if there isn't one
+// (apart from CSV, which also needs `pairs_output`)
+fn write_format(
+    doc: &GridDocument,
+    format: Format,
+    output: Option<&Path>,
+    pairs_output: Option<&Path>,
+    template: Option<&str>,
+) -> Result<(), Error> {
+    let create = |path| File::create(path).map_err(FormatError::Io);
+    match format {
+        Format::Json => match output {
+            Some(path) => formats::write_json(doc, create(path)?)?,
+            None => formats::write_json(doc, std::io::stdout().lock())?,
+        },
+        Format::Csv => {
+            let output = output.ok_or(Error::MissingArgument("--output"))?;
+            let pairs_output = pairs_output.ok_or(Error::MissingArgument("--pairs-output"))?;
+            formats::write_csv(doc, create(output)?, create(pairs_output)?)?;
+        }
+        Format::Template => {
+            let template = template.ok_or(Error::MissingArgument("--template"))?;
+            match output {
+                Some(path) => formats::write_template(doc, template, create(path)?)?,
+                None => formats::write_template(doc, template, std::io::stdout().lock())?,
+            }
+        }
+        Format::Prose => match output {
+            Some(path) => formats::write_prose(doc, create(path)?)?,
+            None => formats::write_prose(doc, std::io::stdout().lock())?,
+        },
+    }
+
+    Ok(())
+}
+
+// Loads a saved grid if one was given, otherwise fetches the day's page
+async fn load_grid(
+    date: chrono::NaiveDate,
+    saved: Option<PathBuf>,
+    fetch: FetchArgs,
+    connectivity: Connectivity,
+) -> Result<GridDocument, Error> {
+    let doc = match saved {
+        Some(path) => formats::read_json(File::open(path).map_err(FormatError::Io)?)?,
+        None => {
+            let reader = fetch.parse.reader(connectivity)?;
+            let body = fetch.fetcher(connectivity)?.fetch_for_date(date).await?;
+            reader.read_grid(&body)?.document(date)
+        }
+    };
+
+    Ok(doc)
+}
+
+async fn show_progress(
+    clock: &dyn Clock,
+    args: ProgressArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let doc = load_grid(date, args.grid, args.fetch, connectivity).await?;
+
+    let words = if args.from_sheets {
+        let spreadsheet_id = args
+            .spreadsheet_id
+            .ok_or(Error::MissingArgument("--spreadsheet-id"))?;
+        let service_account_file = args
+            .service_account_file
+            .ok_or(Error::MissingArgument("--service-account-file"))?;
+        let sheets_client = SheetManager::new(
+            connectivity.network("reading from the spreadsheet")?,
+            &spreadsheet_id,
+            service_account_file,
+        )
+        .await?;
+        sheets_client.read_progress(&date).await?
+    } else {
+        let path = args.words.ok_or(Error::MissingArgument("--words"))?;
+        std::fs::read_to_string(path)
+            .map_err(Error::ReadingWords)?
+            .lines()
+            .map(str::to_string)
+            .collect()
+    };
+
+    let progress = progress::compute(&doc, &words);
+    if args.spoilers.shows_hints() {
+        print!("{}", progress.render(themes().stdout, lang()));
+    } else if args.spoilers.shows_counts() {
+        let found = Message::FoundWords {
+            found: progress.found.len(),
+            total: progress.total_words,
+        };
+        println!("{} {}", tr(found), tr(Message::Points(progress.points)));
+    } else {
+        println!("{}", tr(Message::FoundCount(progress.found.len())));
+    }
+    if let Some(location) = args.grid_dir {
+        open_store(&location)
+            .and_then(|store| store.save_words(date, &progress.found))
+            .map_err(Error::SavingGrid)?;
+    }
+
+    Ok(())
+}
+
+async fn write_leaderboard(
+    clock: &dyn Clock,
+    args: LeaderboardArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let doc = load_grid(date, args.grid, args.fetch, connectivity).await?;
+
+    let sheets_client = args.sheets.connect(connectivity).await?;
+    let members = sheets_client.read_member_words(&date).await?;
+    if let Some(location) = args.grid_dir {
+        let store = open_store(&location).map_err(Error::ReadingArchive)?;
+        let mut finds = store.load_finds(date).map_err(Error::ReadingArchive)?;
+        let new = finds.import(&members, clock.now());
+        store.save_finds(date, &finds).map_err(Error::SavingGrid)?;
+        println!("{}", tr(Message::RecordedFinds(new)));
+    }
+    let standings = leaderboard::rank(&doc, &members);
+    sheets_client.write_leaderboard(&date, &standings).await?;
+
+    for standing in &standings {
+        println!("{standing}");
+    }
+
+    Ok(())
+}
+
+fn install_service(args: InstallServiceArgs) -> Result<(), Error> {
+    let executable = std::env::current_exe().map_err(Error::LocatingExecutable)?;
+
+    let mut sheets = args.sheets;
+    // The service won't run from this directory
+    sheets.service_account_file = sheets
+        .service_account_file
+        .canonicalize()
+        .unwrap_or(sheets.service_account_file);
+    let mut environment = sheets.environment();
+    if args.stats {
+        environment.push(("GRIDDER_UPDATE_STATS".to_string(), "true".to_string()));
+    }
+
+    let config = ServiceConfig {
+        executable: executable.display().to_string(),
+        user: args.user,
+        environment,
+        on_calendar: args
+            .schedules
+            .iter()
+            .flat_map(|schedule| schedule.on_calendar(args.timezone.name()))
+            .collect(),
+    };
+
+    for (name, contents) in [
+        ("gridder.service", config.service_unit()),
+        ("gridder.timer", config.timer_unit()),
+    ] {
+        let path = args.unit_dir.join(name);
+        std::fs::write(&path, contents).map_err(|e| Error::WritingUnit(path.clone(), e))?;
+        println!("{}", tr(Message::WroteFile(&path.display())));
+    }
+    println!("{}", tr(Message::EnableTimer));
+
+    Ok(())
+}
+
+// How one of `gridder doctor`'s checks went
+enum Checked {
+    Passed(String),
+    Skipped(String),
+    Failed(Error),
+}
+
+impl<E: Into<Error>> From<Result<String, E>> for Checked {
+    fn from(result: Result<String, E>) -> Self {
+        match result {
+            Ok(detail) => Self::Passed(detail),
+            Err(e) => Self::Failed(e.into()),
+        }
+    }
+}
+
+fn check_range(since: chrono::NaiveDate, until: chrono::NaiveDate) -> Result<(), Error> {
+    match since <= until {
+        true => Ok(()),
+        false => Err(Error::ReversedRange(since, until)),
+    }
+}
+
+async fn sync_sheets(
+    clock: &dyn Clock,
+    args: SyncArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let until = args
+        .until
+        .unwrap_or_else(|| clock::puzzle_date(clock.now()));
+    check_range(args.since, until)?;
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let docs = store
+        .load_range(args.since, until)
+        .map_err(Error::ReadingArchive)?;
+    let sheets_client = args.sheets.connect(connectivity).await?;
+    let dates = docs.iter().map(|doc| doc.date).collect::<Vec<_>>();
+    let tabs = sheets_client.day_tabs(&dates).await?;
+
+    let mut up_to_date = 0;
+    let mut total = 0;
+    let mut failed = 0;
+    for (doc, (date, tab)) in docs.iter().zip(tabs) {
+        check_shutdown("the previous day")?;
+        let message = match tab {
+            DayTab::Filled => {
+                up_to_date += 1;
+                continue;
+            }
+            DayTab::Missing if args.dry_run => Message::WouldCreateTab(date),
+            DayTab::Empty(_) if args.dry_run => Message::WouldFillTab(date),
+            DayTab::Missing => Message::CreatedTab(date),
+            DayTab::Empty(_) => Message::FilledTab(date),
+        };
+        total += 1;
+        if args.dry_run {
+            println!("{}", tr(message));
+            continue;
+        }
+
+        let synced = async {
+            let (pairs, lengths) = (doc.pair_info()?, doc.length_info());
+            let provenance = saved_provenance(&*store, date);
+            match tab {
+                DayTab::Empty(sheet_id) => {
+                    sheets_client
+                        .fill_for_date(&date, sheet_id, &pairs, &lengths, &provenance)
+                        .await?
+                }
+                _ => {
+                    sheets_client
+                        .create_for_date(&date, &pairs, &lengths, &provenance)
+                        .await?
+                }
+            }
+            Ok::<_, Error>(())
+        };
+        match synced.await {
+            Ok(()) => println!("{}", tr(message)),
+            Err(e) => {
+                eprintln!(
+                    "{}: {}",
+                    tr(Message::Error),
+                    tr(Message::DayFailed(date, &e))
+                );
+                failed += 1;
+            }
+        }
+    }
+    println!("{}", tr(Message::TabsUpToDate(up_to_date)));
+
+    if failed > 0 {
+        return Err(Error::DaysFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+// Opens the state directory, if there is one, and points `sheets` at the
+// spreadsheet it's moved on to, if it has
+fn open_state(dir: Option<PathBuf>, sheets: &mut SheetsArgs) -> Result<Option<StateStore>, Error> {
+    let Some(dir) = dir else {
+        return Ok(None);
+    };
+    let mut state = StateStore::open(dir)?;
+    if let Some(cipher) = cipher() {
+        state = state.encrypted(cipher);
+    }
+    follow_rotation(&state, sheets)?;
+
+    Ok(Some(state))
+}
+
+fn follow_rotation(state: &StateStore, sheets: &mut SheetsArgs) -> Result<(), Error> {
+    if let Some(id) = state.spreadsheet_id()? {
+        sheets.spreadsheet_id = id;
+    }
+
+    Ok(())
+}
+
+// Moves to a new spreadsheet if this one's close enough to the cell limit,
+// after `date` has been written to it
+async fn rotate_if_full(
+    outputs: &mut Outputs,
+    rotation: &RotationArgs,
+    date: chrono::NaiveDate,
+) -> Result<(), Error> {
+    let (Some(percent), Some(state)) = (rotation.rotate_at, &outputs.state) else {
+        return Ok(());
+    };
+    let used = outputs.sheets.cells_used().await?;
+    if !nearly_full(used, percent) {
+        return Ok(());
+    }
+
+    let title = format!("Gridder from {}", date.succ_opt().unwrap_or(date));
+    rotate_spreadsheet(
+        &mut outputs.sheets,
+        state,
+        &outputs.notifiers,
+        &title,
+        &rotation.share_with,
+    )
+    .await
+}
+
+// Whether `used` cells are at least `percent` of the spreadsheet limit
+fn nearly_full(used: i64, percent: u8) -> bool {
+    used * 100 >= CELL_LIMIT * i64::from(percent)
+}
+
+// Moves to a new spreadsheet, remembers it, shares it, and tells the group
+// chats
+async fn rotate_spreadsheet(
+    sheets: &mut SheetManager,
+    state: &StateStore,
+    notifiers: &[Notifier],
+    title: &str,
+    editors: &[String],
+) -> Result<(), Error> {
+    let id = sheets.rotate(title).await?;
+    state.set_spreadsheet_id(&id)?;
+    for editor in editors {
+        // The rotation's done either way, and the spreadsheet can be shared
+        // by hand
+        if let Err(e) = sheets.share(editor).await {
+            eprintln!("{}: {e}", tr(Message::Error));
+        }
+    }
+
+    let url = format!("https://docs.google.com/spreadsheets/d/{id}");
+    let message = tr(Message::Rotated(&url));
+    println!("{message}");
+    for notifier in notifiers.iter().filter(|n| n.takes_announcements()) {
+        // The rotation's done either way, so this is only worth a mention
+        let result = notifier.announce(&message).await;
+        audit_post(
+            notifier,
+            Write::new("announce", "", message.as_bytes()),
+            &result,
+        );
+        if let Err(e) = result {
+            eprintln!(
+                "{}: {} {e}",
+                notifier.name(),
+                themes().stderr.paint(Role::Bad, tr(Message::Failed))
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn rotate_sheets(
+    clock: &dyn Clock,
+    mut args: RotateArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let title = args
+        .title
+        .unwrap_or_else(|| format!("Gridder from {}", clock::puzzle_date(clock.now())));
+    let notifiers = args.notify.notifiers(connectivity)?;
+    let mut state = StateStore::open(args.state_dir)?;
+    if let Some(cipher) = cipher() {
+        state = state.encrypted(cipher);
+    }
+    follow_rotation(&state, &mut args.sheets)?;
+    let mut sheets_client = args.sheets.connect(connectivity).await?;
+    rotate_spreadsheet(
+        &mut sheets_client,
+        &state,
+        &notifiers,
+        &title,
+        &args.share_with,
+    )
+    .await
+}
+
+fn manage_secret(command: SecretCommand, profile: Option<&str>) -> Result<(), Error> {
+    let keyring = Keyring::new(profile);
+    match command {
+        SecretCommand::Set(args) => {
+            if std::io::stdin().is_terminal() {
+                eprint!("{}: ", tr(Message::EnterSecret(&args.secret)));
+            }
+            let mut value = String::new();
+            std::io::stdin()
+                .read_line(&mut value)
+                .map_err(Error::ReadingSecret)?;
+            let value = value.trim_end_matches(['\r', '\n']);
+            if value.is_empty() {
+                return Err(Error::EmptySecret);
+            }
+            keyring.set(args.secret, value)?;
+            println!("{}", tr(Message::StoredSecret(&args.secret)));
+        }
+        SecretCommand::Get(args) => println!("{}", keyring.get(args.secret)?),
+        SecretCommand::Delete(args) => {
+            keyring.delete(args.secret)?;
+            println!("{}", tr(Message::DeletedSecret(&args.secret)));
+        }
+    }
+
+    Ok(())
+}
+
+// Encrypts (or decrypts) each of `files` in place, leaving those that
+// already are alone
+fn encrypt_files(files: &[PathBuf], encrypt: bool) -> Result<(), Error> {
+    let cipher = cipher().ok_or(EncryptionError::NoKey)?;
+    for path in files {
+        let contents = std::fs::read(path).map_err(|e| Error::ReadingFile(path.clone(), e))?;
+        if Cipher::is_encrypted(&contents) == encrypt {
+            let path = path.display();
+            let message = match encrypt {
+                true => Message::AlreadyEncrypted(&path),
+                false => Message::NotEncrypted(&path),
+            };
+            println!("{}", tr(message));
+            continue;
+        }
+        let contents = match encrypt {
+            true => cipher.encrypt(&contents)?,
+            false => cipher
+                .decrypt(&contents)
+                .map_err(|e| Error::DecryptingFile(path.clone(), e))?,
+        };
+        // Written alongside and moved over the original, so it's never
+        // left half-written
+        let mut staged = path.clone().into_os_string();
+        staged.push(".new");
+        std::fs::write(&staged, contents)
+            .and_then(|()| std::fs::rename(&staged, path))
+            .map_err(|e| Error::WritingOutput(path.clone(), e))?;
+        let path = path.display();
+        let message = match encrypt {
+            true => Message::Encrypted(&path),
+            false => Message::Decrypted(&path),
+        };
+        println!("{}", tr(message));
+    }
+
+    Ok(())
+}
+
+async fn backup_sheets(args: BackupArgs, connectivity: Connectivity) -> Result<(), Error> {
+    backup::check(&args.output)?;
+    let sheets_client = args.sheets.connect(connectivity).await?;
+    let tabs = sheets_client.read_all_tabs().await?;
+    backup::write(&tabs, &args.output)?;
+    println!(
+        "{}",
+        tr(Message::BackedUp {
+            tabs: tabs.len(),
+            path: &args.output.display(),
+        })
+    );
+
+    Ok(())
+}
+
+async fn doctor(
+    clock: &dyn Clock,
+    args: DoctorArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    println!(
+        "gridder {} ({}-{})",
+        Version::current(),
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    );
+
+    let reader = args.fetch.parse.reader(connectivity)?;
+    let (site, page) = match args.fetch.fetcher(connectivity) {
+        Ok(fetcher) => check_site(clock, fetcher, &reader).await,
+        Err(Error::Offline(_)) => (
+            Checked::Skipped(tr(Message::NetworkOff)),
+            Checked::Skipped(tr(Message::NoPageToRead)),
+        ),
+        Err(e) => return Err(e),
+    };
+
+    let spreadsheet = match args.sheets.connect(connectivity).await {
+        Ok(sheets) => sheets
+            .check_template()
+            .await
+            .map(|()| tr(Message::SpreadsheetReady))
+            .into(),
+        Err(Error::Offline(_)) => Checked::Skipped(tr(Message::NetworkOff)),
+        Err(e) => Checked::Failed(e),
+    };
+
+    let grids = match args.grid_dir.as_deref().map(open_store) {
+        Some(store) => store
+            .and_then(|store| store.load_range(chrono::NaiveDate::MIN, chrono::NaiveDate::MAX))
+            .map(|docs| tr(Message::GridsReadable(docs.len())))
+            .map_err(Error::ReadingArchive)
+            .into(),
+        None => Checked::Skipped(tr(Message::NoGridDir)),
+    };
+
+    let fingerprints = match &reader.fingerprint_file {
+        Some(path) => FingerprintHistory::load(path)
+            .map(|_| tr(Message::FingerprintsReadable))
+            .map_err(|e| Error::TrackingFingerprint(path.clone(), e))
+            .into(),
+        None => Checked::Skipped(tr(Message::NoFingerprintFile)),
+    };
+
+    let checks = [
+        (Message::CheckSite, site),
+        (Message::CheckPage, page),
+        (Message::CheckSpreadsheet, spreadsheet),
+        (Message::CheckGrids, grids),
+        (Message::CheckFingerprints, fingerprints),
+    ];
+    let theme = themes().stdout;
+    let (mut total, mut failed) = (0, 0);
+    for (name, checked) in &checks {
+        let name = tr(*name);
+        match checked {
+            Checked::Passed(detail) => {
+                total += 1;
+                let status = theme.paint(Role::Good, format!("{:<8}", tr(Message::Ok)));
+                println!("{status}{name}: {detail}");
+            }
+            Checked::Skipped(reason) => {
+                let status = theme.paint(Role::Muted, format!("{:<8}", tr(Message::Skipped)));
+                println!("{status}{name}: {reason}");
+            }
+            Checked::Failed(e) => {
+                total += 1;
+                failed += 1;
+                let status = theme.paint(Role::Bad, format!("{:<8}", tr(Message::Error)));
+                println!("{status}{name}: {e}");
+                if let Some(help) = miette::Diagnostic::help(e) {
+                    let hint = format!("{}: {help}", tr(Message::Hint));
+                    println!("{:8}{}", "", theme.paint(Role::Muted, hint));
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::ChecksFailed { failed, total });
+    }
+    println!("{}", tr(Message::ChecksPassed(total)));
+
+    Ok(())
+}
+
+// Fetches the latest page, and reads it
+async fn check_site(
+    clock: &dyn Clock,
+    mut fetcher: Fetcher,
+    reader: &PageReader,
+) -> (Checked, Checked) {
+    // The current day's page may not be up yet
+    let today = clock::puzzle_date(clock.now());
+    let mut fetched = fetcher
+        .fetch_for_date(today)
+        .await
+        .map(|body| (today, body));
+    if let (Err(FetchDataError::NotFound(_)), Some(yesterday)) = (&fetched, today.pred_opt()) {
+        fetched = fetcher
+            .fetch_for_date(yesterday)
+            .await
+            .map(|body| (yesterday, body));
+    }
+
+    match fetched {
+        Ok((date, body)) => (
+            Checked::Passed(tr(Message::FetchedPage {
+                date,
+                from: fetcher.last_addr(),
+            })),
+            check_page(reader, &body).into(),
+        ),
+        Err(e) => (
+            Checked::Failed(e.into()),
+            Checked::Skipped(tr(Message::NoPageToRead)),
+        ),
+    }
+}
+
+// Lists what might have been misread in a two-letter list that doesn't add
+// up, and which of the fixes were made
+fn print_fixes(fixes: &[Fix], applied: bool) {
+    if fixes.is_empty() {
+        return;
+    }
+    let theme = themes().stderr;
+    let heading = match applied {
+        true => Message::RepairedPairs,
+        false => Message::PairsDontAddUp,
+    };
+    eprintln!(
+        "{}: {}",
+        theme.paint(Role::Warning, tr(Message::Warning).to_uppercase()),
+        tr(heading)
+    );
+    for fix in fixes {
+        let line = match fix.applicable() {
+            true => fix.to_string(),
+            false => format!("{fix} {}", tr(Message::CheckByHand)),
+        };
+        eprintln!("  {}", theme.paint(Role::Muted, line));
+    }
+    if !applied && fixes.iter().any(Fix::applicable) {
+        eprintln!("{}", tr(Message::ApplyFixesHint));
+    }
+}
+
+// Reads a grid from the page without recording its structure, so checking
+// doesn't change what later runs compare against
+fn check_page(reader: &PageReader, body: &str) -> Result<String, Error> {
+    let (pairs, lengths) = parse_content_with(body, &reader.options)?;
+    check_grid(&pairs, &lengths)?;
+    let summary = parse_summary_with(body, &reader.options).ok();
+    let quality = quality::assess(&pairs, &lengths, summary.as_ref(), &[]);
+    let words = lengths.values().map(|count| count.0).sum();
+
+    Ok(tr(Message::ReadPage {
+        words,
+        quality: quality.score,
+    }))
+}
+
+async fn self_update(args: SelfUpdateArgs, connectivity: Connectivity) -> Result<(), Error> {
+    let updater = Updater::new(connectivity.network("checking for updates")?, &args.repo);
+    let release = updater.latest().await?;
+    let (current, latest) = (Version::current(), release.version()?);
+    if latest <= current {
+        println!("{}", tr(Message::UpToDate(&current)));
+        return Ok(());
+    }
+    if args.check {
+        let available = Message::UpdateAvailable {
+            current: &current,
+            latest: &latest,
+        };
+        println!("{}", tr(available));
+        return Ok(());
+    }
+
+    // Found before downloading, so there's nothing to throw away if it
+    // can't be
+    let executable = std::env::current_exe().map_err(Error::LocatingExecutable)?;
+    let binary = updater.download(&release).await?;
+    update::replace_executable(&executable, &binary)?;
+    let updated = Message::Updated {
+        from: &current,
+        to: &latest,
+    };
+    println!("{}", tr(updated));
+
+    Ok(())
+}
+
+async fn watch(
+    clock: &dyn Clock,
+    mut args: WatchArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let reader = args.fetch.parse.reader(connectivity)?;
+    let mut fetcher = args.fetch.fetcher(connectivity)?;
+    let state = open_state(args.pipeline.state_dir, &mut args.sheets)?;
+    let retention = args.retention.retention();
+    // Only files are removed, so keeping fewer days needs a directory
+    let archive = match (&args.pipeline.grid_dir, retention.grids.or(retention.words)) {
+        (Some(location), Some(_)) => Some(open_archive(location).map_err(Error::ReadingArchive)?),
+        _ => None,
+    };
+    let mut outputs = Outputs {
+        sheets: args.sheets.connect(connectivity).await?,
+        stats: args.stats,
+        store: grid_store(args.pipeline.grid_dir)?,
+        notifiers: args.notify.notifiers(connectivity)?,
+        spoilers: args.notify.spoilers,
+        transform: args.pipeline.transform.transform()?,
+        hooks: args.pipeline.hooks,
+        steps: args.steps,
+        state,
+        force: false,
+    };
+
+    systemd::notify_ready();
+    if let Some(interval) = systemd::watchdog_interval() {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                systemd::notify_watchdog();
+            }
+        });
+    }
+
+    loop {
+        let now = clock.now().with_timezone(&args.timezone);
+        let next = args
+            .schedules
+            .iter()
+            .filter_map(|schedule| schedule.next_after(&now))
+            .min()
+            .ok_or(Error::NoUpcomingRun)?;
+        println!("{}", tr(Message::NextRun(&next)));
+
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {},
+            _ = SHUTDOWN_NOTIFY.notified() => return Ok(()),
+        }
+        if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Whatever the schedule's timezone, the puzzle's day is US-West's
+        let date = clock::puzzle_date(next.to_utc());
+        let deadline = Deadline::after(args.deadline);
+        let written = async {
+            let outcome = run_for_date(date, &mut fetcher, &reader, &outputs, deadline).await?;
+            if outcome == RunOutcome::Written {
+                println!("{}", tr(Message::WroteGrid(date)));
+                rotate_if_full(&mut outputs, &args.rotation, date).await?;
+            }
+            Ok(())
+        };
+        match written.await {
+            Ok(()) => {}
+            Err(Error::Interrupted(step)) => {
+                eprintln!("{}", tr(Message::InterruptedAfter(step)));
+                return Ok(());
+            }
+            // Keep going, a later run may well succeed
+            Err(e) => eprintln!(
+                "{}: {}",
+                tr(Message::Error),
+                tr(Message::RunFailed(date, &e))
+            ),
+        }
+        if retention.keeps_everything() {
+            continue;
+        }
+        // Left for next time if it fails, as it's only tidying up
+        match remove_old_files(clock, &retention, archive.as_ref()) {
+            Ok(Collected { files: 0, .. }) => {}
+            Ok(collected) => println!(
+                "{}",
+                tr(Message::Removed {
+                    files: collected.files,
+                    bytes: collected.bytes,
+                })
+            ),
+            Err(e) => eprintln!(
+                "{}: {}",
+                tr(Message::Warning),
+                tr(Message::CleanupFailed(&e))
+            ),
+        }
+    }
+}
+
+async fn track(
+    clock: &dyn Clock,
+    args: TrackArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let doc = load_grid(date, args.grid, args.fetch, connectivity).await?;
+    let sheets_client = args.sheets.connect(connectivity).await?;
+    let interval = std::time::Duration::from_secs(args.interval);
+    // Nobody's working on this grid once the next one is out
+    let until = clock::release_time(date.succ_opt().unwrap_or(date));
+
+    loop {
+        let update = async {
+            let words = sheets_client.read_progress(&date).await?;
+            let progress = progress::compute(&doc, &words);
+            sheets_client.write_remaining(&date, &progress).await?;
+            Ok::<_, Error>(progress)
+        };
+        match update.await {
+            Ok(progress) => println!(
+                "{}: {}",
+                clock.now().with_timezone(&RELEASE_TZ).format("%H:%M"),
+                tr(Message::FoundWords {
+                    found: progress.found.len(),
+                    total: progress.total_words
+                })
+            ),
+            // Keep going, the next update may well succeed
+            Err(e) => eprintln!("{}: {}", tr(Message::Error), tr(Message::UpdateFailed(&e))),
+        }
+
+        if clock.now() >= until {
+            println!("{}", tr(Message::NextPuzzleOut));
+            return Ok(());
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {},
+            _ = SHUTDOWN_NOTIFY.notified() => return Ok(()),
+        }
+        if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+    }
+}
+
+async fn backfill(
+    clock: &dyn Clock,
+    mut args: BackfillArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let to = args.to.unwrap_or_else(|| clock::puzzle_date(clock.now()));
+    check_range(args.from, to)?;
+    let reader = args.fetch.parse.reader(connectivity)?;
+    let mut fetcher = args.fetch.fetcher(connectivity)?;
+    let state = open_state(args.pipeline.state_dir, &mut args.sheets)?;
+    let mut outputs = Outputs {
+        sheets: args.sheets.connect(connectivity).await?,
+        stats: args.stats,
+        store: grid_store(args.pipeline.grid_dir)?,
+        notifiers: args.notify.notifiers(connectivity)?,
+        spoilers: args.notify.spoilers,
+        transform: args.pipeline.transform.transform()?,
+        hooks: args.pipeline.hooks,
+        steps: args.steps,
+        state,
+        force: args.force,
+    };
+
+    let mut total = 0;
+    let mut failed = 0;
+    for date in args.from.iter_days().take_while(|date| *date <= to) {
+        check_shutdown("the previous day")?;
+        total += 1;
+        // Days can take however long they need between them
+        let deadline = Deadline::default();
+        match run_for_date(date, &mut fetcher, &reader, &outputs, deadline).await {
+            Ok(RunOutcome::Written) => {
+                println!("{}", tr(Message::WroteGrid(date)));
+                // Later days can't be written if the spreadsheet's full
+                rotate_if_full(&mut outputs, &args.rotation, date).await?;
+            }
+            Ok(RunOutcome::AlreadyDone) => {}
+            Err(Error::Interrupted(step)) => return Err(Error::Interrupted(step)),
+            // Carry on with the other days, this one can be re-run later
+            Err(e) => {
+                eprintln!(
+                    "{}: {}",
+                    tr(Message::Error),
+                    tr(Message::DayFailed(date, &e))
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::DaysFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "dashboard")]
+async fn serve_dashboard(args: ServeArgs) -> Result<(), Error> {
+    let serving = |e| Error::ServingDashboard(args.listen, e);
+    let listener = tokio::net::TcpListener::bind(args.listen)
+        .await
+        .map_err(serving)?;
+    println!("{}", tr(Message::ServingDashboard(&args.listen)));
+
+    let mut access = dashboard::Access::default();
+    if let Some(token) = &args.auth_token {
+        access = access.bearer_token(token);
+    }
+    if let (Some(username), Some(password)) = (&args.auth_username, &args.auth_password) {
+        access = access.basic(username, password);
+    }
+    for path in &args.public_paths {
+        access = access.public(path);
+    }
+
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let router = dashboard::router(store.clone());
+    #[cfg(feature = "graphql")]
+    let router = router.merge(graphql::router(store));
+    #[cfg(feature = "openapi")]
+    let router = router.merge(dashboard::api_docs());
+    let router = dashboard::protect(router, access, &args.cors_origins);
+    axum::serve(listener, router)
+        .with_graceful_shutdown(SHUTDOWN_NOTIFY.notified())
+        .await
+        .map_err(serving)
+}
+
+#[cfg(feature = "grpc")]
+async fn serve_grpc(args: GrpcArgs) -> Result<(), Error> {
+    let mut access = Access::default();
+    if let Some(token) = &args.auth_token {
+        access = access.bearer_token(token);
+    }
+    if let (Some(username), Some(password)) = (&args.auth_username, &args.auth_password) {
+        access = access.basic(username, password);
+    }
+    for method in args.public_methods {
+        access = access.public(method);
+    }
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let service = GridService::new(store).access(access);
+    let watching = service.clone();
+    let poll_interval = std::time::Duration::from_secs(args.poll_interval);
+    let serving = tonic::transport::Server::builder()
+        .add_service(service.server())
+        .serve_with_shutdown(args.listen, SHUTDOWN_NOTIFY.notified());
+    println!("{}", tr(Message::ServingGrpc(&args.listen)));
+
+    tokio::select! {
+        served = serving => served.map_err(Error::ServingGrpc),
+        watched = watching.watch(poll_interval) => watched.map_err(Error::ReadingArchive),
+    }
+}
+
+async fn replay(args: ReplayArgs, connectivity: Connectivity) -> Result<(), Error> {
+    if let (Some(since), Some(until)) = (args.since, args.until) {
+        check_range(since, until)?;
+    }
+    let formats = args
+        .targets
+        .iter()
+        .filter_map(|target| match target {
+            ReplayTarget::Format(format) => Some(*format),
+            ReplayTarget::Sink(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let outputs = match args.output_dir.as_deref() {
+        Some(dir) => formats.iter().map(|&format| (format, dir)).collect(),
+        None if formats.is_empty() => Vec::new(),
+        None => return Err(Error::MissingArgument("--output-dir")),
+    };
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let dates = store.list_dates().map_err(Error::ReadingArchive)?;
+    let in_range = |date: &&chrono::NaiveDate| {
+        args.since.is_none_or(|since| **date >= since)
+            && args.until.is_none_or(|until| **date <= until)
+    };
+    let dates = dates.iter().filter(in_range).copied().collect::<Vec<_>>();
+    if dates.is_empty() {
+        println!("{}", tr(Message::NoSavedGrids));
+        return Ok(());
+    }
+
+    let template = match formats.contains(&Format::Template) {
+        true => read_template(Format::Template, args.template.as_deref())?,
+        false => None,
+    };
+    if let Some(&(_, dir)) = outputs.first() {
+        std::fs::create_dir_all(dir).map_err(|e| Error::WritingOutput(dir.to_owned(), e))?;
+    }
+    // Files are named after the template, so e.g. HTML can be opened as it
+    let extension = |format| match format {
+        Format::Json => "json",
+        Format::Template => args
+            .template
+            .as_deref()
+            .and_then(|path| path.extension())
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("txt"),
+        _ => "txt",
+    };
+    let transform = args.transform.transform()?;
+    let notifiers = args
+        .targets
+        .iter()
+        .filter_map(|target| match target {
+            ReplayTarget::Sink(sink) => Some(sink),
+            ReplayTarget::Format(_) => None,
+        })
+        .map(|sink| sink_notifier(sink, &args.sink_options, connectivity))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut failed = 0;
+    for &date in &dates {
+        check_shutdown("the previous day")?;
+        let replayed = async {
+            let Some(mut doc) = store.load_grid(date).map_err(Error::ReadingArchive)? else {
+                return Ok(());
+            };
+            if let Some(transform) = &transform {
+                doc = transform.apply(&doc).await?;
+            }
+            for &(format, dir) in &outputs {
+                let (output, pairs_output) = match format {
+                    Format::Csv => (
+                        dir.join(format!("{date}.lengths.csv")),
+                        Some(dir.join(format!("{date}.pairs.csv"))),
+                    ),
+                    _ => (dir.join(format!("{date}.{}", extension(format))), None),
+                };
+                write_format(
+                    &doc,
+                    format,
+                    Some(&output),
+                    pairs_output.as_deref(),
+                    template.as_deref(),
+                )?;
+            }
+            for notifier in &notifiers {
+                // Commands always get the whole document
+                let result = notifier.notify(&doc, SpoilerLevel::FullHints).await;
+                let write = Write::json("post grid", &date.to_string(), &doc);
+                audit_post(notifier, write, &result);
+                result?;
+            }
+            Ok::<_, Error>(())
+        };
+        match replayed.await {
+            Ok(()) => println!("{}", tr(Message::WroteGrid(date))),
+            Err(Error::Interrupted(step)) => return Err(Error::Interrupted(step)),
+            // Carry on with the other days, this one can be replayed later
+            Err(e) => {
+                eprintln!(
+                    "{}: {}",
+                    tr(Message::Error),
+                    tr(Message::DayFailed(date, &e))
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::DaysFailed {
+            failed,
+            total: dates.len(),
+        });
+    }
+
+    Ok(())
+}
+
+async fn find_gaps(
+    clock: &dyn Clock,
+    args: GapsArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let until = args
+        .until
+        .unwrap_or_else(|| clock::puzzle_date(clock.now()));
+    check_range(args.since, until)?;
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let gaps = store
+        .gaps(args.since, until, args.min_quality)
+        .map_err(Error::ReadingArchive)?;
+
+    for (date, gap) in &gaps {
+        println!("{date}: {gap}");
+    }
+    if !args.fetch || gaps.is_empty() {
+        return Ok(());
+    }
+
+    let reader = args.fetch_args.parse.reader(connectivity)?;
+    let mut fetcher = args.fetch_args.fetcher(connectivity)?;
+    let mut failed = 0;
+    for (date, _) in &gaps {
+        check_shutdown("the previous day")?;
+        let saved = async {
+            let body = fetcher.fetch_for_date(*date).await?;
+            let doc = reader.read_grid(&body)?.document(*date);
+            store.save_grid(&doc).map_err(Error::SavingGrid)
+        };
+        match saved.await {
+            Ok(()) => println!("{}", tr(Message::SavedGrid(*date))),
+            Err(e) => {
+                eprintln!(
+                    "{}: {}",
+                    tr(Message::Error),
+                    tr(Message::DayFailed(*date, &e))
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::DaysFailed {
+            failed,
+            total: gaps.len(),
+        });
+    }
+
+    Ok(())
+}
+
+fn collect_garbage(clock: &dyn Clock, args: GcArgs) -> Result<(), Error> {
+    let archive = args
+        .grid_dir
+        .as_deref()
+        .map(open_archive)
+        .transpose()
+        .map_err(Error::ReadingArchive)?;
+    let collected = remove_old_files(clock, &args.retention.retention(), archive.as_ref())?;
+    println!(
+        "{}",
+        tr(Message::Removed {
+            files: collected.files,
+            bytes: collected.bytes,
+        })
+    );
+
+    Ok(())
+}
+
+// Removes what `retention` says is old from `archive` and --debug-http
+fn remove_old_files(
+    clock: &dyn Clock,
+    retention: &Retention,
+    archive: Option<&Archive>,
+) -> Result<Collected, RetentionError> {
+    let http_log = http_log();
+    retention.collect(
+        clock::puzzle_date(clock.now()),
+        archive,
+        http_log.as_ref().map(HttpLog::dir),
+    )
+}
+
+// Every grid saved before `date`, which is what's been normal up to then
+fn past_grids(store: &dyn Store, date: chrono::NaiveDate) -> Result<Vec<GridDocument>, Error> {
+    let mut docs = store
+        .load_range(chrono::NaiveDate::MIN, date)
+        .map_err(Error::ReadingArchive)?;
+    docs.retain(|doc| doc.date < date);
+    Ok(docs)
+}
+
+fn print_unusual_pairs(unusual: &[UnusualPair]) {
+    if unusual.is_empty() {
+        return;
+    }
+    println!("{}", tr(Message::UnusualPairs));
+    for pair in unusual {
+        println!("  {pair}");
+    }
+}
+
+async fn show_pairs(
+    clock: &dyn Clock,
+    args: PairsArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let history = PairHistory::from_docs(&past_grids(&*store, date)?);
+    let doc = match store.load_grid(date).map_err(Error::ReadingArchive)? {
+        Some(doc) => doc,
+        None => load_grid(date, None, args.fetch, connectivity).await?,
+    };
+
+    println!("{}", tr(Message::MostFrequentPairs(history.days())));
+    for (pair, stats) in history.ranked().into_iter().take(args.top) {
+        let stats = Message::PairStats {
+            days: stats.days,
+            words: stats.words,
+        };
+        println!("  {pair}: {}", tr(stats));
+    }
+
+    let unusual = history.unusual(&doc, args.rare_below);
+    if history.days() < MIN_HISTORY_DAYS {
+        let message = Message::NotEnoughHistory {
+            date,
+            need: MIN_HISTORY_DAYS,
+        };
+        println!("{}", tr(message));
+    } else if unusual.is_empty() {
+        println!("{}", tr(Message::NoUnusualPairs(date)));
+    } else {
+        print_unusual_pairs(&unusual);
+    }
+
+    Ok(())
+}
+
+async fn show_wheel(
+    clock: &dyn Clock,
+    args: ShowArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let doc = load_grid(date, args.grid, args.fetch, connectivity).await?;
+
+    let wheel = doc
+        .center
+        .and_then(|center| wheel::render(&doc.letters, center, themes().stdout));
+    match wheel {
+        Some(wheel) => print!("{wheel}"),
+        // e.g. grids saved before the center letter was read
+        None => {
+            let letters = doc.letters.iter().map(char::to_string).collect::<Vec<_>>();
+            println!("{}", letters.join(" "));
+            eprintln!("{}: {}", tr(Message::Warning), tr(Message::NoCenterLetter));
+        }
+    }
+
+    Ok(())
+}
+
+async fn show_coverage(
+    clock: &dyn Clock,
+    args: CoverageArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let dictionary = Dictionary::load(&args.dictionary)
+        .map_err(|e| Error::ReadingDictionary(args.dictionary.clone(), e))?;
+    let doc = load_grid(date, args.grid, args.fetch, connectivity).await?;
+
+    print!("{}", dictionary.coverage(&doc));
+
+    Ok(())
+}
+
+fn show_past_words(args: PastWordsArgs) -> Result<(), Error> {
+    let letters = args.letters.chars().collect::<Vec<_>>();
+    let words = open_store(&args.grid_dir)
+        .and_then(|store| store.accepted_words())
+        .map_err(Error::ReadingArchive)?
+        .fitting(&letters, args.center);
+
+    for word in &words {
+        println!("{word}");
+    }
+    let fit = Message::PastWordsFit {
+        count: words.len(),
+        letters: &args.letters,
+    };
+    eprintln!("{}", tr(fit));
+
+    Ok(())
+}
+
+async fn export_constraints(
+    clock: &dyn Clock,
+    args: ExportConstraintsArgs,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let date = resolve_date(clock, args.date)?;
+    let doc = load_grid(date, args.grid, args.fetch, connectivity).await?;
+    let found = match args.words {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(Error::ReadingWords)?
+            .lines()
+            .map(str::to_string)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let progress = progress::compute(&doc, &found);
+    let rendered = constraints::render(
+        &constraints::remaining(&progress),
+        &doc.letters,
+        args.format,
+    );
+    match args.output {
+        Some(path) => {
+            std::fs::write(&path, rendered).map_err(|e| Error::WritingOutput(path.clone(), e))?
+        }
+        None => print!("{rendered}"),
+    }
+
+    Ok(())
+}
+
+fn show_member_stats(clock: &dyn Clock, args: MemberStatsArgs) -> Result<(), Error> {
+    let until = args
+        .until
+        .unwrap_or_else(|| clock::puzzle_date(clock.now()));
+    check_range(args.since, until)?;
+    let days = open_store(&args.grid_dir)
+        .and_then(|store| store.load_finds_range(args.since, until))
+        .map_err(Error::ReadingArchive)?;
+
+    for stats in team::member_stats(&days) {
+        println!("{stats}");
+    }
+
+    Ok(())
+}
+
+fn report(args: ReportArgs) -> Result<(), Error> {
+    let period = match (args.month, args.week) {
+        (Some(month), _) => month,
+        (None, Some(day)) => Period::week_of(day),
+        // clap requires one or the other
+        (None, None) => return Err(Error::MissingArgument("--month or --week")),
+    };
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let docs = store
+        .load_range(period.first, period.last)
+        .map_err(Error::ReadingArchive)?;
+    let model = DifficultyModel::train(&past_grids(&*store, period.first)?);
+    let rendered = Report::build(period, &docs, model.as_ref()).render(args.format, args.spoilers);
+
+    match args.output {
+        Some(path) => {
+            std::fs::write(&path, rendered).map_err(|e| Error::WritingOutput(path.clone(), e))?
+        }
+        None => print!("{rendered}"),
+    }
+
+    Ok(())
+}
+
+fn export(args: ExportArgs) -> Result<(), Error> {
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let since = args.since.unwrap_or(chrono::NaiveDate::MIN);
+    let until = args.until.unwrap_or(chrono::NaiveDate::MAX);
+    check_range(since, until)?;
+    let output: Box<dyn std::io::Write> = match &args.output {
+        Some(path) => {
+            Box::new(File::create(path).map_err(|e| Error::WritingOutput(path.clone(), e))?)
+        }
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let mut table = LongCsvWriter::new(output, args.buffer_size)?;
+    let docs = store
+        .iter_range(since, until)
+        .map_err(Error::ReadingArchive)?;
+    for (day, doc) in (1..).zip(docs) {
+        table.write(&doc.map_err(Error::ReadingArchive)?)?;
+        if args.flush_every.is_some_and(|every| day % every.get() == 0) {
+            table.flush()?;
+        }
+    }
+    table.flush()?;
+
+    Ok(())
+}
+
+fn import_csv(args: ImportCsvArgs) -> Result<(), Error> {
+    let mut files = Vec::new();
+    for path in args.paths {
+        match path.is_dir() {
+            true => files.extend(csv_files(&path)?),
+            false => files.push(path),
+        }
+    }
+
+    // Everything is read before anything's saved, so a bad file leaves the
+    // saved grids as they were
+    let mut days = BTreeMap::<chrono::NaiveDate, (PathBuf, LengthInfo)>::new();
+    for file in files {
+        let read = from_mapped_csv(&file, &args.map, args.delimiter, date_in_name(&file))
+            .map_err(|e| Error::ImportingCsv(file.clone(), e))?;
+        for (date, lengths) in read {
+            if let Some((first, _)) = days.get(&date) {
+                return Err(Error::ImportedTwice(date, first.clone(), file));
+            }
+            days.insert(date, (file.clone(), lengths));
+        }
+    }
+
+    let store = open_store(&args.grid_dir).map_err(Error::ReadingArchive)?;
+    let saved = store.list_dates().map_err(Error::ReadingArchive)?;
+    for (date, (_, lengths)) in days {
+        if saved.contains(&date) && !args.replace {
+            println!("{}", tr(Message::AlreadySaved(date)));
+            continue;
+        }
+        // Hand-made files only have the grid, not the two-letter list
+        let doc = GridDocument::new(date, &PairInfo::default(), &lengths);
+        store.save_grid(&doc).map_err(Error::SavingGrid)?;
+        println!("{}", tr(Message::SavedGrid(date)));
+    }
+
+    Ok(())
+}
+
+fn merge_grids(args: MergeArgs) -> Result<(), Error> {
+    let target = open_archive(&args.grid_dir).map_err(Error::Merging)?;
+    let sources = args
+        .sources
+        .iter()
+        .map(|source| open_archive(source))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Merging)?;
+    let reconciliation =
+        merge::merge(&target, &sources, args.policy, args.dry_run).map_err(Error::Merging)?;
+    let rendered = reconciliation.render_markdown();
+
+    match &args.report {
+        Some(path) => {
+            std::fs::write(path, rendered).map_err(|e| Error::WritingOutput(path.clone(), e))?;
+            println!("{}", tr(Message::WroteFile(&path.display())));
+        }
+        None => print!("{rendered}"),
+    }
+    let to_review = reconciliation.to_review();
+    if !to_review.is_empty() {
+        eprintln!("{}", tr(Message::ToReview(to_review.len())));
+    }
+
+    Ok(())
+}
+
+fn manage_database(command: DbCommand) -> Result<(), Error> {
+    match command {
+        DbCommand::Migrate(args) => {
+            let database = open_database(&args.grid_dir).map_err(Error::Migrating)?;
+            let applied = database.migrate().map_err(Error::Migrating)?;
+            for migration in &applied {
+                println!(
+                    "{}",
+                    tr(Message::Migrated {
+                        version: migration.version,
+                        description: migration.description,
+                    })
+                );
+            }
+            if applied.is_empty() {
+                let statuses = database.migrations().map_err(Error::Migrating)?;
+                let version = statuses.last().map_or(0, |status| status.migration.version);
+                println!("{}", tr(Message::SchemaUpToDate(version)));
+            }
+        }
+        DbCommand::Status(args) => {
+            let database = open_database(&args.grid_dir).map_err(Error::ReadingArchive)?;
+            for status in database.migrations().map_err(Error::ReadingArchive)? {
+                let Migration {
+                    version,
+                    description,
+                    ..
+                } = status.migration;
+                let message = match status.applied {
+                    Some(applied) => Message::MigrationApplied {
+                        version,
+                        description,
+                        applied,
+                    },
+                    None => Message::MigrationPending {
+                        version,
+                        description,
+                    },
+                };
+                println!("{}", tr(message));
+            }
+        }
+        DbCommand::Backup(args) => {
+            let database = open_database(&args.db.grid_dir).map_err(Error::BackingUpDatabase)?;
+            database
+                .backup(&args.file)
+                .map_err(Error::BackingUpDatabase)?;
+            println!("{}", tr(Message::WroteFile(&args.file.display())));
+        }
+        DbCommand::Restore(args) => {
+            let database = open_database(&args.db.grid_dir).map_err(Error::RestoringDatabase)?;
+            database
+                .restore(&args.file)
+                .map_err(Error::RestoringDatabase)?;
+            println!("{}", tr(Message::Restored(&args.file.display())));
+        }
+        DbCommand::Check(args) => {
+            let database = open_database(&args.db.grid_dir).map_err(Error::CheckingDatabase)?;
+            database
+                .check(args.vacuum)
+                .map_err(Error::CheckingDatabase)?;
+            println!("{}", tr(Message::DatabaseChecked));
+        }
+    }
+
+    Ok(())
+}
+
+fn csv_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let listing = |e| Error::ListingCsvFiles(dir.to_path_buf(), e);
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(listing)? {
+        let path = entry.map_err(listing)?.path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+// Hand-made files tend to be named for their day, e.g. grid-2023-06-01.csv
+fn date_in_name(path: &Path) -> Option<chrono::NaiveDate> {
+    let name = path.file_stem()?.to_str()?;
+    (0..name.len())
+        .filter_map(|i| name.get(i..i + 10))
+        .find_map(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+}
+
+async fn real_main(args: Args) -> Result<(), Error> {
+    let _ = THEMES.set(args.color.themes());
+    let _ = LANG.set(args.lang.unwrap_or_else(Lang::detect));
+    let _ = CIPHER.set(args.encryption.cipher()?);
+    let audit = args.audit_log.as_deref().map(AuditLog::open).transpose()?;
+    let _ = AUDIT_LOG.set(audit);
+    let http = args
+        .debug_http
+        .as_deref()
+        .map(|dir| HttpLog::open(dir, args.debug_http_bodies))
+        .transpose()?;
+    let _ = HTTP_LOG.set(http);
+    let clock = SystemClock;
+    let connectivity = match args.offline {
+        true => Connectivity::Offline,
+        false => Connectivity::Online,
+    };
+    let command = match args.command {
+        Some(command) => command,
+        // Always read when there's no subcommand
+        None => match args.run {
+            Some(run_args) => Command::Run(run_args),
+            None => Args::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "a subcommand, or the arguments for reading the grid, is required",
+                )
+                .exit(),
+        },
+    };
+
+    let result = match command {
+        Command::Run(run_args) => run(&clock, run_args, connectivity).await,
+        Command::Schema(schema_args) => schema(schema_args),
+        Command::Convert(convert_args) => convert(convert_args),
+        Command::Emit(emit_args) => emit(emit_args),
+        Command::Show(show_args) => show_wheel(&clock, show_args, connectivity).await,
+        Command::Progress(progress_args) => {
+            show_progress(&clock, progress_args, connectivity).await
+        }
+        Command::Leaderboard(leaderboard_args) => {
+            write_leaderboard(&clock, leaderboard_args, connectivity).await
+        }
+        Command::InstallService(install_args) => install_service(install_args),
+        Command::Watch(watch_args) => watch(&clock, watch_args, connectivity).await,
+        Command::Backfill(backfill_args) => backfill(&clock, backfill_args, connectivity).await,
+        Command::Replay(replay_args) => replay(replay_args, connectivity).await,
+        #[cfg(feature = "grpc")]
+        Command::Grpc(grpc_args) => serve_grpc(grpc_args).await,
+        #[cfg(feature = "dashboard")]
+        Command::Serve(serve_args) => serve_dashboard(serve_args).await,
+        Command::Gaps(gaps_args) => find_gaps(&clock, gaps_args, connectivity).await,
+        Command::Gc(gc_args) => collect_garbage(&clock, gc_args),
+        Command::Report(report_args) => report(report_args),
+        Command::Export(export_args) => export(export_args),
+        Command::ImportCsv(import_args) => import_csv(import_args),
+        Command::Merge(merge_args) => merge_grids(merge_args),
+        Command::Pairs(pairs_args) => show_pairs(&clock, pairs_args, connectivity).await,
+        Command::Coverage(coverage_args) => {
+            show_coverage(&clock, coverage_args, connectivity).await
+        }
+        Command::PastWords(past_words_args) => show_past_words(past_words_args),
+        Command::ExportConstraints(export_args) => {
+            export_constraints(&clock, export_args, connectivity).await
+        }
+        Command::Track(track_args) => track(&clock, track_args, connectivity).await,
+        Command::MemberStats(member_stats_args) => show_member_stats(&clock, member_stats_args),
+        Command::SelfUpdate(self_update_args) => self_update(self_update_args, connectivity).await,
+        Command::Doctor(doctor_args) => doctor(&clock, doctor_args, connectivity).await,
+        Command::Sheets(SheetsCommand::Sync(sync_args)) => {
+            sync_sheets(&clock, sync_args, connectivity).await
+        }
+        Command::Sheets(SheetsCommand::Backup(backup_args)) => {
+            backup_sheets(backup_args, connectivity).await
+        }
+        Command::Secret(secret_command) => manage_secret(secret_command, args.profile.as_deref()),
+        Command::Db(db_command) => manage_database(db_command),
+        Command::EncryptFile(encrypt_args) => encrypt_files(&encrypt_args.files, true),
+        Command::DecryptFile(decrypt_args) => encrypt_files(&decrypt_args.files, false),
+        Command::Sheets(SheetsCommand::Rotate(rotate_args)) => {
+            rotate_sheets(&clock, rotate_args, connectivity).await
+        }
+    };
+    // Changes that weren't recorded were still made, so the command's own
+    // error comes first
+    let audited = audit_log().map_or(Ok(()), |log| log.check());
+    let recorded = http_log().map_or(Ok(()), |log| log.check());
+    result?;
+    audited?;
+
+    Ok(recorded?)
+}
+
+fn check_shutdown(finished_step: &'static str) -> Result<(), Error> {
+    if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        return Err(Error::Interrupted(finished_step));
+    }
+
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+// The first signal asks the current step to finish before exiting, and a
+// second one exits straight away
+fn handle_shutdown_signals() {
+    tokio::spawn(async {
+        shutdown_signal().await;
+        eprintln!("{}", tr(Message::StoppingAfterStep));
+        SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+        SHUTDOWN_NOTIFY.notify_one();
+
+        shutdown_signal().await;
+        std::process::exit(130);
+    });
+}
+
+fn main() {
+    // Parsed before the runtime starts its threads, as a profile's settings
+    // and secrets are passed on through the environment
+    let result = Args::parse_with_profile().and_then(|args| {
+        tokio::runtime::Runtime::new()
+            .expect("failed to start the async runtime")
+            .block_on(async {
+                handle_shutdown_signals();
+                real_main(args).await
+            })
+    });
+    if let Err(e) = result {
+        let theme = match themes().stderr.is_plain() {
+            true => GraphicalTheme::unicode_nocolor(),
+            false => GraphicalTheme::unicode(),
+        };
+        let mut out = String::new();
+        let rendered = GraphicalReportHandler::new_themed(theme)
+            .without_cause_chain()
+            .render_report(&mut out, &e);
+        match rendered {
+            Ok(()) => eprint!("{out}"),
+            Err(_) => eprintln!("{}: {e}", tr(Message::Error)),
+        }
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(clap::Parser, Debug)]
+    struct Steps {
+        #[command(flatten)]
+        steps: StepArgs,
+    }
+
+    fn steps(args: &[&str]) -> StepArgs {
+        let args = std::iter::once("gridder").chain(args.iter().copied());
+        Steps::try_parse_from(args).unwrap().steps
+    }
+
+    #[derive(clap::Parser, Debug)]
+    struct Sheets {
+        #[command(flatten)]
+        sheets: SheetsArgs,
+    }
+
+    fn sheets(flags: &[&str]) -> SheetsArgs {
+        let args = ["gridder", "-i", "sheet-id", "-p", "account.json"];
+        let args = args.into_iter().chain(flags.iter().copied());
+        Sheets::try_parse_from(args).unwrap().sheets
+    }
+
+    #[test]
+    fn failed_tabs_are_removed_unless_kept() {
+        let removed = sheets(&[]);
+        assert!(!removed.keep_failed_sheets);
+        let kept = |env: Vec<(String, String)>| {
+            env.contains(&("GRIDDER_KEEP_FAILED_SHEETS".into(), "true".into()))
+        };
+        assert!(!kept(removed.environment()));
+
+        let args = sheets(&["--keep-failed-sheets"]);
+        assert!(args.keep_failed_sheets);
+        // Kept by the service `install-service` sets up
+        assert!(kept(args.environment()));
+    }
+
+    #[test]
+    fn every_step_runs_by_default() {
+        let steps = steps(&[]);
+        assert!(steps.runs(Step::Fetch) && steps.runs(Step::Notify));
+        assert!(!steps.leaves_out_outputs());
+    }
+
+    #[test]
+    fn only_the_steps_picked_run() {
+        let steps = steps(&["--only", "fetch,grid-file"]);
+        assert!(steps.runs(Step::Fetch) && steps.runs(Step::GridFile));
+        assert!(!steps.runs(Step::Transform) && !steps.runs(Step::Sheets));
+        assert!(steps.leaves_out_outputs());
+    }
+
+    #[test]
+    fn saved_grids_are_not_saved_or_transformed_again() {
+        let steps = steps(&["--skip", "fetch"]);
+        assert!(!steps.runs(Step::GridFile) && !steps.runs(Step::Transform));
+        assert!(steps.runs(Step::Sheets) && steps.runs(Step::Notify));
+        // So a day can be finished with the outputs it missed
+        assert!(!steps.leaves_out_outputs());
+    }
+
+    #[test]
+    fn only_and_skip_are_not_given_together() {
+        let args = ["gridder", "--only", "fetch", "--skip", "sheets"];
+        assert!(Steps::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn one_failed_output_fails_the_run_after_the_rest() {
+        let results = vec![
+            ("sheets", Some(Err(Error::MissingArgument("--grid-dir")))),
+            ("grid file", Some(Ok(Delivery::Sent))),
+            ("telegram", None),
+            ("matrix", Some(Ok(Delivery::AlreadySent))),
+        ];
+        assert!(matches!(
+            report_outputs(results),
+            Err(Error::OutputsFailed {
+                failed: 1,
+                total: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn outputs_that_all_went_through_pass() {
+        let results = vec![
+            ("sheets", Some(Ok(Delivery::Sent))),
+            ("grid file", Some(Ok(Delivery::AlreadySent))),
+        ];
+        assert!(report_outputs(results).is_ok());
+        assert!(report_outputs(Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn spreadsheets_rotate_once_they_reach_the_share_given() {
+        assert!(!nearly_full(0, 1));
+        assert!(!nearly_full(7_999_999, 80));
+        assert!(nearly_full(8_000_000, 80));
+        assert!(nearly_full(CELL_LIMIT, 100));
+        assert!(!nearly_full(CELL_LIMIT - 1, 100));
+    }
+
+    #[test]
+    fn delimiters_are_one_character() {
+        assert_eq!(parse_delimiter(";"), Ok(b';'));
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert!(parse_delimiter(";;").is_err());
+        assert!(parse_delimiter("§").is_err());
+        assert!(parse_delimiter("").is_err());
+    }
+
+    #[test]
+    fn files_are_dated_by_their_name() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 5, 1);
+        assert_eq!(date_in_name(Path::new("grids/2024-05-01.csv")), date);
+        assert_eq!(date_in_name(Path::new("counts 2024-05-01 (2).csv")), date);
+        assert_eq!(date_in_name(Path::new("2024-05-01/counts.csv")), None);
+        assert_eq!(date_in_name(Path::new("2024-13-01.csv")), None);
+    }
+
+    #[test]
+    fn running_out_of_time_is_reported_over_failed_outputs() {
+        let results = vec![
+            ("sheets", Some(Err(Error::MissingArgument("--grid-dir")))),
+            (
+                "grid file",
+                Some(Err(DeadlineExceeded("the grid file").into())),
+            ),
+        ];
+        assert!(matches!(
+            report_outputs(results),
+            Err(Error::DeadlineExceeded(_))
+        ));
     }
 }
@@ -0,0 +1,393 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::archive::{Archive, ArchiveError};
+use crate::document::GridDocument;
+
+/// How to choose between sources that saved different grids for a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Policy {
+    /// The grid with the highest quality, then the most recently saved.
+    HighestQuality,
+    /// The most recently saved grid.
+    Newest,
+    /// None of them; the day is listed in the report to settle by hand.
+    Review,
+}
+
+/// One source's grid for a day.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub source: PathBuf,
+    pub doc: GridDocument,
+    pub modified: Option<SystemTime>,
+}
+
+/// What happened to a day in the merged grids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The merged grids already had it, and nothing disagreed.
+    Unchanged,
+    /// It was missing, and was taken from the candidate at this index.
+    Added(usize),
+    /// It was replaced with the candidate at this index.
+    Replaced(usize),
+    /// The sources disagreed, and the merged grids' own was chosen.
+    Kept,
+    /// The sources disagreed, and it was left for review.
+    Review,
+}
+
+/// A day found in any of the sources.
+#[derive(Debug, Clone)]
+pub struct Day {
+    pub date: NaiveDate,
+    /// The merged grids' own first, if they had one, then the sources' in
+    /// the order they were given.
+    pub candidates: Vec<Candidate>,
+    /// Whether the candidates have different grids.
+    pub conflict: bool,
+    pub outcome: Outcome,
+}
+
+/// Every day [`merge`] looked at, to show how it was reconciled.
+#[derive(Debug, Clone)]
+pub struct Reconciliation {
+    pub target: PathBuf,
+    pub sources: Vec<PathBuf>,
+    pub policy: Policy,
+    pub days: Vec<Day>,
+}
+
+/// Merges the grids saved in `sources` into `target`. Days only one of
+/// them has are copied across; days they have different grids for are
+/// settled by `policy`. With `dry_run`, `target` is left as it was, but
+/// the returned report shows what would have been done.
+///
+/// Grids agree if their counts and two-letter lists match. One without a
+/// two-letter list, e.g. from `gridder import-csv`, agrees with any that
+/// has the same counts, and is completed from it.
+pub fn merge(
+    target: &Archive,
+    sources: &[Archive],
+    policy: Policy,
+    dry_run: bool,
+) -> Result<Reconciliation, ArchiveError> {
+    let mut dates = target.dates()?;
+    for source in sources {
+        dates.extend(source.dates()?);
+    }
+
+    let mut days = Vec::new();
+    for date in dates {
+        let mut candidates = Vec::new();
+        let mut has_own = false;
+        for (i, archive) in std::iter::once(target).chain(sources).enumerate() {
+            if let Some(doc) = archive.load(date)? {
+                has_own |= i == 0;
+                candidates.push(Candidate {
+                    source: archive.dir().to_path_buf(),
+                    doc,
+                    modified: archive.modified(date)?,
+                });
+            }
+        }
+
+        let day = reconcile(date, candidates, has_own, policy);
+        if !dry_run {
+            if let Outcome::Added(i) | Outcome::Replaced(i) = day.outcome {
+                target.save(&day.candidates[i].doc)?;
+            }
+        }
+        days.push(day);
+    }
+
+    Ok(Reconciliation {
+        target: target.dir().to_path_buf(),
+        sources: sources.iter().map(|s| s.dir().to_path_buf()).collect(),
+        policy,
+        days,
+    })
+}
+
+fn reconcile(date: NaiveDate, candidates: Vec<Candidate>, has_own: bool, policy: Policy) -> Day {
+    let conflict = candidates
+        .iter()
+        .any(|candidate| !agrees(&candidate.doc, &candidates[0].doc));
+    let chosen = match conflict {
+        // The most complete, which is the merged grids' own unless it's
+        // missing the two-letter list
+        false => Some(
+            candidates
+                .iter()
+                .position(|candidate| !candidate.doc.pairs.is_empty())
+                .unwrap_or(0),
+        ),
+        true => match policy {
+            Policy::HighestQuality => Some(best_by(&candidates, |c| (c.doc.quality, c.modified))),
+            Policy::Newest => Some(best_by(&candidates, |c| c.modified)),
+            Policy::Review => None,
+        },
+    };
+    let outcome = match (chosen, has_own) {
+        (None, _) => Outcome::Review,
+        (Some(i), false) => Outcome::Added(i),
+        (Some(0), true) if conflict => Outcome::Kept,
+        (Some(0), true) => Outcome::Unchanged,
+        (Some(i), true) => Outcome::Replaced(i),
+    };
+
+    Day {
+        date,
+        candidates,
+        conflict,
+        outcome,
+    }
+}
+
+fn agrees(a: &GridDocument, b: &GridDocument) -> bool {
+    a.lengths == b.lengths && (a.pairs == b.pairs || a.pairs.is_empty() || b.pairs.is_empty())
+}
+
+// The index of the candidate with the greatest key, the earliest on a tie
+fn best_by<K: Ord>(candidates: &[Candidate], key: impl Fn(&Candidate) -> K) -> usize {
+    let mut best = 0;
+    for (i, candidate) in candidates.iter().enumerate().skip(1) {
+        if key(candidate) > key(&candidates[best]) {
+            best = i;
+        }
+    }
+    best
+}
+
+impl Reconciliation {
+    /// Days whose outcome matches `keep`.
+    fn count(&self, keep: impl Fn(&Outcome) -> bool) -> usize {
+        self.days.iter().filter(|day| keep(&day.outcome)).count()
+    }
+
+    /// Days the sources disagreed on and left for review.
+    pub fn to_review(&self) -> BTreeSet<NaiveDate> {
+        self.days
+            .iter()
+            .filter(|day| day.outcome == Outcome::Review)
+            .map(|day| day.date)
+            .collect()
+    }
+
+    /// A Markdown summary, with a table of each source's grid for every day
+    /// they disagreed on.
+    pub fn render_markdown(&self) -> String {
+        // Writing to a String can't fail
+        let mut out = String::new();
+        let _ = writeln!(out, "# Merging into {}\n", self.target.display());
+        let _ = writeln!(out, "Sources:\n");
+        for source in &self.sources {
+            let _ = writeln!(out, "- {}", source.display());
+        }
+        let policy = match self.policy {
+            Policy::HighestQuality => "highest quality",
+            Policy::Newest => "newest",
+            Policy::Review => "review",
+        };
+        let _ = writeln!(out, "\nConflicts settled by: {policy}\n");
+
+        let _ = writeln!(out, "## Overview\n");
+        let overview = [
+            ("Days", self.days.len()),
+            ("Added", self.count(|o| matches!(o, Outcome::Added(_)))),
+            (
+                "Replaced",
+                self.count(|o| matches!(o, Outcome::Replaced(_))),
+            ),
+            ("Kept", self.count(|o| *o == Outcome::Kept)),
+            ("Unchanged", self.count(|o| *o == Outcome::Unchanged)),
+            ("Needing review", self.count(|o| *o == Outcome::Review)),
+        ];
+        for (label, value) in overview {
+            let _ = writeln!(out, "- {label}: {value}");
+        }
+
+        let conflicts = self.days.iter().filter(|day| day.conflict);
+        let _ = writeln!(out, "\n## Conflicts\n");
+        let mut any = false;
+        for day in conflicts {
+            any = true;
+            let outcome = match day.outcome {
+                Outcome::Review => "needs review",
+                Outcome::Kept | Outcome::Unchanged => "kept",
+                Outcome::Added(_) => "added",
+                Outcome::Replaced(_) => "replaced",
+            };
+            let _ = writeln!(out, "### {} ({outcome})\n", day.date);
+            let _ = writeln!(out, "| Source | Quality | Words | Pairs | Saved | Chosen |");
+            let _ = writeln!(out, "| --- | ---: | ---: | ---: | --- | :---: |");
+            let chosen = match day.outcome {
+                Outcome::Added(i) | Outcome::Replaced(i) => Some(i),
+                Outcome::Kept | Outcome::Unchanged => Some(0),
+                Outcome::Review => None,
+            };
+            for (i, candidate) in day.candidates.iter().enumerate() {
+                let quality = candidate
+                    .doc
+                    .quality
+                    .map(|quality| quality.to_string())
+                    .unwrap_or_default();
+                let saved = candidate
+                    .modified
+                    .map(|modified| {
+                        DateTime::<Utc>::from(modified)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "| {} | {quality} | {} | {} | {saved} | {} |",
+                    candidate.source.display(),
+                    candidate.doc.totals.words,
+                    candidate.doc.pairs.len(),
+                    if chosen == Some(i) { "✓" } else { "" },
+                );
+            }
+            let _ = writeln!(out);
+        }
+        if !any {
+            let _ = writeln!(out, "None.");
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+    }
+
+    // A grid of words starting "AC", with or without its two-letter list
+    fn doc(words: usize, with_pairs: bool) -> GridDocument {
+        let lengths = LengthInfo::from([(('A', WordLength(4)), WordCount(words))]);
+        let pairs = match with_pairs {
+            true => PairInfo::from([(('A', 'C'), WordCount(words))]),
+            false => PairInfo::new(),
+        };
+        GridDocument::new(date(), &pairs, &lengths)
+    }
+
+    fn candidate(source: &str, doc: GridDocument, quality: u8, saved: u64) -> Candidate {
+        Candidate {
+            source: source.into(),
+            doc: GridDocument {
+                quality: Some(quality),
+                ..doc
+            },
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(saved)),
+        }
+    }
+
+    fn outcome(candidates: Vec<Candidate>, has_own: bool, policy: Policy) -> (bool, Outcome) {
+        let day = reconcile(date(), candidates, has_own, policy);
+        (day.conflict, day.outcome)
+    }
+
+    #[test]
+    fn days_the_sources_agree_on_need_no_policy() {
+        let same = || {
+            vec![
+                candidate("target", doc(3, true), 90, 1),
+                candidate("other", doc(3, true), 100, 2),
+            ]
+        };
+        assert_eq!(
+            outcome(same(), true, Policy::Review),
+            (false, Outcome::Unchanged)
+        );
+        assert_eq!(
+            outcome(same(), false, Policy::Review),
+            (false, Outcome::Added(0))
+        );
+    }
+
+    #[test]
+    fn grids_without_two_letter_lists_are_completed() {
+        let candidates = vec![
+            candidate("target", doc(3, false), 100, 2),
+            candidate("other", doc(3, true), 50, 1),
+        ];
+        assert_eq!(
+            outcome(candidates, true, Policy::Review),
+            (false, Outcome::Replaced(1))
+        );
+    }
+
+    #[test]
+    fn conflicts_are_settled_by_the_policy() {
+        let candidates = || {
+            vec![
+                candidate("target", doc(3, true), 90, 3),
+                candidate("newer", doc(4, true), 80, 5),
+                candidate("better", doc(5, true), 100, 1),
+            ]
+        };
+        let settled = |policy| outcome(candidates(), true, policy);
+        assert_eq!(
+            settled(Policy::HighestQuality),
+            (true, Outcome::Replaced(2))
+        );
+        assert_eq!(settled(Policy::Newest), (true, Outcome::Replaced(1)));
+        assert_eq!(settled(Policy::Review), (true, Outcome::Review));
+
+        let own_is_best = vec![
+            candidate("target", doc(3, true), 100, 3),
+            candidate("other", doc(4, true), 100, 1),
+        ];
+        assert_eq!(
+            outcome(own_is_best, true, Policy::HighestQuality),
+            (true, Outcome::Kept)
+        );
+    }
+
+    #[test]
+    fn ties_go_to_the_earliest_source() {
+        let candidates = vec![
+            candidate("first", doc(3, true), 100, 1),
+            candidate("second", doc(4, true), 100, 1),
+        ];
+        assert_eq!(
+            outcome(candidates, false, Policy::HighestQuality),
+            (true, Outcome::Added(0))
+        );
+    }
+
+    #[test]
+    fn dry_runs_leave_the_target_alone() {
+        let root = std::env::temp_dir().join(format!("gridder-merge-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["target", "source"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let target = Archive::new(root.join("target"));
+        let source = Archive::new(root.join("source"));
+        source.save(&doc(3, true)).unwrap();
+
+        let sources = [source];
+        let report = merge(&target, &sources, Policy::Review, true).unwrap();
+        assert_eq!(report.days[0].outcome, Outcome::Added(0));
+        assert_eq!(target.load(date()).unwrap(), None);
+
+        merge(&target, &sources, Policy::Review, false).unwrap();
+        assert_eq!(target.load(date()).unwrap(), Some(doc(3, true)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+
+const DEFAULT_TEMPLATE: &str = "%Y-%m-%d";
+
+// Friendlier spellings of common strftime specifiers
+const TOKENS: [(&str, &str); 5] = [
+    ("{weekday}", "%A"),
+    ("{weekday_short}", "%a"),
+    ("{month}", "%B"),
+    ("{month_short}", "%b"),
+    ("{day}", "%-d"),
+];
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid sheet name template {0:?}")]
+pub struct NameTemplateError(String);
+
+/// How to name the tab created for each day: a strftime format string, which
+/// may also use `{weekday}`, `{weekday_short}`, `{month}`, `{month_short}`
+/// and `{day}`. For example, `{month_short} {day} ({weekday_short})` gives
+/// "May 1 (Wed)".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetNameTemplate {
+    template: String,
+    // The template with tokens swapped for strftime specifiers
+    format: String,
+}
+
+impl SheetNameTemplate {
+    pub fn render(&self, date: &NaiveDate) -> String {
+        date.format(&self.format).to_string()
+    }
+}
+
+impl Default for SheetNameTemplate {
+    fn default() -> Self {
+        DEFAULT_TEMPLATE.parse().unwrap()
+    }
+}
+
+impl FromStr for SheetNameTemplate {
+    type Err = NameTemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let format = TOKENS
+            .iter()
+            .fold(template.to_string(), |acc, (token, spec)| {
+                acc.replace(token, spec)
+            });
+
+        // Rendering an invalid format panics, so catch it here instead
+        if format.trim().is_empty()
+            || StrftimeItems::new(&format).any(|item| matches!(item, Item::Error))
+        {
+            return Err(NameTemplateError(template.to_string()));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            format,
+        })
+    }
+}
+
+impl fmt::Display for SheetNameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
@@ -0,0 +1,56 @@
+use std::future::Future;
+
+/// Whether a run may use the network. Decided once, up front.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    #[default]
+    Online,
+    /// No network requests at all: grids only come from saved files, and
+    /// only go to files.
+    Offline,
+}
+
+/// Permission to make network requests. HTTP clients are only made from
+/// one, and requests through other libraries' clients (buckets, MQTT) are
+/// only sent through one, so everything that makes requests (a
+/// [`Fetcher`](crate::fetch::Fetcher), a
+/// [`SheetManager`](crate::sheets::SheetManager), the notifiers and the
+/// [`Updater`](crate::update::Updater)) needs one. The only way to get one
+/// is [`Connectivity::network`], so an offline run can't make a request by
+/// mistake.
+#[derive(Debug, Clone, Copy)]
+pub struct Network(());
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0} needs the network, which is off (--offline)")]
+pub struct OfflineError(pub &'static str);
+
+impl Connectivity {
+    /// Permission to use the network for `purpose` (e.g. "fetching pages"),
+    /// if it's allowed.
+    pub fn network(self, purpose: &'static str) -> Result<Network, OfflineError> {
+        match self {
+            Self::Online => Ok(Network(())),
+            Self::Offline => Err(OfflineError(purpose)),
+        }
+    }
+}
+
+impl Network {
+    /// A client to make requests with.
+    pub fn client(self) -> reqwest::Client {
+        reqwest::Client::new()
+    }
+
+    /// A builder for a client with its own settings, e.g. for how it looks
+    /// hosts up.
+    pub fn client_builder(self) -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+    }
+
+    /// Sends `request`, for libraries that make requests with their own
+    /// clients rather than one from [`client`](Self::client).
+    pub async fn send<F: Future>(self, request: F) -> F::Output {
+        request.await
+    }
+}
@@ -0,0 +1,899 @@
+use std::fmt::{self, Write};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use reqwest::{header, Url};
+#[cfg(feature = "mqtt")]
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet, QoS, Transport};
+use serde_json::json;
+use tokio::process::Command;
+
+use crate::document::GridDocument;
+use crate::drive::{DriveError, DriveSink};
+use crate::dropbox::{DropboxError, DropboxSink};
+use crate::events::{EventError, EventSink};
+use crate::git::{GitError, GitSink};
+use crate::network::Network;
+use crate::spoiler::SpoilerLevel;
+use crate::storage::{BucketSink, StorageError};
+use crate::transform::feed_and_wait;
+use crate::webdav::{WebdavError, WebdavSink};
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+
+// Tries at an MQTT post before giving up, waiting twice as long after each
+const MQTT_ATTEMPTS: u32 = 3;
+const MQTT_FIRST_RETRY: Duration = Duration::from_secs(5);
+// Longest to wait for the broker to take a post
+const MQTT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const DEFAULT_POST_TEMPLATE: &str =
+    "Today's grid ({date}): {words} words, {points} points, {pangrams} pangrams";
+// What a post template can fill in. None of them give away the letters.
+const POST_TOKENS: [&str; 4] = ["{date}", "{words}", "{points}", "{pangrams}"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("invalid homeserver URL {0:?}")]
+    BadHomeserver(String),
+    #[error("invalid MQTT broker URL {0:?}, expected mqtt://HOST[:PORT] or mqtts://HOST[:PORT]")]
+    BadBroker(String),
+    #[error("gridder was built without {0} support (the {0} feature)")]
+    NotBuiltIn(&'static str),
+    #[cfg(feature = "mqtt")]
+    #[error("failed to publish to MQTT broker ({0})")]
+    Publishing(#[from] rumqttc::ClientError),
+    #[cfg(feature = "mqtt")]
+    #[error("lost MQTT connection ({0})")]
+    // Boxed, as it can hold a whole packet
+    MqttConnection(Box<rumqttc::ConnectionError>),
+    #[error("MQTT broker didn't take the post in time")]
+    MqttTimedOut,
+    #[error("failed to send message ({0})")]
+    Sending(reqwest::Error),
+    #[error("got bad http status from server ({0})")]
+    BadResponse(reqwest::Error),
+    #[error("failed to read response ({0})")]
+    ReadingResponse(reqwest::Error),
+    #[error("unexpected response from server: {0}")]
+    UnexpectedResponse(String),
+    #[error("failed to run {0:?}: {1}")]
+    Running(String, std::io::Error),
+    #[error(transparent)]
+    Event(#[from] EventError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Git(#[from] GitError),
+    #[error(transparent)]
+    Webdav(#[from] WebdavError),
+    #[error(transparent)]
+    Drive(#[from] DriveError),
+    #[error(transparent)]
+    Dropbox(#[from] DropboxError),
+    #[error("{command:?} failed ({status}){}", stderr_tail(.stderr))]
+    CommandFailed {
+        command: String,
+        status: std::process::ExitStatus,
+        /// The last line the command wrote to stderr, if any.
+        stderr: String,
+    },
+}
+
+// The last line of a failed command's stderr, which is usually the error
+fn last_line(stderr: &str) -> String {
+    stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn stderr_tail(stderr: &str) -> String {
+    match stderr.is_empty() {
+        true => String::new(),
+        false => format!(": {stderr}"),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid sink {0:?}, expected exec:COMMAND, git:DIR, webdav:URL, drive:FOLDER_ID, dropbox:/PATH, s3://BUCKET[/PREFIX] or gs://BUCKET[/PREFIX]")]
+pub struct SinkError(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid post template {0:?}, it can only use {{date}}, {{words}}, {{points}} and {{pangrams}}"
+)]
+pub struct PostTemplateError(String);
+
+/// Somewhere to post each day's grid.
+///
+/// Group chats get the whole grid. Public social posts only get a
+/// spoiler-free summary, written with a [`PostTemplate`].
+#[derive(Debug)]
+pub enum Notifier {
+    Matrix(MatrixNotifier),
+    Telegram(TelegramNotifier),
+    Mastodon(MastodonNotifier, PostTemplate),
+    Bluesky(BlueskyNotifier, PostTemplate),
+    Mqtt(MqttNotifier),
+    Event(EventSink),
+    Bucket(BucketSink),
+    Git(GitSink),
+    Webdav(WebdavSink),
+    Drive(DriveSink),
+    Dropbox(DropboxSink),
+    Exec(ExecSink),
+}
+
+impl Notifier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Matrix(_) => "matrix",
+            Self::Telegram(_) => "telegram",
+            Self::Mastodon(..) => "mastodon",
+            Self::Bluesky(..) => "bluesky",
+            Self::Mqtt(_) => "mqtt",
+            Self::Event(sink) => sink.name(),
+            Self::Bucket(sink) => sink.name(),
+            Self::Git(_) => "git",
+            Self::Webdav(_) => "webdav",
+            Self::Drive(_) => "drive",
+            Self::Dropbox(_) => "dropbox",
+            Self::Exec(_) => "exec",
+        }
+    }
+
+    /// Tells this notifier apart from the others in a run, the same way
+    /// from one run to the next, e.g. for recording what's been sent.
+    pub fn key(&self) -> String {
+        match self {
+            Self::Exec(sink) => format!("exec:{}", sink.command),
+            Self::Bucket(sink) => sink.url().to_string(),
+            Self::Git(sink) => format!("git:{}", sink.dir().display()),
+            Self::Webdav(sink) => format!("webdav:{}", sink.folder()),
+            Self::Drive(sink) => format!("drive:{}", sink.folder_id()),
+            Self::Dropbox(sink) => format!("dropbox:{}", sink.folder()),
+            _ => self.name().to_string(),
+        }
+    }
+
+    /// Posts the day's grid, or its summary for public posts, giving away
+    /// no more than `spoilers` allows. Event sinks, buckets, repositories,
+    /// shared folders and commands always get the whole document, as they're not read by people.
+    pub async fn notify(
+        &self,
+        doc: &GridDocument,
+        spoilers: SpoilerLevel,
+    ) -> Result<(), NotifyError> {
+        // Public posts never show more than the counts
+        let post = |template: &PostTemplate, doc| match spoilers.shows_counts() {
+            true => template.render(doc),
+            false => grid_message(doc, SpoilerLevel::None),
+        };
+        match self {
+            Self::Matrix(matrix) => matrix.post(&grid_message(doc, spoilers)).await,
+            Self::Telegram(telegram) => telegram.post(&grid_message(doc, spoilers)).await,
+            Self::Mastodon(mastodon, template) => mastodon.post(&post(template, doc)).await,
+            Self::Bluesky(bluesky, template) => bluesky.post(&post(template, doc)).await,
+            Self::Mqtt(mqtt) => mqtt.publish(&summary_json(doc, spoilers)).await,
+            Self::Event(sink) => Ok(sink.publish(doc).await?),
+            Self::Bucket(sink) => Ok(sink.upload(doc).await?),
+            Self::Git(sink) => Ok(sink.commit(doc).await?),
+            Self::Webdav(sink) => Ok(sink.upload(doc).await?),
+            Self::Drive(sink) => Ok(sink.upload(doc).await?),
+            Self::Dropbox(sink) => Ok(sink.upload(doc).await?),
+            Self::Exec(sink) => sink.send(doc).await,
+        }
+    }
+
+    /// Whether [`announce`](Self::announce) posts anything. Public posts,
+    /// dashboards, event sinks, buckets, repositories, shared folders and
+    /// commands are only for grids.
+    pub fn takes_announcements(&self) -> bool {
+        matches!(self, Self::Matrix(_) | Self::Telegram(_))
+    }
+
+    /// Tells a group chat about gridder itself, e.g. that it's moved to a
+    /// new spreadsheet. Notifiers that don't
+    /// [take announcements](Self::takes_announcements) are left alone.
+    pub async fn announce(&self, text: &str) -> Result<(), NotifyError> {
+        match self {
+            Self::Matrix(matrix) => matrix.post(text).await,
+            Self::Telegram(telegram) => telegram.post(text).await,
+            Self::Mastodon(..)
+            | Self::Bluesky(..)
+            | Self::Mqtt(_)
+            | Self::Event(_)
+            | Self::Bucket(_)
+            | Self::Git(_)
+            | Self::Webdav(_)
+            | Self::Drive(_)
+            | Self::Dropbox(_)
+            | Self::Exec(_) => Ok(()),
+        }
+    }
+}
+
+/// The text of a public post about the day, which may use `{date}`,
+/// `{words}`, `{points}` and `{pangrams}`. Points and pangrams show as `?`
+/// if the page's summary couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostTemplate(String);
+
+impl PostTemplate {
+    pub fn render(&self, doc: &GridDocument) -> String {
+        let or_unknown = |value: Option<usize>| value.map_or("?".to_string(), |v| v.to_string());
+        self.0
+            .replace("{date}", &doc.date.to_string())
+            .replace("{words}", &doc.totals.words.to_string())
+            .replace("{points}", &or_unknown(doc.summary.map(|s| s.points)))
+            .replace("{pangrams}", &or_unknown(doc.summary.map(|s| s.pangrams)))
+    }
+}
+
+impl Default for PostTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_POST_TEMPLATE.to_string())
+    }
+}
+
+impl FromStr for PostTemplate {
+    type Err = PostTemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        // Anything left in braces once the known tokens are gone is a typo
+        let rest = POST_TOKENS
+            .iter()
+            .fold(template.to_string(), |acc, token| acc.replace(token, ""));
+        if template.trim().is_empty() || rest.contains('{') || rest.contains('}') {
+            return Err(PostTemplateError(template.to_string()));
+        }
+
+        Ok(Self(template.to_string()))
+    }
+}
+
+impl fmt::Display for PostTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Hands each day's grid to a command of the user's, for destinations
+/// gridder doesn't know about.
+///
+/// The command is run with `sh -c`, with the [`GridDocument`] JSON (see
+/// `gridder schema`) on its stdin and the grid's date in `GRIDDER_DATE`.
+/// Its stdout is passed through. Exiting with 0 means the grid was
+/// delivered; anything else, or being killed by a signal, fails this output
+/// with the last line of the command's stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecSink {
+    command: String,
+}
+
+impl ExecSink {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+
+    pub async fn send(&self, doc: &GridDocument) -> Result<(), NotifyError> {
+        let running = |e| NotifyError::Running(self.command.clone(), e);
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("GRIDDER_DATE", doc.date.to_string())
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Given up on (e.g. past the deadline), it mustn't carry on
+            // writing after the run's been reported failed
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(running)?;
+
+        // Serializing a document can't fail
+        let json = serde_json::to_vec_pretty(doc).unwrap_or_default();
+        let output = feed_and_wait(child, json).await.map_err(running)?;
+        if !output.status.success() {
+            return Err(NotifyError::CommandFailed {
+                command: self.command.clone(),
+                status: output.status,
+                stderr: last_line(&String::from_utf8_lossy(&output.stderr)),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ExecSink {
+    type Err = SinkError;
+
+    /// Parses `exec:COMMAND`.
+    fn from_str(sink: &str) -> Result<Self, Self::Err> {
+        match sink.strip_prefix("exec:") {
+            Some(command) if !command.trim().is_empty() => Ok(Self::new(command)),
+            _ => Err(SinkError(sink.to_string())),
+        }
+    }
+}
+
+/// Somewhere given with `--to` to hand each day's grid: a command, a git
+/// repository (see [`GitSink`]), a WebDAV, Google Drive or Dropbox folder
+/// (see [`WebdavSink`], [`DriveSink`] and [`DropboxSink`]) or a bucket (see
+/// [`BucketSink`]). Those are only set up once the network's known to be
+/// allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sink {
+    Exec(ExecSink),
+    Git(PathBuf),
+    Webdav(String),
+    Drive(String),
+    Dropbox(String),
+    Bucket(String),
+}
+
+impl FromStr for Sink {
+    type Err = SinkError;
+
+    /// Parses `exec:COMMAND`, `git:DIR`, `webdav:URL`, `drive:FOLDER_ID`,
+    /// `dropbox:/PATH`, `s3://BUCKET[/PREFIX]` or `gs://BUCKET[/PREFIX]`.
+    fn from_str(sink: &str) -> Result<Self, Self::Err> {
+        if let Some(dir) = sink.strip_prefix("git:") {
+            return match dir.is_empty() {
+                true => Err(SinkError(sink.to_string())),
+                false => Ok(Self::Git(PathBuf::from(dir))),
+            };
+        }
+        if let Some(folder_id) = sink.strip_prefix("drive:") {
+            let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+            return match !folder_id.is_empty() && folder_id.chars().all(valid) {
+                true => Ok(Self::Drive(folder_id.to_string())),
+                false => Err(SinkError(sink.to_string())),
+            };
+        }
+        if let Some(folder) = sink.strip_prefix("dropbox:") {
+            return match folder.starts_with('/') {
+                true => Ok(Self::Dropbox(folder.to_string())),
+                false => Err(SinkError(sink.to_string())),
+            };
+        }
+        if let Some(url) = sink.strip_prefix("webdav:") {
+            return match url.starts_with("http://") || url.starts_with("https://") {
+                true => Ok(Self::Webdav(url.to_string())),
+                false => Err(SinkError(sink.to_string())),
+            };
+        }
+        match sink.split_once("://") {
+            Some(("s3" | "gs", bucket)) if !bucket.is_empty() && !bucket.starts_with('/') => {
+                Ok(Self::Bucket(sink.to_string()))
+            }
+            _ => sink.parse().map(Self::Exec),
+        }
+    }
+}
+
+/// Publishes each day's summary to a topic on an MQTT broker, e.g. for a
+/// Home Assistant dashboard. It's retained, so a dashboard that connects
+/// later still gets the latest day's.
+///
+/// Each post connects afresh, so a broker restarted between days doesn't
+/// matter, and one that can't get through is tried again a few times,
+/// waiting longer each time.
+///
+/// Only builds with the `mqtt` feature can publish.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+pub struct MqttNotifier {
+    network: Network,
+    host: String,
+    port: u16,
+    tls: bool,
+    credentials: Option<(String, String)>,
+    topic: String,
+}
+
+impl MqttNotifier {
+    /// `broker` is `mqtt://HOST[:PORT]`, or `mqtts://HOST[:PORT]` for TLS.
+    pub fn new(
+        network: Network,
+        broker: &str,
+        topic: &str,
+        credentials: Option<(&str, &str)>,
+    ) -> Result<Self, NotifyError> {
+        let bad_broker = || NotifyError::BadBroker(broker.to_string());
+        let url = Url::parse(broker).map_err(|_| bad_broker())?;
+        let (tls, default_port) = match url.scheme() {
+            "mqtt" => (false, 1883),
+            "mqtts" => (true, 8883),
+            _ => return Err(bad_broker()),
+        };
+        let host = url.host_str().ok_or_else(bad_broker)?;
+        if !cfg!(feature = "mqtt") {
+            return Err(NotifyError::NotBuiltIn("mqtt"));
+        }
+
+        Ok(Self {
+            network,
+            host: host.to_string(),
+            port: url.port().unwrap_or(default_port),
+            tls,
+            credentials: credentials
+                .map(|(user, password)| (user.to_string(), password.to_string())),
+            topic: topic.to_string(),
+        })
+    }
+
+    pub async fn publish(&self, payload: &str) -> Result<(), NotifyError> {
+        let mut wait = MQTT_FIRST_RETRY;
+        let mut attempt = 1;
+        loop {
+            let publishing = tokio::time::timeout(MQTT_TIMEOUT, self.try_publish(payload));
+            let published = self
+                .network
+                .send(publishing)
+                .await
+                .unwrap_or(Err(NotifyError::MqttTimedOut));
+            match published {
+                Err(_) if attempt < MQTT_ATTEMPTS => {
+                    tokio::time::sleep(wait).await;
+                    wait *= 2;
+                    attempt += 1;
+                }
+                published => return published,
+            }
+        }
+    }
+
+    // Connects, publishes and waits for the broker to acknowledge it
+    #[cfg(feature = "mqtt")]
+    async fn try_publish(&self, payload: &str) -> Result<(), NotifyError> {
+        // Unique, as brokers drop an older connection with the same ID
+        let client_id = format!("gridder-{}", std::process::id());
+        let mut options = MqttOptions::new(client_id, &self.host, self.port);
+        if self.tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+        if let Some((user, password)) = &self.credentials {
+            options.set_credentials(user, password);
+        }
+
+        let (client, mut events) = AsyncClient::new(options, 10);
+        client
+            .publish(&self.topic, QoS::AtLeastOnce, true, payload.to_string())
+            .await?;
+        loop {
+            let event = events
+                .poll()
+                .await
+                .map_err(|e| NotifyError::MqttConnection(Box::new(e)))?;
+            if let Event::Incoming(Packet::PubAck(_)) = event {
+                break;
+            }
+        }
+        // Already delivered, so a disconnect that fails doesn't matter
+        if client.disconnect().await.is_ok() {
+            while let Ok(event) = events.poll().await {
+                if let Event::Outgoing(Outgoing::Disconnect) = event {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "mqtt"))]
+    async fn try_publish(&self, _payload: &str) -> Result<(), NotifyError> {
+        Err(NotifyError::NotBuiltIn("mqtt"))
+    }
+}
+
+// The day's counts as JSON, for dashboards. Points and pangrams are null if
+// the page's summary couldn't be read, and only the date is given if
+// `spoilers` hides the counts.
+fn summary_json(doc: &GridDocument, spoilers: SpoilerLevel) -> String {
+    if !spoilers.shows_counts() {
+        return json!({ "date": doc.date }).to_string();
+    }
+
+    json!({
+        "date": doc.date,
+        "words": doc.totals.words,
+        "points": doc.summary.map(|summary| summary.points),
+        "pangrams": doc.summary.map(|summary| summary.pangrams),
+    })
+    .to_string()
+}
+
+/// Posts messages to a Matrix room through the client-server API.
+#[derive(Debug)]
+pub struct MatrixNotifier {
+    client: reqwest::Client,
+    homeserver: Url,
+    access_token: String,
+    room_id: String,
+    // Matrix drops a message if its transaction ID was used before, so each
+    // one needs its own
+    next_txn: AtomicU64,
+}
+
+impl MatrixNotifier {
+    /// `room_id` is the room's internal ID (e.g. `!abc123:example.org`),
+    /// which the account behind `access_token` must have joined.
+    pub fn new(
+        network: Network,
+        homeserver: &str,
+        access_token: &str,
+        room_id: &str,
+    ) -> Result<Self, NotifyError> {
+        let homeserver = Url::parse(homeserver)
+            .map_err(|_| NotifyError::BadHomeserver(homeserver.to_string()))?;
+        if homeserver.cannot_be_a_base() {
+            return Err(NotifyError::BadHomeserver(homeserver.to_string()));
+        }
+
+        Ok(Self {
+            client: network.client(),
+            homeserver,
+            access_token: access_token.to_string(),
+            room_id: room_id.to_string(),
+            next_txn: AtomicU64::new(0),
+        })
+    }
+
+    /// Posts `text` as a notice, which bots are expected to use so other
+    /// bots don't reply to it.
+    pub async fn post(&self, text: &str) -> Result<(), NotifyError> {
+        // Unique across restarts as well as within this run
+        let txn_id = format!(
+            "gridder-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            self.next_txn.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut url = self.homeserver.clone();
+        // Checked to be a base in new(), and the segments are escaped here
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+        }
+
+        // Sent preformatted too, so the table's columns line up
+        let body = json!({
+            "msgtype": "m.notice",
+            "body": text,
+            "format": "org.matrix.custom.html",
+            "formatted_body": preformatted(text),
+        });
+        self.client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(NotifyError::Sending)?
+            .error_for_status()
+            .map_err(NotifyError::BadResponse)?;
+
+        Ok(())
+    }
+}
+
+/// Sends messages to a Telegram chat through the Bot API.
+#[derive(Debug)]
+pub struct TelegramNotifier {
+    client: reqwest::Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl TelegramNotifier {
+    /// `chat_id` is the numeric ID of the chat, or `@name` for a public
+    /// channel. The bot must be a member of it.
+    pub fn new(network: Network, bot_token: &str, chat_id: &str) -> Self {
+        Self {
+            client: network.client(),
+            bot_token: bot_token.to_string(),
+            chat_id: chat_id.to_string(),
+        }
+    }
+
+    pub async fn post(&self, text: &str) -> Result<(), NotifyError> {
+        let url = format!("{TELEGRAM_API}/bot{}/sendMessage", self.bot_token);
+        // Sent preformatted, so the table's columns line up
+        let body = json!({
+            "chat_id": self.chat_id,
+            "text": preformatted(text),
+            "parse_mode": "HTML",
+        });
+        self.client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(NotifyError::Sending)?
+            .error_for_status()
+            .map_err(NotifyError::BadResponse)?;
+
+        Ok(())
+    }
+}
+
+/// Posts statuses to a Mastodon account.
+#[derive(Debug)]
+pub struct MastodonNotifier {
+    client: reqwest::Client,
+    instance: String,
+    access_token: String,
+}
+
+impl MastodonNotifier {
+    /// `instance` is the server's base URL, e.g. `https://mastodon.social`,
+    /// and `access_token` needs the `write:statuses` scope.
+    pub fn new(network: Network, instance: &str, access_token: &str) -> Self {
+        Self {
+            client: network.client(),
+            instance: instance.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+        }
+    }
+
+    pub async fn post(&self, text: &str) -> Result<(), NotifyError> {
+        let url = format!("{}/api/v1/statuses", self.instance);
+        self.client
+            .post(url)
+            .bearer_auth(&self.access_token)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(json!({ "status": text }).to_string())
+            .send()
+            .await
+            .map_err(NotifyError::Sending)?
+            .error_for_status()
+            .map_err(NotifyError::BadResponse)?;
+
+        Ok(())
+    }
+}
+
+/// Posts to a Bluesky account, signing in with an app password each time.
+#[derive(Debug)]
+pub struct BlueskyNotifier {
+    client: reqwest::Client,
+    service: String,
+    handle: String,
+    app_password: String,
+}
+
+impl BlueskyNotifier {
+    /// `service` is the account's PDS, e.g. `https://bsky.social`.
+    pub fn new(network: Network, service: &str, handle: &str, app_password: &str) -> Self {
+        Self {
+            client: network.client(),
+            service: service.trim_end_matches('/').to_string(),
+            handle: handle.to_string(),
+            app_password: app_password.to_string(),
+        }
+    }
+
+    pub async fn post(&self, text: &str) -> Result<(), NotifyError> {
+        let session = self
+            .call(
+                "com.atproto.server.createSession",
+                None,
+                json!({ "identifier": self.handle, "password": self.app_password }),
+            )
+            .await?;
+        let (Some(token), Some(did)) = (session["accessJwt"].as_str(), session["did"].as_str())
+        else {
+            return Err(NotifyError::UnexpectedResponse(
+                "session has no accessJwt or did".to_string(),
+            ));
+        };
+
+        let record = json!({
+            "$type": "app.bsky.feed.post",
+            "text": text,
+            "createdAt": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        });
+        self.call(
+            "com.atproto.repo.createRecord",
+            Some(token),
+            json!({ "repo": did, "collection": "app.bsky.feed.post", "record": record }),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    // Calls an XRPC procedure, returning its JSON output
+    async fn call(
+        &self,
+        method: &str,
+        token: Option<&str>,
+        input: serde_json::Value,
+    ) -> Result<serde_json::Value, NotifyError> {
+        let mut request = self
+            .client
+            .post(format!("{}/xrpc/{method}", self.service))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(input.to_string());
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        let body = request
+            .send()
+            .await
+            .map_err(NotifyError::Sending)?
+            .error_for_status()
+            .map_err(NotifyError::BadResponse)?
+            .text()
+            .await
+            .map_err(NotifyError::ReadingResponse)?;
+
+        serde_json::from_str(&body).map_err(|e| NotifyError::UnexpectedResponse(e.to_string()))
+    }
+}
+
+// `text` as HTML that keeps its layout
+fn preformatted(text: &str) -> String {
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!("<pre>{escaped}</pre>")
+}
+
+/// The day's grid as a plain-text message: its totals, then word counts by
+/// letter and length and the two-letter list, as far as `spoilers` allows.
+pub fn grid_message(doc: &GridDocument, spoilers: SpoilerLevel) -> String {
+    // Writing to a String can't fail
+    let mut out = String::new();
+    if !spoilers.shows_counts() {
+        let _ = writeln!(out, "The grid for {} is out", doc.date);
+        return out;
+    }
+
+    let _ = write!(out, "Grid for {}: ", doc.date);
+    if spoilers.shows_letters() {
+        let _ = write!(out, "{}, ", doc.letters.iter().collect::<String>());
+    }
+    let _ = write!(out, "{} words", doc.totals.words);
+    if let Some(summary) = doc.summary {
+        let _ = write!(
+            out,
+            ", {} points, {} pangrams",
+            summary.points, summary.pangrams
+        );
+    }
+    out.push('\n');
+    if !spoilers.shows_hints() {
+        return out;
+    }
+
+    let lengths = doc.totals.by_length.keys().copied().collect::<Vec<_>>();
+    let mut header = String::from("   ");
+    for length in &lengths {
+        let _ = write!(header, "{length:>3}");
+    }
+    let _ = writeln!(out, "{header}   Σ");
+    for (letter, total) in &doc.totals.by_letter {
+        let _ = write!(out, "{letter}: ");
+        for length in &lengths {
+            let count = doc
+                .lengths
+                .iter()
+                .find(|e| e.letter == *letter && e.length.0 == *length)
+                .map_or(0, |e| e.count.0);
+            match count {
+                0 => out.push_str("  -"),
+                count => {
+                    let _ = write!(out, "{count:>3}");
+                }
+            }
+        }
+        let _ = writeln!(out, "{total:>4}");
+    }
+
+    let pairs = doc
+        .pairs
+        .iter()
+        .map(|e| format!("{}-{}", e.pair.to_ascii_uppercase(), e.count))
+        .collect::<Vec<_>>();
+    let _ = writeln!(out, "\n{}", pairs.join(" "));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::SummaryEntry;
+
+    fn document() -> GridDocument {
+        serde_json::from_str(
+            r#"{
+                "schema_version": 1,
+                "date": "2024-05-01",
+                "letters": ["A", "C"],
+                "lengths": [{"letter": "A", "length": 4, "count": 2}],
+                "pairs": [{"pair": "AC", "count": 2}],
+                "totals": {"words": 2, "by_letter": {"A": 2}, "by_length": {"4": 2}}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn commands_can_write_stderr_before_reading_the_grid() {
+        // More than a pipe holds both ways, before the document's read
+        let mut doc = document();
+        doc.extra
+            .insert("notes".to_string(), json!("x".repeat(200_000)));
+        let sink =
+            ExecSink::new("yes noise | head -n 40000 >&2; cat >/dev/null; echo done >&2; exit 3");
+        let sent = tokio::time::timeout(Duration::from_secs(10), sink.send(&doc))
+            .await
+            .expect("the command stalled");
+
+        match sent {
+            Err(NotifyError::CommandFailed { stderr, .. }) => assert_eq!(stderr, "done"),
+            other => panic!("expected the command to fail, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn commands_exiting_with_0_deliver_the_grid() {
+        ExecSink::new("grep -q 2024-05-01")
+            .send(&document())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn post_templates_fill_in_the_day() {
+        let template = "{date}: {words} words, {points} points".parse::<PostTemplate>();
+        let mut doc = document();
+        assert_eq!(
+            template.as_ref().unwrap().render(&doc),
+            "2024-05-01: 2 words, ? points"
+        );
+        doc.summary = Some(SummaryEntry {
+            points: 9,
+            pangrams: 1,
+        });
+        assert_eq!(
+            template.unwrap().render(&doc),
+            "2024-05-01: 2 words, 9 points"
+        );
+    }
+
+    #[test]
+    fn post_templates_with_unknown_tokens_are_refused() {
+        for template in ["{date} {word}", "{words", "words}", " ", ""] {
+            assert!(template.parse::<PostTemplate>().is_err(), "{template:?}");
+        }
+        let default = PostTemplate::default();
+        assert_eq!(
+            default.to_string().parse::<PostTemplate>().unwrap(),
+            default
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+// How far a run of quota errors can slow writes down
+const MAX_WRITE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spaces out write requests to stay under a per-minute write quota. Each
+/// quota error doubles the spacing, and each successful write brings it back
+/// down a little, so long runs like backfills settle at a rate the API
+/// accepts.
+#[derive(Debug)]
+pub struct WritePacer {
+    base_interval: Duration,
+    interval: Duration,
+    last_write: Option<Instant>,
+}
+
+impl WritePacer {
+    pub fn new(writes_per_minute: u32) -> Self {
+        let base_interval = Duration::from_secs(60) / writes_per_minute.max(1);
+        Self {
+            base_interval,
+            interval: base_interval,
+            last_write: None,
+        }
+    }
+
+    /// Waits until the next write may be made.
+    pub async fn wait(&mut self) {
+        if let Some(last) = self.last_write {
+            tokio::time::sleep_until(last + self.interval).await;
+        }
+        self.last_write = Some(Instant::now());
+    }
+
+    /// Notes how the last write went, i.e. whether it ran into the quota.
+    pub fn record(&mut self, quota_exceeded: bool) {
+        self.interval = match quota_exceeded {
+            true => (self.interval * 2).min(MAX_WRITE_INTERVAL),
+            false => (self.interval * 9 / 10).max(self.base_interval),
+        };
+    }
+
+    /// The spacing currently kept between writes.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn writes_are_spaced_out_to_the_quota() {
+        let mut pacer = WritePacer::new(60);
+        let start = Instant::now();
+        for _ in 0..3 {
+            pacer.wait().await;
+            pacer.record(false);
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn quota_errors_slow_writes_down_up_to_a_limit() {
+        let mut pacer = WritePacer::new(60);
+        pacer.record(true);
+        assert_eq!(pacer.interval(), Duration::from_secs(2));
+        for _ in 0..10 {
+            pacer.record(true);
+        }
+        assert_eq!(pacer.interval(), MAX_WRITE_INTERVAL);
+    }
+
+    #[test]
+    fn successful_writes_speed_back_up_to_the_quota() {
+        let mut pacer = WritePacer::new(60);
+        pacer.record(true);
+        pacer.record(false);
+        assert_eq!(pacer.interval(), Duration::from_millis(1800));
+        for _ in 0..20 {
+            pacer.record(false);
+        }
+        assert_eq!(pacer.interval(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn no_quota_means_one_write_a_minute() {
+        assert_eq!(WritePacer::new(0).interval(), Duration::from_secs(60));
+    }
+}
@@ -1,97 +1,656 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
 
+use chrono::{DateTime, NaiveDate};
 use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
 
-use crate::{LengthInfo, PairInfo};
+use crate::clock;
+use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+/// Every grid is built from this many letters.
+pub const GRID_LETTERS: usize = 7;
+
+const TABLE_CSS: &str = "table.table";
+const ROW_CSS: &str = "tr.row";
+const CELL_CSS: &str = "td.cell";
+const PARAGRAPH_CSS: &str = "p.content";
 
 lazy_static::lazy_static! {
-    static ref TABLE_SELECTOR: Selector = Selector::parse("table.table").unwrap();
-    static ref TR_SELECTOR: Selector = Selector::parse("tr.row").unwrap();
-    static ref TD_SELECTOR: Selector = Selector::parse("td.cell").unwrap();
-    static ref CONTENT_SELECTOR: Selector = Selector::parse("p.content").unwrap();
+    static ref TABLE_SELECTOR: Selector = Selector::parse(TABLE_CSS).unwrap();
+    static ref TR_SELECTOR: Selector = Selector::parse(ROW_CSS).unwrap();
+    static ref TD_SELECTOR: Selector = Selector::parse(CELL_CSS).unwrap();
+    static ref CONTENT_SELECTOR: Selector = Selector::parse(PARAGRAPH_CSS).unwrap();
+    static ref CENTER_SELECTOR: Selector = Selector::parse("span.cluebody strong").unwrap();
+    static ref PUBLISHED_SELECTOR: Selector =
+        Selector::parse(r#"meta[property="article:published_time"]"#).unwrap();
 
     static ref TWO_LETTER_REGEX: Regex = Regex::new(r#"\b([a-zA-Z]{2})-(\d+)\b"#).unwrap();
+    static ref SUMMARY_REGEX: Regex =
+        Regex::new(r#"WORDS:\s*(\d+),\s*POINTS:\s*(\d+),\s*PANGRAMS:\s*(\d+)"#).unwrap();
 }
 
+mod csv_import;
+mod fingerprint;
+#[cfg(feature = "fast-parse")]
+mod streaming;
+
+pub use csv_import::{from_csv, from_mapped_csv, ColumnMap, ColumnMapError, CsvImportError};
+pub use fingerprint::{Fingerprint, FingerprintError, FingerprintHistory};
+
+#[cfg(feature = "fast-parse")]
+pub use streaming::{parse_content_streaming, parse_content_streaming_with};
+
 #[derive(Debug, thiserror::Error)]
-pub enum SiteParseError {}
+pub enum SiteParseError {
+    #[cfg(feature = "fast-parse")]
+    #[error("failed to tokenize page: {0}")]
+    Tokenizing(#[from] lol_html::errors::RewritingError),
+    #[error("missing table on page")]
+    MissingTable,
+    #[error("missing two-letter list on page")]
+    MissingPairList,
+    #[error("missing words/points/pangrams summary on page")]
+    MissingSummary,
+    #[error("table has no header row")]
+    MissingHeaderRow,
+    #[error("table row has no cells")]
+    EmptyRow,
+    #[error("table row is missing its letter")]
+    MissingRowLetter,
+    #[error("row for {0} doesn't have one cell per header column")]
+    RaggedRow(char),
+    #[error("table header {0:?} is neither a word length nor Σ")]
+    BadHeaderLabel(String),
+    #[error("invalid selector {0:?}: {1}")]
+    InvalidSelector(String, String),
+    #[error("could not read {0:?} as a count")]
+    BadCount(String),
+    #[error("table has rows for {0} letters, expected {GRID_LETTERS}")]
+    WrongLetterCount(usize),
+}
+
+impl SiteParseError {
+    /// A name for the kind of failure, without any of the page's text,
+    /// e.g. `missing_table`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "fast-parse")]
+            Self::Tokenizing(_) => "tokenizing",
+            Self::MissingTable => "missing_table",
+            Self::MissingPairList => "missing_pair_list",
+            Self::MissingSummary => "missing_summary",
+            Self::MissingHeaderRow => "missing_header_row",
+            Self::EmptyRow => "empty_row",
+            Self::MissingRowLetter => "missing_row_letter",
+            Self::RaggedRow(_) => "ragged_row",
+            Self::BadHeaderLabel(_) => "bad_header_label",
+            Self::InvalidSelector(..) => "invalid_selector",
+            Self::BadCount(_) => "bad_count",
+            Self::WrongLetterCount(_) => "wrong_letter_count",
+        }
+    }
+}
+
+// A row's letter, and the text of the rest of its cells
+type RowInfo<'a> = (Option<char>, Vec<Cow<'a, str>>);
+
+/// The day's headline numbers, as printed above the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub words: usize,
+    pub points: usize,
+    pub pangrams: usize,
+}
+
+/// Settings for [`parse_content_with`] and [`parse_summary_with`].
+///
+/// The selectors can be changed to cope with the page's markup changing,
+/// without waiting on a new release.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    table_selector: Option<Selector>,
+    row_selector: Selector,
+    cell_selector: Selector,
+    paragraph_selector: Selector,
+    // The selectors as they were given, for the streaming parser, which
+    // compiles its own
+    sources: SelectorSources,
+}
+
+#[derive(Debug, Clone)]
+struct SelectorSources {
+    table: Option<String>,
+    row: String,
+    cell: String,
+    paragraph: String,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            table_selector: None,
+            row_selector: TR_SELECTOR.clone(),
+            cell_selector: TD_SELECTOR.clone(),
+            paragraph_selector: CONTENT_SELECTOR.clone(),
+            sources: SelectorSources {
+                table: None,
+                row: ROW_CSS.to_string(),
+                cell: CELL_CSS.to_string(),
+                paragraph: PARAGRAPH_CSS.to_string(),
+            },
+        }
+    }
+}
+
+fn compile_selector(selector: &str) -> Result<Selector, SiteParseError> {
+    Selector::parse(selector)
+        .map_err(|e| SiteParseError::InvalidSelector(selector.to_string(), e.to_string()))
+}
+
+impl ParseOptions {
+    /// Selects the grid's table directly, for pages where the usual choice
+    /// picks the wrong one. The first match is used.
+    pub fn table_selector(mut self, selector: &str) -> Result<Self, SiteParseError> {
+        self.table_selector = Some(compile_selector(selector)?);
+        self.sources.table = Some(selector.to_string());
+        Ok(self)
+    }
+
+    /// Selects the table's rows. Defaults to `tr.row`.
+    pub fn row_selector(mut self, selector: &str) -> Result<Self, SiteParseError> {
+        self.row_selector = compile_selector(selector)?;
+        self.sources.row = selector.to_string();
+        Ok(self)
+    }
+
+    /// Selects the cells in each row. Defaults to `td.cell`.
+    pub fn cell_selector(mut self, selector: &str) -> Result<Self, SiteParseError> {
+        self.cell_selector = compile_selector(selector)?;
+        self.sources.cell = selector.to_string();
+        Ok(self)
+    }
+
+    /// Selects the paragraphs holding the summary and the two-letter list.
+    /// Defaults to `p.content`.
+    pub fn paragraph_selector(mut self, selector: &str) -> Result<Self, SiteParseError> {
+        self.paragraph_selector = compile_selector(selector)?;
+        self.sources.paragraph = selector.to_string();
+        Ok(self)
+    }
+}
 
 pub fn parse_content(body: &str) -> Result<(PairInfo, LengthInfo), SiteParseError> {
+    parse_content_with(body, &ParseOptions::default())
+}
+
+pub fn parse_content_with(
+    body: &str,
+    options: &ParseOptions,
+) -> Result<(PairInfo, LengthInfo), SiteParseError> {
     let page = Html::parse_document(body);
 
-    let table = match page.select(&TABLE_SELECTOR).next() {
-        Some(i) => i,
-        None => panic!("missing table on page"),
-    };
+    let table = match &options.table_selector {
+        Some(selector) => page.select(selector).next(),
+        // Posts sometimes have other tables too, like a recap of yesterday's
+        // grid, so take the first one that's labelled like a grid
+        None => page
+            .select(&TABLE_SELECTOR)
+            .find(|table| is_grid_table(*table, options)),
+    }
+    .ok_or(SiteParseError::MissingTable)?;
 
-    let main_node = table.parent().unwrap();
-    let main_el = ElementRef::wrap(main_node).unwrap();
+    // The root element can't be a table.table, so this always has an element
+    // parent in practice
+    let main_el = table
+        .parent()
+        .and_then(ElementRef::wrap)
+        .ok_or(SiteParseError::MissingPairList)?;
 
-    let two_letters_el = main_el.select(&CONTENT_SELECTOR).nth(4).unwrap();
+    let two_letters_el = main_el
+        .select(&options.paragraph_selector)
+        .nth(4)
+        .ok_or(SiteParseError::MissingPairList)?;
 
-    let pairs = extract_pair_info(two_letters_el);
-    let table_info = extract_table_info(table);
+    let pairs = extract_pair_info(two_letters_el)?;
+    let table_info = extract_table_info(table, options)?;
 
     Ok((pairs, table_info))
 }
 
-fn extract_pair_info(node: ElementRef) -> PairInfo {
-    let text_vec = node.text().collect::<Vec<_>>();
-    let text = text_vec.concat();
+/// Checks that a parsed grid is complete: the table must have a row for
+/// each of the day's letters, or part of it was lost.
+///
+/// Returns the first letters of any pairs that have no row in the table.
+/// The grid is still usable then, but something is off.
+pub fn check_grid(pairs: &PairInfo, lengths: &LengthInfo) -> Result<Vec<char>, SiteParseError> {
+    let letters = lengths
+        .keys()
+        .map(|(letter, _)| letter.to_ascii_uppercase())
+        .collect::<BTreeSet<_>>();
+    if letters.len() != GRID_LETTERS {
+        return Err(SiteParseError::WrongLetterCount(letters.len()));
+    }
+
+    let stray = pairs
+        .keys()
+        .map(|(first, _)| first.to_ascii_uppercase())
+        .filter(|first| !letters.contains(first))
+        .collect::<BTreeSet<_>>();
+
+    Ok(stray.into_iter().collect())
+}
+
+/// The date of the puzzle a page is for, going by when it was published.
+pub fn page_date(body: &str) -> Option<NaiveDate> {
+    let page = Html::parse_document(body);
+    let published = page
+        .select(&PUBLISHED_SELECTOR)
+        .next()?
+        .value()
+        .attr("content")?;
+    let published = DateTime::parse_from_rfc3339(published).ok()?;
+
+    Some(clock::puzzle_date(published.to_utc()))
+}
+
+/// The day's center letter, which the page lists in bold among the
+/// others, e.g. "A <strong>C</strong> D E L N T".
+pub fn parse_center(body: &str) -> Option<char> {
+    parse_center_with(body, &ParseOptions::default())
+}
+
+pub fn parse_center_with(body: &str, options: &ParseOptions) -> Option<char> {
+    let page = Html::parse_document(body);
+    let strong = page
+        .select(&options.paragraph_selector)
+        .find_map(|el| el.select(&CENTER_SELECTOR).next())?;
+    let letter = strong
+        .text()
+        .flat_map(str::chars)
+        .find(|c| c.is_alphabetic())?;
+
+    // The table's letters are in capitals
+    Some(letter.to_ascii_uppercase())
+}
+
+pub fn parse_summary(body: &str) -> Result<Summary, SiteParseError> {
+    parse_summary_with(body, &ParseOptions::default())
+}
+
+pub fn parse_summary_with(body: &str, options: &ParseOptions) -> Result<Summary, SiteParseError> {
+    let page = Html::parse_document(body);
+
+    // e.g. "WORDS: 41, POINTS: 165, PANGRAMS: 1"
+    for el in page.select(&options.paragraph_selector) {
+        let text = element_text(el);
+        if let Some(c) = SUMMARY_REGEX.captures(&text) {
+            let (_, [words, points, pangrams]) = c.extract();
+            return Ok(Summary {
+                words: parse_cell(words)?,
+                points: parse_cell(points)?,
+                pangrams: parse_cell(pangrams)?,
+            });
+        }
+    }
+
+    Err(SiteParseError::MissingSummary)
+}
+
+fn extract_pair_info(node: ElementRef) -> Result<PairInfo, SiteParseError> {
+    // Matches may span text nodes, so this needs to be joined up first
+    let text = element_text(node);
+    pairs_from_text(&text)
+}
 
+// An element's text, borrowed from the page when it's all one text node (as
+// cells nearly always are), and only joined up into a new string otherwise
+fn element_text(el: ElementRef) -> Cow<str> {
+    let mut texts = el.text();
+    let first = texts.next().unwrap_or_default();
+    match texts.next() {
+        None => Cow::Borrowed(first),
+        Some(second) => {
+            let mut text = String::from(first);
+            text.push_str(second);
+            text.extend(texts);
+            Cow::Owned(text)
+        }
+    }
+}
+
+fn pairs_from_text(text: &str) -> Result<PairInfo, SiteParseError> {
     let mut pair_counts = HashMap::default();
-    for (_, [prefix, count]) in TWO_LETTER_REGEX.captures_iter(&text).map(|c| c.extract()) {
-        assert!(prefix.len() == 2);
-        let i: usize = count.parse().expect("received negative count");
+    for (_, [prefix, count]) in TWO_LETTER_REGEX.captures_iter(text).map(|c| c.extract()) {
+        let i: usize = count
+            .parse()
+            .map_err(|_| SiteParseError::BadCount(count.to_string()))?;
+        // The regex only matches two ASCII letters here
         let mut chars = prefix.chars();
         let char1 = chars.next().unwrap();
         let char2 = chars.next().unwrap();
-        pair_counts.insert((char1, char2), i);
+        pair_counts.insert((char1, char2), WordCount(i));
     }
 
-    pair_counts
+    Ok(pair_counts)
 }
 
-fn extract_table_info(node: ElementRef) -> LengthInfo {
-    let mut rows = node.select(&TR_SELECTOR);
-    // Expecting 8 rows: 1 header, 6 letters, 1 sum
-    let header = rows.next().unwrap();
-    let (_, values) = extract_table_row_info(header);
+// Whether a table's header row labels its columns with word lengths
+fn is_grid_table(table: ElementRef, options: &ParseOptions) -> bool {
+    match table
+        .select(&options.row_selector)
+        .next()
+        .map(|tr| extract_table_row_info(tr, options))
+    {
+        Some(Ok((_, labels))) => are_length_labels(&labels),
+        _ => false,
+    }
+}
+
+fn are_length_labels<S: AsRef<str>>(labels: &[S]) -> bool {
+    let columns = labels
+        .iter()
+        .map(|label| header_column(label.as_ref()))
+        .collect::<Result<Vec<_>, _>>();
+    matches!(columns, Ok(columns) if columns.iter().any(Option::is_some))
+}
+
+fn extract_table_info(
+    node: ElementRef,
+    options: &ParseOptions,
+) -> Result<LengthInfo, SiteParseError> {
+    lengths_from_rows(
+        node.select(&options.row_selector)
+            .map(|tr| extract_table_row_info(tr, options)),
+    )
+}
+
+fn lengths_from_rows<'a, I>(mut rows: I) -> Result<LengthInfo, SiteParseError>
+where
+    I: Iterator<Item = Result<RowInfo<'a>, SiteParseError>>,
+{
+    // Expecting 9 rows: 1 header, 7 letters, 1 sum
+    let (_, labels) = rows.next().ok_or(SiteParseError::MissingHeaderRow)??;
+    let columns = labels
+        .iter()
+        .map(|label| header_column(label))
+        .collect::<Result<Vec<_>, _>>()?;
 
     let mut items = HashMap::default();
     for row in rows {
-        let (l, quants) = extract_table_row_info(row);
-        let letter = l.unwrap();
+        let (l, cells) = row?;
+        let letter = l.ok_or(SiteParseError::MissingRowLetter)?;
         if letter == 'Σ' {
             continue;
         }
+        if cells.len() != columns.len() {
+            return Err(SiteParseError::RaggedRow(letter));
+        }
 
-        for (i, quantity) in quants.iter().enumerate() {
-            items.insert((letter, values[i]), *quantity);
+        for (column, cell) in columns.iter().zip(&cells) {
+            // Skip the row's sum
+            let Some(length) = column else {
+                continue;
+            };
+            items.insert((letter, WordLength(*length)), WordCount(parse_cell(cell)?));
         }
     }
 
-    items
+    Ok(items)
+}
+
+// The word length a header cell labels, or None for the sum column
+fn header_column(label: &str) -> Result<Option<usize>, SiteParseError> {
+    match label.trim() {
+        "Σ" => Ok(None),
+        v => v
+            .parse()
+            .map(Some)
+            .map_err(|_| SiteParseError::BadHeaderLabel(v.to_string())),
+    }
+}
+
+fn extract_table_row_info<'a>(
+    tr: ElementRef<'a>,
+    options: &ParseOptions,
+) -> Result<RowInfo<'a>, SiteParseError> {
+    let mut els = tr.select(&options.cell_selector);
+    let header_char = els
+        .next()
+        .ok_or(SiteParseError::EmptyRow)?
+        .text()
+        .flat_map(str::chars)
+        .find(|c| !c.is_whitespace());
+
+    let cells = els.map(element_text).collect();
+    Ok((header_char, cells))
+}
+
+fn parse_cell(text: &str) -> Result<usize, SiteParseError> {
+    match text.trim() {
+        "-" => Ok(0),
+        v => {
+            // Large sums may be grouped, e.g. "1,024" or "1 024" with a thin
+            // or non-breaking space. Most cells aren't, so are read as they
+            // are. Anything else, like "1.5", isn't a count
+            let is_separator = |c: char| matches!(c, ',' | '\u{2009}' | '\u{202f}' | '\u{a0}');
+            let digits = match v.contains(is_separator) {
+                true => Cow::Owned(v.chars().filter(|c| !is_separator(*c)).collect()),
+                false => Cow::Borrowed(v),
+            };
+            digits
+                .parse()
+                .map_err(|_| SiteParseError::BadCount(v.to_string()))
+        }
+    }
 }
 
-fn extract_table_row_info(tr: ElementRef) -> (Option<char>, Vec<usize>) {
-    let mut els = tr.select(&TD_SELECTOR);
-    let header = els.next().unwrap().text().collect::<Vec<_>>().concat();
-    let header_char = header.trim().chars().next();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WordCount, WordLength};
 
-    let mut items = Vec::new();
-    for el in els {
-        let text = el.text().collect::<Vec<_>>().concat();
-        let num = match text.trim() {
-            // This doesn't matter, and will get dropped just below anyway
-            "Σ" | "-" => 0,
-            v => v.parse().unwrap(),
-        };
-        items.push(num);
+    fn lengths(letters: &str) -> LengthInfo {
+        letters
+            .chars()
+            .map(|letter| ((letter, WordLength(4)), WordCount(1)))
+            .collect()
     }
 
-    // drop the "sum" item
-    items.truncate(items.len() - 1);
-    (header_char, items)
+    fn pairs(pairs: &[&str]) -> PairInfo {
+        pairs
+            .iter()
+            .map(|pair| {
+                let mut letters = pair.chars();
+                let pair = (letters.next().unwrap(), letters.next().unwrap());
+                (pair, WordCount(1))
+            })
+            .collect()
+    }
+
+    // A post holding the given tables, each a list of rows of cells with the
+    // row's letter first, followed by a two-letter list
+    fn page(tables: &[&[&[&str]]]) -> String {
+        let mut page = String::from("<div>");
+        page.push_str(&"<p class=\"content\"></p>".repeat(4));
+        for rows in tables {
+            page.push_str("<table class=\"table\">");
+            for cells in rows.iter() {
+                page.push_str("<tr class=\"row\">");
+                for cell in cells.iter() {
+                    page.push_str(&format!("<td class=\"cell\">{cell}</td>"));
+                }
+                page.push_str("</tr>");
+            }
+            page.push_str("</table>");
+        }
+        page.push_str("<p class=\"content\">AC-1</p></div>");
+        page
+    }
+
+    fn length(letter: char, length: usize) -> (char, WordLength) {
+        (letter, WordLength(length))
+    }
+
+    #[test]
+    fn complete_grids_pass() {
+        let stray = check_grid(&pairs(&["AC", "cl", "TA"]), &lengths("ACDELNT")).unwrap();
+        assert!(stray.is_empty());
+    }
+
+    #[test]
+    fn grids_missing_letters_fail() {
+        assert!(matches!(
+            check_grid(&pairs(&["AC"]), &lengths("ACDELN")),
+            Err(SiteParseError::WrongLetterCount(6))
+        ));
+        // Letters are counted whatever their case
+        assert!(check_grid(&pairs(&["AC"]), &lengths("ACDELNTa"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn pairs_without_a_row_are_listed_once() {
+        let stray = check_grid(&pairs(&["AC", "XA", "XE", "ZA"]), &lengths("ACDELNT")).unwrap();
+        assert_eq!(stray, ['X', 'Z']);
+    }
+
+    #[test]
+    fn cells_are_read_by_their_column_label() {
+        let page = page(&[&[
+            &["", "Σ", "5", "4"],
+            &["A:", "3", "1", "2"],
+            &["Σ:", "3", "1", "2"],
+        ]]);
+        let (_, lengths) = parse_content(&page).unwrap();
+        assert_eq!(
+            lengths,
+            LengthInfo::from([
+                (length('A', 4), WordCount(2)),
+                (length('A', 5), WordCount(1)),
+            ])
+        );
+    }
+
+    #[test]
+    fn rows_must_have_a_cell_per_column() {
+        let short = page(&[&[&["", "4", "5", "Σ"], &["A:", "2", "2"]]]);
+        assert!(matches!(
+            parse_content(&short),
+            Err(SiteParseError::RaggedRow('A'))
+        ));
+        let long = page(&[&[&["", "4", "Σ"], &["A:", "2", "1", "3"]]]);
+        assert!(matches!(
+            parse_content(&long),
+            Err(SiteParseError::RaggedRow('A'))
+        ));
+    }
+
+    #[test]
+    fn columns_must_be_labelled_with_a_length() {
+        let page = page(&[&[&["", "4", "five", "Σ"], &["A:", "2", "1", "3"]]]);
+        let options = ParseOptions::default().table_selector("table").unwrap();
+        assert!(matches!(
+            parse_content_with(&page, &options),
+            Err(SiteParseError::BadHeaderLabel(label)) if label == "five"
+        ));
+    }
+
+    #[test]
+    fn tables_without_lengths_in_their_header_are_passed_over() {
+        let recap: &[&[&str]] = &[&["", "Word", "Points"], &["A:", "2", "9"]];
+        let grid: &[&[&str]] = &[&["", "4", "Σ"], &["C:", "3", "3"]];
+        let (_, lengths) = parse_content(&page(&[recap, grid])).unwrap();
+        assert_eq!(lengths, LengthInfo::from([(length('C', 4), WordCount(3))]));
+
+        assert!(matches!(
+            parse_content(&page(&[recap])),
+            Err(SiteParseError::MissingTable)
+        ));
+    }
+
+    #[test]
+    fn the_first_grid_table_is_taken() {
+        let today: &[&[&str]] = &[&["", "4", "Σ"], &["A:", "1", "1"]];
+        let yesterday: &[&[&str]] = &[&["", "4", "Σ"], &["C:", "3", "3"]];
+        let (_, lengths) = parse_content(&page(&[today, yesterday])).unwrap();
+        assert_eq!(lengths, LengthInfo::from([(length('A', 4), WordCount(1))]));
+    }
+
+    #[cfg(feature = "fast-parse")]
+    #[test]
+    fn both_parsers_pass_over_the_same_tables() {
+        let recap: &[&[&str]] = &[&["", "Word", "Points"], &["A:", "2", "9"]];
+        let grid: &[&[&str]] = &[&["", "4", "Σ"], &["C:", "3", "3"]];
+        let page = page(&[recap, grid, grid]);
+        assert_eq!(
+            parse_content_streaming(&page).unwrap(),
+            parse_content(&page).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_table_selector_picks_the_table() {
+        let yesterday: &[&[&str]] = &[&["", "4", "Σ"], &["C:", "3", "3"]];
+        let today: &[&[&str]] = &[&["", "4", "Σ"], &["A:", "1", "1"]];
+        let options = ParseOptions::default()
+            .table_selector("table:nth-of-type(2)")
+            .unwrap();
+        let (_, lengths) = parse_content_with(&page(&[yesterday, today]), &options).unwrap();
+        assert_eq!(lengths, LengthInfo::from([(length('A', 4), WordCount(1))]));
+    }
+
+    #[test]
+    fn selectors_follow_renamed_markup() {
+        let page = page(&[&[&["", "4", "Σ"], &["A:", "1", "1"]]])
+            .replace("tr class=\"row\"", "tr class=\"line\"")
+            .replace("td class=\"cell\"", "td")
+            .replacen(
+                "<p class=\"content\">",
+                "<p class=\"content\">WORDS: 1, POINTS: 2, PANGRAMS: 0",
+                1,
+            )
+            .replace("class=\"content\"", "class=\"text\"");
+        assert!(parse_content(&page).is_err());
+
+        let options = ParseOptions::default()
+            .row_selector("tr.line")
+            .and_then(|o| o.cell_selector("td"))
+            .and_then(|o| o.paragraph_selector("p.text"))
+            .unwrap();
+        let (pairs, lengths) = parse_content_with(&page, &options).unwrap();
+        assert_eq!(lengths, LengthInfo::from([(length('A', 4), WordCount(1))]));
+        assert_eq!(pairs, PairInfo::from([(('A', 'C'), WordCount(1))]));
+        let summary = parse_summary_with(&page, &options).unwrap();
+        assert_eq!((summary.words, summary.points), (1, 2));
+    }
+
+    #[test]
+    fn bad_selectors_are_refused() {
+        assert!(matches!(
+            ParseOptions::default().cell_selector("td..cell"),
+            Err(SiteParseError::InvalidSelector(selector, _)) if selector == "td..cell"
+        ));
+    }
+
+    #[test]
+    fn cells_may_be_grouped() {
+        assert_eq!(parse_cell("-").unwrap(), 0);
+        assert_eq!(parse_cell(" 24 ").unwrap(), 24);
+        assert_eq!(parse_cell("1,024").unwrap(), 1024);
+        assert_eq!(parse_cell("1\u{2009}024").unwrap(), 1024);
+        assert_eq!(parse_cell("1\u{202f}024").unwrap(), 1024);
+        assert_eq!(parse_cell("1\u{a0}024").unwrap(), 1024);
+    }
+
+    #[test]
+    fn other_separators_are_refused() {
+        for cell in ["1.5", "1.024", "1'024", "1 024", "1_024"] {
+            assert!(
+                matches!(parse_cell(cell), Err(SiteParseError::BadCount(_))),
+                "{cell:?}"
+            );
+        }
+    }
 }
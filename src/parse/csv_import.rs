@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CsvImportError {
+    #[error("failed to read CSV: {0}")]
+    Reading(#[from] csv::Error),
+    #[error("line {line}: expected {expected} columns, got {found}")]
+    WrongColumnCount {
+        line: u64,
+        expected: usize,
+        found: usize,
+    },
+    #[error("line {line}: {value:?} is not a {what}")]
+    BadField {
+        line: u64,
+        value: String,
+        what: &'static str,
+    },
+    #[error("line {line}: has no column {column}")]
+    MissingColumn { line: u64, column: usize },
+    #[error("line {line}: {letter}{length} was already counted")]
+    Repeated {
+        line: u64,
+        letter: char,
+        length: usize,
+    },
+    #[error("no date column was mapped, and the file's name has no YYYY-MM-DD date")]
+    Undated,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid column map {0:?}, expected e.g. letter=1,length=2,count=3 with an optional date=N"
+)]
+pub struct ColumnMapError(String);
+
+/// Which column of a hand-made CSV holds what, written
+/// `letter=1,length=2,count=3`, counting columns from 1. A `date` column
+/// is optional: without one, each file is one day, dated by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub letter: usize,
+    pub length: usize,
+    pub count: usize,
+    pub date: Option<usize>,
+}
+
+impl FromStr for ColumnMap {
+    type Err = ColumnMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad_map = || ColumnMapError(s.to_string());
+        let mut columns = HashMap::new();
+        for part in s.split(',') {
+            let (name, column) = part.split_once('=').ok_or_else(bad_map)?;
+            let column = column
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|column| *column > 0)
+                .ok_or_else(bad_map)?;
+            let name = name.trim();
+            if !matches!(name, "letter" | "length" | "count" | "date")
+                || columns.insert(name, column - 1).is_some()
+            {
+                return Err(bad_map());
+            }
+        }
+
+        Ok(Self {
+            letter: *columns.get("letter").ok_or_else(bad_map)?,
+            length: *columns.get("length").ok_or_else(bad_map)?,
+            count: *columns.get("count").ok_or_else(bad_map)?,
+            date: columns.get("date").copied(),
+        })
+    }
+}
+
+/// Loads letter and length counts from a hand-made CSV laid out as `map`
+/// says, by day. Rows without a date column are all for `file_date`, and
+/// other columns are ignored.
+///
+/// A header row is skipped, as with [`from_csv`], as are blank rows.
+/// Letters are upper-cased, as they're kept.
+pub fn from_mapped_csv(
+    path: &Path,
+    map: &ColumnMap,
+    delimiter: u8,
+    file_date: Option<NaiveDate>,
+) -> Result<BTreeMap<NaiveDate, LengthInfo>, CsvImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_path(path)?;
+
+    let mut days = BTreeMap::<NaiveDate, LengthInfo>::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |column: usize| {
+            record.get(column).ok_or(CsvImportError::MissingColumn {
+                line,
+                column: column + 1,
+            })
+        };
+        let count = field(map.count)?;
+        if i == 0 && count.trim().parse::<usize>().is_err() {
+            continue;
+        }
+
+        let date = match map.date {
+            Some(column) => {
+                let date = field(column)?;
+                date.trim()
+                    .parse()
+                    .map_err(|_| bad_field(line, date, "date"))?
+            }
+            None => file_date.ok_or(CsvImportError::Undated)?,
+        };
+        let letter = field(map.letter)?;
+        let letter = single_char(line, letter, "letter")
+            .ok()
+            .filter(char::is_ascii_alphabetic)
+            .ok_or_else(|| bad_field(line, letter, "letter"))?
+            .to_ascii_uppercase();
+        let length = number(line, field(map.length)?, "length")?;
+        let count = number(line, count, "count")?;
+        let previous = days
+            .entry(date)
+            .or_default()
+            .insert((letter, WordLength(length)), WordCount(count));
+        if previous.is_some() {
+            return Err(CsvImportError::Repeated {
+                line,
+                letter,
+                length,
+            });
+        }
+    }
+
+    Ok(days)
+}
+
+/// Loads grid data back from a pair of CSV files, as written by older
+/// versions of gridder.
+///
+/// The lengths file has `letter,length,count` rows and the pairs file has
+/// `pair,count` rows (e.g. `ab,3`). A leading header row is skipped if
+/// present.
+pub fn from_csv<P, Q>(
+    lengths_path: P,
+    pairs_path: Q,
+) -> Result<(PairInfo, LengthInfo), CsvImportError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let lengths = read_rows(lengths_path.as_ref(), 3)?
+        .into_iter()
+        .map(|(line, row)| {
+            let letter = single_char(line, &row[0], "letter")?;
+            let length = number(line, &row[1], "length")?;
+            let count = number(line, &row[2], "count")?;
+            Ok(((letter, WordLength(length)), WordCount(count)))
+        })
+        .collect::<Result<HashMap<_, _>, CsvImportError>>()?;
+
+    let pairs = read_rows(pairs_path.as_ref(), 2)?
+        .into_iter()
+        .map(|(line, row)| {
+            let mut chars = row[0].trim().chars();
+            let pair = match (chars.next(), chars.next(), chars.next()) {
+                (Some(a), Some(b), None) => (a, b),
+                _ => return Err(bad_field(line, &row[0], "two-letter pair")),
+            };
+            let count = number(line, &row[1], "count")?;
+            Ok((pair, WordCount(count)))
+        })
+        .collect::<Result<HashMap<_, _>, CsvImportError>>()?;
+
+    Ok((pairs, lengths))
+}
+
+fn read_rows(path: &Path, columns: usize) -> Result<Vec<(u64, csv::StringRecord)>, CsvImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)?;
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        if record.len() != columns {
+            return Err(CsvImportError::WrongColumnCount {
+                line,
+                expected: columns,
+                found: record.len(),
+            });
+        }
+
+        // Header rows are the only ones whose last column isn't a count
+        if i == 0 && record[columns - 1].trim().parse::<usize>().is_err() {
+            continue;
+        }
+
+        rows.push((line, record));
+    }
+
+    Ok(rows)
+}
+
+fn single_char(line: u64, value: &str, what: &'static str) -> Result<char, CsvImportError> {
+    let mut chars = value.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(bad_field(line, value, what)),
+    }
+}
+
+fn number(line: u64, value: &str, what: &'static str) -> Result<usize, CsvImportError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| bad_field(line, value, what))
+}
+
+fn bad_field(line: u64, value: &str, what: &'static str) -> CsvImportError {
+    CsvImportError::BadField {
+        line,
+        value: value.to_string(),
+        what,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: ColumnMap = ColumnMap {
+        letter: 0,
+        length: 1,
+        count: 2,
+        date: None,
+    };
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    // Imports `contents` from a file of its own
+    fn import(
+        name: &str,
+        contents: &str,
+        map: &ColumnMap,
+        delimiter: u8,
+        file_date: Option<NaiveDate>,
+    ) -> Result<BTreeMap<NaiveDate, LengthInfo>, CsvImportError> {
+        let path = std::env::temp_dir().join(format!("gridder-{name}-{}.csv", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let imported = from_mapped_csv(&path, map, delimiter, file_date);
+        let _ = std::fs::remove_file(&path);
+        imported
+    }
+
+    #[test]
+    fn column_maps_name_each_column() {
+        let map = "count=3, letter=1,length=2".parse::<ColumnMap>().unwrap();
+        assert_eq!(map, MAP);
+        let map = "date=4,letter=1,length=2,count=3".parse::<ColumnMap>();
+        assert_eq!(map.unwrap().date, Some(3));
+
+        for bad in [
+            "letter=1,length=2",
+            "letter=0,length=2,count=3",
+            "letter=1,length=2,count=3,count=4",
+            "letter=1,length=2,count=3,points=4",
+            "letter=1,length=2,count",
+            "",
+        ] {
+            assert!(bad.parse::<ColumnMap>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn files_without_a_date_column_are_one_day() {
+        let contents = "Letter;Length;Count\na;4;2\n\nA;5;1\n";
+        let days = import("one-day", contents, &MAP, b';', Some(date(1))).unwrap();
+        assert_eq!(
+            days,
+            BTreeMap::from([(
+                date(1),
+                LengthInfo::from([
+                    (('A', WordLength(4)), WordCount(2)),
+                    (('A', WordLength(5)), WordCount(1)),
+                ])
+            )])
+        );
+
+        assert!(matches!(
+            import("undated", contents, &MAP, b';', None),
+            Err(CsvImportError::Undated)
+        ));
+    }
+
+    #[test]
+    fn a_date_column_splits_the_rows_by_day() {
+        let map = ColumnMap {
+            date: Some(0),
+            letter: 2,
+            length: 3,
+            count: 1,
+        };
+        let contents = "2024-05-01,2,A,4,note\n2024-05-02,3,C,4\n";
+        let days = import("dated", contents, &map, b',', Some(date(9))).unwrap();
+        assert_eq!(days.keys().copied().collect::<Vec<_>>(), [date(1), date(2)]);
+        assert_eq!(days[&date(2)][&('C', WordLength(4))], WordCount(3));
+    }
+
+    #[test]
+    fn mistakes_give_their_line() {
+        let failures = [
+            ("A,4,2\nA,4,3\n", 2, "repeated"),
+            ("A,4,2\nAB,4,3\n", 2, "letter"),
+            ("A,4,2\nC,four,3\n", 2, "length"),
+            ("A,4,2\nC,4\n", 2, "column"),
+        ];
+        for (contents, expected, what) in failures {
+            let line = match import("mistakes", contents, &MAP, b',', Some(date(1))) {
+                Err(CsvImportError::Repeated { line, .. })
+                | Err(CsvImportError::BadField { line, .. })
+                | Err(CsvImportError::MissingColumn { line, .. }) => line,
+                other => panic!("expected a bad {what}, got {other:?}"),
+            };
+            assert_eq!(line, expected, "{what}");
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::path::Path;
+
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+
+use super::{is_grid_table, ParseOptions, SUMMARY_REGEX, TABLE_SELECTOR, TWO_LETTER_REGEX};
+
+// How many past runs to judge a new page against
+const HISTORY_LEN: usize = 14;
+// Fewer runs than this aren't enough to call anything normal
+const MIN_HISTORY: usize = 3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FingerprintError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid fingerprint history: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A rough outline of how a page is laid out, for noticing when that
+/// changes even though the page still parses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub tables: usize,
+    pub grid_tables: usize,
+    /// Rows across every table. The number of columns isn't counted, as it
+    /// follows the day's longest word.
+    pub rows: usize,
+    /// One character per paragraph, in page order: `S` for the summary,
+    /// `P` for the two-letter list, and `-` for anything else.
+    pub paragraphs: String,
+}
+
+impl Fingerprint {
+    pub fn of_page(body: &str, options: &ParseOptions) -> Self {
+        let page = Html::parse_document(body);
+        let table_selector = options.table_selector.as_ref().unwrap_or(&TABLE_SELECTOR);
+
+        let mut fingerprint = Self {
+            tables: 0,
+            grid_tables: 0,
+            rows: 0,
+            paragraphs: String::new(),
+        };
+        for table in page.select(table_selector) {
+            fingerprint.tables += 1;
+            if is_grid_table(table, options) {
+                fingerprint.grid_tables += 1;
+            }
+            fingerprint.rows += table.select(&options.row_selector).count();
+        }
+        fingerprint.paragraphs = page
+            .select(&options.paragraph_selector)
+            .map(|p| {
+                let text = p.text().collect::<String>();
+                if SUMMARY_REGEX.is_match(&text) {
+                    'S'
+                } else if TWO_LETTER_REGEX.is_match(&text) {
+                    'P'
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+
+        fingerprint
+    }
+
+    /// Describes how this differs from `other`, one item per changed field.
+    pub fn differences(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        let mut compare = |what: &str, ours: &dyn fmt::Display, theirs: &dyn fmt::Display| {
+            let (ours, theirs) = (ours.to_string(), theirs.to_string());
+            if ours != theirs {
+                differences.push(format!("{what}: {theirs} -> {ours}"));
+            }
+        };
+        compare("tables", &self.tables, &other.tables);
+        compare("grid tables", &self.grid_tables, &other.grid_tables);
+        compare("table rows", &self.rows, &other.rows);
+        compare("paragraphs", &self.paragraphs, &other.paragraphs);
+
+        differences
+    }
+}
+
+/// The fingerprints of recent pages, kept in a JSON file between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FingerprintHistory {
+    recent: Vec<Fingerprint>,
+}
+
+impl FingerprintHistory {
+    /// Loads the history, starting a new one if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, FingerprintError> {
+        match std::fs::read(path) {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), FingerprintError> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The most common recent fingerprint, once there's enough history to
+    /// say what's normal.
+    pub fn norm(&self) -> Option<&Fingerprint> {
+        if self.recent.len() < MIN_HISTORY {
+            return None;
+        }
+
+        self.recent
+            .iter()
+            .max_by_key(|candidate| self.recent.iter().filter(|f| f == candidate).count())
+    }
+
+    pub fn record(&mut self, fingerprint: Fingerprint) {
+        self.recent.push(fingerprint);
+        let excess = self.recent.len().saturating_sub(HISTORY_LEN);
+        self.recent.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_PAGE: &str = include_str!("../../fixtures/sample.html");
+
+    fn fingerprint(rows: usize) -> Fingerprint {
+        Fingerprint {
+            tables: 1,
+            grid_tables: 1,
+            rows,
+            paragraphs: "--S-P".to_string(),
+        }
+    }
+
+    #[test]
+    fn sample_page_is_outlined() {
+        let fingerprint = Fingerprint::of_page(SAMPLE_PAGE, &ParseOptions::default());
+        assert_eq!(
+            (
+                fingerprint.tables,
+                fingerprint.grid_tables,
+                fingerprint.rows
+            ),
+            (1, 1, 9)
+        );
+        assert_eq!(fingerprint.paragraphs.matches('S').count(), 1);
+        assert_eq!(fingerprint.paragraphs.matches('P').count(), 1);
+        assert!(fingerprint.paragraphs.find('S') < fingerprint.paragraphs.find('P'));
+    }
+
+    #[test]
+    fn only_changed_fields_are_described() {
+        assert!(fingerprint(9).differences(&fingerprint(9)).is_empty());
+
+        let mut changed = fingerprint(8);
+        changed.tables = 2;
+        assert_eq!(
+            changed.differences(&fingerprint(9)),
+            ["tables: 1 -> 2", "table rows: 9 -> 8"]
+        );
+    }
+
+    #[test]
+    fn the_norm_needs_a_few_runs() {
+        let mut history = FingerprintHistory::default();
+        history.record(fingerprint(9));
+        history.record(fingerprint(9));
+        assert_eq!(history.norm(), None);
+        history.record(fingerprint(8));
+        assert_eq!(history.norm(), Some(&fingerprint(9)));
+    }
+
+    #[test]
+    fn only_recent_runs_are_kept() {
+        let mut history = FingerprintHistory::default();
+        for _ in 0..HISTORY_LEN {
+            history.record(fingerprint(9));
+        }
+        for _ in 0..HISTORY_LEN / 2 + 1 {
+            history.record(fingerprint(8));
+        }
+        assert_eq!(history.recent.len(), HISTORY_LEN);
+        assert_eq!(history.norm(), Some(&fingerprint(8)));
+    }
+}
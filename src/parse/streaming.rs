@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+use lol_html::html_content::{Element, TextChunk};
+use lol_html::{ElementContentHandlers, HtmlRewriter, Selector, Settings};
+
+use super::{
+    are_length_labels, lengths_from_rows, pairs_from_text, ParseOptions, SiteParseError, TABLE_CSS,
+};
+use crate::{LengthInfo, PairInfo};
+
+// Cell text of each row, header cell first
+type Rows = Vec<Vec<String>>;
+
+#[derive(Default)]
+struct Collected {
+    tables: Vec<Rows>,
+    paragraphs: Vec<String>,
+}
+
+impl Collected {
+    fn rows(&mut self) -> Option<&mut Rows> {
+        self.tables.last_mut()
+    }
+}
+
+/// Extracts the same data as [`super::parse_content`] from a single pass over
+/// the token stream, only keeping the text of the cells and paragraphs it
+/// needs rather than building a DOM of the whole page.
+///
+/// Unlike the DOM-based parser, paragraphs are counted from the top of the
+/// page rather than from the table's parent element.
+pub fn parse_content_streaming(body: &str) -> Result<(PairInfo, LengthInfo), SiteParseError> {
+    parse_content_streaming_with(body, &ParseOptions::default())
+}
+
+/// [`parse_content_streaming`] with the selectors from `options`. They're
+/// compiled again for the tokenizer, which only supports some of CSS (e.g.
+/// no `+` or `~`), so a selector [`ParseOptions`] took may be refused here.
+pub fn parse_content_streaming_with(
+    body: &str,
+    options: &ParseOptions,
+) -> Result<(PairInfo, LengthInfo), SiteParseError> {
+    let sources = &options.sources;
+    let table_css = sources.table.as_deref().unwrap_or(TABLE_CSS);
+    let row_css = within(table_css, &sources.row);
+    let cell_css = within(&row_css, &sources.cell);
+    let collected = RefCell::new(Collected::default());
+
+    let handlers = vec![
+        (
+            compile(table_css)?,
+            ElementContentHandlers::default().element(|_: &mut Element| {
+                collected.borrow_mut().tables.push(Vec::new());
+                Ok(())
+            }),
+        ),
+        (
+            compile(&row_css)?,
+            ElementContentHandlers::default().element(|_: &mut Element| {
+                if let Some(rows) = collected.borrow_mut().rows() {
+                    rows.push(Vec::new());
+                }
+                Ok(())
+            }),
+        ),
+        (
+            compile(&cell_css)?,
+            ElementContentHandlers::default()
+                .element(|_: &mut Element| {
+                    if let Some(row) = collected.borrow_mut().rows().and_then(|r| r.last_mut()) {
+                        row.push(String::new());
+                    }
+                    Ok(())
+                })
+                .text(|chunk: &mut TextChunk| {
+                    let mut c = collected.borrow_mut();
+                    if let Some(cell) = c
+                        .rows()
+                        .and_then(|r| r.last_mut())
+                        .and_then(|r| r.last_mut())
+                    {
+                        cell.push_str(chunk.as_str());
+                    }
+                    Ok(())
+                }),
+        ),
+        (
+            compile(&sources.paragraph)?,
+            ElementContentHandlers::default()
+                .element(|_: &mut Element| {
+                    collected.borrow_mut().paragraphs.push(String::new());
+                    Ok(())
+                })
+                .text(|chunk: &mut TextChunk| {
+                    if let Some(p) = collected.borrow_mut().paragraphs.last_mut() {
+                        p.push_str(chunk.as_str());
+                    }
+                    Ok(())
+                }),
+        ),
+    ];
+
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            element_content_handlers: handlers,
+            ..Settings::new()
+        },
+        // We only want to observe the page, not rewrite it
+        |_: &[u8]| {},
+    );
+
+    rewriter.write(body.as_bytes())?;
+    rewriter.end()?;
+
+    let collected = collected.into_inner();
+    // As with the DOM parser, a table that was picked out is taken as it is,
+    // and otherwise it's the first one that's labelled like a grid
+    let table = match sources.table {
+        Some(_) => collected.tables.first(),
+        None => collected.tables.iter().find(|rows| {
+            rows.first()
+                .and_then(|header| header.get(1..))
+                .is_some_and(are_length_labels)
+        }),
+    }
+    .ok_or(SiteParseError::MissingTable)?;
+
+    let pairs_text = collected
+        .paragraphs
+        .get(4)
+        .ok_or(SiteParseError::MissingPairList)?;
+    let pairs = pairs_from_text(pairs_text)?;
+
+    let rows = table.iter().map(|cells| {
+        let header = cells.first().ok_or(SiteParseError::EmptyRow)?;
+        let header_char = header.chars().find(|c| !c.is_whitespace());
+        let cells = cells[1..].iter().map(|cell| Cow::Borrowed(cell.as_str()));
+        Ok((header_char, cells.collect()))
+    });
+    let lengths = lengths_from_rows(rows)?;
+
+    Ok((pairs, lengths))
+}
+
+fn compile(css: &str) -> Result<Cow<'static, Selector>, SiteParseError> {
+    css.parse()
+        .map(Cow::Owned)
+        .map_err(|e: lol_html::errors::SelectorError| {
+            SiteParseError::InvalidSelector(css.to_string(), e.to_string())
+        })
+}
+
+// A selector for `inner` elements inside `outer` ones. Each is split on its
+// commas, so lists come out as every pairing of their parts.
+fn within(outer: &str, inner: &str) -> String {
+    let mut parts = Vec::new();
+    for outer in outer.split(',') {
+        for inner in inner.split(',') {
+            parts.push(format!("{} {}", outer.trim(), inner.trim()));
+        }
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_content_with;
+
+    const SAMPLE_PAGE: &str = include_str!("../../fixtures/sample.html");
+
+    #[test]
+    fn sample_page_parses_the_same_both_ways() {
+        let options = ParseOptions::default();
+        let streamed = parse_content_streaming_with(SAMPLE_PAGE, &options).unwrap();
+        assert_eq!(streamed, parse_content_with(SAMPLE_PAGE, &options).unwrap());
+    }
+
+    #[test]
+    fn custom_selectors_parse_the_same_both_ways() {
+        let options = ParseOptions::default()
+            .table_selector("div.interactive-content > table")
+            .and_then(|o| o.row_selector("tr"))
+            .and_then(|o| o.cell_selector("td.cell, th.cell"))
+            .and_then(|o| o.paragraph_selector("div p.content"))
+            .unwrap();
+        let streamed = parse_content_streaming_with(SAMPLE_PAGE, &options).unwrap();
+        assert_eq!(streamed, parse_content_with(SAMPLE_PAGE, &options).unwrap());
+    }
+
+    #[test]
+    fn unsupported_selectors_are_refused() {
+        let options = ParseOptions::default().row_selector("tr + tr").unwrap();
+        assert!(matches!(
+            parse_content_streaming_with(SAMPLE_PAGE, &options),
+            Err(SiteParseError::InvalidSelector(..))
+        ));
+    }
+
+    #[test]
+    fn lists_are_nested_part_by_part() {
+        assert_eq!(within("table", "tr.a, tr.b"), "table tr.a, table tr.b");
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::encryption::{self, Cipher, EncryptionError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("failed to read {0}: {1}")]
+    Reading(PathBuf, std::io::Error),
+    #[error("failed to decrypt {0}: {1}")]
+    Decrypting(PathBuf, EncryptionError),
+    #[error("{0} isn't UTF-8")]
+    NotText(PathBuf),
+    #[error("{path}, line {line}: {reason}")]
+    Parsing {
+        path: PathBuf,
+        line: usize,
+        reason: &'static str,
+    },
+    #[error("no [profile.{0}] in {1}")]
+    UnknownProfile(String, PathBuf),
+    #[error("profile {profile} sets {key}, which isn't a gridder option")]
+    UnknownSetting { profile: String, key: String },
+    #[error("no config file to read profiles from (set GRIDDER_CONFIG or HOME)")]
+    NoConfigFile,
+}
+
+/// A setting's value: one value, or several for options that can be
+/// repeated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Named sets of settings, so one machine can run gridder for several
+/// groups, each with its own spreadsheet, notifiers and files.
+///
+/// They're read from a config file of `[profile.NAME]` sections, each
+/// setting options by their long names:
+///
+/// ```toml
+/// [profile.family]
+/// spreadsheet-id = "1AbC..."
+/// service-account-file = "/etc/gridder/family.json"
+/// grid-dir = "/var/lib/gridder/family"
+/// session-cookie-file = ["/etc/gridder/a.cookie", "/etc/gridder/b.cookie"]
+/// stats = true
+/// ```
+///
+/// Values are strings, bare words (e.g. `true` or `60`), or lists of
+/// strings for options that can be repeated. `#` starts a comment.
+///
+/// The file can be encrypted (see `gridder encrypt-file`), to keep the
+/// secrets in it from others on the machine.
+#[derive(Debug, Clone, Default)]
+pub struct Profiles {
+    path: PathBuf,
+    profiles: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl Profiles {
+    /// Reads profiles from `path`, decrypting it with `cipher` if it's
+    /// encrypted.
+    pub fn load(path: &Path, cipher: Option<&Cipher>) -> Result<Self, ProfileError> {
+        let contents =
+            std::fs::read(path).map_err(|e| ProfileError::Reading(path.to_path_buf(), e))?;
+        let contents = encryption::decrypt_if_needed(contents, cipher)
+            .map_err(|e| ProfileError::Decrypting(path.to_path_buf(), e))?;
+        let contents =
+            String::from_utf8(contents).map_err(|_| ProfileError::NotText(path.to_path_buf()))?;
+        Self::parse(path, &contents)
+    }
+
+    /// Reads profiles from `contents`, as read from `path`.
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, ProfileError> {
+        let mut profiles = BTreeMap::<String, BTreeMap<String, Value>>::new();
+        let mut current = None;
+        for (index, line) in contents.lines().enumerate() {
+            let failed = |reason| ProfileError::Parsing {
+                path: path.to_path_buf(),
+                line: index + 1,
+                reason,
+            };
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .and_then(|header| header.trim().strip_prefix("profile."))
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| failed("sections must be [profile.NAME]"))?;
+                profiles.entry(name.to_string()).or_default();
+                current = Some(name.to_string());
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| failed("expected a setting, e.g. grid-dir = \"/srv/grids\""))?;
+            let profile = current
+                .as_ref()
+                .ok_or_else(|| failed("settings must come under a [profile.NAME] section"))?;
+            let key = key.trim().replace('_', "-");
+            let value = parse_value(value.trim()).ok_or_else(|| failed("invalid value"))?;
+            profiles
+                .entry(profile.clone())
+                .or_default()
+                .insert(key, value);
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            profiles,
+        })
+    }
+
+    /// The settings of the profile called `name`, by option name.
+    pub fn get(&self, name: &str) -> Result<&BTreeMap<String, Value>, ProfileError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| ProfileError::UnknownProfile(name.to_string(), self.path.clone()))
+    }
+}
+
+// A `#` outside quotes starts a comment
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    if let Some(list) = value.strip_prefix('[') {
+        let list = list.strip_suffix(']')?.trim();
+        let mut values = Vec::new();
+        let mut rest = list;
+        while !rest.is_empty() {
+            let (value, after) = parse_string(rest)?;
+            values.push(value);
+            rest = after.trim_start();
+            rest = match rest.strip_prefix(',') {
+                Some(after) => after.trim_start(),
+                None if rest.is_empty() => rest,
+                None => return None,
+            };
+        }
+        return Some(Value::Many(values));
+    }
+    if value.starts_with('"') {
+        let (value, rest) = parse_string(value)?;
+        return rest.trim().is_empty().then_some(Value::One(value));
+    }
+
+    let bare = !value.is_empty() && !value.contains(char::is_whitespace);
+    bare.then(|| Value::One(value.to_string()))
+}
+
+// A quoted string at the start of `s`, and what's after it
+fn parse_string(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut value = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &s[index + 2..])),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                c @ ('"' | '\\') => value.push(c),
+                _ => return None,
+            },
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+/// Where profiles are read from if no config file is given:
+/// `$XDG_CONFIG_HOME/gridder/config.toml`, or under `~/.config` if that's
+/// unset.
+pub fn default_config_file() -> Result<PathBuf, ProfileError> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .ok_or(ProfileError::NoConfigFile)?;
+
+    Ok(config_home.join("gridder").join("config.toml"))
+}
+
+/// The value of the option `--{long}` in a command line, before it's
+/// parsed properly, for options that decide how it's parsed. Falls back to
+/// the environment variable `env`.
+pub fn find_option(argv: &[OsString], long: &str, env: &str) -> Option<OsString> {
+    let flag = format!("--{long}");
+    let prefix = format!("--{long}=");
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        if arg == "--" {
+            break;
+        }
+        if arg == flag {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.into());
+        }
+    }
+
+    std::env::var_os(env).filter(|value| !value.is_empty())
+}
+
+/// Gives the options a profile's settings name those values, everywhere
+/// they appear in `command`, unless the command line or the environment
+/// gives them already.
+///
+/// Options read from an environment variable get their value through it,
+/// so that it counts as given (e.g. for options that need others); the
+/// rest get it as their default. As this sets environment variables, it
+/// must be called before the program starts any threads.
+pub fn apply(
+    command: clap::Command,
+    profile: &str,
+    settings: &BTreeMap<String, Value>,
+) -> Result<clap::Command, ProfileError> {
+    let mut used = BTreeSet::new();
+    let mut env = BTreeMap::new();
+    let command = apply_to(command, settings, &mut used, &mut env);
+    if let Some(key) = settings.keys().find(|key| !used.contains(*key)) {
+        return Err(ProfileError::UnknownSetting {
+            profile: profile.to_string(),
+            key: key.clone(),
+        });
+    }
+    env.retain(|name, _| std::env::var_os(name).is_none());
+    for (name, value) in &env {
+        std::env::set_var(name, value);
+    }
+
+    Ok(reread_env(command, &env))
+}
+
+// clap reads environment variables as options are made, so those set
+// since need reading again
+fn reread_env(command: clap::Command, env: &BTreeMap<OsString, String>) -> clap::Command {
+    let command = command.mut_args(|arg| match arg.get_env() {
+        Some(name) if env.contains_key(name) => {
+            let name = name.to_string_lossy().into_owned().leak();
+            arg.env(&*name)
+        }
+        _ => arg,
+    });
+
+    command.mut_subcommands(|subcommand| reread_env(subcommand, env))
+}
+
+fn apply_to(
+    command: clap::Command,
+    settings: &BTreeMap<String, Value>,
+    used: &mut BTreeSet<String>,
+    env: &mut BTreeMap<OsString, String>,
+) -> clap::Command {
+    for arg in command.get_arguments() {
+        if let Some(long) = arg.get_long().filter(|long| settings.contains_key(*long)) {
+            used.insert(long.to_string());
+        }
+    }
+    let command = command.mut_args(|arg| {
+        let Some(value) = arg.get_long().and_then(|long| settings.get(long)) else {
+            return arg;
+        };
+        if let (Some(name), Value::One(value)) = (arg.get_env(), value) {
+            env.insert(name.to_os_string(), value.clone());
+            return arg;
+        }
+        // clap only takes defaults that live as long as the program, which
+        // these do anyway
+        let leak = |value: &String| -> &'static str { value.clone().leak() };
+        // A required option isn't needed once the profile gives it
+        let arg = arg.required(false);
+        match value {
+            Value::One(value) => arg.default_value(leak(value)),
+            Value::Many(values) => arg.default_values(values.iter().map(leak)),
+        }
+    });
+
+    command.mut_subcommands(|subcommand| apply_to(subcommand, settings, used, env))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Shared by the family
+[profile.family]
+spreadsheet-id = "1AbC#def"  # not part of the ID
+grid_dir = /srv/grids
+session-cookie-file = ["a.cookie", "b \"quoted\".cookie"]
+
+[profile.work]
+stats = true
+"#;
+
+    fn parse(contents: &str) -> Result<Profiles, ProfileError> {
+        Profiles::parse(Path::new("config.toml"), contents)
+    }
+
+    fn one(value: &str) -> Value {
+        Value::One(value.to_string())
+    }
+
+    #[test]
+    fn profiles_are_read_by_section() {
+        let profiles = parse(CONFIG).unwrap();
+        let family = profiles.get("family").unwrap();
+        assert_eq!(family["spreadsheet-id"], one("1AbC#def"));
+        assert_eq!(family["grid-dir"], one("/srv/grids"));
+        assert_eq!(
+            family["session-cookie-file"],
+            Value::Many(vec![
+                "a.cookie".to_string(),
+                "b \"quoted\".cookie".to_string()
+            ])
+        );
+        assert_eq!(profiles.get("work").unwrap()["stats"], one("true"));
+        assert!(matches!(
+            profiles.get("club"),
+            Err(ProfileError::UnknownProfile(name, _)) if name == "club"
+        ));
+    }
+
+    #[test]
+    fn mistakes_give_their_line() {
+        let mistakes = [
+            ("[family]", 1),
+            ("stats = true", 1),
+            ("[profile.a]\n\ngrid-dir", 3),
+            ("[profile.a]\ngrid-dir = two words", 2),
+            ("[profile.a]\ngrid-dir = \"unterminated", 2),
+            ("[profile.a]\nlist = [\"a\" \"b\"]", 2),
+        ];
+        for (contents, expected) in mistakes {
+            match parse(contents) {
+                Err(ProfileError::Parsing { line, .. }) => {
+                    assert_eq!(line, expected, "{contents:?}")
+                }
+                other => panic!("expected {contents:?} to fail, got {other:?}"),
+            }
+        }
+    }
+
+    fn argv(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn options_are_found_before_parsing() {
+        let env = "GRIDDER_TEST_FIND_OPTION";
+        let found = |args: &[&str]| find_option(&argv(args), "profile", env);
+        assert_eq!(
+            found(&["gridder", "--profile", "a", "run"]),
+            Some("a".into())
+        );
+        assert_eq!(found(&["gridder", "run", "--profile=b"]), Some("b".into()));
+        assert_eq!(found(&["gridder", "exec", "--", "--profile", "c"]), None);
+        assert_eq!(found(&["--profile", "gridder"]), None);
+    }
+
+    fn command() -> clap::Command {
+        clap::Command::new("gridder").subcommand(
+            clap::Command::new("run")
+                .arg(clap::Arg::new("grid-dir").long("grid-dir").required(true))
+                .arg(
+                    clap::Arg::new("cookie")
+                        .long("session-cookie-file")
+                        .action(clap::ArgAction::Append),
+                ),
+        )
+    }
+
+    #[test]
+    fn settings_become_defaults() {
+        let profiles = parse(CONFIG).unwrap();
+        let settings = profiles.get("family").unwrap();
+        let mut settings = settings.clone();
+        settings.remove("spreadsheet-id");
+        let matches = apply(command(), "family", &settings)
+            .unwrap()
+            .try_get_matches_from(["gridder", "run"])
+            .unwrap();
+        let run = matches.subcommand_matches("run").unwrap();
+        assert_eq!(run.get_one::<String>("grid-dir").unwrap(), "/srv/grids");
+        assert_eq!(run.get_many::<String>("cookie").unwrap().count(), 2);
+
+        let matches = apply(command(), "family", &settings)
+            .unwrap()
+            .try_get_matches_from(["gridder", "run", "--grid-dir", "/tmp"])
+            .unwrap();
+        let run = matches.subcommand_matches("run").unwrap();
+        assert_eq!(run.get_one::<String>("grid-dir").unwrap(), "/tmp");
+    }
+
+    #[test]
+    fn settings_for_no_option_are_refused() {
+        let profiles = parse(CONFIG).unwrap();
+        assert!(matches!(
+            apply(command(), "family", profiles.get("family").unwrap()),
+            Err(ProfileError::UnknownSetting { key, .. }) if key == "spreadsheet-id"
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+
+use crate::document::GridDocument;
+use crate::i18n::{Lang, Message};
+use crate::score;
+use crate::theme::{Role, Theme};
+use crate::{WordCount, WordLength};
+
+/// How far a group has got through a day's grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Distinct found words that fit the grid.
+    pub found: Vec<String>,
+    pub total_words: usize,
+    pub points: usize,
+    /// Counts still to find, keyed by (first letter, length). Only non-zero
+    /// entries are kept.
+    pub remaining_lengths: BTreeMap<(char, WordLength), WordCount>,
+    /// Counts still to find, keyed by two-letter prefix. Only non-zero
+    /// entries are kept.
+    pub remaining_pairs: BTreeMap<String, WordCount>,
+    /// Words that don't fit anywhere in the grid (typos, or more words in a
+    /// slot than the grid allows).
+    pub unmatched: Vec<String>,
+}
+
+/// Compares found words against the grid. Words are matched
+/// case-insensitively; blank lines and repeats are ignored.
+pub fn compute<S: AsRef<str>>(doc: &GridDocument, words: &[S]) -> Progress {
+    let mut remaining_lengths = doc
+        .lengths
+        .iter()
+        .map(|e| ((e.letter.to_ascii_uppercase(), e.length), e.count))
+        .collect::<BTreeMap<_, _>>();
+    let mut remaining_pairs = doc
+        .pairs
+        .iter()
+        .map(|e| (e.pair.to_ascii_uppercase(), e.count))
+        .collect::<BTreeMap<_, _>>();
+
+    let words = words
+        .iter()
+        .map(|w| w.as_ref().trim().to_ascii_uppercase())
+        .filter(|w| !w.is_empty())
+        .collect::<BTreeSet<_>>();
+
+    let mut found = Vec::new();
+    let mut unmatched = Vec::new();
+    for word in words {
+        let first = word.chars().next().unwrap_or_default();
+        let length = WordLength(word.chars().count());
+        let pair = word.chars().take(2).collect::<String>();
+
+        let length_slot = remaining_lengths
+            .get_mut(&(first, length))
+            .filter(|c| c.0 > 0);
+        let pair_slot = remaining_pairs.get_mut(&pair).filter(|c| c.0 > 0);
+        match (length_slot, pair_slot) {
+            (Some(length_count), Some(pair_count)) => {
+                length_count.0 -= 1;
+                pair_count.0 -= 1;
+                found.push(word);
+            }
+            _ => unmatched.push(word),
+        }
+    }
+
+    remaining_lengths.retain(|_, count| count.0 > 0);
+    remaining_pairs.retain(|_, count| count.0 > 0);
+
+    Progress {
+        points: score::total_score(&found, &doc.letters),
+        found,
+        total_words: doc.totals.words,
+        remaining_lengths,
+        remaining_pairs,
+        unmatched,
+    }
+}
+
+impl Progress {
+    /// The same as the `Display` output, in `lang` and colored with
+    /// `theme`.
+    pub fn render(&self, theme: Theme, lang: Lang) -> String {
+        // Writing to a String can't fail
+        let mut out = String::new();
+        let found = Message::FoundWords {
+            found: self.found.len(),
+            total: self.total_words,
+        };
+        let _ = writeln!(
+            out,
+            "{} {}",
+            theme.paint(Role::Good, found.text(lang)),
+            Message::Points(self.points).text(lang)
+        );
+
+        if !self.remaining_lengths.is_empty() {
+            let heading = Message::RemainingByLength.text(lang);
+            let _ = writeln!(out, "{}", theme.paint(Role::Heading, heading));
+            for ((letter, length), count) in &self.remaining_lengths {
+                let _ = writeln!(out, "  {letter}{length}: {count}");
+            }
+        }
+
+        if !self.remaining_pairs.is_empty() {
+            let heading = Message::RemainingByPair.text(lang);
+            let _ = writeln!(out, "{}", theme.paint(Role::Heading, heading));
+            for (pair, count) in &self.remaining_pairs {
+                let _ = writeln!(out, "  {pair}: {count}");
+            }
+        }
+
+        if !self.unmatched.is_empty() {
+            let _ = writeln!(
+                out,
+                "{} {}",
+                theme.paint(Role::Warning, Message::DidntFit.text(lang)),
+                self.unmatched.join(", ")
+            );
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(Theme::plain(), Lang::En))
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::parse::{Summary, GRID_LETTERS};
+use crate::repair::Fix;
+use crate::{LengthInfo, PairInfo};
+
+// What each problem costs, out of 100
+const WRONG_LETTER_COUNT_PENALTY: u8 = 40;
+const TOTALS_MISMATCH_PENALTY: u8 = 20;
+const STRAY_PAIRS_PENALTY: u8 = 15;
+const PAIR_COVERAGE_PENALTY: u8 = 15;
+const DRIFT_PENALTY: u8 = 10;
+const REPAIRED_PENALTY: u8 = 5;
+
+/// How far a parsed grid can be trusted, from 0 to 100, and why points were
+/// taken off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quality {
+    pub score: u8,
+    pub issues: Vec<String>,
+}
+
+/// Scores a parsed grid by cross-checking its parts against each other.
+///
+/// `summary` is the page's headline numbers, if they could be read, and
+/// `drift` is how the page's layout differs from recent ones (see
+/// [`crate::parse::Fingerprint::differences`]).
+pub fn assess(
+    pairs: &PairInfo,
+    lengths: &LengthInfo,
+    summary: Option<&Summary>,
+    drift: &[String],
+) -> Quality {
+    let mut quality = Quality {
+        score: 100,
+        issues: Vec::new(),
+    };
+    let mut penalise = |penalty: u8, issue: String| {
+        quality.score = quality.score.saturating_sub(penalty);
+        quality.issues.push(issue);
+    };
+
+    let mut by_letter = BTreeMap::new();
+    for ((letter, _), count) in lengths {
+        *by_letter.entry(letter.to_ascii_uppercase()).or_insert(0) += count.0;
+    }
+    let total_words = by_letter.values().sum::<usize>();
+
+    if by_letter.len() != GRID_LETTERS {
+        penalise(
+            WRONG_LETTER_COUNT_PENALTY,
+            format!(
+                "table has {} letters, expected {GRID_LETTERS}",
+                by_letter.len()
+            ),
+        );
+    }
+
+    if let Some(summary) = summary {
+        if summary.words != total_words {
+            penalise(
+                TOTALS_MISMATCH_PENALTY,
+                format!(
+                    "table totals {total_words} words, summary says {}",
+                    summary.words
+                ),
+            );
+        }
+    }
+
+    let mut pairs_by_letter = BTreeMap::new();
+    for ((first, _), count) in pairs {
+        *pairs_by_letter
+            .entry(first.to_ascii_uppercase())
+            .or_insert(0) += count.0;
+    }
+
+    let stray = pairs_by_letter
+        .keys()
+        .filter(|letter| !by_letter.contains_key(letter))
+        .collect::<BTreeSet<_>>();
+    if !stray.is_empty() {
+        penalise(
+            STRAY_PAIRS_PENALTY,
+            format!("pairs start with letters not in the table: {stray:?}"),
+        );
+    }
+
+    // Every word starts with some pair, so each letter's pairs should add
+    // up to its row in the table
+    let uncovered = by_letter
+        .iter()
+        .filter(|(letter, count)| pairs_by_letter.get(*letter).copied().unwrap_or(0) != **count)
+        .map(|(letter, _)| *letter)
+        .collect::<Vec<_>>();
+    if !uncovered.is_empty() {
+        penalise(
+            PAIR_COVERAGE_PENALTY,
+            format!("pair counts don't add up to the table for {uncovered:?}"),
+        );
+    }
+
+    if !drift.is_empty() {
+        penalise(
+            DRIFT_PENALTY,
+            format!("page layout changed ({})", drift.join(", ")),
+        );
+    }
+
+    quality
+}
+
+impl Quality {
+    /// Notes that `fixes` were made to the two-letter list so it adds up
+    /// (see [`crate::repair`]), as it's then a guess at what the page said.
+    pub fn note_repairs(&mut self, fixes: &[Fix]) {
+        if fixes.is_empty() {
+            return;
+        }
+        self.score = self.score.saturating_sub(REPAIRED_PENALTY);
+        let fixes = fixes.iter().map(ToString::to_string).collect::<Vec<_>>();
+        self.issues.push(format!(
+            "two-letter list was repaired ({})",
+            fixes.join("; ")
+        ));
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/100", self.score)?;
+        for issue in &self.issues {
+            write!(f, "\n  - {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WordCount, WordLength};
+
+    // One word of each length given per letter, and pairs to match
+    fn grid(letters: &str) -> (PairInfo, LengthInfo) {
+        let pairs = letters
+            .chars()
+            .map(|letter| ((letter, 'A'), WordCount(2)))
+            .collect();
+        let lengths = letters
+            .chars()
+            .flat_map(|letter| [4, 5].map(|len| ((letter, WordLength(len)), WordCount(1))))
+            .collect();
+        (pairs, lengths)
+    }
+
+    fn summary(words: usize) -> Summary {
+        Summary {
+            words,
+            points: 0,
+            pangrams: 1,
+        }
+    }
+
+    #[test]
+    fn consistent_grids_score_full_marks() {
+        let (pairs, lengths) = grid("ACDELNT");
+        let quality = assess(&pairs, &lengths, Some(&summary(14)), &[]);
+        assert_eq!(quality.score, 100);
+        assert!(quality.issues.is_empty());
+        assert_eq!(quality.to_string(), "100/100");
+    }
+
+    #[test]
+    fn each_problem_costs_its_penalty() {
+        let (pairs, lengths) = grid("ACDELN");
+        let quality = assess(&pairs, &lengths, None, &[]);
+        assert_eq!(quality.score, 100 - WRONG_LETTER_COUNT_PENALTY);
+
+        let (pairs, lengths) = grid("ACDELNT");
+        let quality = assess(&pairs, &lengths, Some(&summary(15)), &[]);
+        assert_eq!(quality.score, 100 - TOTALS_MISMATCH_PENALTY);
+
+        let drift = ["tables: 1 -> 2".to_string()];
+        let quality = assess(&pairs, &lengths, None, &drift);
+        assert_eq!(quality.score, 100 - DRIFT_PENALTY);
+        assert_eq!(quality.issues, ["page layout changed (tables: 1 -> 2)"]);
+    }
+
+    #[test]
+    fn pairs_are_checked_against_the_table() {
+        let (mut pairs, lengths) = grid("ACDELNT");
+        pairs.insert(('X', 'A'), WordCount(1));
+        let quality = assess(&pairs, &lengths, None, &[]);
+        assert_eq!(quality.score, 100 - STRAY_PAIRS_PENALTY);
+
+        let (mut pairs, lengths) = grid("ACDELNT");
+        pairs.insert(('a', 'C'), WordCount(1));
+        let quality = assess(&pairs, &lengths, None, &[]);
+        assert_eq!(quality.score, 100 - PAIR_COVERAGE_PENALTY);
+        assert_eq!(
+            quality.to_string(),
+            "85/100\n  - pair counts don't add up to the table for ['A']"
+        );
+    }
+
+    #[test]
+    fn repairs_are_noted() {
+        let (pairs, lengths) = grid("ACDELNT");
+        let mut quality = assess(&pairs, &lengths, None, &[]);
+        quality.note_repairs(&[]);
+        assert_eq!(quality.score, 100);
+
+        quality.note_repairs(&[Fix::Remove {
+            pair: ('X', 'A'),
+            count: WordCount(1),
+        }]);
+        assert_eq!(quality.score, 100 - REPAIRED_PENALTY);
+        assert_eq!(
+            quality.issues,
+            ["two-letter list was repaired (XA-1 isn't in the table)"]
+        );
+    }
+
+    #[test]
+    fn every_problem_at_once_leaves_nothing() {
+        let (mut pairs, lengths) = grid("AC");
+        pairs.insert(('X', 'A'), WordCount(1));
+        pairs.insert(('A', 'C'), WordCount(1));
+        let drift = ["tables: 1 -> 2".to_string()];
+        let quality = assess(&pairs, &lengths, Some(&summary(1)), &drift);
+        assert_eq!(quality.score, 0);
+        assert_eq!(quality.issues.len(), 5);
+    }
+}
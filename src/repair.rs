@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::{LengthInfo, PairInfo, WordCount};
+
+/// A change to the two-letter list that would make it add up to the table,
+/// from [`suggest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// The pair was read under the wrong first letter: its count is just
+    /// what `to`'s letter is short by.
+    Move {
+        from: (char, char),
+        to: (char, char),
+        count: WordCount,
+    },
+    /// The pair's count was misread. Only suggested for a letter with one
+    /// pair, as otherwise there's no telling which it was.
+    Recount {
+        pair: (char, char),
+        from: WordCount,
+        to: WordCount,
+    },
+    /// Pairs starting with `letter` are `count` words short: one of
+    /// `candidates` is missing, or else more than one pair is.
+    Add {
+        letter: char,
+        candidates: Vec<(char, char)>,
+        count: WordCount,
+    },
+    /// The pair starts with a letter that isn't in the table, and nothing
+    /// else is short by its count.
+    Remove {
+        pair: (char, char),
+        count: WordCount,
+    },
+}
+
+impl Fix {
+    /// Whether there's only one way to make the change, so it can be made
+    /// without asking.
+    pub fn applicable(&self) -> bool {
+        match self {
+            Self::Add { candidates, .. } => candidates.len() == 1,
+            _ => true,
+        }
+    }
+
+    /// Makes the change to `pairs`, if it's [`applicable`](Self::applicable).
+    pub fn apply(&self, pairs: &mut PairInfo) {
+        match self {
+            Self::Move { from, to, count } => {
+                pairs.remove(from);
+                pairs.insert(*to, *count);
+            }
+            Self::Recount { pair, to, .. } => {
+                pairs.insert(*pair, *to);
+            }
+            Self::Add {
+                candidates, count, ..
+            } => {
+                if let [pair] = candidates.as_slice() {
+                    pairs.insert(*pair, *count);
+                }
+            }
+            Self::Remove { pair, .. } => {
+                pairs.remove(pair);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pair = |(a, b): (char, char)| format!("{a}{b}").to_uppercase();
+        match self {
+            Self::Move { from, to, count } => {
+                write!(f, "{}-{count} should be {}-{count}", pair(*from), pair(*to))
+            }
+            Self::Recount { pair: p, from, to } => {
+                write!(f, "{}-{from} should be {}-{to}", pair(*p), pair(*p))
+            }
+            Self::Add {
+                letter,
+                candidates,
+                count,
+            } => match candidates.as_slice() {
+                [p] => write!(f, "{}-{count} is missing", pair(*p)),
+                _ => {
+                    let candidates = candidates.iter().map(|p| pair(*p)).collect::<Vec<_>>();
+                    write!(
+                        f,
+                        "pairs starting with {} are {count} short, perhaps from one of {} missing",
+                        letter.to_ascii_uppercase(),
+                        candidates.join(", ")
+                    )
+                }
+            },
+            Self::Remove { pair: p, count } => {
+                write!(f, "{}-{count} isn't in the table", pair(*p))
+            }
+        }
+    }
+}
+
+/// Guesses at what was misread when a letter's pairs don't add up to its
+/// row in the table, taking the table to be right. Every word's second
+/// letter is one of the day's letters too, which narrows down a missing
+/// pair.
+///
+/// Returns nothing if the pairs already add up.
+pub fn suggest(pairs: &PairInfo, lengths: &LengthInfo) -> Vec<Fix> {
+    let mut rows = BTreeMap::<char, usize>::new();
+    for ((letter, _), count) in lengths {
+        *rows.entry(letter.to_ascii_uppercase()).or_default() += count.0;
+    }
+    // How many words each letter's pairs are over (or, below zero, under)
+    // its row
+    let mut off = rows
+        .iter()
+        .map(|(letter, count)| (*letter, -(*count as i64)))
+        .collect::<BTreeMap<_, _>>();
+    for ((first, _), count) in pairs {
+        *off.entry(first.to_ascii_uppercase()).or_default() += count.0 as i64;
+    }
+    // In order, so the same grid always gets the same suggestions
+    let sorted = pairs
+        .iter()
+        .map(|(pair, count)| (*pair, *count))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut fixes = Vec::new();
+    for (&(a, b), &count) in &sorted {
+        let first = a.to_ascii_uppercase();
+        if off[&first] != count.0 as i64 {
+            continue;
+        }
+        let taken = |to: char| {
+            pairs
+                .keys()
+                .any(|(x, y)| x.eq_ignore_ascii_case(&to) && y.eq_ignore_ascii_case(&b))
+        };
+        let short = off
+            .iter()
+            .filter(|(letter, by)| rows.contains_key(letter) && **by == -(count.0 as i64))
+            .map(|(letter, _)| *letter)
+            .filter(|letter| !taken(*letter))
+            .collect::<Vec<_>>();
+        // More than one letter short by as much could have had it
+        if let [to] = short[..] {
+            fixes.push(Fix::Move {
+                from: (a, b),
+                to: (same_case(to, a), b),
+                count,
+            });
+            *off.entry(first).or_default() -= count.0 as i64;
+            *off.entry(to).or_default() += count.0 as i64;
+        }
+    }
+
+    for (&(a, b), &count) in &sorted {
+        let first = a.to_ascii_uppercase();
+        if !rows.contains_key(&first) && off[&first] > 0 {
+            fixes.push(Fix::Remove {
+                pair: (a, b),
+                count,
+            });
+        }
+    }
+
+    // New pairs take after the ones that were read
+    let case = sorted.keys().next().map_or('a', |(a, _)| *a);
+    for (&letter, &by) in &off {
+        if by == 0 || !rows.contains_key(&letter) {
+            continue;
+        }
+        let own = sorted
+            .iter()
+            .filter(|((a, _), _)| a.to_ascii_uppercase() == letter)
+            .collect::<Vec<_>>();
+        match own.as_slice() {
+            [(pair, count)] if count.0 as i64 > by => fixes.push(Fix::Recount {
+                pair: **pair,
+                from: **count,
+                to: WordCount((count.0 as i64 - by) as usize),
+            }),
+            _ if by < 0 => {
+                let seconds = own
+                    .iter()
+                    .map(|((_, b), _)| b.to_ascii_uppercase())
+                    .collect::<BTreeSet<_>>();
+                let candidates = rows
+                    .keys()
+                    .filter(|second| !seconds.contains(second))
+                    .map(|second| (same_case(letter, case), same_case(*second, case)))
+                    .collect();
+                fixes.push(Fix::Add {
+                    letter,
+                    candidates,
+                    count: WordCount(-by as usize),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fixes
+}
+
+fn same_case(letter: char, like: char) -> char {
+    match like.is_ascii_lowercase() {
+        true => letter.to_ascii_lowercase(),
+        false => letter.to_ascii_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WordLength;
+
+    // Each letter's row in the table, all as four-letter words
+    fn table(rows: &[(char, usize)]) -> LengthInfo {
+        rows.iter()
+            .map(|(letter, count)| ((*letter, WordLength(4)), WordCount(*count)))
+            .collect()
+    }
+
+    fn pairs(pairs: &[(&str, usize)]) -> PairInfo {
+        pairs
+            .iter()
+            .map(|(pair, count)| {
+                let mut letters = pair.chars();
+                let pair = (letters.next().unwrap(), letters.next().unwrap());
+                (pair, WordCount(*count))
+            })
+            .collect()
+    }
+
+    // Suggests fixes for `read`, checking that making them all leaves
+    // nothing more to suggest
+    fn repaired(read: &[(&str, usize)], rows: &[(char, usize)]) -> Vec<Fix> {
+        let lengths = table(rows);
+        let mut read = pairs(read);
+        let fixes = suggest(&read, &lengths);
+        for fix in &fixes {
+            fix.apply(&mut read);
+        }
+        assert!(suggest(&read, &lengths).is_empty(), "{fixes:?}");
+        fixes
+    }
+
+    const ROWS: [(char, usize); 4] = [('A', 7), ('C', 3), ('N', 2), ('T', 1)];
+
+    #[test]
+    fn pairs_that_add_up_need_nothing() {
+        let read = [
+            ("AA", 1),
+            ("AC", 3),
+            ("AN", 2),
+            ("AT", 1),
+            ("CA", 3),
+            ("NA", 2),
+            ("TA", 1),
+        ];
+        assert!(repaired(&read, &ROWS).is_empty());
+    }
+
+    #[test]
+    fn pairs_under_the_wrong_letter_are_moved() {
+        let read = [
+            ("AA", 1),
+            ("AC", 3),
+            ("AN", 2),
+            ("AT", 1),
+            ("CA", 3),
+            ("NA", 2),
+            ("LA", 1),
+        ];
+        let fixes = repaired(&read, &ROWS);
+        assert_eq!(
+            fixes,
+            [Fix::Move {
+                from: ('L', 'A'),
+                to: ('T', 'A'),
+                count: WordCount(1),
+            }]
+        );
+        assert_eq!(fixes[0].to_string(), "LA-1 should be TA-1");
+    }
+
+    #[test]
+    fn a_letters_only_pair_is_recounted() {
+        let read = [
+            ("AA", 1),
+            ("AC", 3),
+            ("AN", 2),
+            ("AT", 1),
+            ("CA", 4),
+            ("NA", 2),
+            ("TA", 1),
+        ];
+        let fixes = repaired(&read, &ROWS);
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].to_string(), "CA-4 should be CA-3");
+    }
+
+    #[test]
+    fn missing_pairs_are_added_when_only_one_fits() {
+        let read = [
+            ("AA", 1),
+            ("AC", 3),
+            ("AT", 1),
+            ("CA", 3),
+            ("NA", 2),
+            ("TA", 1),
+        ];
+        let fixes = repaired(&read, &ROWS);
+        assert_eq!(fixes.len(), 1);
+        assert!(fixes[0].applicable());
+        assert_eq!(fixes[0].to_string(), "AN-2 is missing");
+
+        let read = pairs(&[("AC", 3), ("AT", 1), ("CA", 3), ("NA", 2), ("TA", 1)]);
+        let fixes = suggest(&read, &table(&ROWS));
+        assert_eq!(fixes.len(), 1);
+        assert!(!fixes[0].applicable());
+        assert_eq!(
+            fixes[0].to_string(),
+            "pairs starting with A are 3 short, perhaps from one of AA, AN missing"
+        );
+        let mut unchanged = read.clone();
+        fixes[0].apply(&mut unchanged);
+        assert_eq!(unchanged, read);
+    }
+
+    #[test]
+    fn pairs_for_letters_not_in_the_table_are_removed() {
+        let read = [
+            ("AA", 1),
+            ("AC", 3),
+            ("AN", 2),
+            ("AT", 1),
+            ("CA", 3),
+            ("NA", 2),
+            ("TA", 1),
+            ("XA", 2),
+        ];
+        let fixes = repaired(&read, &ROWS);
+        assert_eq!(
+            fixes,
+            [Fix::Remove {
+                pair: ('X', 'A'),
+                count: WordCount(2),
+            }]
+        );
+        assert_eq!(fixes[0].to_string(), "XA-2 isn't in the table");
+    }
+
+    #[test]
+    fn new_pairs_take_after_the_ones_read() {
+        let read = [
+            ("aa", 1),
+            ("ac", 3),
+            ("at", 1),
+            ("ca", 3),
+            ("na", 2),
+            ("ta", 1),
+        ];
+        let fixes = repaired(&read, &ROWS);
+        assert!(matches!(
+            &fixes[..],
+            [Fix::Add { candidates, .. }] if candidates == &[('a', 'n')]
+        ));
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+use crate::analytics::{DifficultyModel, Rating};
+use crate::document::GridDocument;
+use crate::spoiler::SpoilerLevel;
+
+// How many pairs to list as the most frequent
+const TOP_PAIRS: usize = 10;
+// Width of the longest bar in a Markdown chart, in characters
+const BAR_WIDTH: usize = 40;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PeriodError {
+    #[error("{0:?} isn't a month, expected YYYY-MM")]
+    BadMonth(String),
+}
+
+/// Output formats for a [`Report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/// The days a report covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Period {
+    pub name: String,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+impl Period {
+    /// The calendar month given as `YYYY-MM`.
+    pub fn month(month: &str) -> Result<Self, PeriodError> {
+        let bad_month = || PeriodError::BadMonth(month.to_string());
+        let first = NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+            .map_err(|_| bad_month())?;
+        let last = first
+            .checked_add_months(chrono::Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .ok_or_else(bad_month)?;
+
+        Ok(Self {
+            name: first.format("%B %Y").to_string(),
+            first,
+            last,
+        })
+    }
+
+    /// The Monday-to-Sunday week that `date` falls in.
+    pub fn week_of(date: NaiveDate) -> Self {
+        let first = date - Days::new(date.weekday().num_days_from_monday().into());
+        let week = first.iso_week();
+        Self {
+            name: format!("week {} of {}", week.week(), week.year()),
+            first,
+            last: first + Days::new(6),
+        }
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.first.iter_days().take_while(|date| *date <= self.last)
+    }
+}
+
+/// One day's line in a [`Report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayTotals {
+    pub date: NaiveDate,
+    pub words: usize,
+    /// Mean length of the day's words.
+    pub average_length: f64,
+    pub points: Option<usize>,
+    pub pangrams: Option<usize>,
+    pub difficulty: Option<Rating>,
+}
+
+/// Totals and averages over the saved grids in a [`Period`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub period: Period,
+    pub days: Vec<DayTotals>,
+    /// Days in the period with no saved grid.
+    pub missing: Vec<NaiveDate>,
+    pub words: usize,
+    /// Mean length of every word in the period.
+    pub average_length: Option<f64>,
+    /// Words in the period by length.
+    pub by_length: BTreeMap<usize, usize>,
+    /// The most common two-letter prefixes over the period, most common
+    /// first.
+    pub top_pairs: Vec<(String, usize)>,
+    /// Pangrams over the days whose summary was saved.
+    pub pangrams: usize,
+    /// How many days `pangrams` was counted from.
+    pub days_with_summary: usize,
+}
+
+impl Report {
+    /// Builds the report from the grids saved for `period`. Grids for days
+    /// outside of it are ignored. Each day is rated with `model` if there
+    /// is one, which should be trained on the days before the period.
+    pub fn build(period: Period, docs: &[GridDocument], model: Option<&DifficultyModel>) -> Self {
+        let docs = docs
+            .iter()
+            .filter(|doc| (period.first..=period.last).contains(&doc.date))
+            .collect::<Vec<_>>();
+
+        let mut by_length = BTreeMap::new();
+        let mut pairs = BTreeMap::new();
+        let mut days = Vec::new();
+        for doc in &docs {
+            for (length, count) in &doc.totals.by_length {
+                *by_length.entry(*length).or_insert(0) += count;
+            }
+            for entry in &doc.pairs {
+                *pairs.entry(entry.pair.to_ascii_uppercase()).or_insert(0) += entry.count.0;
+            }
+            days.push(DayTotals {
+                date: doc.date,
+                words: doc.totals.words,
+                average_length: average_length(&doc.totals.by_length).unwrap_or_default(),
+                points: doc.summary.map(|summary| summary.points),
+                pangrams: doc.summary.map(|summary| summary.pangrams),
+                difficulty: model.map(|model| model.estimate(doc).rating),
+            });
+        }
+        days.sort_by_key(|day| day.date);
+
+        let mut top_pairs = pairs.into_iter().collect::<Vec<_>>();
+        // Stable, so ties stay in alphabetical order
+        top_pairs.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        top_pairs.truncate(TOP_PAIRS);
+
+        let missing = period
+            .days()
+            .filter(|date| !days.iter().any(|day| day.date == *date))
+            .collect();
+
+        Self {
+            missing,
+            words: days.iter().map(|day| day.words).sum(),
+            average_length: average_length(&by_length),
+            by_length,
+            top_pairs,
+            pangrams: days.iter().filter_map(|day| day.pangrams).sum(),
+            days_with_summary: days.iter().filter(|day| day.pangrams.is_some()).count(),
+            days,
+            period,
+        }
+    }
+
+    /// Renders the report, leaving out anything `spoilers` doesn't allow:
+    /// totals need counts, and the length chart and pairs need full hints.
+    pub fn render(&self, format: ReportFormat, spoilers: SpoilerLevel) -> String {
+        match format {
+            ReportFormat::Markdown => self.render_markdown(spoilers),
+            ReportFormat::Html => self.render_html(spoilers),
+        }
+    }
+
+    pub fn render_markdown(&self, spoilers: SpoilerLevel) -> String {
+        // Writing to a String can't fail
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# {}\n\n{} to {}\n",
+            self.period.name, self.period.first, self.period.last
+        );
+
+        let _ = writeln!(out, "## Overview\n");
+        for (label, value) in self.overview(spoilers) {
+            let _ = writeln!(out, "- {label}: {value}");
+        }
+        if !self.missing.is_empty() {
+            let missing = self.missing.iter().map(ToString::to_string);
+            let _ = writeln!(
+                out,
+                "- Missing days: {}",
+                missing.collect::<Vec<_>>().join(", ")
+            );
+        }
+
+        if !spoilers.shows_counts() {
+            return out;
+        }
+
+        let _ = writeln!(out, "\n## Days\n");
+        let _ = writeln!(
+            out,
+            "| Date | Words | Average length | Points | Pangrams | Difficulty |"
+        );
+        let _ = writeln!(out, "|---|---:|---:|---:|---:|---|");
+        for day in &self.days {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {:.2} | {} | {} | {} |",
+                day.date,
+                day.words,
+                day.average_length,
+                or_dash(day.points),
+                or_dash(day.pangrams),
+                or_dash(day.difficulty)
+            );
+        }
+
+        let _ = writeln!(out, "\n## Words per day\n");
+        out.push_str(&bar_chart(
+            self.days
+                .iter()
+                .map(|day| (day.date.to_string(), day.words)),
+        ));
+
+        if !spoilers.shows_hints() {
+            return out;
+        }
+
+        let _ = writeln!(out, "\n## Words by length\n");
+        out.push_str(&bar_chart(
+            self.by_length
+                .iter()
+                .map(|(length, count)| (length.to_string(), *count)),
+        ));
+
+        let _ = writeln!(out, "\n## Most frequent pairs\n");
+        for (pair, count) in &self.top_pairs {
+            let _ = writeln!(out, "1. {pair} ({count})");
+        }
+
+        out
+    }
+
+    pub fn render_html(&self, spoilers: SpoilerLevel) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{name}</title>\n\
+             <style>\n.bar {{ background: #4a7ebb; height: 1em; }}\n\
+             td, th {{ padding: 0 0.5em; text-align: right; }}\n</style>\n</head>\n<body>\n\
+             <h1>{name}</h1>\n<p>{} to {}</p>",
+            self.period.first,
+            self.period.last,
+            name = escape(&self.period.name),
+        );
+
+        let _ = writeln!(out, "<h2>Overview</h2>\n<ul>");
+        for (label, value) in self.overview(spoilers) {
+            let _ = writeln!(out, "<li>{label}: {value}</li>");
+        }
+        if !self.missing.is_empty() {
+            let missing = self.missing.iter().map(ToString::to_string);
+            let _ = writeln!(
+                out,
+                "<li>Missing days: {}</li>",
+                missing.collect::<Vec<_>>().join(", ")
+            );
+        }
+        let _ = writeln!(out, "</ul>");
+        if !spoilers.shows_counts() {
+            let _ = writeln!(out, "</body>\n</html>");
+            return out;
+        }
+
+        let _ = writeln!(out, "<h2>Days</h2>\n<table>");
+        let _ = writeln!(
+            out,
+            "<tr><th>Date</th><th>Words</th><th>Average length</th><th>Points</th><th>Pangrams</th><th>Difficulty</th></tr>"
+        );
+        for day in &self.days {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                day.date,
+                day.words,
+                day.average_length,
+                or_dash(day.points),
+                or_dash(day.pangrams),
+                or_dash(day.difficulty)
+            );
+        }
+        let _ = writeln!(out, "</table>");
+
+        let _ = writeln!(out, "<h2>Words per day</h2>");
+        out.push_str(&html_bar_chart(
+            self.days
+                .iter()
+                .map(|day| (day.date.to_string(), day.words)),
+        ));
+
+        if !spoilers.shows_hints() {
+            let _ = writeln!(out, "</body>\n</html>");
+            return out;
+        }
+
+        let _ = writeln!(out, "<h2>Words by length</h2>");
+        out.push_str(&html_bar_chart(
+            self.by_length
+                .iter()
+                .map(|(length, count)| (length.to_string(), *count)),
+        ));
+
+        let _ = writeln!(out, "<h2>Most frequent pairs</h2>\n<ol>");
+        for (pair, count) in &self.top_pairs {
+            let _ = writeln!(out, "<li>{} ({count})</li>", escape(pair));
+        }
+        let _ = writeln!(out, "</ol>\n</body>\n</html>");
+
+        out
+    }
+
+    // Headline numbers shared by every format
+    fn overview(&self, spoilers: SpoilerLevel) -> Vec<(&'static str, String)> {
+        let saved_days = self.days.len();
+        let mut overview = vec![("Days saved", saved_days.to_string())];
+        if !spoilers.shows_counts() {
+            return overview;
+        }
+
+        overview.push(("Total words", self.words.to_string()));
+        if saved_days > 0 {
+            let per_day = self.words as f64 / saved_days as f64;
+            overview.push(("Average words per day", format!("{per_day:.1}")));
+        }
+        if let Some(average) = self.average_length {
+            overview.push(("Average word length", format!("{average:.2}")));
+        }
+        if self.days_with_summary > 0 {
+            overview.push(("Pangrams", self.pangrams.to_string()));
+            overview.push((
+                "Days with points and pangrams saved",
+                self.days_with_summary.to_string(),
+            ));
+        }
+
+        overview
+    }
+}
+
+fn average_length(by_length: &BTreeMap<usize, usize>) -> Option<f64> {
+    let words = by_length.values().sum::<usize>();
+    if words == 0 {
+        return None;
+    }
+
+    let letters = by_length
+        .iter()
+        .map(|(length, count)| length * count)
+        .sum::<usize>();
+    Some(letters as f64 / words as f64)
+}
+
+fn or_dash<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+// A horizontal bar per row, in a code block so the bars line up
+fn bar_chart(rows: impl Iterator<Item = (String, usize)>) -> String {
+    let rows = rows.collect::<Vec<_>>();
+    let max = rows
+        .iter()
+        .map(|(_, value)| *value)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+
+    let mut out = String::from("```\n");
+    for (label, value) in &rows {
+        let bar = "█".repeat(value * BAR_WIDTH / max);
+        let _ = writeln!(out, "{label:>label_width$} | {bar} {value}");
+    }
+    out.push_str("```\n");
+
+    out
+}
+
+fn html_bar_chart(rows: impl Iterator<Item = (String, usize)>) -> String {
+    let rows = rows.collect::<Vec<_>>();
+    let max = rows
+        .iter()
+        .map(|(_, value)| *value)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let mut out = String::from("<table>\n");
+    for (label, value) in &rows {
+        let _ = writeln!(
+            out,
+            "<tr><th>{}</th><td style=\"width: 30em; text-align: left\">\
+             <div class=\"bar\" style=\"width: {}%\"></div></td><td>{value}</td></tr>",
+            escape(label),
+            value * 100 / max,
+        );
+    }
+    out.push_str("</table>\n");
+
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::SummaryEntry;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    // A day's grid with `words` words of length 4 starting with A, and one
+    // of length 6 starting with C
+    fn doc(day: &str, words: usize) -> GridDocument {
+        let pairs = PairInfo::from([(('A', 'C'), WordCount(words)), (('C', 'A'), WordCount(1))]);
+        let lengths = LengthInfo::from([
+            (('A', WordLength(4)), WordCount(words)),
+            (('C', WordLength(6)), WordCount(1)),
+        ]);
+        GridDocument::new(date(day), &pairs, &lengths)
+    }
+
+    #[test]
+    fn months_run_to_their_last_day() {
+        let period = Period::month("2024-02").unwrap();
+        assert_eq!(period.name, "February 2024");
+        assert_eq!(
+            (period.first, period.last),
+            (date("2024-02-01"), date("2024-02-29"))
+        );
+        assert_eq!(period.days().count(), 29);
+
+        for month in ["2024-13", "2024", "February"] {
+            assert!(matches!(
+                Period::month(month),
+                Err(PeriodError::BadMonth(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn weeks_run_from_monday() {
+        let period = Period::week_of(date("2024-01-03"));
+        assert_eq!(period.name, "week 1 of 2024");
+        assert_eq!(
+            (period.first, period.last),
+            (date("2024-01-01"), date("2024-01-07"))
+        );
+        assert_eq!(Period::week_of(date("2024-01-07")), period);
+    }
+
+    #[test]
+    fn days_are_totalled_over_the_period() {
+        let mut with_summary = doc("2024-01-02", 3);
+        with_summary.summary = Some(SummaryEntry {
+            points: 20,
+            pangrams: 2,
+        });
+        let docs = [
+            with_summary,
+            doc("2024-01-01", 1),
+            // Outside of the week
+            doc("2024-01-08", 5),
+        ];
+        let report = Report::build(Period::week_of(date("2024-01-01")), &docs, None);
+
+        let days = report.days.iter().map(|day| day.date).collect::<Vec<_>>();
+        assert_eq!(days, [date("2024-01-01"), date("2024-01-02")]);
+        assert_eq!(report.missing.len(), 5);
+        assert_eq!(report.words, 6);
+        assert_eq!(report.by_length, BTreeMap::from([(4, 4), (6, 2)]));
+        assert_eq!(report.average_length, Some(28.0 / 6.0));
+        assert_eq!(report.days[0].average_length, 5.0);
+        assert_eq!((report.pangrams, report.days_with_summary), (2, 1));
+        assert_eq!(
+            report.top_pairs,
+            [("AC".to_string(), 4), ("CA".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn days_are_rated_with_the_model() {
+        let past = (1..=14)
+            .map(|day| doc(&format!("2023-12-{day:02}"), 2 + day % 2))
+            .collect::<Vec<_>>();
+        let model = DifficultyModel::train(&past).unwrap();
+        let docs = [doc("2024-01-01", 2)];
+        let period = Period::week_of(date("2024-01-01"));
+
+        let report = Report::build(period.clone(), &docs, Some(&model));
+        assert!(report.days[0].difficulty.is_some());
+        let rendered = report.render(ReportFormat::Markdown, SpoilerLevel::CountsOnly);
+        assert!(rendered.contains(&report.days[0].difficulty.unwrap().to_string()));
+
+        let report = Report::build(period, &docs, None);
+        assert_eq!(report.days[0].difficulty, None);
+    }
+
+    #[test]
+    fn spoilers_limit_what_is_rendered() {
+        let docs = [doc("2024-01-01", 1)];
+        let report = Report::build(Period::week_of(date("2024-01-01")), &docs, None);
+
+        for format in [ReportFormat::Markdown, ReportFormat::Html] {
+            let none = report.render(format, SpoilerLevel::None);
+            assert!(none.contains("Days saved: 1"));
+            assert!(!none.contains("Total words"));
+
+            let counts = report.render(format, SpoilerLevel::CountsOnly);
+            assert!(counts.contains("Total words: 2"));
+            assert!(counts.contains("Words per day"));
+            assert!(!counts.contains("Most frequent pairs"));
+
+            let hints = report.render(format, SpoilerLevel::FullHints);
+            assert!(hints.contains("Most frequent pairs"));
+            assert!(hints.contains("AC (1)"));
+        }
+    }
+
+    #[test]
+    fn bars_are_scaled_to_the_longest() {
+        let chart = bar_chart([("a".to_string(), 2), ("bb".to_string(), 1)].into_iter());
+        let bar = "█".repeat(BAR_WIDTH / 2);
+        assert_eq!(
+            chart,
+            format!("```\n a | {} 2\nbb | {bar} 1\n```\n", "█".repeat(BAR_WIDTH))
+        );
+    }
+}
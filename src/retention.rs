@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Days, NaiveDate};
+
+use crate::archive::{Archive, ArchiveError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RetentionError {
+    #[error(transparent)]
+    Listing(#[from] ArchiveError),
+    #[error("failed to list {0}: {1}")]
+    ListingHttpLog(PathBuf, std::io::Error),
+    #[error("failed to remove {0}: {1}")]
+    Removing(PathBuf, std::io::Error),
+}
+
+/// How many days of each kind of file that builds up over time to keep.
+/// Kinds without a limit are kept forever.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Saved grids, `DATE.json`.
+    pub grids: Option<u64>,
+    /// The words and finds saved alongside them, `DATE.words.txt` and
+    /// `DATE.finds.json`.
+    pub words: Option<u64>,
+    /// Requests recorded with `--debug-http`, which hold whole pages when
+    /// bodies are kept.
+    pub http_log: Option<u64>,
+}
+
+/// What [`Retention::collect`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collected {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+impl Retention {
+    /// Whether anything is ever removed.
+    pub fn keeps_everything(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Removes whatever is older than its limit as of `today`: grids and
+    /// words from `archive`, and recorded requests from `http_log_dir`.
+    /// Files are dated by their names, not when they were written, so a
+    /// backfilled day goes as soon as it's past its limit.
+    pub fn collect(
+        &self,
+        today: NaiveDate,
+        archive: Option<&Archive>,
+        http_log_dir: Option<&Path>,
+    ) -> Result<Collected, RetentionError> {
+        let mut old = Vec::new();
+        if let Some(archive) = archive {
+            if let Some(cutoff) = cutoff(today, self.grids) {
+                let dates = archive.dates()?;
+                old.extend(dates.range(..cutoff).map(|date| archive.path_for(*date)));
+            }
+            if let Some(cutoff) = cutoff(today, self.words) {
+                let words = archive.dated_files(".words.txt")?;
+                old.extend(
+                    words
+                        .range(..cutoff)
+                        .map(|date| archive.words_path_for(*date)),
+                );
+                let finds = archive.dated_files(".finds.json")?;
+                old.extend(
+                    finds
+                        .range(..cutoff)
+                        .map(|date| archive.finds_path_for(*date)),
+                );
+            }
+        }
+        if let (Some(dir), Some(cutoff)) = (http_log_dir, cutoff(today, self.http_log)) {
+            old.extend(http_log_before(dir, cutoff)?);
+        }
+
+        let mut collected = Collected::default();
+        for path in old {
+            let removing = |e| RetentionError::Removing(path.clone(), e);
+            let bytes = std::fs::metadata(&path).map_err(removing)?.len();
+            std::fs::remove_file(&path).map_err(removing)?;
+            collected.files += 1;
+            collected.bytes += bytes;
+        }
+
+        Ok(collected)
+    }
+}
+
+// The first day that's kept, if there's a limit
+fn cutoff(today: NaiveDate, days: Option<u64>) -> Option<NaiveDate> {
+    days.map(|days| {
+        today
+            .checked_sub_days(Days::new(days))
+            .unwrap_or(NaiveDate::MIN)
+    })
+}
+
+// Recorded requests are named from when they were made, starting YYYYMMDD
+fn http_log_before(dir: &Path, cutoff: NaiveDate) -> Result<Vec<PathBuf>, RetentionError> {
+    let listing = |e| RetentionError::ListingHttpLog(dir.to_path_buf(), e);
+    let mut old = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(listing)? {
+        let entry = entry.map_err(listing)?;
+        let name = entry.file_name();
+        let date = name
+            .to_str()
+            .filter(|name| name.ends_with(".json"))
+            .and_then(|name| name.get(..8))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok());
+        if date.is_some_and(|date| date < cutoff) {
+            old.push(entry.path());
+        }
+    }
+
+    Ok(old)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::GridDocument;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    // A directory of the test's own, emptied first
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gridder-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(paths: Vec<PathBuf>) -> Vec<String> {
+        let mut names = paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn limits_keep_that_many_days_back() {
+        let today = date("2024-05-10");
+        assert_eq!(cutoff(today, None), None);
+        assert_eq!(cutoff(today, Some(0)), Some(today));
+        assert_eq!(cutoff(today, Some(7)), Some(date("2024-05-03")));
+        assert_eq!(cutoff(today, Some(u64::MAX)), Some(NaiveDate::MIN));
+    }
+
+    #[test]
+    fn recorded_requests_are_dated_by_name() {
+        let dir = scratch_dir("http-log-before");
+        for name in [
+            "20240501T070000-sheets.json",
+            "20240502T070000-site.json",
+            "20240503T070000-site.json",
+            "20240501-notes.txt",
+            "latest.json",
+        ] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+
+        let old = http_log_before(&dir, date("2024-05-03")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            names(old),
+            ["20240501T070000-sheets.json", "20240502T070000-site.json"]
+        );
+    }
+
+    #[test]
+    fn each_kind_is_kept_for_its_own_limit() {
+        let dir = scratch_dir("retention-collect");
+        let archive = Archive::new(&dir);
+        let lengths = LengthInfo::from([(('A', WordLength(4)), WordCount(1))]);
+        for day in ["2024-05-01", "2024-05-08", "2024-05-10"] {
+            let doc = GridDocument::new(date(day), &PairInfo::new(), &lengths);
+            archive.save(&doc).unwrap();
+            archive.save_words(date(day), &["acta"]).unwrap();
+        }
+        let retention = Retention {
+            grids: Some(7),
+            words: Some(1),
+            http_log: None,
+        };
+        assert!(!retention.keeps_everything());
+
+        let collected = retention
+            .collect(date("2024-05-10"), Some(&archive), None)
+            .unwrap();
+        let grids = archive.dates().unwrap();
+        let words = archive.dated_files(".words.txt").unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(collected.files, 3);
+        assert_eq!(
+            grids,
+            [date("2024-05-08"), date("2024-05-10")]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(words, [date("2024-05-10")].into_iter().collect());
+    }
+}
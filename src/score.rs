@@ -0,0 +1,25 @@
+/// Bonus awarded on top of a pangram's length score.
+pub const PANGRAM_BONUS: usize = 7;
+
+/// Whether `word` uses every one of the puzzle's `letters`.
+pub fn is_pangram(word: &str, letters: &[char]) -> bool {
+    letters
+        .iter()
+        .all(|letter| word.chars().any(|c| c.eq_ignore_ascii_case(letter)))
+}
+
+/// Points for an accepted word: one for four letters, otherwise one per
+/// letter, plus [`PANGRAM_BONUS`] for a pangram.
+pub fn word_score(word: &str, letters: &[char]) -> usize {
+    let length = word.chars().count();
+    let base = if length <= 4 { 1 } else { length };
+    if is_pangram(word, letters) {
+        base + PANGRAM_BONUS
+    } else {
+        base
+    }
+}
+
+pub fn total_score<S: AsRef<str>>(words: &[S], letters: &[char]) -> usize {
+    words.iter().map(|w| word_score(w.as_ref(), letters)).sum()
+}
@@ -0,0 +1,175 @@
+use std::ffi::OsString;
+
+use crate::profile;
+
+/// The service secrets are filed under in the keyring.
+#[cfg(feature = "keyring")]
+const SERVICE: &str = "gridder";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SecretError {
+    #[cfg(feature = "keyring")]
+    #[error("failed to reach the keyring: {0}")]
+    Keyring(keyring::Error),
+    #[error("no {0} is stored in the keyring")]
+    NotStored(Secret),
+    #[error("gridder was built without keyring support (the keyring feature)")]
+    NotBuiltIn,
+}
+
+/// A setting that can be kept in the OS keyring (the macOS Keychain,
+/// Windows Credential Manager, or the Secret Service on Linux) rather than
+/// on the command line, in the environment or in a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Secret {
+    MatrixAccessToken,
+    TelegramBotToken,
+    MastodonAccessToken,
+    BlueskyAppPassword,
+    MqttPassword,
+    WebdavPassword,
+    DropboxRefreshToken,
+    GrpcToken,
+    GrpcPassword,
+    Passphrase,
+}
+
+impl Secret {
+    pub const ALL: [Self; 10] = [
+        Self::MatrixAccessToken,
+        Self::TelegramBotToken,
+        Self::MastodonAccessToken,
+        Self::BlueskyAppPassword,
+        Self::MqttPassword,
+        Self::WebdavPassword,
+        Self::DropboxRefreshToken,
+        Self::GrpcToken,
+        Self::GrpcPassword,
+        Self::Passphrase,
+    ];
+
+    /// The long name of the option it's a value for.
+    pub fn option(self) -> &'static str {
+        match self {
+            Self::MatrixAccessToken => "matrix-access-token",
+            Self::TelegramBotToken => "telegram-bot-token",
+            Self::MastodonAccessToken => "mastodon-access-token",
+            Self::BlueskyAppPassword => "bluesky-app-password",
+            Self::MqttPassword => "mqtt-password",
+            Self::WebdavPassword => "webdav-password",
+            Self::DropboxRefreshToken => "dropbox-refresh-token",
+            Self::GrpcToken => "auth-token",
+            Self::GrpcPassword => "auth-password",
+            Self::Passphrase => "passphrase",
+        }
+    }
+
+    /// The environment variable that sets it, which takes its place on
+    /// machines without a keyring.
+    pub fn env(self) -> &'static str {
+        match self {
+            Self::MatrixAccessToken => "GRIDDER_MATRIX_ACCESS_TOKEN",
+            Self::TelegramBotToken => "GRIDDER_TELEGRAM_BOT_TOKEN",
+            Self::MastodonAccessToken => "GRIDDER_MASTODON_ACCESS_TOKEN",
+            Self::BlueskyAppPassword => "GRIDDER_BLUESKY_APP_PASSWORD",
+            Self::MqttPassword => "GRIDDER_MQTT_PASSWORD",
+            Self::WebdavPassword => "GRIDDER_WEBDAV_PASSWORD",
+            Self::DropboxRefreshToken => "GRIDDER_DROPBOX_REFRESH_TOKEN",
+            Self::GrpcToken => "GRIDDER_GRPC_TOKEN",
+            Self::GrpcPassword => "GRIDDER_GRPC_PASSWORD",
+            Self::Passphrase => "GRIDDER_PASSPHRASE",
+        }
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.option())
+    }
+}
+
+/// Secrets in the OS keyring. Each profile has its own, so groups run from
+/// one machine can post as different accounts.
+///
+/// Only builds with the `keyring` feature can reach the keyring; without
+/// it, nothing is ever stored.
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    #[cfg_attr(not(feature = "keyring"), allow(dead_code))]
+    profile: Option<String>,
+}
+
+impl Keyring {
+    /// The secrets of `profile`, or the ones used without a profile.
+    pub fn new(profile: Option<&str>) -> Self {
+        Self {
+            profile: profile.map(str::to_string),
+        }
+    }
+
+    /// Puts the stored secrets that `argv` and the environment don't
+    /// already give, and that aren't `given` some other way, in the
+    /// environment variables for their options. A keyring that can't be
+    /// reached, as on most headless servers, just adds nothing.
+    ///
+    /// clap reads the environment as options are made, so this must be
+    /// called before then, and before the program starts any threads.
+    pub fn export(&self, argv: &[OsString], given: impl Fn(Secret) -> bool) {
+        let missing = Secret::ALL.into_iter().filter(|secret| {
+            !given(*secret) && profile::find_option(argv, secret.option(), secret.env()).is_none()
+        });
+        for secret in missing {
+            if let Ok(value) = self.get(secret) {
+                std::env::set_var(secret.env(), value);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl Keyring {
+    pub fn set(&self, secret: Secret, value: &str) -> Result<(), SecretError> {
+        self.entry(secret)?
+            .set_password(value)
+            .map_err(SecretError::Keyring)
+    }
+
+    pub fn get(&self, secret: Secret) -> Result<String, SecretError> {
+        match self.entry(secret)?.get_password() {
+            Ok(value) => Ok(value),
+            Err(keyring::Error::NoEntry) => Err(SecretError::NotStored(secret)),
+            Err(e) => Err(SecretError::Keyring(e)),
+        }
+    }
+
+    pub fn delete(&self, secret: Secret) -> Result<(), SecretError> {
+        match self.entry(secret)?.delete_credential() {
+            Ok(()) => Ok(()),
+            Err(keyring::Error::NoEntry) => Err(SecretError::NotStored(secret)),
+            Err(e) => Err(SecretError::Keyring(e)),
+        }
+    }
+
+    fn entry(&self, secret: Secret) -> Result<keyring::Entry, SecretError> {
+        let user = match &self.profile {
+            Some(profile) => format!("{profile}/{}", secret.option()),
+            None => secret.option().to_string(),
+        };
+        keyring::Entry::new(SERVICE, &user).map_err(SecretError::Keyring)
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+impl Keyring {
+    pub fn set(&self, _secret: Secret, _value: &str) -> Result<(), SecretError> {
+        Err(SecretError::NotBuiltIn)
+    }
+
+    pub fn get(&self, _secret: Secret) -> Result<String, SecretError> {
+        Err(SecretError::NotBuiltIn)
+    }
+
+    pub fn delete(&self, _secret: Secret) -> Result<(), SecretError> {
+        Err(SecretError::NotBuiltIn)
+    }
+}
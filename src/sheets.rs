@@ -1,17 +1,37 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::ops::Deref;
 // use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use google_sheets4::api::{
-    BatchUpdateSpreadsheetRequest, BatchUpdateValuesRequest, DuplicateSheetRequest, Request,
+    AddChartRequest, AddProtectedRangeRequest, AddSheetRequest, AutoResizeDimensionsRequest,
+    BasicChartDomain, BasicChartSeries, BasicChartSpec, BatchUpdateSpreadsheetRequest,
+    BatchUpdateValuesRequest, CellData, ChartData, ChartSourceRange, ChartSpec,
+    CopySheetToAnotherSpreadsheetRequest, DeleteProtectedRangeRequest, DeleteSheetRequest,
+    DimensionRange, DuplicateSheetRequest, EmbeddedChart, EmbeddedObjectPosition, GridCoordinate,
+    GridProperties, GridRange, OverlayPosition, ProtectedRange, Request, RowData, SheetProperties,
+    Spreadsheet, SpreadsheetProperties, UpdateCellsRequest, UpdateSheetPropertiesRequest,
     ValueRange,
 };
 use google_sheets4::hyper::client::HttpConnector;
 use google_sheets4::hyper_rustls::HttpsConnector;
-use google_sheets4::{hyper, hyper_rustls, oauth2, Sheets};
+use google_sheets4::{hyper, hyper_rustls, oauth2, FieldMask, Sheets};
 use serde_json::json;
+use tokio::sync::Mutex;
 
+use crate::a1::{self, Cell, Range};
+use crate::audit::{AuditLog, Write};
+use crate::backup::Tab;
+use crate::delta::{changed_runs, count_cell, lengths_to_values, pairs_to_values};
+use crate::http_log::{Exchange, HttpLog};
+use crate::leaderboard::Standing;
+use crate::naming::SheetNameTemplate;
+use crate::network::Network;
+use crate::pacer::WritePacer;
+use crate::parse::Summary;
+use crate::progress::Progress;
 use crate::{LengthInfo, PairInfo};
 
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +40,10 @@ pub enum NewSheetError {
     ReadingCredentialsFile(std::io::Error),
     #[error("failed to authenticate as service account: {0}")]
     AuthenticatingAsServiceAccount(std::io::Error),
+    #[error("failed to get an access token for the service account: {0}")]
+    GettingToken(oauth2::Error),
+    #[error("no access token was given for the service account")]
+    NoToken,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -32,6 +56,40 @@ pub enum FindingTemplateError {
     DidNotFindSheet,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum CheckTemplateError {
+    #[error("could not identify template sheet: {0}")]
+    FindingTemplate(#[from] FindingTemplateError),
+    #[error(
+        "template sheet is {columns} columns wide, but outputs are written up to column {needed}"
+    )]
+    TooFewColumns { columns: i32, needed: &'static str },
+    #[error("template sheet has {rows} rows, but data is written from row {}", DATA_START_ROW + 1)]
+    TooFewRows { rows: i32 },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReadTabsError {
+    #[error("API request failed: {0}")]
+    APIError(#[from] google_sheets4::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RotateError {
+    #[error("could not identify template sheet: {0}")]
+    FindingTemplate(#[from] FindingTemplateError),
+    #[error("API request failed: {0}")]
+    APIError(#[from] google_sheets4::Error),
+    #[error("Response missing key fields")]
+    MissingResponse,
+    #[error("failed to log in to Google Drive: {0}")]
+    DriveLogin(oauth2::Error),
+    #[error("invalid request to share the new spreadsheet: {0}")]
+    SharingRequest(#[from] hyper::http::Error),
+    #[error("failed to share the new spreadsheet with {0}: {1}")]
+    Sharing(String, google_sheets4::Error),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DuplicatingTemplateError {
     #[error("API request failed: {0}")]
@@ -46,6 +104,40 @@ pub enum PopulateNewSheetError {
     RequestFailed(#[from] google_sheets4::Error),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ReadProgressError {
+    #[error("API request failed: {0}")]
+    RequestFailed(#[from] google_sheets4::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WriteLeaderboardError {
+    #[error("API request failed: {0}")]
+    RequestFailed(#[from] google_sheets4::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WriteRemainingError {
+    #[error("API request failed: {0}")]
+    RequestFailed(#[from] google_sheets4::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateStatsError {
+    #[error("API request failed: {0}")]
+    RequestFailed(#[from] google_sheets4::Error),
+    #[error("no sheets in get() response")]
+    NoSheets,
+    #[error("Response missing key fields")]
+    MissingResponse,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProtectRangesError {
+    #[error("API request failed: {0}")]
+    RequestFailed(#[from] google_sheets4::Error),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SheetCreationError {
     #[error("could not identify template sheet: {0}")]
@@ -54,40 +146,329 @@ pub enum SheetCreationError {
     DuplicatingTemplateSheet(#[from] DuplicatingTemplateError),
     #[error("could not populate data in new sheet: {0}")]
     PopulatingNewSheet(#[from] PopulateNewSheetError),
+    #[error("could not protect sheet ranges: {0}")]
+    ProtectingRanges(#[from] ProtectRangesError),
+    #[error("could not populate data in new sheet ({populating}), nor remove it afterwards ({removing})")]
+    RemovingFailedSheet {
+        populating: PopulateNewSheetError,
+        removing: google_sheets4::Error,
+    },
 }
 
-fn pairs_to_values(pairs: &PairInfo) -> Vec<Vec<serde_json::Value>> {
-    pairs
-        .iter()
-        .filter(|(_, count)| **count > 0)
-        .map(|((a, b), count)| vec![json!(format!("{a}{b}")), json!(count)])
-        .collect()
+/// How a day's tab looks, as far as the grid written to it goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayTab {
+    Missing,
+    /// There, but with no grid in it (e.g. cleared by mistake). Holds the
+    /// tab's ID.
+    Empty(i32),
+    Filled,
 }
 
-fn lengths_to_values(lengths: &LengthInfo) -> Vec<Vec<serde_json::Value>> {
-    lengths
-        .iter()
-        .filter(|(_, count)| **count > 0)
-        .map(|((letter, len), count)| vec![json!(letter), json!(len), json!(count)])
-        .collect()
+/// Where a day's grid came from, noted on the cells it's written to if
+/// [`provenance_notes`](SheetManagerBuilder::provenance_notes) is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// When the grid was read from its page, if known.
+    pub parsed_at: Option<DateTime<Utc>>,
+    /// Where it was read from: the page's URL, or the saved grid's path.
+    pub source: String,
 }
 
+impl Provenance {
+    fn note(&self) -> String {
+        let mut lines = vec![format!("Written by gridder {}", env!("CARGO_PKG_VERSION"))];
+        if let Some(parsed_at) = self.parsed_at {
+            lines.push(format!(
+                "Parsed {}",
+                parsed_at.format("%Y-%m-%d %H:%M:%S UTC")
+            ));
+        }
+        lines.push(format!("From {}", self.source));
+
+        lines.join("\n")
+    }
+}
+
+// Most ranges to read in one request, to keep its URL a sane length
+const MAX_RANGES_PER_READ: usize = 100;
+
+/// Google's limit on the cells in a spreadsheet, across all of its tabs.
+pub const CELL_LIMIT: i64 = 10_000_000;
+
+// Needed to share a spreadsheet, which the Sheets API can't do
+const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive";
+
+// Column of each day's tab where the group records the words they've found,
+// one per row, below the header rows
+const FOUND_WORDS_COLUMN: &str = "I";
+
+// Block of per-member columns: each member's name in row 2, and the words
+// they've found below it
+const MEMBER_COLUMNS: (&str, &str) = ("K", "R");
+
+// Where the leaderboard (rank, member, points, words) is written
+const LEADERBOARD_COLUMNS: (&str, &str) = ("T", "W");
+
+// Where the counts still to find are written: (letter, length) slots in the
+// first two columns and pairs in the last two, with a gap between
+const REMAINING_COLUMNS: (&str, &str) = ("Y", "AC");
+
+// Tab holding one row of headline numbers per day, charted over time
+const STATS_SHEET: &str = "Stats";
+const STATS_HEADER: [&str; 4] = ["Date", "Words", "Points", "Pangrams"];
+
+fn cell_to_string(cell: serde_json::Value) -> Option<String> {
+    match cell {
+        serde_json::Value::String(s) if !s.trim().is_empty() => Some(s),
+        _ => None,
+    }
+}
+
+// Columns of the data written to each day's tab (lengths in B:D, pairs in
+// F:G), as 0-based, end-exclusive indices
+const DATA_COLUMNS: [(i32, i32); 2] = [(1, 4), (5, 7)];
+// The same, as the letters of the first and last columns
+const DATA_CELL_COLUMNS: (&str, &str) = ("B", "G");
+// Data starts on the third row
+const DATA_START_ROW: i32 = 2;
+
+// The API allows 60 write requests a minute per user
+const DEFAULT_WRITES_PER_MINUTE: u32 = 60;
+
+// What the API's calls give: the raw response, and what was parsed from it
+type ApiResult<T> = Result<(hyper::Response<hyper::Body>, T), google_sheets4::Error>;
+
+type HttpsClient = hyper::Client<HttpsConnector<HttpConnector>>;
+
 pub struct SheetManager {
     client: Sheets<HttpsConnector<HttpConnector>>,
+    // The one under `client`, for the Drive API's calls
+    http_client: HttpsClient,
+    auth: oauth2::authenticator::Authenticator<HttpsConnector<HttpConnector>>,
     spreadsheet_id: String,
+    sheet_names: SheetNameTemplate,
+    protect_ranges: bool,
+    tidy_layout: bool,
+    remove_failed_sheets: bool,
+    provenance_notes: bool,
+    write_pacer: Mutex<WritePacer>,
+    audit_log: Option<AuditLog>,
+    http_log: Option<HttpLog>,
 }
 
-fn is_template(sheet: &google_sheets4::api::Sheet) -> bool {
+fn has_title(sheet: &google_sheets4::api::Sheet, title: &str) -> bool {
     sheet
         .properties
         .as_ref()
         .and_then(|props| props.title.as_ref())
-        .map(|title| title == "TEMPLATE")
+        .map(|t| t == title)
         .unwrap_or(false)
 }
 
+fn is_template(sheet: &google_sheets4::api::Sheet) -> bool {
+    has_title(sheet, "TEMPLATE")
+}
+
+fn stats_chart(stats_sheet_id: i32) -> EmbeddedChart {
+    let column = |index: i32| ChartData {
+        source_range: Some(ChartSourceRange {
+            sources: Some(vec![GridRange {
+                sheet_id: Some(stats_sheet_id),
+                start_column_index: Some(index),
+                end_column_index: Some(index + 1),
+                ..Default::default()
+            }]),
+        }),
+        ..Default::default()
+    };
+
+    let series = (1..STATS_HEADER.len() as i32)
+        .map(|index| BasicChartSeries {
+            series: Some(column(index)),
+            target_axis: Some("LEFT_AXIS".to_string()),
+            ..Default::default()
+        })
+        .collect();
+
+    EmbeddedChart {
+        spec: Some(ChartSpec {
+            title: Some("Daily totals".to_string()),
+            basic_chart: Some(BasicChartSpec {
+                chart_type: Some("LINE".to_string()),
+                legend_position: Some("BOTTOM_LEGEND".to_string()),
+                header_count: Some(1),
+                domains: Some(vec![BasicChartDomain {
+                    domain: Some(column(0)),
+                    ..Default::default()
+                }]),
+                series: Some(series),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        position: Some(EmbeddedObjectPosition {
+            overlay_position: Some(OverlayPosition {
+                // Just to the right of the table
+                anchor_cell: Some(GridCoordinate {
+                    sheet_id: Some(stats_sheet_id),
+                    row_index: Some(0),
+                    column_index: Some(STATS_HEADER.len() as i32 + 1),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Settings for a [`SheetManager`], all optional apart from which
+/// spreadsheet to use and how to log in. Start one with
+/// [`SheetManager::builder`].
+pub struct SheetManagerBuilder {
+    spreadsheet_id: String,
+    service_account_file: PathBuf,
+    sheet_names: SheetNameTemplate,
+    protect_ranges: bool,
+    tidy_layout: bool,
+    remove_failed_sheets: bool,
+    provenance_notes: bool,
+    writes_per_minute: u32,
+    audit_log: Option<AuditLog>,
+    http_log: Option<HttpLog>,
+}
+
+impl SheetManagerBuilder {
+    /// How to name each day's tab. Defaults to `%Y-%m-%d`.
+    pub fn sheet_names(mut self, template: SheetNameTemplate) -> Self {
+        self.sheet_names = template;
+        self
+    }
+
+    /// Whether to freeze the header rows and fit the data columns to their
+    /// contents on each new tab, so it's readable without manual tweaks.
+    pub fn tidy_layout(mut self, enabled: bool) -> Self {
+        self.tidy_layout = enabled;
+        self
+    }
+
+    /// Whether to add warning-only protection to the TEMPLATE tab and to the
+    /// data written to each new tab, so they aren't overwritten by accident.
+    pub fn protect_ranges(mut self, enabled: bool) -> Self {
+        self.protect_ranges = enabled;
+        self
+    }
+
+    /// Whether to delete a newly duplicated tab if writing the data to it
+    /// fails, so that a retry doesn't trip over a half-made tab. On by
+    /// default.
+    pub fn remove_failed_sheets(mut self, enabled: bool) -> Self {
+        self.remove_failed_sheets = enabled;
+        self
+    }
+
+    /// Whether to note where the grid came from (the gridder version, when
+    /// it was read and from where) on the top left cell of each block of it
+    /// written to a day's tab.
+    pub fn provenance_notes(mut self, enabled: bool) -> Self {
+        self.provenance_notes = enabled;
+        self
+    }
+
+    /// The most write requests to make in a minute, before slowing down
+    /// further on quota errors. Defaults to the API's quota of 60.
+    pub fn max_writes_per_minute(mut self, writes: u32) -> Self {
+        self.writes_per_minute = writes;
+        self
+    }
+
+    /// Records every change made to the spreadsheet in `log`.
+    pub fn audit_log(mut self, log: AuditLog) -> Self {
+        self.audit_log = Some(log);
+        self
+    }
+
+    /// Records each request made to the API, and what came back, in `log`.
+    pub fn http_log(mut self, log: HttpLog) -> Self {
+        self.http_log = Some(log);
+        self
+    }
+
+    /// Logs in with the service account and gives the configured manager.
+    pub async fn build(self, network: Network) -> Result<SheetManager, NewSheetError> {
+        let http_client = network.https_client();
+        let auth = authenticate(&self.service_account_file, http_client.clone()).await?;
+        Ok(SheetManager {
+            client: Sheets::new(http_client.clone(), auth.clone()),
+            http_client,
+            auth,
+            spreadsheet_id: self.spreadsheet_id,
+            sheet_names: self.sheet_names,
+            protect_ranges: self.protect_ranges,
+            tidy_layout: self.tidy_layout,
+            remove_failed_sheets: self.remove_failed_sheets,
+            provenance_notes: self.provenance_notes,
+            write_pacer: Mutex::new(WritePacer::new(self.writes_per_minute)),
+            audit_log: self.audit_log,
+            http_log: self.http_log,
+        })
+    }
+}
+
+impl Network {
+    // The client the Sheets API and the service account's logins go through
+    fn https_client(self) -> HttpsClient {
+        hyper::Client::builder().build(
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_native_roots()
+                .unwrap()
+                .https_only()
+                .enable_http1()
+                .enable_http2()
+                .build(),
+        )
+    }
+}
+
+// Logs in as the service account in `service_account_file`
+async fn authenticate(
+    service_account_file: &Path,
+    http_client: HttpsClient,
+) -> Result<oauth2::authenticator::Authenticator<HttpsConnector<HttpConnector>>, NewSheetError> {
+    let creds = oauth2::read_service_account_key(service_account_file)
+        .await
+        .map_err(NewSheetError::ReadingCredentialsFile)?;
+    oauth2::ServiceAccountAuthenticator::with_client(creds, http_client)
+        .build()
+        .await
+        .map_err(NewSheetError::AuthenticatingAsServiceAccount)
+}
+
+/// An access token for the service account in `service_account_file`, for
+/// other Google APIs (e.g. Drive's) with `scopes`. It lasts about an hour.
+pub async fn access_token(
+    network: Network,
+    service_account_file: &Path,
+    scopes: &[&str],
+) -> Result<String, NewSheetError> {
+    let auth = authenticate(service_account_file, network.https_client()).await?;
+    let token = auth
+        .token(scopes)
+        .await
+        .map_err(NewSheetError::GettingToken)?;
+
+    token
+        .token()
+        .map(str::to_string)
+        .ok_or(NewSheetError::NoToken)
+}
+
 impl SheetManager {
+    /// Connects with the default settings. See [`SheetManager::builder`] to
+    /// change them.
     pub async fn new<P, S>(
+        network: Network,
         spreadsheet_id: S,
         service_account_file: P,
     ) -> Result<Self, NewSheetError>
@@ -95,25 +476,85 @@ impl SheetManager {
         P: AsRef<Path>,
         S: Deref<Target = String>,
     {
-        let creds = google_sheets4::oauth2::read_service_account_key(service_account_file)
+        Self::builder(spreadsheet_id, service_account_file)
+            .build(network)
             .await
-            .map_err(NewSheetError::ReadingCredentialsFile)?;
-        let auth = oauth2::ServiceAccountAuthenticator::builder(creds)
-            .build()
-            .await
-            .map_err(NewSheetError::AuthenticatingAsServiceAccount)?;
-        let http_client = hyper::Client::builder().build(
-            hyper_rustls::HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .unwrap()
-                .https_only()
-                .enable_http2()
-                .build(),
-        );
-        Ok(Self {
-            client: Sheets::new(http_client, auth),
+    }
+
+    pub fn builder<P, S>(spreadsheet_id: S, service_account_file: P) -> SheetManagerBuilder
+    where
+        P: AsRef<Path>,
+        S: Deref<Target = String>,
+    {
+        SheetManagerBuilder {
             spreadsheet_id: spreadsheet_id.to_string(),
-        })
+            service_account_file: service_account_file.as_ref().to_path_buf(),
+            sheet_names: SheetNameTemplate::default(),
+            protect_ranges: false,
+            tidy_layout: false,
+            remove_failed_sheets: true,
+            provenance_notes: false,
+            writes_per_minute: DEFAULT_WRITES_PER_MINUTE,
+            audit_log: None,
+            http_log: None,
+        }
+    }
+
+    // Makes a write request once the pacer allows it, and adds it to the
+    // audit log
+    async fn paced_write<T, F>(&self, write: Write, request: F) -> ApiResult<T>
+    where
+        F: Future<Output = ApiResult<T>>,
+        T: serde::Serialize,
+    {
+        let mut pacer = self.write_pacer.lock().await;
+        pacer.wait().await;
+        let result = self.traced(&write.action, request).await;
+        pacer.record(matches!(
+            &result,
+            Err(google_sheets4::Error::Failure(resp))
+                if resp.status() == hyper::StatusCode::TOO_MANY_REQUESTS
+        ));
+        self.audit(&write, result.as_ref().map(|_| ()));
+        result
+    }
+
+    // Makes a request, and adds it to the HTTP log. The client doesn't show
+    // its requests, so they're recorded by what they do, e.g. `get values`
+    async fn traced<T, F>(&self, call: &str, request: F) -> ApiResult<T>
+    where
+        F: Future<Output = ApiResult<T>>,
+        T: serde::Serialize,
+    {
+        let Some(log) = &self.http_log else {
+            return request.await;
+        };
+        let mut exchange = Exchange::start("sheets", call);
+        let result = request.await;
+        let body = match &result {
+            Ok((response, parsed)) => {
+                exchange.response(response.status().as_u16(), header_pairs(response.headers()));
+                // Serializing API responses can't fail
+                log.records_bodies()
+                    .then(|| serde_json::to_vec(parsed).unwrap_or_default())
+            }
+            Err(google_sheets4::Error::Failure(response)) => {
+                exchange.response(response.status().as_u16(), header_pairs(response.headers()));
+                None
+            }
+            Err(e) => {
+                exchange.failed(e);
+                None
+            }
+        };
+        log.record(exchange, body.as_deref());
+        result
+    }
+
+    fn audit<E: std::fmt::Display>(&self, write: &Write, outcome: Result<(), E>) {
+        if let Some(log) = &self.audit_log {
+            log.record(&format!("sheets:{}", self.spreadsheet_id), write, outcome);
+        }
     }
 
     pub async fn create_for_date(
@@ -121,35 +562,861 @@ impl SheetManager {
         date: &NaiveDate,
         pairs: &PairInfo,
         lengths: &LengthInfo,
+        provenance: &Provenance,
     ) -> Result<(), SheetCreationError> {
         let template_sheet = self.find_template().await?;
         let template_sheet_id = template_sheet
             .properties
             .and_then(|p| p.sheet_id)
             .expect("missing sheet ID");
+        let template_protected = template_sheet
+            .protected_ranges
+            .map(|ranges| !ranges.is_empty())
+            .unwrap_or(false);
         let new_sheet = self.duplicate_template(date, template_sheet_id).await?;
+        let new_sheet_id = new_sheet.sheet_id.expect("missing ID of new sheet");
         let new_sheet_name = new_sheet.title.expect("missing name of new sheet");
-        self.populate_new_sheet(&new_sheet_name, pairs, lengths)
+        if let Err(populating) = self
+            .populate_new_sheet(new_sheet_id, &new_sheet_name, pairs, lengths, provenance)
+            .await
+        {
+            if self.remove_failed_sheets {
+                if let Err(removing) = self.delete_sheet(new_sheet_id).await {
+                    return Err(SheetCreationError::RemovingFailedSheet {
+                        populating,
+                        removing,
+                    });
+                }
+            }
+            return Err(populating.into());
+        }
+        if self.protect_ranges {
+            self.protect_sheet_ranges(template_sheet_id, template_protected, new_sheet_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Checks the spreadsheet can be reached with the service account, and
+    /// that its TEMPLATE tab is big enough for everything written to a
+    /// day's tab (the grid, found words, leaderboard and remaining counts).
+    /// Nothing is written.
+    pub async fn check_template(&self) -> Result<(), CheckTemplateError> {
+        let grid = self
+            .find_template()
+            .await?
+            .properties
+            .and_then(|props| props.grid_properties)
+            .unwrap_or_default();
+
+        let columns = grid.column_count.unwrap_or_default();
+        let needed = REMAINING_COLUMNS.1;
+        if usize::try_from(columns).unwrap_or_default()
+            < a1::column_number(needed).unwrap_or_default()
+        {
+            return Err(CheckTemplateError::TooFewColumns { columns, needed });
+        }
+        let rows = grid.row_count.unwrap_or_default();
+        if rows <= DATA_START_ROW {
+            return Err(CheckTemplateError::TooFewRows { rows });
+        }
+
+        Ok(())
+    }
+
+    /// Looks up the tab for each of `dates`. The spreadsheet's tabs are
+    /// listed once, and the grids on the ones that exist are read in
+    /// batches, so this takes a few requests however many dates there are.
+    pub async fn day_tabs(
+        &self,
+        dates: &[NaiveDate],
+    ) -> Result<Vec<(NaiveDate, DayTab)>, ReadTabsError> {
+        let ids = self
+            .traced(
+                "get spreadsheet",
+                self.client.spreadsheets().get(&self.spreadsheet_id).doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|sheet| {
+                let props = sheet.properties?;
+                Some((props.title?, props.sheet_id?))
+            })
+            .collect::<HashMap<_, _>>();
+        let existing = dates
+            .iter()
+            .filter_map(|date| {
+                let name = self.sheet_names.render(date);
+                let id = *ids.get(&name)?;
+                Some((*date, name, id))
+            })
+            .collect::<Vec<_>>();
+
+        let mut filled = HashSet::new();
+        for batch in existing.chunks(MAX_RANGES_PER_READ) {
+            let mut request = self
+                .client
+                .spreadsheets()
+                .values_batch_get(&self.spreadsheet_id);
+            for (_, name, _) in batch {
+                let (first, last) = DATA_CELL_COLUMNS;
+                let range = Range::between(name, Cell::new(first, 3), Cell::column(last));
+                request = request.add_ranges(&range.to_string());
+            }
+            let ranges = self
+                .traced("get values", request.doit())
+                .await?
+                .1
+                .value_ranges
+                .unwrap_or_default();
+            // Ranges come back in the order they were asked for
+            for ((date, ..), range) in batch.iter().zip(ranges) {
+                if range.values.is_some_and(|rows| !rows.is_empty()) {
+                    filled.insert(*date);
+                }
+            }
+        }
+
+        let tabs = dates
+            .iter()
+            .map(|date| {
+                let tab = match existing.iter().find(|(d, ..)| d == date) {
+                    None => DayTab::Missing,
+                    Some(_) if filled.contains(date) => DayTab::Filled,
+                    Some((.., id)) => DayTab::Empty(*id),
+                };
+                (*date, tab)
+            })
+            .collect();
+
+        Ok(tabs)
+    }
+
+    /// Reads every tab of the spreadsheet, in order, with formulas as
+    /// written rather than their results.
+    pub async fn read_all_tabs(&self) -> Result<Vec<Tab>, ReadTabsError> {
+        let names = self
+            .traced(
+                "get spreadsheet",
+                self.client.spreadsheets().get(&self.spreadsheet_id).doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|sheet| sheet.properties?.title)
+            .collect::<Vec<_>>();
+
+        let mut tabs = Vec::with_capacity(names.len());
+        for batch in names.chunks(MAX_RANGES_PER_READ) {
+            let mut request = self
+                .client
+                .spreadsheets()
+                .values_batch_get(&self.spreadsheet_id)
+                .value_render_option("FORMULA");
+            for name in batch {
+                request = request.add_ranges(&Range::sheet(name).to_string());
+            }
+            let ranges = self
+                .traced("get values", request.doit())
+                .await?
+                .1
+                .value_ranges
+                .unwrap_or_default();
+            // Ranges come back in the order they were asked for
+            for (name, range) in batch.iter().zip(ranges) {
+                tabs.push(Tab {
+                    name: name.clone(),
+                    rows: range.values.unwrap_or_default(),
+                });
+            }
+        }
+
+        Ok(tabs)
+    }
+
+    /// How many cells the spreadsheet's tabs take up, filled in or not,
+    /// which is what counts towards [`CELL_LIMIT`].
+    pub async fn cells_used(&self) -> Result<i64, ReadTabsError> {
+        let cells = self
+            .traced(
+                "get spreadsheet",
+                self.client.spreadsheets().get(&self.spreadsheet_id).doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|sheet| sheet.properties?.grid_properties)
+            .map(|grid| {
+                i64::from(grid.row_count.unwrap_or(0)) * i64::from(grid.column_count.unwrap_or(0))
+            })
+            .sum();
+
+        Ok(cells)
+    }
+
+    /// Starts a new spreadsheet called `title` with a copy of this one's
+    /// TEMPLATE tab, and switches to writing to it once it's set up. Gives
+    /// the new spreadsheet's ID.
+    ///
+    /// The new spreadsheet belongs to the service account, so only it can
+    /// open it until it's [shared](Self::share). If setting it up fails,
+    /// this one's kept, and the half set up one is left behind.
+    pub async fn rotate(&mut self, title: &str) -> Result<String, RotateError> {
+        let template_id = self
+            .find_template()
+            .await?
+            .properties
+            .and_then(|p| p.sheet_id)
+            .ok_or(RotateError::MissingResponse)?;
+
+        let spreadsheet = Spreadsheet {
+            properties: Some(SpreadsheetProperties {
+                title: Some(title.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let created = self
+            .paced_write(
+                Write::json("create spreadsheet", title, &spreadsheet),
+                self.client.spreadsheets().create(spreadsheet).doit(),
+            )
+            .await?
+            // parsed response only
+            .1;
+        let new_id = created.spreadsheet_id.ok_or(RotateError::MissingResponse)?;
+        // New spreadsheets come with an empty tab, which can go once the
+        // template's there
+        let blank_ids = created
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|sheet| sheet.properties?.sheet_id)
+            .collect::<Vec<_>>();
+
+        let copy = CopySheetToAnotherSpreadsheetRequest {
+            destination_spreadsheet_id: Some(new_id.clone()),
+        };
+        let copied_id = self
+            .paced_write(
+                Write::json("copy tab", "TEMPLATE", &copy),
+                self.client
+                    .spreadsheets()
+                    .sheets_copy_to(copy, &self.spreadsheet_id, template_id)
+                    .doit(),
+            )
+            .await?
+            .1
+            .sheet_id
+            .ok_or(RotateError::MissingResponse)?;
+
+        // Copies are named "Copy of TEMPLATE"
+        let rename = Request {
+            update_sheet_properties: Some(UpdateSheetPropertiesRequest {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(copied_id),
+                    title: Some("TEMPLATE".to_string()),
+                    ..Default::default()
+                }),
+                fields: Some(FieldMask::new(&["title"])),
+            }),
+            ..Default::default()
+        };
+        let delete_blanks = blank_ids.into_iter().map(|sheet_id| Request {
+            delete_sheet: Some(DeleteSheetRequest {
+                sheet_id: Some(sheet_id),
+            }),
+            ..Default::default()
+        });
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(std::iter::once(rename).chain(delete_blanks).collect()),
+            ..Default::default()
+        };
+        self.paced_write(
+            Write::json("set up tabs", "TEMPLATE", &request),
+            self.client
+                .spreadsheets()
+                .batch_update(request, &new_id)
+                .doit(),
+        )
+        .await?;
+        self.spreadsheet_id = new_id.clone();
+
+        Ok(new_id)
+    }
+
+    /// Gives `email` edit access to the spreadsheet, through the Drive API.
+    pub async fn share(&self, email: &str) -> Result<(), RotateError> {
+        let token = self
+            .auth
+            .token(&[DRIVE_SCOPE])
+            .await
+            .map_err(RotateError::DriveLogin)?;
+        let url = format!(
+            "https://www.googleapis.com/drive/v3/files/{}/permissions",
+            self.spreadsheet_id
+        );
+        let permission = json!({
+            "type": "user",
+            "role": "writer",
+            "emailAddress": email,
+        });
+        let request = hyper::Request::post(url)
+            .header(
+                hyper::header::AUTHORIZATION,
+                format!("Bearer {}", token.token().unwrap_or_default()),
+            )
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(hyper::Body::from(permission.to_string()))?;
+        self.paced_write(
+            Write::json("share", email, &permission),
+            self.send_json(request),
+        )
+        .await
+        .map_err(|e| RotateError::Sharing(email.to_string(), e))?;
+
+        Ok(())
+    }
+
+    // Sends a request to another Google API through the Sheets client's
+    // connections, giving back its JSON like the Sheets calls do
+    async fn send_json(
+        &self,
+        request: hyper::Request<hyper::Body>,
+    ) -> ApiResult<serde_json::Value> {
+        let response = self
+            .http_client
+            .request(request)
+            .await
+            .map_err(google_sheets4::Error::HttpError)?;
+        if !response.status().is_success() {
+            return Err(google_sheets4::Error::Failure(response));
+        }
+        let (parts, body) = response.into_parts();
+        let bytes = hyper::body::to_bytes(body)
+            .await
+            .map_err(google_sheets4::Error::HttpError)?;
+        let parsed = serde_json::from_slice(&bytes).map_err(|e| {
+            google_sheets4::Error::JsonDecodeError(String::from_utf8_lossy(&bytes).into_owned(), e)
+        })?;
+
+        Ok((
+            hyper::Response::from_parts(parts, hyper::Body::empty()),
+            parsed,
+        ))
+    }
+
+    /// Writes the grid for `date` to its existing tab, `sheet_id`, e.g.
+    /// one [`day_tabs`](Self::day_tabs) found empty.
+    pub async fn fill_for_date(
+        &self,
+        date: &NaiveDate,
+        sheet_id: i32,
+        pairs: &PairInfo,
+        lengths: &LengthInfo,
+        provenance: &Provenance,
+    ) -> Result<(), SheetCreationError> {
+        let sheet_name = self.sheet_names.render(date);
+        self.populate_new_sheet(sheet_id, &sheet_name, pairs, lengths, provenance)
             .await?;
+
         Ok(())
     }
 
-    async fn find_template(&self) -> Result<google_sheets4::api::Sheet, FindingTemplateError> {
-        self.client
-            .spreadsheets()
-            .get(&self.spreadsheet_id)
-            // perform spreadsheets.get() request
-            .doit()
+    /// Reads the words the group has found so far from the tab for `date`.
+    pub async fn read_progress(&self, date: &NaiveDate) -> Result<Vec<String>, ReadProgressError> {
+        let sheet_name = self.sheet_names.render(date);
+        let range = Range::between(
+            &sheet_name,
+            Cell::new(FOUND_WORDS_COLUMN, 3),
+            Cell::column(FOUND_WORDS_COLUMN),
+        )
+        .to_string();
+
+        let values = self
+            .traced(
+                "get values",
+                self.client
+                    .spreadsheets()
+                    .values_get(&self.spreadsheet_id, &range)
+                    .doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .values
+            .unwrap_or_default();
+
+        let words = values
+            .into_iter()
+            .filter_map(|row| row.into_iter().next())
+            .filter_map(cell_to_string)
+            .collect();
+
+        Ok(words)
+    }
+
+    /// Reads each member's found words from the tab for `date`, skipping
+    /// columns without a member name.
+    pub async fn read_member_words(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<(String, Vec<String>)>, ReadProgressError> {
+        let sheet_name = self.sheet_names.render(date);
+        let (first, last) = MEMBER_COLUMNS;
+        let range =
+            Range::between(&sheet_name, Cell::new(first, 2), Cell::column(last)).to_string();
+
+        let columns = self
+            .traced(
+                "get values",
+                self.client
+                    .spreadsheets()
+                    .values_get(&self.spreadsheet_id, &range)
+                    .major_dimension("COLUMNS")
+                    .doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .values
+            .unwrap_or_default();
+
+        let members = columns
+            .into_iter()
+            .filter_map(|column| {
+                let mut cells = column.into_iter();
+                let member = cells.next().and_then(cell_to_string)?;
+                Some((member, cells.filter_map(cell_to_string).collect()))
+            })
+            .collect();
+
+        Ok(members)
+    }
+
+    /// Replaces the leaderboard block on the tab for `date`.
+    pub async fn write_leaderboard(
+        &self,
+        date: &NaiveDate,
+        standings: &[Standing],
+    ) -> Result<(), WriteLeaderboardError> {
+        let sheet_name = self.sheet_names.render(date);
+        let (first, last) = LEADERBOARD_COLUMNS;
+        let mut values = vec![vec![
+            json!("Rank"),
+            json!("Member"),
+            json!("Points"),
+            json!("Words"),
+        ]];
+        values.extend(standings.iter().map(|s| {
+            vec![
+                json!(s.rank),
+                json!(s.member),
+                json!(s.points),
+                json!(s.words),
+            ]
+        }));
+
+        // Members who dropped off are cleared from the bottom
+        self.write_changes(&sheet_name, (first, last), (2, None), values)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replaces the block of counts still to find on the tab for `date`.
+    pub async fn write_remaining(
+        &self,
+        date: &NaiveDate,
+        progress: &Progress,
+    ) -> Result<(), WriteRemainingError> {
+        let sheet_name = self.sheet_names.render(date);
+        let (first, last) = REMAINING_COLUMNS;
+
+        let slots = progress
+            .remaining_lengths
+            .iter()
+            .map(|((letter, length), count)| (format!("{letter}{length}"), *count))
+            .collect::<Vec<_>>();
+        let pairs = progress.remaining_pairs.iter().collect::<Vec<_>>();
+
+        let mut values = vec![vec![
+            json!("Slot"),
+            json!("Left"),
+            json!(""),
+            json!("Pair"),
+            json!("Left"),
+        ]];
+        for i in 0..slots.len().max(pairs.len()) {
+            let mut row = match slots.get(i) {
+                Some((slot, count)) => vec![json!(slot), count_cell(*count)],
+                None => vec![json!(""), json!("")],
+            };
+            row.push(json!(""));
+            if let Some((pair, count)) = pairs.get(i) {
+                row.extend([json!(pair), count_cell(**count)]);
+            }
+            values.push(row);
+        }
+
+        // Slots that have been finished are cleared from the bottom
+        self.write_changes(&sheet_name, (first, last), (2, None), values)
+            .await?;
+
+        Ok(())
+    }
+
+    // Writes `values` to the block of `columns` on `sheet_name` from `row`
+    // down to `last_row` (or the bottom of the tab), sending only the cells
+    // that differ from what's there, so quota isn't spent on cells that
+    // haven't changed and notes on them are left alone. Anything below or
+    // beside `values` in the block is cleared.
+    async fn write_changes(
+        &self,
+        sheet_name: &str,
+        (first, last): (&str, &str),
+        (row, last_row): (usize, Option<usize>),
+        values: Vec<Vec<serde_json::Value>>,
+    ) -> Result<(), google_sheets4::Error> {
+        let end = match last_row {
+            Some(last_row) => Cell::new(last, last_row),
+            None => Cell::column(last),
+        };
+        let range = Range::between(sheet_name, Cell::new(first, row), end);
+        let current = self
+            .traced(
+                "get values",
+                self.client
+                    .spreadsheets()
+                    .values_get(&self.spreadsheet_id, &range.to_string())
+                    // Numbers as numbers, to compare with what's written
+                    .value_render_option("UNFORMATTED_VALUE")
+                    .doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .values
+            .unwrap_or_default();
+
+        // Already checked by Cell::new above
+        let first = a1::column_number(first).unwrap_or_default();
+        let data = changed_runs(&current, &values)
+            .into_iter()
+            .map(|(offset, column, cells)| {
+                let start = Cell::at(first + column, row + offset);
+                let end = Cell::at(first + column + cells.len() - 1, row + offset);
+                ValueRange {
+                    major_dimension: Some("ROWS".to_string()),
+                    range: Some(Range::between(sheet_name, start, end).to_string()),
+                    values: Some(vec![cells]),
+                }
+            })
+            .collect::<Vec<_>>();
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let request = BatchUpdateValuesRequest {
+            data: Some(data),
+            value_input_option: Some("RAW".to_string()),
+            ..Default::default()
+        };
+        self.paced_write(
+            Write::json("update changed values", &range.to_string(), &request),
+            self.client
+                .spreadsheets()
+                .values_batch_update(request, &self.spreadsheet_id)
+                .doit(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records the day's headline numbers on the Stats tab, creating the tab
+    /// and its chart if they don't exist yet. Re-running for a date replaces
+    /// that date's row.
+    pub async fn update_stats(
+        &self,
+        date: &NaiveDate,
+        summary: &Summary,
+    ) -> Result<(), UpdateStatsError> {
+        let (stats_sheet_id, has_chart) = self.find_or_create_stats_sheet().await?;
+
+        let date_str = date.format("%Y-%m-%d").to_string();
+        let row = vec![
+            json!(date_str),
+            json!(summary.words),
+            json!(summary.points),
+            json!(summary.pangrams),
+        ];
+
+        let existing_dates = self
+            .traced(
+                "get values",
+                self.client
+                    .spreadsheets()
+                    .values_get(
+                        &self.spreadsheet_id,
+                        &Range::between(STATS_SHEET, Cell::new("A", 2), Cell::column("A"))
+                            .to_string(),
+                    )
+                    .doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .values
+            .unwrap_or_default();
+        let existing_row = existing_dates
+            .into_iter()
+            .position(|r| r.into_iter().next().and_then(cell_to_string) == Some(date_str.clone()));
+
+        match existing_row {
+            Some(index) => {
+                // Data starts on the second row
+                let row_number = index + 2;
+                self.write_changes(
+                    STATS_SHEET,
+                    ("A", "D"),
+                    (row_number, Some(row_number)),
+                    vec![row],
+                )
+                .await?;
+            }
+            None => {
+                let range =
+                    Range::between(STATS_SHEET, Cell::column("A"), Cell::column("D")).to_string();
+                let value_range = ValueRange {
+                    major_dimension: Some("ROWS".to_string()),
+                    range: Some(range.clone()),
+                    values: Some(vec![row]),
+                };
+                self.paced_write(
+                    Write::json("append values", &range, &value_range),
+                    self.client
+                        .spreadsheets()
+                        .values_append(value_range, &self.spreadsheet_id, &range)
+                        .value_input_option("RAW")
+                        .insert_data_option("INSERT_ROWS")
+                        .doit(),
+                )
+                .await?;
+            }
+        }
+
+        if !has_chart {
+            let request = BatchUpdateSpreadsheetRequest {
+                requests: Some(vec![Request {
+                    add_chart: Some(AddChartRequest {
+                        chart: Some(stats_chart(stats_sheet_id)),
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            };
+            self.paced_write(
+                Write::json("add chart", STATS_SHEET, &request),
+                self.client
+                    .spreadsheets()
+                    .batch_update(request, &self.spreadsheet_id)
+                    .doit(),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // Returns the Stats tab's ID, and whether it already has a chart
+    async fn find_or_create_stats_sheet(&self) -> Result<(i32, bool), UpdateStatsError> {
+        let existing = self
+            .traced(
+                "get spreadsheet",
+                self.client.spreadsheets().get(&self.spreadsheet_id).doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .sheets
+            .ok_or(UpdateStatsError::NoSheets)?
+            .into_iter()
+            .find(|sheet| has_title(sheet, STATS_SHEET));
+
+        if let Some(sheet) = existing {
+            let sheet_id = sheet
+                .properties
+                .and_then(|p| p.sheet_id)
+                .ok_or(UpdateStatsError::MissingResponse)?;
+            let has_chart = sheet.charts.map(|c| !c.is_empty()).unwrap_or(false);
+            return Ok((sheet_id, has_chart));
+        }
+
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(vec![Request {
+                add_sheet: Some(AddSheetRequest {
+                    properties: Some(SheetProperties {
+                        title: Some(STATS_SHEET.to_string()),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let sheet_id = self
+            .paced_write(
+                Write::json("add tab", STATS_SHEET, &request),
+                self.client
+                    .spreadsheets()
+                    .batch_update(request, &self.spreadsheet_id)
+                    .doit(),
+            )
+            .await?
+            // parsed response only
+            .1
+            .replies
+            // use mut vector out so we can remove only response
+            .as_mut()
+            .map(|replies| replies.remove(0))
+            .and_then(|reply| reply.add_sheet)
+            .and_then(|resp| resp.properties)
+            .and_then(|props| props.sheet_id)
+            .ok_or(UpdateStatsError::MissingResponse)?;
+
+        let range = Range::between(STATS_SHEET, Cell::new("A", 1), Cell::new("D", 1)).to_string();
+        let header = ValueRange {
+            major_dimension: Some("ROWS".to_string()),
+            range: Some(range.clone()),
+            values: Some(vec![STATS_HEADER.iter().map(|h| json!(h)).collect()]),
+        };
+        self.paced_write(
+            Write::json("update values", &range, &header),
+            self.client
+                .spreadsheets()
+                .values_update(header, &self.spreadsheet_id, &range)
+                .value_input_option("RAW")
+                .doit(),
+        )
+        .await?;
+
+        Ok((sheet_id, false))
+    }
+
+    async fn protect_sheet_ranges(
+        &self,
+        template_id: i32,
+        template_protected: bool,
+        new_sheet_id: i32,
+    ) -> Result<(), ProtectRangesError> {
+        let protect = |range: GridRange, description: &str| Request {
+            add_protected_range: Some(AddProtectedRangeRequest {
+                protected_range: Some(ProtectedRange {
+                    range: Some(range),
+                    description: Some(description.to_string()),
+                    warning_only: Some(true),
+                    ..Default::default()
+                }),
+            }),
+            ..Default::default()
+        };
+
+        let mut requests = Vec::new();
+
+        // The new tab inherits the template's protection when it's
+        // duplicated, which would warn on every edit; swap it for protection
+        // on just the data we wrote.
+        let copied_protection = self
+            .traced(
+                "get spreadsheet",
+                self.client.spreadsheets().get(&self.spreadsheet_id).doit(),
+            )
             .await?
-            // get parsed data only
+            // parsed response only
             .1
-            // sheets of document
             .sheets
-            .ok_or(FindingTemplateError::NoSheets)?
+            .unwrap_or_default()
             .into_iter()
-            // find template sheet in sheets
-            .find(is_template)
-            .ok_or(FindingTemplateError::DidNotFindSheet)
+            .find(|sheet| sheet.properties.as_ref().and_then(|p| p.sheet_id) == Some(new_sheet_id))
+            .and_then(|sheet| sheet.protected_ranges)
+            .unwrap_or_default();
+        requests.extend(
+            copied_protection
+                .into_iter()
+                .filter_map(|range| range.protected_range_id)
+                .map(|id| Request {
+                    delete_protected_range: Some(DeleteProtectedRangeRequest {
+                        protected_range_id: Some(id),
+                    }),
+                    ..Default::default()
+                }),
+        );
+
+        if !template_protected {
+            let whole_sheet = GridRange {
+                sheet_id: Some(template_id),
+                ..Default::default()
+            };
+            requests.push(protect(whole_sheet, "gridder template"));
+        }
+
+        for (start, end) in DATA_COLUMNS {
+            let data = GridRange {
+                sheet_id: Some(new_sheet_id),
+                start_row_index: Some(DATA_START_ROW),
+                start_column_index: Some(start),
+                end_column_index: Some(end),
+                ..Default::default()
+            };
+            requests.push(protect(data, "imported by gridder"));
+        }
+
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(requests),
+            ..Default::default()
+        };
+        self.paced_write(
+            Write::json("protect ranges", &new_sheet_id.to_string(), &request),
+            self.client
+                .spreadsheets()
+                .batch_update(request, &self.spreadsheet_id)
+                .doit(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_template(&self) -> Result<google_sheets4::api::Sheet, FindingTemplateError> {
+        self.traced(
+            "get spreadsheet",
+            self.client
+                .spreadsheets()
+                .get(&self.spreadsheet_id)
+                // perform spreadsheets.get() request
+                .doit(),
+        )
+        .await?
+        // get parsed data only
+        .1
+        // sheets of document
+        .sheets
+        .ok_or(FindingTemplateError::NoSheets)?
+        .into_iter()
+        // find template sheet in sheets
+        .find(is_template)
+        .ok_or(FindingTemplateError::DidNotFindSheet)
     }
 
     async fn duplicate_template(
@@ -160,7 +1427,7 @@ impl SheetManager {
         let duplicate_req = DuplicateSheetRequest {
             source_sheet_id: Some(template_id),
             insert_sheet_index: Some(1),
-            new_sheet_name: Some(date.format("%Y-%m-%d").to_string()),
+            new_sheet_name: Some(self.sheet_names.render(date)),
             new_sheet_id: None,
         };
         let request = BatchUpdateSpreadsheetRequest {
@@ -171,52 +1438,174 @@ impl SheetManager {
             ..Default::default()
         };
 
-        self.client
-            .spreadsheets()
-            .batch_update(request, &self.spreadsheet_id)
-            .doit()
-            .await?
-            // parsed response only
-            .1
-            .replies
-            // use mut vector out so we can remove only response
-            .as_mut()
-            .map(|replies| replies.remove(0))
-            .and_then(|reply| reply.duplicate_sheet)
-            .and_then(|resp| resp.properties)
-            .ok_or(DuplicatingTemplateError::MissingResponse)
+        self.paced_write(
+            Write::json("duplicate tab", "TEMPLATE", &request),
+            self.client
+                .spreadsheets()
+                .batch_update(request, &self.spreadsheet_id)
+                .doit(),
+        )
+        .await?
+        // parsed response only
+        .1
+        .replies
+        // use mut vector out so we can remove only response
+        .as_mut()
+        .map(|replies| replies.remove(0))
+        .and_then(|reply| reply.duplicate_sheet)
+        .and_then(|resp| resp.properties)
+        .ok_or(DuplicatingTemplateError::MissingResponse)
+    }
+
+    async fn delete_sheet(&self, sheet_id: i32) -> Result<(), google_sheets4::Error> {
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(vec![Request {
+                delete_sheet: Some(DeleteSheetRequest {
+                    sheet_id: Some(sheet_id),
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        self.paced_write(
+            Write::json("delete tab", &sheet_id.to_string(), &request),
+            self.client
+                .spreadsheets()
+                .batch_update(request, &self.spreadsheet_id)
+                .doit(),
+        )
+        .await?;
+
+        Ok(())
     }
 
     async fn populate_new_sheet(
         &self,
+        sheet_id: i32,
         sheet_name: &str,
         pairs: &PairInfo,
         lengths: &LengthInfo,
+        provenance: &Provenance,
     ) -> Result<(), PopulateNewSheetError> {
-        let pairs_value_range = ValueRange {
-            major_dimension: Some("ROWS".to_string()),
-            range: Some(format!("'{sheet_name}'!F3:G")),
-            values: Some(pairs_to_values(pairs)),
-        };
+        // A tab being filled again may already have some of the grid, so
+        // only what differs is sent
+        self.write_changes(sheet_name, ("F", "G"), (3, None), pairs_to_values(pairs))
+            .await?;
+        self.write_changes(
+            sheet_name,
+            ("B", "D"),
+            (3, None),
+            lengths_to_values(lengths),
+        )
+        .await?;
 
-        let lengths_value_range = ValueRange {
-            major_dimension: Some("ROWS".to_string()),
-            range: Some(format!("'{sheet_name}'!B3:D")),
-            values: Some(lengths_to_values(lengths)),
+        if self.provenance_notes {
+            self.add_notes(sheet_id, &[("B", 3), ("F", 3)], &provenance.note())
+                .await?;
+        }
+        if self.tidy_layout {
+            self.tidy_new_sheet(sheet_id).await?;
+        }
+
+        Ok(())
+    }
+
+    // Puts `note` on each of `cells` on the tab `sheet_id`
+    async fn add_notes(
+        &self,
+        sheet_id: i32,
+        cells: &[(&str, i32)],
+        note: &str,
+    ) -> Result<(), google_sheets4::Error> {
+        let requests = cells
+            .iter()
+            .map(|(column, row)| Request {
+                update_cells: Some(UpdateCellsRequest {
+                    start: Some(GridCoordinate {
+                        sheet_id: Some(sheet_id),
+                        // Both 0-based
+                        row_index: Some(row - 1),
+                        column_index: a1::column_number(column)
+                            .and_then(|number| i32::try_from(number - 1).ok()),
+                    }),
+                    rows: Some(vec![RowData {
+                        values: Some(vec![CellData {
+                            note: Some(note.to_string()),
+                            ..Default::default()
+                        }]),
+                    }]),
+                    fields: Some(FieldMask::new(&["note"])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect();
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(requests),
+            ..Default::default()
         };
 
-        let request = BatchUpdateValuesRequest {
-            data: Some(vec![pairs_value_range, lengths_value_range]),
-            value_input_option: Some("RAW".to_string()),
+        self.paced_write(
+            Write::json("add notes", &sheet_id.to_string(), &request),
+            self.client
+                .spreadsheets()
+                .batch_update(request, &self.spreadsheet_id)
+                .doit(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn tidy_new_sheet(&self, sheet_id: i32) -> Result<(), PopulateNewSheetError> {
+        let freeze_header = Request {
+            update_sheet_properties: Some(UpdateSheetPropertiesRequest {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(sheet_id),
+                    grid_properties: Some(GridProperties {
+                        frozen_row_count: Some(DATA_START_ROW),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                fields: Some(FieldMask::new(&["gridProperties.frozenRowCount"])),
+            }),
             ..Default::default()
         };
 
-        self.client
-            .spreadsheets()
-            .values_batch_update(request, &self.spreadsheet_id)
-            .doit()
-            .await?;
+        let mut requests = vec![freeze_header];
+        requests.extend(DATA_COLUMNS.iter().map(|(start, end)| Request {
+            auto_resize_dimensions: Some(AutoResizeDimensionsRequest {
+                dimensions: Some(DimensionRange {
+                    sheet_id: Some(sheet_id),
+                    dimension: Some("COLUMNS".to_string()),
+                    start_index: Some(*start),
+                    end_index: Some(*end),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }));
+
+        let request = BatchUpdateSpreadsheetRequest {
+            requests: Some(requests),
+            ..Default::default()
+        };
+        self.paced_write(
+            Write::json("tidy layout", &sheet_id.to_string(), &request),
+            self.client
+                .spreadsheets()
+                .batch_update(request, &self.spreadsheet_id)
+                .doit(),
+        )
+        .await?;
 
         Ok(())
     }
 }
+
+fn header_pairs(headers: &hyper::header::HeaderMap) -> impl Iterator<Item = (&str, &[u8])> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_bytes()))
+}
@@ -0,0 +1,28 @@
+/// How much of a day's puzzle a human-facing output may give away, from
+/// nothing at all up to every hint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum SpoilerLevel {
+    /// Only that there's a grid for the day.
+    None,
+    /// Word, point and pangram totals.
+    CountsOnly,
+    /// The totals, and the puzzle's letters.
+    Letters,
+    /// Everything: counts by letter and length, and the two-letter list.
+    #[default]
+    FullHints,
+}
+
+impl SpoilerLevel {
+    pub fn shows_counts(self) -> bool {
+        self >= Self::CountsOnly
+    }
+
+    pub fn shows_letters(self) -> bool {
+        self >= Self::Letters
+    }
+
+    pub fn shows_hints(self) -> bool {
+        self >= Self::FullHints
+    }
+}
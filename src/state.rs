@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::encryption::{self, Cipher, EncryptionError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum StateError {
+    #[error("failed to lock {0}: {1}")]
+    Locking(PathBuf, std::io::Error),
+    #[error("another gridder ({holder}) is already using {path}")]
+    AlreadyRunning { path: PathBuf, holder: String },
+    #[error("failed to read {0}: {1}")]
+    Reading(PathBuf, std::io::Error),
+    #[error("failed to parse {0}: {1}")]
+    Parsing(PathBuf, serde_json::Error),
+    #[error("failed to write {0}: {1}")]
+    Writing(PathBuf, std::io::Error),
+    #[error("failed to decrypt {0}: {1}")]
+    Decrypting(PathBuf, EncryptionError),
+    #[error("failed to encrypt {0}: {1}")]
+    Encrypting(PathBuf, EncryptionError),
+}
+
+/// A directory recording what runs have done, so a day isn't written
+/// twice.
+///
+/// Alongside the days written to every output, it journals which outputs
+/// each unfinished day has reached, so a run retried after a partial
+/// failure only sends to the outputs that missed out.
+///
+/// Only one gridder can have a directory open at a time: opening it takes
+/// an exclusive lock on `gridder.lock`, which is held until the store is
+/// dropped or the process exits, however it exits. The lock file holds
+/// the holder's process ID, to say who has it.
+///
+/// Given a [`Cipher`], what it records is encrypted. A plain directory is
+/// still read, and encrypted as it's next written.
+#[derive(Debug)]
+pub struct StateStore {
+    dir: PathBuf,
+    cipher: Option<Arc<Cipher>>,
+    _lock: File,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunState {
+    /// Days written to every output.
+    #[serde(default)]
+    processed: BTreeSet<NaiveDate>,
+    /// Outputs each day not yet processed has been written to, by key.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    delivered: BTreeMap<NaiveDate, BTreeSet<String>>,
+    /// The spreadsheet moved on to when the last one filled up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spreadsheet_id: Option<String>,
+}
+
+impl StateStore {
+    /// Opens `dir`, creating it if need be, and locks it.
+    pub fn open<P: Into<PathBuf>>(dir: P) -> Result<Self, StateError> {
+        let dir = dir.into();
+        let path = dir.join("gridder.lock");
+        let locking = |e| StateError::Locking(path.clone(), e);
+        fs::create_dir_all(&dir).map_err(locking)?;
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(locking)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut holder = String::new();
+                let _ = file.read_to_string(&mut holder);
+                let holder = match holder.trim() {
+                    "" => "unknown process".to_string(),
+                    pid => format!("pid {pid}"),
+                };
+                return Err(StateError::AlreadyRunning { path, holder });
+            }
+            Err(TryLockError::Error(e)) => return Err(locking(e)),
+        }
+        file.set_len(0)
+            .and_then(|()| writeln!(file, "{}", std::process::id()))
+            .map_err(locking)?;
+
+        Ok(Self {
+            dir,
+            cipher: None,
+            _lock: file,
+        })
+    }
+
+    /// Encrypts what's recorded with `cipher`, and decrypts it when read.
+    pub fn encrypted(mut self, cipher: Arc<Cipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether `date` has been written to every output.
+    pub fn is_processed(&self, date: NaiveDate) -> Result<bool, StateError> {
+        Ok(self.load()?.processed.contains(&date))
+    }
+
+    /// Records that `date` has been written to every output. Its journal
+    /// of outputs isn't needed after that, so it's dropped.
+    pub fn mark_processed(&self, date: NaiveDate) -> Result<(), StateError> {
+        let mut state = self.load()?;
+        state.processed.insert(date);
+        state.delivered.remove(&date);
+        self.save(&state)
+    }
+
+    /// The keys of the outputs `date` has been written to, if it hasn't
+    /// been processed.
+    pub fn delivered(&self, date: NaiveDate) -> Result<BTreeSet<String>, StateError> {
+        Ok(self.load()?.delivered.remove(&date).unwrap_or_default())
+    }
+
+    /// Records that `date` has been written to the output `key`.
+    pub fn mark_delivered(&self, date: NaiveDate, key: &str) -> Result<(), StateError> {
+        let mut state = self.load()?;
+        state
+            .delivered
+            .entry(date)
+            .or_default()
+            .insert(key.to_string());
+        self.save(&state)
+    }
+
+    /// The spreadsheet to write to instead of the configured one, if it's
+    /// been rotated.
+    pub fn spreadsheet_id(&self) -> Result<Option<String>, StateError> {
+        Ok(self.load()?.spreadsheet_id)
+    }
+
+    /// Records that writes have moved on to the spreadsheet `id`.
+    pub fn set_spreadsheet_id(&self, id: &str) -> Result<(), StateError> {
+        let mut state = self.load()?;
+        state.spreadsheet_id = Some(id.to_string());
+        self.save(&state)
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.dir.join("state.json")
+    }
+
+    fn load(&self) -> Result<RunState, StateError> {
+        let path = self.state_path();
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RunState::default()),
+            Err(e) => return Err(StateError::Reading(path, e)),
+        };
+        let contents = encryption::decrypt_if_needed(contents, self.cipher.as_deref())
+            .map_err(|e| StateError::Decrypting(path.clone(), e))?;
+
+        serde_json::from_slice(&contents).map_err(|e| StateError::Parsing(path, e))
+    }
+
+    // Written to a temporary file and renamed into place, so a crash part
+    // way through can't leave half a file
+    fn save(&self, state: &RunState) -> Result<(), StateError> {
+        let path = self.state_path();
+        let staged = self.dir.join("state.json.new");
+        // Serializing the state can't fail
+        let mut json = serde_json::to_vec_pretty(state).unwrap_or_default();
+        if let Some(cipher) = &self.cipher {
+            json = cipher
+                .encrypt(&json)
+                .map_err(|e| StateError::Encrypting(path.clone(), e))?;
+        }
+        fs::write(&staged, json)
+            .and_then(|()| fs::rename(&staged, &path))
+            .map_err(|e| StateError::Writing(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory of the test's own, emptied first
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gridder-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn a_rotated_spreadsheet_is_remembered() {
+        let dir = scratch_dir("rotated-spreadsheet");
+        let state = StateStore::open(&dir).unwrap();
+        assert_eq!(state.spreadsheet_id().unwrap(), None);
+        state.set_spreadsheet_id("first").unwrap();
+        state.set_spreadsheet_id("second").unwrap();
+        drop(state);
+
+        let state = StateStore::open(&dir).unwrap();
+        assert_eq!(state.spreadsheet_id().unwrap().as_deref(), Some("second"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
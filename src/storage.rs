@@ -0,0 +1,133 @@
+#[cfg(feature = "buckets")]
+use object_store::aws::AmazonS3Builder;
+#[cfg(feature = "buckets")]
+use object_store::gcp::GoogleCloudStorageBuilder;
+#[cfg(feature = "buckets")]
+use object_store::path::Path;
+#[cfg(feature = "buckets")]
+use object_store::{Attribute, Attributes, ObjectStore, PutOptions, PutPayload};
+
+use crate::document::GridDocument;
+#[cfg(feature = "buckets")]
+use crate::formats;
+use crate::formats::FormatError;
+use crate::network::Network;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("invalid bucket URL {0:?}, expected s3://BUCKET[/PREFIX] or gs://BUCKET[/PREFIX]")]
+    BadUrl(String),
+    #[error("gridder was built without {0} support (the {0} feature)")]
+    NotBuiltIn(&'static str),
+    #[cfg(feature = "buckets")]
+    #[error("failed to set up {0} ({1})")]
+    Connecting(String, object_store::Error),
+    #[error("failed to write the grid ({0})")]
+    Writing(#[from] FormatError),
+    #[cfg(feature = "buckets")]
+    #[error("failed to upload {0} ({1})")]
+    Uploading(String, object_store::Error),
+}
+
+/// Uploads each day's grid to an S3 or Google Cloud Storage bucket, for
+/// keeping outputs somewhere other than the local disk.
+///
+/// Each day's [files](formats::day_files) are uploaded under the URL's
+/// prefix, with their content types set. Credentials are read from
+/// the environment as each cloud's own tools do, e.g. `AWS_ACCESS_KEY_ID`
+/// or `GOOGLE_APPLICATION_CREDENTIALS`, and an S3-compatible service can be
+/// used with `AWS_ENDPOINT`.
+///
+/// Only builds with the `buckets` feature can upload.
+#[derive(Debug)]
+pub struct BucketSink {
+    url: String,
+    #[cfg(feature = "buckets")]
+    network: Network,
+    #[cfg(feature = "buckets")]
+    store: Box<dyn ObjectStore>,
+    #[cfg(feature = "buckets")]
+    prefix: Path,
+}
+
+impl BucketSink {
+    /// `url` is `s3://BUCKET[/PREFIX]` or `gs://BUCKET[/PREFIX]`.
+    #[cfg(feature = "buckets")]
+    pub fn new(network: Network, url: &str) -> Result<Self, StorageError> {
+        let bad_url = || StorageError::BadUrl(url.to_string());
+        let (scheme, rest) = url.split_once("://").ok_or_else(bad_url)?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(bad_url());
+        }
+
+        let connecting = |e| StorageError::Connecting(url.to_string(), e);
+        let store: Box<dyn ObjectStore> = match scheme {
+            "s3" => Box::new(
+                AmazonS3Builder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()
+                    .map_err(connecting)?,
+            ),
+            "gs" => Box::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()
+                    .map_err(connecting)?,
+            ),
+            _ => return Err(bad_url()),
+        };
+
+        Ok(Self {
+            url: url.to_string(),
+            network,
+            store,
+            prefix: Path::from(prefix),
+        })
+    }
+
+    #[cfg(not(feature = "buckets"))]
+    pub fn new(_network: Network, url: &str) -> Result<Self, StorageError> {
+        match url.split_once("://") {
+            Some(("s3" | "gs", _)) => Err(StorageError::NotBuiltIn("buckets")),
+            _ => Err(StorageError::BadUrl(url.to_string())),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// "s3" or "gs", after the URL's scheme.
+    pub fn name(&self) -> &'static str {
+        match self.url.starts_with("gs://") {
+            true => "gs",
+            false => "s3",
+        }
+    }
+
+    #[cfg(feature = "buckets")]
+    pub async fn upload(&self, doc: &GridDocument) -> Result<(), StorageError> {
+        for file in formats::day_files(doc)? {
+            let path = self.prefix.child(file.name);
+            let options = PutOptions {
+                attributes: Attributes::from_iter([(Attribute::ContentType, file.content_type)]),
+                ..PutOptions::default()
+            };
+            let put = self
+                .store
+                .put_opts(&path, PutPayload::from(file.contents), options);
+            self.network
+                .send(put)
+                .await
+                .map_err(|e| StorageError::Uploading(path.to_string(), e))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "buckets"))]
+    pub async fn upload(&self, _doc: &GridDocument) -> Result<(), StorageError> {
+        Err(StorageError::NotBuiltIn("buckets"))
+    }
+}
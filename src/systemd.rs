@@ -0,0 +1,103 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Settings for a systemd service and timer that run `gridder` daily.
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    /// Absolute path to the gridder binary.
+    pub executable: String,
+    /// User to run as. systemd's default (root) if unset.
+    pub user: Option<String>,
+    pub environment: Vec<(String, String)>,
+    /// When to run, as `OnCalendar=` values, e.g. from
+    /// [`Schedule::on_calendar`](crate::daemon::Schedule::on_calendar).
+    pub on_calendar: Vec<String>,
+}
+
+// Escapes a value for use inside a double-quoted unit file setting. `%`
+// introduces a specifier in unit files, so it needs doubling too.
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{escaped}\"")
+}
+
+impl ServiceConfig {
+    pub fn service_unit(&self) -> String {
+        let mut unit = String::new();
+        writeln!(unit, "[Unit]").unwrap();
+        writeln!(unit, "Description=Gridder generation task").unwrap();
+        writeln!(unit, "Wants=network-online.target").unwrap();
+        writeln!(unit, "After=network-online.target").unwrap();
+        writeln!(unit).unwrap();
+        writeln!(unit, "[Service]").unwrap();
+        writeln!(unit, "Type=oneshot").unwrap();
+        writeln!(unit, "ExecStart={}", quote(&self.executable)).unwrap();
+        if let Some(user) = &self.user {
+            writeln!(unit, "User={user}").unwrap();
+        }
+        for (key, value) in &self.environment {
+            writeln!(unit, "Environment={}", quote(&format!("{key}={value}"))).unwrap();
+        }
+
+        unit
+    }
+
+    pub fn timer_unit(&self) -> String {
+        let mut unit = String::new();
+        writeln!(unit, "[Unit]").unwrap();
+        writeln!(unit, "Description=Run gridder generation task daily").unwrap();
+        writeln!(unit).unwrap();
+        writeln!(unit, "[Timer]").unwrap();
+        writeln!(unit, "Unit=gridder.service").unwrap();
+        for calendar in &self.on_calendar {
+            writeln!(unit, "OnCalendar={calendar}").unwrap();
+        }
+        // Catch up if the machine was off at release time
+        writeln!(unit, "Persistent=true").unwrap();
+        writeln!(unit).unwrap();
+        writeln!(unit, "[Install]").unwrap();
+        writeln!(unit, "WantedBy=timers.target").unwrap();
+
+        unit
+    }
+}
+
+/// Tells systemd the service has started, when it's run as a
+/// `Type=notify` service (i.e. with `NOTIFY_SOCKET` set). Does nothing
+/// otherwise.
+pub fn notify_ready() {
+    #[cfg(unix)]
+    notify(sd_notify::NotifyState::Ready);
+}
+
+/// Keeps systemd's watchdog from restarting the service, when the unit
+/// sets `WatchdogSec=` (i.e. with `WATCHDOG_USEC` set). Does nothing
+/// otherwise.
+pub fn notify_watchdog() {
+    #[cfg(unix)]
+    notify(sd_notify::NotifyState::Watchdog);
+}
+
+/// How often to call [`notify_watchdog`]: half the unit's `WatchdogSec=`,
+/// as systemd suggests. Nothing if the watchdog is off.
+pub fn watchdog_interval() -> Option<Duration> {
+    #[cfg(unix)]
+    {
+        let mut usec = 0;
+        if sd_notify::watchdog_enabled(false, &mut usec) {
+            return Some(Duration::from_micros(usec) / 2);
+        }
+    }
+
+    None
+}
+
+// Nothing can be done if systemd isn't listening, and it'll say so itself
+// if it misses a ping it wanted
+#[cfg(unix)]
+fn notify(state: sd_notify::NotifyState) {
+    let _ = sd_notify::notify(false, &[state]);
+}
@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::document::GridDocument;
+use crate::{progress, score};
+
+/// A word a member found, and when it was first seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Find {
+    pub member: String,
+    pub word: String,
+    pub found_at: DateTime<Utc>,
+}
+
+/// Who found which words on one day.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayFinds {
+    pub finds: Vec<Find>,
+}
+
+/// One member's totals over a run of days.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberStats {
+    pub member: String,
+    /// Days the member found at least one word on.
+    pub days: usize,
+    pub words: usize,
+    pub points: usize,
+}
+
+impl DayFinds {
+    /// Records that `member` found `word` at `at`, unless they already had.
+    /// Returns whether it was new.
+    pub fn record(&mut self, member: &str, word: &str, at: DateTime<Utc>) -> bool {
+        let word = word.trim().to_ascii_uppercase();
+        let known = self
+            .finds
+            .iter()
+            .any(|find| find.member == member && find.word == word);
+        if known || word.is_empty() {
+            return false;
+        }
+
+        self.finds.push(Find {
+            member: member.to_string(),
+            word,
+            found_at: at,
+        });
+        true
+    }
+
+    /// Records each member's words as found at `at`, keeping the earlier
+    /// time for words seen before. Returns how many were new.
+    pub fn import(&mut self, members: &[(String, Vec<String>)], at: DateTime<Utc>) -> usize {
+        members
+            .iter()
+            .flat_map(|(member, words)| words.iter().map(move |word| (member, word)))
+            .filter(|(member, word)| self.record(member, word, at))
+            .count()
+    }
+
+    /// Each member's words, in the order they were found.
+    pub fn by_member(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut finds = self.finds.iter().collect::<Vec<_>>();
+        finds.sort_by_key(|find| find.found_at);
+
+        let mut members = BTreeMap::<_, Vec<_>>::new();
+        for find in finds {
+            members
+                .entry(find.member.as_str())
+                .or_default()
+                .push(find.word.as_str());
+        }
+
+        members
+    }
+}
+
+/// Totals up each member's finds, most points first. With a day's grid,
+/// only words that fit it count, and they're scored; without one, words
+/// are counted but score nothing.
+pub fn member_stats(days: &[(DayFinds, Option<GridDocument>)]) -> Vec<MemberStats> {
+    let mut stats = BTreeMap::<String, MemberStats>::new();
+    for (finds, doc) in days {
+        for (member, words) in finds.by_member() {
+            let (words, points) = match doc {
+                Some(doc) => {
+                    let found = progress::compute(doc, &words).found;
+                    (found.len(), score::total_score(&found, &doc.letters))
+                }
+                None => (words.iter().collect::<BTreeSet<_>>().len(), 0),
+            };
+
+            let entry = stats
+                .entry(member.to_string())
+                .or_insert_with(|| MemberStats {
+                    member: member.to_string(),
+                    days: 0,
+                    words: 0,
+                    points: 0,
+                });
+            entry.days += 1;
+            entry.words += words;
+            entry.points += points;
+        }
+    }
+
+    let mut stats = stats.into_values().collect::<Vec<_>>();
+    stats.sort_by(|a, b| {
+        (b.points, b.words)
+            .cmp(&(a.points, a.words))
+            .then_with(|| a.member.cmp(&b.member))
+    });
+
+    stats
+}
+
+impl fmt::Display for MemberStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {} points, {} words over {} days",
+            self.member, self.points, self.words, self.days
+        )
+    }
+}
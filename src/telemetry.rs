@@ -0,0 +1,99 @@
+use chrono::NaiveDate;
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::network::Network;
+use crate::parse::{Fingerprint, SiteParseError};
+use crate::update::Version;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[error("invalid telemetry URL {0:?}")]
+    BadUrl(String),
+    #[error("failed to send a parse failure report to {0} ({1})")]
+    Sending(Url, reqwest::Error),
+}
+
+/// Whether to report pages that fail to parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TelemetryChoice {
+    On,
+    #[default]
+    Off,
+}
+
+/// What's sent about a page that failed to parse: the kind of failure and
+/// the page's [`Fingerprint`], which only counts what the selectors match.
+/// Nothing from the page's text, the grid or the run's settings is sent.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseFailureReport {
+    pub version: String,
+    pub date: NaiveDate,
+    /// e.g. `missing_table`, from [`SiteParseError::kind`].
+    pub error: &'static str,
+    /// Whether any selectors were changed from the defaults, as the counts
+    /// then aren't comparable with other users'.
+    pub custom_selectors: bool,
+    pub fingerprint: Fingerprint,
+}
+
+impl ParseFailureReport {
+    pub fn new(
+        date: NaiveDate,
+        error: &SiteParseError,
+        custom_selectors: bool,
+        fingerprint: Fingerprint,
+    ) -> Self {
+        Self {
+            version: Version::current().to_string(),
+            date,
+            error: error.kind(),
+            custom_selectors,
+            fingerprint,
+        }
+    }
+}
+
+/// Sends a [`ParseFailureReport`] for each page that fails to parse, so
+/// the maintainers hear about the site's layout changing as soon as
+/// anyone's runs start breaking. Only ever used when opted into.
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl Telemetry {
+    /// Reports are POSTed to `url` as JSON.
+    pub fn new(network: Network, url: &str) -> Result<Self, TelemetryError> {
+        let url = Url::parse(url).map_err(|_| TelemetryError::BadUrl(url.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(TelemetryError::BadUrl(url.to_string()));
+        }
+
+        Ok(Self {
+            client: network.client(),
+            url,
+        })
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub async fn report(&self, report: &ParseFailureReport) -> Result<(), TelemetryError> {
+        let sending = |e| TelemetryError::Sending(self.url.clone(), e);
+        // Serializing a report can't fail
+        let body = serde_json::to_vec(report).unwrap_or_default();
+        self.client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(sending)?;
+
+        Ok(())
+    }
+}
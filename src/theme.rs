@@ -0,0 +1,106 @@
+use std::fmt;
+
+/// Whether to color terminal output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color output going to a terminal, unless NO_COLOR is set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Sets of colors to choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Palette {
+    #[default]
+    Standard,
+    /// Bold, bright colors only, for low-contrast terminals and color
+    /// blindness (no red/green pairs).
+    HighContrast,
+}
+
+/// What a piece of output is, which decides its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Heading,
+    /// Something done or found.
+    Good,
+    /// Something to look at, but not a failure.
+    Warning,
+    Bad,
+    /// Detail that can be skimmed past.
+    Muted,
+}
+
+/// How to color output for one stream. Plain themes leave text untouched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Theme {
+    palette: Option<Palette>,
+}
+
+impl Theme {
+    pub fn plain() -> Self {
+        Self { palette: None }
+    }
+
+    /// The theme for a stream, given whether it's a terminal. With `Auto`,
+    /// a set and non-empty NO_COLOR turns color off (see
+    /// https://no-color.org).
+    pub fn new(choice: ColorChoice, palette: Palette, is_terminal: bool) -> Self {
+        let enabled = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                is_terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+        };
+
+        Self {
+            palette: enabled.then_some(palette),
+        }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.palette.is_none()
+    }
+
+    pub fn paint<T: fmt::Display>(&self, role: Role, text: T) -> Painted<T> {
+        Painted {
+            code: self.palette.map(|palette| code(palette, role)),
+            text,
+        }
+    }
+}
+
+// SGR parameters for each role
+fn code(palette: Palette, role: Role) -> &'static str {
+    match (palette, role) {
+        (Palette::Standard, Role::Heading) => "1",
+        (Palette::Standard, Role::Good) => "32",
+        (Palette::Standard, Role::Warning) => "33",
+        (Palette::Standard, Role::Bad) => "31",
+        (Palette::Standard, Role::Muted) => "2",
+        (Palette::HighContrast, Role::Heading) => "1;4",
+        (Palette::HighContrast, Role::Good) => "1;94",
+        (Palette::HighContrast, Role::Warning) => "1;93",
+        (Palette::HighContrast, Role::Bad) => "1;95",
+        (Palette::HighContrast, Role::Muted) => "37",
+    }
+}
+
+/// Text that displays in a [`Role`]'s color, if its theme has any.
+#[derive(Debug, Clone, Copy)]
+pub struct Painted<T> {
+    code: Option<&'static str>,
+    text: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "\x1b[{code}m{}\x1b[0m", self.text),
+            None => self.text.fmt(f),
+        }
+    }
+}
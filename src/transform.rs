@@ -0,0 +1,262 @@
+#[cfg(feature = "scripting")]
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+
+use crate::document::{DocumentError, GridDocument};
+use crate::formats::{self, FormatError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransformError {
+    #[error("failed to run {0:?}: {1}")]
+    Running(String, std::io::Error),
+    #[error("{command:?} failed ({status})")]
+    CommandFailed {
+        command: String,
+        status: std::process::ExitStatus,
+    },
+    #[error("{0:?} didn't give back a grid document: {1}")]
+    BadOutput(String, FormatError),
+    #[error("{0:?} gave back an unusable grid: {1}")]
+    Document(String, DocumentError),
+    #[error("{0:?} changed the grid's date from {1} to {2}")]
+    ChangedDate(String, chrono::NaiveDate, chrono::NaiveDate),
+    #[cfg(feature = "scripting")]
+    #[error("couldn't load {0:?}: {1}")]
+    LoadingScript(String, Box<rhai::EvalAltResult>),
+    #[cfg(feature = "scripting")]
+    #[error("{0:?} failed: {1}")]
+    ScriptFailed(String, Box<rhai::EvalAltResult>),
+}
+
+// A script stuck in a loop can't be given up on at the deadline, as it
+// runs on the task that started it, so it's stopped after this many steps
+#[cfg(feature = "scripting")]
+const MAX_SCRIPT_OPERATIONS: u64 = 10_000_000;
+
+/// A command, or with the `scripting` feature a rhai script, that
+/// post-processes each day's document before any output is written, e.g.
+/// to add derived figures under `extra`.
+///
+/// The command is run with `sh -c`, with the [`GridDocument`] JSON on its
+/// stdin and the grid's date in `GRIDDER_DATE`, and must print the
+/// document to use instead on its stdout. Its stderr is passed through.
+/// A script is given the document as `doc`, and whatever it leaves there
+/// is used. Anything but a valid document for the same day fails the run
+/// before anything is written.
+#[derive(Debug, Clone)]
+pub struct Transform {
+    // The command, or the script's path, for errors to name
+    name: String,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Command,
+    #[cfg(feature = "scripting")]
+    Script(rhai::AST),
+}
+
+impl Transform {
+    pub fn new(command: &str) -> Self {
+        Self {
+            name: command.to_string(),
+            kind: Kind::Command,
+        }
+    }
+
+    /// Loads the rhai script at `path`, so mistakes in it are found before
+    /// any day's run.
+    #[cfg(feature = "scripting")]
+    pub fn script(path: &Path) -> Result<Self, TransformError> {
+        let name = path.display().to_string();
+        let ast = script_engine()
+            .compile_file(path.to_path_buf())
+            .map_err(|e| TransformError::LoadingScript(name.clone(), e))?;
+
+        Ok(Self {
+            name,
+            kind: Kind::Script(ast),
+        })
+    }
+
+    pub async fn apply(&self, doc: &GridDocument) -> Result<GridDocument, TransformError> {
+        let output = match &self.kind {
+            Kind::Command => self.run_command(doc).await?,
+            #[cfg(feature = "scripting")]
+            Kind::Script(ast) => self.run_script(ast, doc)?,
+        };
+
+        let transformed = formats::read_json(output.as_slice())
+            .map_err(|e| TransformError::BadOutput(self.name.clone(), e))?;
+        transformed
+            .pair_info()
+            .map_err(|e| TransformError::Document(self.name.clone(), e))?;
+        if transformed.date != doc.date {
+            return Err(TransformError::ChangedDate(
+                self.name.clone(),
+                doc.date,
+                transformed.date,
+            ));
+        }
+
+        Ok(transformed)
+    }
+
+    // The document the command printed
+    async fn run_command(&self, doc: &GridDocument) -> Result<Vec<u8>, TransformError> {
+        let running = |e| TransformError::Running(self.name.clone(), e);
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.name)
+            .env("GRIDDER_DATE", doc.date.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Given up on (e.g. past the deadline), it mustn't carry on
+            // writing after the run's been reported failed
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(running)?;
+
+        // Serializing a document can't fail
+        let json = serde_json::to_vec_pretty(doc).unwrap_or_default();
+        let output = feed_and_wait(child, json).await.map_err(running)?;
+        if !output.status.success() {
+            return Err(TransformError::CommandFailed {
+                command: self.name.clone(),
+                status: output.status,
+            });
+        }
+
+        Ok(output.stdout)
+    }
+
+    // The document the script left in `doc`, as JSON to be read back the
+    // same way as a command's
+    #[cfg(feature = "scripting")]
+    fn run_script(&self, ast: &rhai::AST, doc: &GridDocument) -> Result<Vec<u8>, TransformError> {
+        let failed = |e| TransformError::ScriptFailed(self.name.clone(), e);
+        // As the JSON a command would get, so letters are strings rather
+        // than rhai's own characters
+        let mut json = serde_json::to_value(doc).unwrap_or_default();
+        // Left out when empty, but it's where scripts add what they work out
+        if let Some(fields) = json.as_object_mut() {
+            fields
+                .entry("extra")
+                .or_insert_with(|| serde_json::json!({}));
+        }
+        let mut scope = rhai::Scope::new();
+        scope.push("doc", rhai::serde::to_dynamic(json).map_err(failed)?);
+        script_engine()
+            .run_ast_with_scope(&mut scope, ast)
+            .map_err(failed)?;
+
+        let doc = scope.get("doc").cloned().unwrap_or_default();
+        serde_json::to_vec(&doc).map_err(|e| TransformError::BadOutput(self.name.clone(), e.into()))
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn script_engine() -> rhai::Engine {
+    let mut engine = rhai::Engine::new();
+    engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+    engine
+}
+
+/// Writes `input` to a child's stdin while collecting its output, so
+/// neither side can fill its pipe and stall the other. A command that
+/// doesn't read its input closes the pipe early, which is its business;
+/// the exit status says how it went.
+pub(crate) async fn feed_and_wait(mut child: Child, input: Vec<u8>) -> std::io::Result<Output> {
+    let stdin = child.stdin.take();
+    let write = async move {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(&input).await;
+        }
+    };
+    let (_, output) = tokio::join!(write, child.wait_with_output());
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LengthInfo, PairInfo, WordCount, WordLength};
+
+    fn doc() -> GridDocument {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let pairs = PairInfo::from([(('A', 'C'), WordCount(2))]);
+        let lengths = LengthInfo::from([(('A', WordLength(4)), WordCount(2))]);
+        GridDocument::new(date, &pairs, &lengths)
+    }
+
+    #[tokio::test]
+    async fn commands_give_back_the_document_to_use() {
+        assert_eq!(Transform::new("cat").apply(&doc()).await.unwrap(), doc());
+
+        let add = r#"sed 's/"hints": \[\]/"hints": ["seen"]/'"#;
+        let transformed = Transform::new(add).apply(&doc()).await.unwrap();
+        assert_eq!(transformed.hints, ["seen"]);
+    }
+
+    #[tokio::test]
+    async fn anything_but_a_document_for_the_day_fails() {
+        let apply = |command| async move { Transform::new(command).apply(&doc()).await };
+        assert!(matches!(
+            apply("cat >/dev/null; exit 3").await,
+            Err(TransformError::CommandFailed { .. })
+        ));
+        assert!(matches!(
+            apply("echo nope").await,
+            Err(TransformError::BadOutput(..))
+        ));
+        assert!(matches!(
+            apply("sed s/2024-05-01/2024-05-02/").await,
+            Err(TransformError::ChangedDate(..))
+        ));
+    }
+
+    #[cfg(feature = "scripting")]
+    fn script(name: &str, source: &str) -> Result<Transform, TransformError> {
+        let path = std::env::temp_dir().join(format!("gridder-{name}-{}.rhai", std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let script = Transform::script(&path);
+        let _ = std::fs::remove_file(&path);
+        script
+    }
+
+    #[cfg(feature = "scripting")]
+    #[tokio::test]
+    async fn scripts_change_the_document_in_place() {
+        let transform = script("extra", "doc.extra.pairs = doc.pairs.len();").unwrap();
+        let transformed = transform.apply(&doc()).await.unwrap();
+        assert_eq!(transformed.extra["pairs"], serde_json::json!(1));
+        assert_eq!(transformed.pairs, doc().pairs);
+    }
+
+    #[cfg(feature = "scripting")]
+    #[tokio::test]
+    async fn scripts_are_checked_like_commands() {
+        assert!(matches!(
+            script("syntax", "doc.extra ="),
+            Err(TransformError::LoadingScript(..))
+        ));
+        let apply = |source| async move { script("checked", source).unwrap().apply(&doc()).await };
+        assert!(matches!(
+            apply("loop {}").await,
+            Err(TransformError::ScriptFailed(..))
+        ));
+        assert!(matches!(
+            apply("doc = 1;").await,
+            Err(TransformError::BadOutput(..))
+        ));
+        assert!(matches!(
+            apply(r#"doc.date = "2024-05-02";"#).await,
+            Err(TransformError::ChangedDate(..))
+        ));
+    }
+}
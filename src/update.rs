@@ -0,0 +1,335 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use reqwest::header;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::network::Network;
+
+/// Where releases are published, unless an [`Updater`] is told otherwise.
+pub const DEFAULT_REPO: &str = "denbeigh2000/gridder";
+
+const USER_AGENT: &str = concat!("gridder/", env!("CARGO_PKG_VERSION"));
+
+/// The Ed25519 public key releases are signed with, in hex, as given in
+/// `GRIDDER_RELEASE_KEY` when this was built. Builds without one can't
+/// update themselves.
+const RELEASE_KEY: Option<&str> = option_env!("GRIDDER_RELEASE_KEY");
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateError {
+    #[error("failed to reach GitHub ({0})")]
+    Requesting(reqwest::Error),
+    #[error("failed to read the latest release ({0})")]
+    ParsingRelease(serde_json::Error),
+    #[error("release {0} isn't named after a version")]
+    BadVersion(String),
+    #[error("release {tag} has no {name} to download")]
+    MissingAsset { tag: String, name: String },
+    #[error("{0} isn't a SHA-256 checksum file for {1}")]
+    BadChecksumFile(String, String),
+    #[error("{name} doesn't match its published checksum (expected {expected}, got {actual})")]
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("this build has no release key to check downloads with")]
+    NoReleaseKey,
+    #[error("{0} isn't signed with the release key")]
+    BadSignature(String),
+    #[error("failed to replace {0} ({1})")]
+    Replacing(PathBuf, std::io::Error),
+}
+
+/// A release version, e.g. `1.2.3` or the tag `v1.2.3`. Anything after a
+/// `-` or `+` (pre-release or build details) is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u64, pub u64, pub u64);
+
+impl Version {
+    /// The version of this build.
+    pub fn current() -> Self {
+        // Cargo only accepts semver versions
+        env!("CARGO_PKG_VERSION").parse().unwrap()
+    }
+}
+
+impl FromStr for Version {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('v').unwrap_or(s);
+        let core = s.split(['-', '+']).next().unwrap_or_default();
+        let mut parts = core.split('.').map(|part| part.parse::<u64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => {
+                Ok(Self(major, minor, patch))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// A published release, as GitHub describes it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    pub fn version(&self) -> Result<Version, UpdateError> {
+        self.tag_name
+            .parse()
+            .map_err(|()| UpdateError::BadVersion(self.tag_name.clone()))
+    }
+
+    fn asset(&self, name: &str) -> Result<&Asset, UpdateError> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| UpdateError::MissingAsset {
+                tag: self.tag_name.clone(),
+                name: name.to_string(),
+            })
+    }
+}
+
+/// The name of the release binary for this platform, e.g.
+/// `gridder-x86_64-linux`. Each is published alongside a `.sha256` file
+/// in `sha256sum`'s format, and a `.sig` file holding its 64 byte Ed25519
+/// signature (as `openssl pkeyutl -sign -rawin` writes).
+pub fn asset_name() -> String {
+    format!(
+        "gridder-{}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    )
+}
+
+/// Finds and downloads releases from a GitHub repository.
+pub struct Updater {
+    client: reqwest::Client,
+    repo: String,
+}
+
+impl Updater {
+    /// `repo` is the repository's `owner/name`.
+    pub fn new(network: Network, repo: &str) -> Self {
+        Self {
+            client: network.client(),
+            repo: repo.to_string(),
+        }
+    }
+
+    /// The newest release that isn't a draft or pre-release.
+    pub async fn latest(&self) -> Result<Release, UpdateError> {
+        let url = format!("https://api.github.com/repos/{}/releases/latest", self.repo);
+        let body = self.get(&url, "application/vnd.github+json").await?;
+        serde_json::from_slice(&body).map_err(UpdateError::ParsingRelease)
+    }
+
+    /// Downloads this platform's binary from `release`, and checks it
+    /// against the checksum published with it and its signature, which
+    /// must be from the release key this was built with.
+    pub async fn download(&self, release: &Release) -> Result<Vec<u8>, UpdateError> {
+        let key = release_key().ok_or(UpdateError::NoReleaseKey)?;
+        let name = asset_name();
+        let checksum_name = format!("{name}.sha256");
+        let signature_name = format!("{name}.sig");
+        let binary_asset = release.asset(&name)?;
+        let checksum_asset = release.asset(&checksum_name)?;
+        let signature_asset = release.asset(&signature_name)?;
+
+        let checksum = self
+            .get(
+                &checksum_asset.browser_download_url,
+                "application/octet-stream",
+            )
+            .await?;
+        let expected = parse_checksum(&checksum, &name)
+            .ok_or_else(|| UpdateError::BadChecksumFile(checksum_name, name.clone()))?;
+        let signature = self
+            .get(
+                &signature_asset.browser_download_url,
+                "application/octet-stream",
+            )
+            .await?;
+
+        let binary = self
+            .get(
+                &binary_asset.browser_download_url,
+                "application/octet-stream",
+            )
+            .await?;
+        let actual = sha256_hex(&binary);
+        if actual != expected {
+            return Err(UpdateError::ChecksumMismatch {
+                name,
+                expected,
+                actual,
+            });
+        }
+        if !signed_by(&key, &binary, &signature) {
+            return Err(UpdateError::BadSignature(name));
+        }
+
+        Ok(binary)
+    }
+
+    async fn get(&self, url: &str, accept: &str) -> Result<Vec<u8>, UpdateError> {
+        let response = self
+            .client
+            .get(url)
+            .header(header::USER_AGENT, USER_AGENT)
+            .header(header::ACCEPT, accept)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(UpdateError::Requesting)?;
+        let body = response.bytes().await.map_err(UpdateError::Requesting)?;
+
+        Ok(body.to_vec())
+    }
+}
+
+// The hash for `name` from `sha256sum` lines ("HASH  NAME", or "HASH
+// *NAME" for binary mode), or from a file holding just the hash
+fn parse_checksum(contents: &[u8], name: &str) -> Option<String> {
+    let contents = std::str::from_utf8(contents).ok()?;
+    contents.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let hash = fields.next()?.to_ascii_lowercase();
+        let named = match fields.next() {
+            Some(file) => file.strip_prefix('*').unwrap_or(file) == name,
+            None => true,
+        };
+        let is_hash = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        (named && is_hash && fields.next().is_none()).then_some(hash)
+    })
+}
+
+fn release_key() -> Option<Vec<u8>> {
+    let key = RELEASE_KEY?.trim();
+    let key = (0..key.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(key.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    (key.len() == 32).then_some(key)
+}
+
+// Whether `signature` is `key`'s Ed25519 signature of `data`
+fn signed_by(key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(data, signature)
+        .is_ok()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Replaces the executable at `path` with `binary`, keeping its
+/// permissions. The new binary is written next to it first and renamed
+/// into place, so a failure part way leaves the old one working.
+pub fn replace_executable(path: &Path, binary: &[u8]) -> Result<(), UpdateError> {
+    let replacing = |e| UpdateError::Replacing(path.to_path_buf(), e);
+    let permissions = fs::metadata(path).map_err(replacing)?.permissions();
+
+    let mut staged_name = path.file_name().unwrap_or_default().to_os_string();
+    staged_name.push(".new");
+    let staged = path.with_file_name(staged_name);
+    let result = fs::write(&staged, binary)
+        .and_then(|()| fs::set_permissions(&staged, permissions))
+        .and_then(|()| fs::rename(&staged, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+
+    result.map_err(replacing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn versions_parse_with_or_without_a_tag_prefix() {
+        assert_eq!("1.2.3".parse(), Ok(Version(1, 2, 3)));
+        assert_eq!("v10.0.7".parse(), Ok(Version(10, 0, 7)));
+        assert_eq!("v1.2.3-rc.1+build.5".parse(), Ok(Version(1, 2, 3)));
+        assert_eq!("1.2".parse::<Version>(), Err(()));
+        assert_eq!("1.2.3.4".parse::<Version>(), Err(()));
+        assert_eq!("latest".parse::<Version>(), Err(()));
+        assert!(Version(1, 10, 0) > Version(1, 9, 9));
+    }
+
+    #[test]
+    fn checksums_must_be_for_the_asset() {
+        let name = "gridder-x86_64-linux";
+        let line = |file: &str| format!("{HASH}  {file}\n");
+        assert_eq!(parse_checksum(HASH.as_bytes(), name).as_deref(), Some(HASH));
+        assert_eq!(
+            parse_checksum(line(name).as_bytes(), name).as_deref(),
+            Some(HASH)
+        );
+        assert_eq!(
+            parse_checksum(format!("{HASH} *{name}").as_bytes(), name).as_deref(),
+            Some(HASH)
+        );
+        assert_eq!(
+            parse_checksum(line("gridder-aarch64-macos").as_bytes(), name),
+            None
+        );
+
+        let several = format!("{}  gridder-aarch64-macos\n{}", "0".repeat(64), line(name));
+        assert_eq!(
+            parse_checksum(several.as_bytes(), name).as_deref(),
+            Some(HASH)
+        );
+        assert_eq!(parse_checksum(b"not a hash", name), None);
+    }
+
+    #[test]
+    fn only_the_release_keys_signatures_are_accepted() {
+        let rng = SystemRandom::new();
+        let pair = |rng| {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(rng).unwrap();
+            Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+        };
+        let release = pair(&rng);
+        let other = pair(&rng);
+        let binary = b"\x7fELF...";
+        let signature = release.sign(binary);
+
+        let key = release.public_key().as_ref();
+        assert!(signed_by(key, binary, signature.as_ref()));
+        assert!(!signed_by(key, b"\x7fELF!!!", signature.as_ref()));
+        assert!(!signed_by(key, binary, other.sign(binary).as_ref()));
+        assert!(!signed_by(key, binary, &signature.as_ref()[..63]));
+    }
+}
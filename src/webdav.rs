@@ -0,0 +1,115 @@
+use reqwest::{header, Method, StatusCode, Url};
+
+use crate::document::GridDocument;
+use crate::formats::{self, FormatError};
+use crate::network::Network;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebdavError {
+    #[error("invalid WebDAV URL {0:?}, expected webdav:https://HOST/PATH")]
+    BadUrl(String),
+    #[error("failed to write the grid ({0})")]
+    Formatting(#[from] FormatError),
+    #[error("failed to upload to {0} ({1})")]
+    Sending(Url, reqwest::Error),
+    #[error("{url} refused the upload ({status})")]
+    Refused { url: Url, status: StatusCode },
+}
+
+/// Uploads each day's grid to a folder on a WebDAV server, e.g. a
+/// Nextcloud's (`https://HOST/remote.php/dav/files/USER/FOLDER`), for groups
+/// that share files there.
+///
+/// Each day's [files](formats::day_files) are put in the folder, which is
+/// made first if it's missing (though its parents have to be there), and
+/// replace any already uploaded for the day.
+#[derive(Debug)]
+pub struct WebdavSink {
+    client: reqwest::Client,
+    // Always ends in a slash, so files are joined on inside it
+    folder: Url,
+    credentials: Option<(String, String)>,
+}
+
+impl WebdavSink {
+    /// `credentials` are a username and password (for Nextcloud, better an
+    /// app password) to log in with, if the server needs them.
+    pub fn new(
+        network: Network,
+        folder: &str,
+        credentials: Option<(&str, &str)>,
+    ) -> Result<Self, WebdavError> {
+        let bad_url = || WebdavError::BadUrl(folder.to_string());
+        let mut url = Url::parse(folder).map_err(|_| bad_url())?;
+        if !matches!(url.scheme(), "http" | "https") || url.cannot_be_a_base() {
+            return Err(bad_url());
+        }
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Ok(Self {
+            client: network.client(),
+            folder: url,
+            credentials: credentials.map(|(user, pass)| (user.to_string(), pass.to_string())),
+        })
+    }
+
+    pub fn folder(&self) -> &Url {
+        &self.folder
+    }
+
+    pub async fn upload(&self, doc: &GridDocument) -> Result<(), WebdavError> {
+        let files = formats::day_files(doc)?;
+
+        // It's a valid method name, so this can't fail
+        let mkcol = Method::from_bytes(b"MKCOL").unwrap();
+        // Already there (405) is what it'll usually be
+        let made = self.send(mkcol, &self.folder, None).await?;
+        if !made.is_success() && made != StatusCode::METHOD_NOT_ALLOWED {
+            return Err(WebdavError::Refused {
+                url: self.folder.clone(),
+                status: made,
+            });
+        }
+
+        for file in files {
+            // Checked to be a base in new(), and dates make plain names
+            let url = self
+                .folder
+                .join(&file.name)
+                .map_err(|_| WebdavError::BadUrl(self.folder.to_string()))?;
+            let status = self
+                .send(Method::PUT, &url, Some((file.contents, file.content_type)))
+                .await?;
+            if !status.is_success() {
+                return Err(WebdavError::Refused { url, status });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: &Url,
+        body: Option<(Vec<u8>, &'static str)>,
+    ) -> Result<StatusCode, WebdavError> {
+        let mut request = self.client.request(method, url.clone());
+        if let Some((user, pass)) = &self.credentials {
+            request = request.basic_auth(user, Some(pass));
+        }
+        if let Some((contents, content_type)) = body {
+            request = request
+                .header(header::CONTENT_TYPE, content_type)
+                .body(contents);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| WebdavError::Sending(url.clone(), e))?;
+
+        Ok(response.status())
+    }
+}
@@ -0,0 +1,59 @@
+use crate::parse::GRID_LETTERS;
+use crate::theme::{Role, Theme};
+
+/// Draws the day's letters the way the puzzle shows them, as a wheel of
+/// six around the center letter, e.g. for center C:
+///
+/// ```text
+///    A   D
+///  T  [C]  E
+///    N   L
+/// ```
+///
+/// The others go clockwise from the top left in alphabetical order. The
+/// center is bracketed as well as painted with `theme`, so it stands out
+/// without color too.
+///
+/// Nothing if there aren't seven letters, or `center` isn't one of them.
+pub fn render(letters: &[char], center: char, theme: Theme) -> Option<String> {
+    let mut outer = letters
+        .iter()
+        .map(char::to_ascii_uppercase)
+        .collect::<Vec<_>>();
+    outer.sort_unstable();
+    outer.dedup();
+    if outer.len() != GRID_LETTERS {
+        return None;
+    }
+    let center = center.to_ascii_uppercase();
+    outer.retain(|letter| *letter != center);
+    let [top_left, top_right, right, bottom_right, bottom_left, left] = outer[..] else {
+        return None;
+    };
+
+    let center = theme.paint(Role::Heading, format!("[{center}]"));
+    Some(format!(
+        "   {top_left}   {top_right}\n {left}  {center}  {right}\n   {bottom_left}   {bottom_right}\n"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wheel_of_sample() {
+        let letters = ['A', 'C', 'D', 'E', 'L', 'N', 'T'];
+        assert_eq!(
+            render(&letters, 'c', Theme::plain()).unwrap(),
+            "   A   D\n T  [C]  E\n   N   L\n"
+        );
+    }
+
+    #[test]
+    fn center_must_be_a_letter() {
+        let letters = ['A', 'C', 'D', 'E', 'L', 'N', 'T'];
+        assert_eq!(render(&letters, 'B', Theme::plain()), None);
+        assert_eq!(render(&letters[1..], 'C', Theme::plain()), None);
+    }
+}
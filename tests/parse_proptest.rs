@@ -0,0 +1,121 @@
+//! Mutated versions of a real page must come back from the parser as data
+//! or a `SiteParseError`, never a panic.
+
+use proptest::prelude::*;
+
+use gridder::parse::{parse_center, parse_content};
+
+const SAMPLE_PAGE: &str = include_str!("../fixtures/sample.html");
+
+// What a mangled or half-saved page tends to have in it
+const PIECES: &[&str] = &[
+    "<",
+    ">",
+    "</",
+    "/>",
+    "\"",
+    "=",
+    "-",
+    "<tr>",
+    "</tr>",
+    "<td>",
+    "</td>",
+    "<table>",
+    "</table>",
+    "<p>",
+    "</p>",
+    "<strong>",
+    "Σ",
+    "\u{a0}",
+    "\u{2009}",
+    ",",
+    ".",
+    "0",
+    "9",
+    "99999999999999999999",
+    "AB-",
+    "-3",
+    "WORDS: ",
+    "row",
+    "cell",
+    "content",
+];
+
+#[derive(Debug, Clone)]
+enum Mutation {
+    Delete { at: usize, len: usize },
+    Insert { at: usize, text: String },
+    Duplicate { at: usize, len: usize },
+    Replace { at: usize, len: usize, text: String },
+}
+
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(PIECES).prop_map(str::to_string),
+        ".{0,8}",
+    ]
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    // Positions are taken modulo the page's length when applied
+    prop_oneof![
+        (any::<usize>(), 1..64usize).prop_map(|(at, len)| Mutation::Delete { at, len }),
+        (any::<usize>(), text()).prop_map(|(at, text)| Mutation::Insert { at, text }),
+        (any::<usize>(), 1..256usize).prop_map(|(at, len)| Mutation::Duplicate { at, len }),
+        (any::<usize>(), 1..8usize, text()).prop_map(|(at, len, text)| Mutation::Replace {
+            at,
+            len,
+            text
+        }),
+    ]
+}
+
+// Works on chars, so a mutation never splits one
+fn mutate(page: &str, mutations: &[Mutation]) -> String {
+    let mut chars = page.chars().collect::<Vec<_>>();
+    for mutation in mutations {
+        let len = chars.len();
+        let span = |at: usize, n: usize| {
+            let start = at % (len + 1);
+            start..(start + n).min(len)
+        };
+        match mutation {
+            Mutation::Delete { at, len } => {
+                chars.drain(span(*at, *len));
+            }
+            Mutation::Insert { at, text } => {
+                let at = at % (len + 1);
+                chars.splice(at..at, text.chars());
+            }
+            Mutation::Duplicate { at, len } => {
+                let range = span(*at, *len);
+                let copy = chars[range.clone()].to_vec();
+                chars.splice(range.end..range.end, copy);
+            }
+            Mutation::Replace { at, len, text } => {
+                chars.splice(span(*at, *len), text.chars());
+            }
+        }
+    }
+    chars.into_iter().collect()
+}
+
+#[test]
+fn sample_page_parses() {
+    assert!(parse_content(SAMPLE_PAGE).is_ok());
+    assert_eq!(parse_center(SAMPLE_PAGE), Some('C'));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn mutated_pages_never_panic(mutations in prop::collection::vec(mutation(), 1..8)) {
+        let page = mutate(SAMPLE_PAGE, &mutations);
+        // Either is fine, so long as it returns
+        let _ = parse_content(&page);
+        let _ = parse_center(&page);
+        #[cfg(feature = "fast-parse")]
+        let _ = gridder::parse::parse_content_streaming(&page);
+    }
+}